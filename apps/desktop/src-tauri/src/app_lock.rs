@@ -0,0 +1,216 @@
+//! The application lock.
+//!
+//! [`AppLock`] is managed before [`crate::commands::AppState`], as soon as the lock settings
+//! have been read from the database, and starts locked when a passphrase is set. While it is
+//! locked, [`guard`] rejects every command outside [`ALLOWED_WHILE_LOCKED`] before it runs.
+//! Every command that does run counts as activity for the auto-lock timeout.
+
+use flow_core::error::FlowError;
+use flow_db::repositories::AppLockSettings;
+use serde::Serialize;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tauri::ipc::Invoke;
+use tauri::{Manager, Runtime};
+
+/// Commands that work while the app is locked: finding out what to unlock, and unlocking.
+const ALLOWED_WHILE_LOCKED: &[&str] = &["get_encryption_status", "unlock_database", "get_lock_status", "unlock_app"];
+
+pub struct AppLock {
+    state: Mutex<LockState>,
+}
+
+struct LockState {
+    enabled: bool,
+    auto_lock: Option<Duration>,
+    locked: bool,
+    last_activity: Instant,
+}
+
+impl LockState {
+    /// Locks once the auto-lock timeout has passed since the last activity.
+    fn expire(&mut self) {
+        if let (true, Some(timeout)) = (self.enabled, self.auto_lock) {
+            if self.last_activity.elapsed() >= timeout {
+                self.locked = true;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub auto_lock_minutes: Option<u32>,
+}
+
+impl AppLock {
+    /// Starts locked when a passphrase is set.
+    pub fn new(settings: &AppLockSettings) -> Self {
+        let lock = AppLock {
+            state: Mutex::new(LockState { enabled: false, auto_lock: None, locked: settings.enabled, last_activity: Instant::now() }),
+        };
+        lock.apply(settings);
+        lock
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, LockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes over changed settings without locking or unlocking.
+    pub fn apply(&self, settings: &AppLockSettings) {
+        let mut state = self.state();
+        state.enabled = settings.enabled;
+        state.auto_lock = settings.auto_lock_minutes.map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+        state.locked &= settings.enabled;
+    }
+
+    /// Records activity; `false` when the app is, or has just become, locked.
+    pub fn touch(&self) -> bool {
+        let mut state = self.state();
+        state.expire();
+        if !state.locked {
+            state.last_activity = Instant::now();
+        }
+        !state.locked
+    }
+
+    /// Locks now; does nothing without a passphrase to unlock with.
+    pub fn lock(&self) {
+        let mut state = self.state();
+        state.locked = state.enabled;
+    }
+
+    pub fn unlock(&self) {
+        let mut state = self.state();
+        state.locked = false;
+        state.last_activity = Instant::now();
+    }
+
+    pub fn status(&self) -> LockStatus {
+        let mut state = self.state();
+        state.expire();
+        LockStatus {
+            enabled: state.enabled,
+            locked: state.locked,
+            auto_lock_minutes: state.auto_lock.map(|timeout| (timeout.as_secs() / 60) as u32),
+        }
+    }
+}
+
+/// Whether `command` may run: an allow-listed one always, any other only once the lock
+/// exists and is unlocked.
+fn admits(command: &str, lock: Option<&AppLock>) -> bool {
+    ALLOWED_WHILE_LOCKED.contains(&command) || lock.is_some_and(AppLock::touch)
+}
+
+/// Wraps the command handler so a locked app rejects commands with [`FlowError::Locked`].
+///
+/// Before the database is open there is no [`AppLock`] yet. Until there is, only
+/// [`ALLOWED_WHILE_LOCKED`] commands run, so a slow or failed start-up cannot leave the
+/// app open.
+pub fn guard<R: Runtime>(handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let webview = invoke.message.webview();
+        if !admits(invoke.message.command(), webview.try_state::<AppLock>().as_deref()) {
+            invoke.resolver.reject(FlowError::Locked);
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(enabled: bool, auto_lock_minutes: Option<u32>) -> AppLockSettings {
+        AppLockSettings { enabled, auto_lock_minutes }
+    }
+
+    /// Pretends the last activity was `ago`.
+    fn idle(lock: &AppLock, ago: Duration) {
+        lock.state().last_activity = Instant::now() - ago;
+    }
+
+    #[test]
+    fn test_starts_locked_only_with_a_passphrase() {
+        assert!(AppLock::new(&settings(true, None)).status().locked);
+        let open = AppLock::new(&settings(false, Some(5)));
+        assert!(!open.status().locked);
+
+        // Without a passphrase there is nothing to unlock with, so locking does nothing
+        open.lock();
+        assert!(open.touch());
+    }
+
+    #[test]
+    fn test_lock_and_unlock() {
+        let lock = AppLock::new(&settings(true, None));
+        assert!(!lock.touch());
+        lock.unlock();
+        assert!(lock.touch());
+        lock.lock();
+        assert!(!lock.touch());
+        assert!(lock.status().locked);
+    }
+
+    #[test]
+    fn test_auto_locks_after_the_timeout() {
+        let lock = AppLock::new(&settings(true, Some(5)));
+        lock.unlock();
+        assert_eq!(lock.status().auto_lock_minutes, Some(5));
+        // Seconds instead of minutes, so the monotonic clock can be wound back safely
+        lock.state().auto_lock = Some(Duration::from_secs(2));
+
+        // Activity inside the timeout keeps it open and restarts the timer
+        idle(&lock, Duration::from_secs(1));
+        assert!(lock.touch());
+        idle(&lock, Duration::from_secs(1));
+        assert!(!lock.status().locked);
+
+        idle(&lock, Duration::from_secs(2));
+        assert!(lock.status().locked);
+        assert!(!lock.touch());
+
+        // Without a timeout it only locks at start-up or when asked
+        let lock = AppLock::new(&settings(true, None));
+        lock.unlock();
+        idle(&lock, Duration::from_secs(2));
+        assert!(lock.touch());
+    }
+
+    #[test]
+    fn test_changed_settings_neither_lock_nor_unlock() {
+        let lock = AppLock::new(&settings(false, None));
+        lock.apply(&settings(true, Some(1)));
+        assert!(!lock.status().locked);
+
+        lock.lock();
+        lock.apply(&settings(true, None));
+        assert!(lock.status().locked);
+        // ...except that removing the passphrase leaves nothing to unlock with
+        lock.apply(&settings(false, None));
+        assert!(!lock.status().locked);
+    }
+
+    #[test]
+    fn test_admits_only_unlocking_while_locked() {
+        let lock = AppLock::new(&settings(true, None));
+        for command in ALLOWED_WHILE_LOCKED {
+            assert!(admits(command, Some(&lock)), "{}", command);
+            // ...and before start-up has read the lock settings
+            assert!(admits(command, None), "{}", command);
+        }
+        for command in ["get_clients", "export_data", "set_app_passphrase", "lock_app"] {
+            assert!(!admits(command, Some(&lock)), "{}", command);
+            assert!(!admits(command, None), "{}", command);
+        }
+
+        lock.unlock();
+        assert!(admits("get_clients", Some(&lock)));
+        assert_eq!(FlowError::Locked.code(), "locked");
+    }
+}
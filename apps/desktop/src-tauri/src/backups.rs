@@ -0,0 +1,89 @@
+//! Scheduled backups: where they go, how often they are made and how many are kept.
+//!
+//! The settings are kept in `backup.json` beside the database rather than in it, so restoring
+//! a backup does not move where the next ones are written.
+
+use crate::commands::AppState;
+use chrono::Utc;
+use flow_core::error::FlowResult;
+use flow_db::backup::{self, BackupInfo, BackupRetention};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const CONFIG_FILE: &str = "backup.json";
+/// Gives start-up, and an app lock or database passphrase prompt, time before the first check.
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+/// How often the scheduler looks whether a backup is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Whether backups are made on schedule; manual backups work either way.
+    pub enabled: bool,
+    /// `None` for `backups` in the app data directory.
+    pub directory: Option<PathBuf>,
+    pub interval_hours: u32,
+    pub retention: BackupRetention,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        BackupConfig { enabled: true, directory: None, interval_hours: 24, retention: BackupRetention::default() }
+    }
+}
+
+pub fn load_config(app_data_dir: &Path) -> BackupConfig {
+    std::fs::read_to_string(app_data_dir.join(CONFIG_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_config(app_data_dir: &Path, config: &BackupConfig) -> FlowResult<()> {
+    let content = serde_json::to_string_pretty(config)?;
+    Ok(std::fs::write(app_data_dir.join(CONFIG_FILE), content)?)
+}
+
+pub fn backup_dir(app_data_dir: &Path, config: &BackupConfig) -> PathBuf {
+    config.directory.clone().unwrap_or_else(|| app_data_dir.join("backups"))
+}
+
+/// Backs up now, then deletes the backups the retention no longer keeps.
+pub async fn back_up(state: &AppState) -> FlowResult<BackupInfo> {
+    let config = load_config(&state.app_data_dir);
+    let dir = backup_dir(&state.app_data_dir, &config);
+    let info = backup::create_backup(&state.db(), &dir).await?;
+    backup::prune_backups(&dir, &config.retention)?;
+    Ok(info)
+}
+
+/// Runs for the life of the app, backing up whenever the newest backup is older than the
+/// configured interval. Nothing happens until the database is open.
+pub async fn run_scheduler(app: AppHandle) {
+    tokio::time::sleep(FIRST_CHECK_DELAY).await;
+    loop {
+        if let Some(state) = app.try_state::<AppState>() {
+            if let Err(e) = back_up_if_due(&state).await {
+                eprintln!("❌ Scheduled backup failed: {}", e);
+            }
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+async fn back_up_if_due(state: &AppState) -> FlowResult<()> {
+    let config = load_config(&state.app_data_dir);
+    if !config.enabled {
+        return Ok(());
+    }
+    let interval = chrono::Duration::hours(i64::from(config.interval_hours.max(1)));
+    let newest = backup::list_backups(&backup_dir(&state.app_data_dir, &config))?.first().map(|b| b.manifest.created_at);
+    if newest.is_none_or(|created_at| Utc::now() - created_at >= interval) {
+        let info = back_up(state).await?;
+        println!("✅ Backup written to {:?}", info.path);
+    }
+    Ok(())
+}
@@ -1,16 +1,39 @@
-use flow_db::database::DbPool;
-use flow_db::repositories::{ClientRepository, InvoiceRepository};
+use flow_db::database::{DatabaseKey, DbPool};
+use flow_db::repositories::{asset_repo, AssetRepository, AuditLogRepository, ClientRepository, InvoiceRepository};
 use flow_db::repositories::invoice_repo::InvoiceSummary;
+use flow_db::query::{ClientQuery, InvoiceQuery, Page};
+use flow_db::unit_of_work::UnitOfWork;
 use flow_analytics::{AnalyticsEngine, RevenueMetrics};
+use flow_core::duplicates::find_duplicates;
+use flow_core::error::FlowError;
+use flow_core::models::{Address, AuditEntry, Client, ClientContact, CustomFieldValue, DuplicateMatch, TaxIdentifier};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
 use tauri::State;
 
 pub struct AppState {
-    pub db: DbPool,
+    /// The open pool and the key it was opened with.
+    db: RwLock<(DbPool, Option<DatabaseKey>)>,
     pub db_path: std::path::PathBuf,
     pub app_data_dir: std::path::PathBuf,
 }
 
+impl AppState {
+    /// The open pool; replaced when the database key changes or a backup is restored.
+    pub fn db(&self) -> DbPool {
+        self.db.read().unwrap_or_else(PoisonError::into_inner).0.clone()
+    }
+
+    pub fn db_key(&self) -> Option<DatabaseKey> {
+        self.db.read().unwrap_or_else(PoisonError::into_inner).1.clone()
+    }
+
+    fn replace_db(&self, pool: DbPool, key: Option<DatabaseKey>) {
+        *self.db.write().unwrap_or_else(PoisonError::into_inner) = (pool, key);
+    }
+}
+
 // ─── Client Commands ──────────────────────────────────────────
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,6 +41,37 @@ pub struct CreateClientRequest {
     pub name: String,
     pub email: Option<String>,
     pub company: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    /// Billing address.
+    #[serde(default)]
+    pub address: Address,
+    /// Left out when goods ship to the billing address.
+    #[serde(default)]
+    pub shipping_address: Option<Address>,
+    #[serde(default)]
+    pub tax_id: Option<String>,
+    #[serde(default)]
+    pub tax_ids: Vec<TaxIdentifier>,
+    #[serde(default)]
+    pub contacts: Vec<ContactRequest>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Custom field values keyed by field definition id.
+    #[serde(default)]
+    pub custom_fields: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactRequest {
+    /// Absent for contacts added in this edit.
+    pub id: Option<String>,
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub role: Option<String>,
+    #[serde(default)]
+    pub is_primary: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,74 +79,223 @@ pub struct ClientResponse {
     pub id: String,
     pub name: String,
     pub email: Option<String>,
+    pub phone: Option<String>,
     pub company: Option<String>,
+    pub address: Address,
+    pub shipping_address: Option<Address>,
+    pub tax_id: Option<String>,
+    pub tax_ids: Vec<TaxIdentifier>,
+    pub contacts: Vec<ClientContact>,
+    pub notes: Option<String>,
+    pub custom_fields: Vec<CustomFieldValue>,
+    pub archived_at: Option<String>,
 }
 
-#[tauri::command]
-pub async fn get_clients(state: State<'_, AppState>) -> Result<Vec<ClientResponse>, String> {
-    let repo = ClientRepository::new(state.db.clone());
-    let clients = repo.list_all().await.map_err(|e| e.to_string())?;
-    Ok(clients
-        .into_iter()
-        .map(|c| ClientResponse {
+impl From<Client> for ClientResponse {
+    fn from(c: Client) -> Self {
+        Self {
             id: c.id.to_string(),
             name: c.name,
             email: c.email,
+            phone: c.phone,
             company: c.company,
-        })
-        .collect())
+            address: c.address,
+            shipping_address: c.shipping_address,
+            tax_id: c.tax_id,
+            tax_ids: c.tax_ids,
+            contacts: c.contacts,
+            notes: c.notes,
+            custom_fields: c.custom_fields,
+            archived_at: c.archived_at.map(|dt| dt.to_rfc3339()),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_clients(state: State<'_, AppState>) -> Result<Vec<ClientResponse>, FlowError> {
+    let repo = ClientRepository::new(state.db());
+    let clients = repo.list_all().await?;
+    Ok(clients.into_iter().map(ClientResponse::from).collect())
+}
+
+/// Searched, sorted page of clients; pass `next_cursor` back as `query.cursor` for more.
+#[tauri::command]
+pub async fn query_clients(state: State<'_, AppState>, query: ClientQuery) -> Result<Page<ClientResponse>, FlowError> {
+    let repo = ClientRepository::new(state.db());
+    let page = repo.query(&query).await?;
+    Ok(Page {
+        items: page.items.into_iter().map(ClientResponse::from).collect(),
+        next_cursor: page.next_cursor,
+        total: page.total,
+    })
+}
+
+#[tauri::command]
+pub async fn get_client(state: State<'_, AppState>, id: String) -> Result<ClientResponse, FlowError> {
+    let repo = ClientRepository::new(state.db());
+    repo.get_by_id(&id)
+        .await?
+        .map(ClientResponse::from)
+        .ok_or_else(|| FlowError::NotFound(format!("Client {}", id)))
 }
 
 #[tauri::command]
 pub async fn create_client(
     state: State<'_, AppState>,
     request: CreateClientRequest,
-) -> Result<ClientResponse, String> {
-    let repo = ClientRepository::new(state.db.clone());
-    let client = repo
-        .create(&request.name, request.email.as_deref(), request.company.as_deref())
-        .await
-        .map_err(|e| e.to_string())?;
-
-    Ok(ClientResponse {
-        id: client.id.to_string(),
-        name: client.name,
-        email: client.email,
-        company: client.company,
-    })
+) -> Result<ClientResponse, FlowError> {
+    let custom_fields = resolve_custom_fields(&state.db(), CustomFieldEntity::Client, &request.custom_fields).await?;
+    let client = client_from_request(uuid::Uuid::new_v4(), request, custom_fields)?;
+    let repo = ClientRepository::new(state.db());
+    repo.create(&client).await?;
+
+    Ok(client.into())
+}
+
+/// Fails with `FlowError::ClientHasInvoices` for clients with invoices; archive those instead.
+#[tauri::command]
+pub async fn delete_client(state: State<'_, AppState>, id: String) -> Result<(), FlowError> {
+    let repo = ClientRepository::new(state.db());
+    repo.delete(&id).await
 }
 
 #[tauri::command]
-pub async fn delete_client(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    let repo = ClientRepository::new(state.db.clone());
-    repo.delete(&id).await.map_err(|e| e.to_string())
+pub async fn set_client_archived(state: State<'_, AppState>, id: String, archived: bool) -> Result<(), FlowError> {
+    let repo = ClientRepository::new(state.db());
+    repo.set_archived(&id, archived).await
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateClientRequest {
     pub id: String,
-    pub name: String,
-    pub email: Option<String>,
-    pub company: Option<String>,
+    #[serde(flatten)]
+    pub client: CreateClientRequest,
 }
 
 #[tauri::command]
 pub async fn update_client(
     state: State<'_, AppState>,
     request: UpdateClientRequest,
-) -> Result<(), String> {
-    let repo = ClientRepository::new(state.db.clone());
-    repo.update(&request.id, &request.name, request.email.as_deref(), request.company.as_deref())
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), FlowError> {
+    let custom_fields = resolve_custom_fields(&state.db(), CustomFieldEntity::Client, &request.client.custom_fields).await?;
+    let id = uuid::Uuid::parse_str(&request.id).map_err(|_| FlowError::Validation(format!("Invalid client id: {}", request.id)))?;
+    let client = client_from_request(id, request.client, custom_fields)?;
+    let repo = ClientRepository::new(state.db());
+    repo.update(&client).await
+}
+
+/// Pairs of clients that look like the same customer, most likely first.
+#[tauri::command]
+pub async fn find_duplicate_clients(state: State<'_, AppState>) -> Result<Vec<DuplicateMatch>, FlowError> {
+    let repo = ClientRepository::new(state.db());
+    let clients = repo.list_all().await?;
+    Ok(find_duplicates(&clients))
+}
+
+/// Moves everything of `source_id` onto `target_id`, deletes the source and returns the merged client.
+#[tauri::command]
+pub async fn merge_clients(state: State<'_, AppState>, source_id: String, target_id: String) -> Result<ClientResponse, FlowError> {
+    let repo = ClientRepository::new(state.db());
+    repo.merge(&source_id, &target_id).await.map(ClientResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_audit_log(state: State<'_, AppState>, entity_id: Option<String>, limit: Option<u32>) -> Result<Vec<AuditEntry>, FlowError> {
+    let repo = AuditLogRepository::new(state.db());
+    repo.list(entity_id.as_deref(), limit.unwrap_or(100)).await
+}
+
+/// Builds the client to store from a create/update request: blank optional values become
+/// `None`, empty contacts and tax IDs are dropped, an all-blank shipping address means
+/// "ship to the billing address" and only the first contact flagged primary stays primary.
+fn client_from_request(id: uuid::Uuid, request: CreateClientRequest, custom_fields: Vec<CustomFieldValue>) -> Result<Client, FlowError> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err(FlowError::invalid_field("name", "Client name is required"));
+    }
+
+    let mut has_primary = false;
+    let mut contacts = Vec::new();
+    for contact in request.contacts {
+        if contact.name.trim().is_empty() {
+            continue;
+        }
+        let contact_id = match contact.id.as_deref() {
+            Some(contact_id) => uuid::Uuid::parse_str(contact_id).map_err(|_| FlowError::Validation(format!("Invalid contact id: {}", contact_id)))?,
+            None => uuid::Uuid::new_v4(),
+        };
+        let is_primary = contact.is_primary && !has_primary;
+        has_primary |= is_primary;
+        contacts.push(ClientContact {
+            id: contact_id,
+            name: contact.name.trim().to_string(),
+            email: non_blank(contact.email),
+            phone: non_blank(contact.phone),
+            role: non_blank(contact.role),
+            is_primary,
+        });
+    }
+
+    let tax_ids = request
+        .tax_ids
+        .into_iter()
+        .filter(|t| !t.value.trim().is_empty())
+        .map(|t| TaxIdentifier {
+            scheme: t.scheme.trim().to_string(),
+            value: t.value.trim().to_string(),
+        })
+        .collect();
+
+    let now = chrono::Utc::now();
+    Ok(Client {
+        id,
+        name,
+        email: non_blank(request.email),
+        phone: non_blank(request.phone),
+        company: non_blank(request.company),
+        address: trim_address(request.address),
+        shipping_address: request.shipping_address.map(trim_address).filter(|a| {
+            !(a.line1.is_empty() && a.line2.is_none() && a.city.is_empty() && a.state.is_none() && a.postal_code.is_empty() && a.country.is_empty())
+        }),
+        tax_id: non_blank(request.tax_id),
+        tax_ids,
+        contacts,
+        notes: non_blank(request.notes),
+        custom_fields,
+        archived_at: None,
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn trim_address(address: Address) -> Address {
+    Address {
+        line1: address.line1.trim().to_string(),
+        line2: non_blank(address.line2),
+        city: address.city.trim().to_string(),
+        state: non_blank(address.state),
+        postal_code: address.postal_code.trim().to_string(),
+        country: address.country.trim().to_string(),
+    }
 }
 
 // ─── Invoice Commands ─────────────────────────────────────────
 
 #[tauri::command]
-pub async fn get_invoices(state: State<'_, AppState>) -> Result<Vec<InvoiceSummary>, String> {
-    let repo = InvoiceRepository::new(state.db.clone());
-    repo.list_all().await.map_err(|e| e.to_string())
+pub async fn get_invoices(state: State<'_, AppState>) -> Result<Vec<InvoiceSummary>, FlowError> {
+    let repo = InvoiceRepository::new(state.db());
+    repo.list_all().await
+}
+
+/// Filtered, sorted page of invoices; pass `next_cursor` back as `query.cursor` for more.
+#[tauri::command]
+pub async fn query_invoices(state: State<'_, AppState>, query: InvoiceQuery) -> Result<Page<InvoiceSummary>, FlowError> {
+    let repo = InvoiceRepository::new(state.db());
+    repo.query(&query).await
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -104,6 +307,21 @@ pub struct CreateInvoiceRequest {
     pub status: Option<String>,
     pub issue_date: Option<String>,
     pub due_date: Option<String>,
+    /// Defaults to the default account for the invoice currency.
+    #[serde(default)]
+    pub bank_account_id: Option<String>,
+    #[serde(default)]
+    pub developer: Option<String>,
+    #[serde(default)]
+    pub logo_path: Option<String>,
+    #[serde(default)]
+    pub qr_code_url: Option<String>,
+    #[serde(default)]
+    pub payment_note: Option<String>,
+    #[serde(default)]
+    pub project_details: Vec<flow_core::models::ProjectDetail>,
+    #[serde(default)]
+    pub custom_fields: HashMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -111,18 +329,22 @@ pub struct InvoiceItemRequest {
     pub description: String,
     pub quantity: f64,
     pub unit_price: f64,
+    #[serde(default)]
+    pub hsn_code: Option<String>,
+    #[serde(default)]
+    pub custom_fields: HashMap<String, String>,
 }
 
 #[tauri::command]
 pub async fn create_invoice(
     state: State<'_, AppState>,
     request: CreateInvoiceRequest,
-) -> Result<String, String> {
+) -> Result<String, FlowError> {
     use chrono::Utc;
     use flow_core::models::{Invoice, InvoiceItem};
     use flow_core::types::InvoiceStatus;
     use flow_invoice::calculator::InvoiceCalculator;
-    use rust_decimal::Decimal;
+    use rust_decimal::{Decimal, RoundingStrategy};
     use std::str::FromStr;
     use uuid::Uuid;
 
@@ -140,49 +362,83 @@ pub async fn create_invoice(
         .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
         .unwrap_or_else(|| issue_date + chrono::Duration::days(30));
 
+    let definitions = CustomFieldRepository::new(state.db())
+        .list_definitions(None)
+        .await?;
+    let invoice_fields = resolve_with(&definitions, CustomFieldEntity::Invoice, &request.custom_fields)?;
+    let item_fields = request
+        .items
+        .iter()
+        .map(|item| resolve_with(&definitions, CustomFieldEntity::InvoiceItem, &item.custom_fields))
+        .collect::<Result<Vec<_>, FlowError>>()?;
+
+    // Get active business profile
+    let profile_repo = flow_db::repositories::BusinessProfileRepository::new(state.db());
+    let profile = profile_repo.get_profile().await?;
+    // Amounts are rounded to the currency's minor unit here; storage refuses finer ones
+    let scale = profile.default_currency.minor_units();
+    let round = |amount: Decimal| amount.round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+
     // Build items
     let items: Vec<InvoiceItem> = request
         .items
         .iter()
+        .zip(item_fields)
         .enumerate()
-        .map(|(i, item)| {
-            let qty = Decimal::from_str(&item.quantity.to_string()).unwrap_or_default();
-            let price = Decimal::from_str(&item.unit_price.to_string()).unwrap_or_default();
-            InvoiceItem {
+        .map(|(i, (item, custom_fields))| {
+            let number = |field: &str, value: f64| {
+                Decimal::from_str(&value.to_string()).map_err(|_| FlowError::invalid_field(field, format!("{} is not a number", value)))
+            };
+            let qty = number("quantity", item.quantity)?;
+            let price = number("unit_price", item.unit_price)?;
+            if price.normalize().scale() > flow_db::money::MAX_SCALE {
+                return Err(FlowError::invalid_field(
+                    "unit_price",
+                    format!("Unit prices have at most {} decimal places", flow_db::money::MAX_SCALE),
+                ));
+            }
+            Ok(InvoiceItem {
                 id: Uuid::new_v4(),
                 invoice_id,
                 description: item.description.clone(),
                 quantity: qty,
                 unit_price: price,
-                amount: qty * price,
+                amount: round(qty * price),
                 tax_rate_name: None,
+                hsn_code: item.hsn_code.as_deref().map(str::trim).filter(|c| !c.is_empty()).map(String::from),
                 sort_order: i as i32,
-            }
+                custom_fields,
+            })
         })
-        .collect();
+        .collect::<Result<_, FlowError>>()?;
 
     let (subtotal, tax, disc, total) = InvoiceCalculator::grand_total(&items, &[], &None);
+    let (subtotal, tax, disc, total) = (round(subtotal), round(tax), round(disc), round(total));
 
     // Generate invoice number
-    let repo = InvoiceRepository::new(state.db.clone());
-    let existing = repo.list_all().await.map_err(|e| e.to_string())?;
-    
+    let repo = InvoiceRepository::new(state.db());
+
     let number = if let Some(nr) = request.invoice_number.filter(|s| !s.trim().is_empty()) {
         nr.trim().to_string()
     } else {
         let gen = flow_invoice::number_generator::InvoiceNumberGenerator::default();
-        let mut offset = existing.len() as u64;
+        let mut offset = repo.count().await? as u64;
         let mut n = gen.next(offset);
-        while existing.iter().any(|i| i.number == n) {
+        while repo.number_exists(&n).await? {
             offset += 1;
             n = gen.next(offset);
         }
         n
     };
 
-    // Get active business profile
-    let profile_repo = flow_db::repositories::BusinessProfileRepository::new(state.db.clone());
-    let profile = profile_repo.get_profile().await.map_err(|e| e.to_string())?;
+    let bank_repo = BankAccountRepository::new(state.db());
+    let bank_account = match request.bank_account_id.filter(|id| !id.is_empty()) {
+        Some(id) => Some(
+            bank_repo.get_by_id(&id).await?
+                .ok_or_else(|| FlowError::NotFound(format!("Bank account {}", id)))?,
+        ),
+        None => bank_repo.default_for_currency(&profile.default_currency).await?,
+    };
 
     let invoice = Invoice {
         id: invoice_id,
@@ -196,7 +452,7 @@ pub async fn create_invoice(
                 _ => InvoiceStatus::Draft,
             }
         }).unwrap_or(InvoiceStatus::Draft),
-        client_id: Uuid::parse_str(&request.client_id).map_err(|e| e.to_string())?,
+        client_id: Uuid::parse_str(&request.client_id).map_err(|_| FlowError::Validation(format!("Invalid client id: {}", request.client_id)))?,
         business_profile_id: profile.id,
         issue_date,
         due_date,
@@ -211,24 +467,39 @@ pub async fn create_invoice(
         amount_paid: Decimal::ZERO,
         amount_due: total,
         payment_terms: profile.default_payment_terms.clone(),
-        notes: request.notes,
+        notes: request.notes.filter(|n| !n.trim().is_empty()),
         terms_and_conditions: None,
+        payment_note: request.payment_note.filter(|n| !n.trim().is_empty()),
+        developer: request.developer.filter(|d| !d.trim().is_empty()),
+        logo_path: request.logo_path.filter(|p| !p.is_empty()),
+        qr_code_url: request.qr_code_url.filter(|u| !u.is_empty()),
+        project_details: request.project_details.into_iter().filter(|d| !d.value.trim().is_empty()).collect(),
+        custom_fields: invoice_fields,
+        gst_registration: None,
+        bank_account,
+        archived_at: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     };
 
-    repo.create(&invoice).await.map_err(|e| e.to_string())?;
+    repo.create(&invoice).await?;
     Ok(number)
 }
 
 #[tauri::command]
-pub async fn delete_invoice(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    let repo = InvoiceRepository::new(state.db.clone());
-    repo.delete(&id).await.map_err(|e| e.to_string())
+pub async fn delete_invoice(state: State<'_, AppState>, id: String) -> Result<(), FlowError> {
+    let repo = InvoiceRepository::new(state.db());
+    repo.delete(&id).await
+}
+
+#[tauri::command]
+pub async fn set_invoice_archived(state: State<'_, AppState>, id: String, archived: bool) -> Result<(), FlowError> {
+    let repo = InvoiceRepository::new(state.db());
+    repo.set_archived(&id, archived).await
 }
 
 #[tauri::command]
-pub async fn update_invoice_status(state: State<'_, AppState>, id: String, status: String) -> Result<(), String> {
+pub async fn update_invoice_status(state: State<'_, AppState>, id: String, status: String) -> Result<(), FlowError> {
 
     
     // Validate status string matches enum variants
@@ -240,207 +511,489 @@ pub async fn update_invoice_status(state: State<'_, AppState>, id: String, statu
         _ => "Draft",
     };
 
-    let repo = InvoiceRepository::new(state.db.clone());
-    repo.update_status(&id, valid_status).await.map_err(|e| e.to_string())
+    let repo = InvoiceRepository::new(state.db());
+    repo.update_status(&id, valid_status).await
 }
 
 // ─── Analytics Commands ───────────────────────────────────────
 
 #[tauri::command]
-pub async fn get_analytics(state: State<'_, AppState>) -> Result<RevenueMetrics, String> {
+pub async fn get_analytics(state: State<'_, AppState>) -> Result<RevenueMetrics, FlowError> {
     let engine = AnalyticsEngine::new();
-    engine.get_revenue_metrics(&state.db_path).map_err(|e| e.to_string())
+    engine.get_revenue_metrics(&state.db_path)
 }
 
-#[tauri::command]
-pub async fn generate_pdf(state: State<'_, AppState>, invoice_id: String) -> Result<String, String> {
-    // Get the active profile
-    let profile_repo = flow_db::repositories::BusinessProfileRepository::new(state.db.clone());
-    let profile = profile_repo.get_profile().await.map_err(|e| e.to_string())?;
+/// Resolves where exported PDFs go: the configured directory, else the OS downloads
+/// directory, else documents, else the app data directory.
+fn resolve_export_dir(app: &tauri::AppHandle, state: &AppState, configured: Option<&str>) -> std::path::PathBuf {
+    use tauri::Manager;
+
+    match configured.map(str::trim) {
+        Some(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+        _ => app
+            .path()
+            .download_dir()
+            .or_else(|_| app.path().document_dir())
+            .unwrap_or_else(|_| state.app_data_dir.join("exports")),
+    }
+}
 
-    let output_dir = if let Some(dir) = profile.pdf_export_dir.clone() {
-        if dir.trim().is_empty() {
-            std::path::PathBuf::from(r#"C:\Users\jabir\Downloads"#)
-        } else {
-            std::path::PathBuf::from(dir)
-        }
-    } else {
-        std::path::PathBuf::from(r#"C:\Users\jabir\Downloads"#)
-    };
+/// Loads an invoice together with its client and the active business profile.
+async fn load_invoice_context(state: &AppState, invoice_id: &str) -> Result<(flow_core::models::Invoice, flow_core::models::Client, flow_core::models::BusinessProfile), FlowError> {
+    let profile_repo = flow_db::repositories::BusinessProfileRepository::new(state.db());
+    let profile = profile_repo.get_profile().await?;
 
-    // Get full Invoice and Client objects
-    let invoice_repo = flow_db::repositories::InvoiceRepository::new(state.db.clone());
-    let invoice = invoice_repo.get_by_id(&invoice_id).await.map_err(|e| e.to_string())?
-        .ok_or_else(|| "Invoice not found or deleted.".to_string())?;
+    let invoice_repo = flow_db::repositories::InvoiceRepository::new(state.db());
+    let invoice = invoice_repo.get_by_id(invoice_id).await?
+        .ok_or_else(|| FlowError::NotFound(format!("Invoice {}", invoice_id)))?;
 
-    let client_repo = flow_db::repositories::ClientRepository::new(state.db.clone());
-    let client = client_repo.get_by_id(&invoice.client_id.to_string()).await.map_err(|e| e.to_string())?
-        .ok_or_else(|| "Client not found or deleted.".to_string())?;
+    let client_repo = flow_db::repositories::ClientRepository::new(state.db());
+    let client = client_repo.get_by_id(&invoice.client_id.to_string()).await?
+        .ok_or_else(|| FlowError::NotFound(format!("Client {}", invoice.client_id)))?;
 
-    let generator = flow_pdf::PdfGenerator::new(output_dir);
-    
-    // Convert domain structs to raw semantic HTML using the edge-parser
-    let html = flow_pdf::template::render_invoice_html(&invoice, &client, &profile);
-    
-    // Save generated PDF headlessly via Edge OS commands
-    let filename = format!("Invoice_{}.pdf", invoice.number);
+    Ok((invoice, client, profile))
+}
+
+/// Saves an e-invoice document beside the PDFs, named by the PDF filename pattern.
+fn write_export(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    invoice: &flow_core::models::Invoice,
+    client: &flow_core::models::Client,
+    profile: &flow_core::models::BusinessProfile,
+    extension: &str,
+    contents: String,
+) -> Result<String, FlowError> {
+    let output_dir = resolve_export_dir(app, state, profile.pdf_export_dir.as_deref());
+    let filename = flow_pdf::export::render_filename(&profile.pdf_filename_pattern, invoice, client)
+        .with_extension(extension);
+    let path = flow_pdf::export::unique_path(&output_dir.join(filename));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, contents)?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub async fn generate_pdf(app: tauri::AppHandle, state: State<'_, AppState>, invoice_id: String) -> Result<String, FlowError> {
+    let (invoice, client, profile) = load_invoice_context(&state, &invoice_id).await?;
+    let output_dir = resolve_export_dir(&app, &state, profile.pdf_export_dir.as_deref());
+
+    let backend = flow_pdf::backend_for_profile(&profile);
+    let generator = flow_pdf::PdfGenerator::with_backend(output_dir, backend);
+
+    // Render headlessly with the backend chosen in settings
+    let filename = flow_pdf::export::render_filename(&profile.pdf_filename_pattern, &invoice, &client);
     let path = tokio::task::spawn_blocking(move || {
-        generator.generate_from_html(&html, &filename)
+        generator.generate_invoice(&invoice, &client, &profile, &filename)
     })
     .await
-    .map_err(|e| e.to_string())?
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| FlowError::Internal(e.to_string()))??;
     
     Ok(path.to_string_lossy().into_owned())
 }
 
+/// Writes the invoice as Peppol BIS Billing 3.0 UBL next to the exported PDFs.
+#[tauri::command]
+pub async fn export_ubl(app: tauri::AppHandle, state: State<'_, AppState>, invoice_id: String) -> Result<String, FlowError> {
+    let (invoice, client, profile) = load_invoice_context(&state, &invoice_id).await?;
+    let xml = flow_einvoice::ubl::to_ubl(&invoice, &client, &profile)?;
+    write_export(&app, &state, &invoice, &client, &profile, "xml", xml)
+}
+
+/// Lists the Peppol rules the invoice currently violates so the UI can point at the fields.
+#[tauri::command]
+pub async fn validate_einvoice(state: State<'_, AppState>, invoice_id: String) -> Result<Vec<flow_einvoice::ValidationIssue>, FlowError> {
+    let (invoice, client, profile) = load_invoice_context(&state, &invoice_id).await?;
+    Ok(flow_einvoice::validate(&invoice, &client, &profile))
+}
+
+/// Writes the GSTN e-invoice JSON for upload to the invoice registration portal.
+#[tauri::command]
+pub async fn export_gst_einvoice(app: tauri::AppHandle, state: State<'_, AppState>, invoice_id: String) -> Result<String, FlowError> {
+    let (invoice, client, profile) = load_invoice_context(&state, &invoice_id).await?;
+    let json = flow_einvoice::gst::to_gst_json(&invoice, &client, &profile)?;
+    write_export(&app, &state, &invoice, &client, &profile, "json", json)
+}
+
+/// Lists the GSTN schema constraints the invoice currently violates.
+#[tauri::command]
+pub async fn validate_gst_einvoice(state: State<'_, AppState>, invoice_id: String) -> Result<Vec<flow_einvoice::ValidationIssue>, FlowError> {
+    let (invoice, client, profile) = load_invoice_context(&state, &invoice_id).await?;
+    Ok(flow_einvoice::gst::validate(&invoice, &client, &profile))
+}
+
+/// Stores the IRN, acknowledgement and signed QR from the portal's registration response.
+#[tauri::command]
+pub async fn record_gst_registration(state: State<'_, AppState>, invoice_id: String, response: String) -> Result<flow_core::models::GstRegistration, FlowError> {
+    let registration = flow_einvoice::gst::parse_irn_response(&response)?;
+    let repo = InvoiceRepository::new(state.db());
+    repo.set_gst_registration(&invoice_id, Some(&registration)).await?;
+    Ok(registration)
+}
+
+/// The browser the print backend would use; `path` is absent when none was found.
+#[derive(Debug, Serialize)]
+pub struct BrowserDetection {
+    pub path: Option<String>,
+    /// Locations probed before giving up, or the configured path when it is missing.
+    pub searched: Vec<String>,
+}
+
+/// Reports which browser the print backend would use, or the locations that were searched.
+#[tauri::command]
+pub async fn detect_pdf_browser(state: State<'_, AppState>) -> Result<BrowserDetection, FlowError> {
+    let repo = flow_db::repositories::BusinessProfileRepository::new(state.db());
+    let profile = repo.get_profile().await?;
+    Ok(match flow_pdf::BrowserBackend::for_profile(&profile).locate() {
+        Ok(path) => BrowserDetection { path: Some(path.to_string_lossy().into_owned()), searched: vec![] },
+        Err(err) => BrowserDetection {
+            path: None,
+            searched: err.searched_paths().iter().map(|p| p.to_string_lossy().into_owned()).collect(),
+        },
+    })
+}
+
 #[tauri::command]
-pub async fn open_pdf(path: String) -> Result<(), String> {
-    open::that(&path).map_err(|e| e.to_string())
+pub async fn open_pdf(path: String) -> Result<(), FlowError> {
+    Ok(open::that(&path)?)
 }
 
 // ─── Logo Commands ─────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn save_logo(state: State<'_, AppState>, base64_data: String) -> Result<(), String> {
-    use std::fs;
-    let logo_path = state.app_data_dir.join("logo.txt");
-    fs::write(&logo_path, base64_data).map_err(|e| e.to_string())
+pub async fn save_logo(state: State<'_, AppState>, base64_data: String) -> Result<(), FlowError> {
+    AssetRepository::new(state.db()).save(asset_repo::LOGO, &base64_data).await
 }
 
 #[tauri::command]
-pub async fn get_logo(state: State<'_, AppState>) -> Result<Option<String>, String> {
-    use std::fs;
-    let logo_path = state.app_data_dir.join("logo.txt");
-    if logo_path.exists() {
-        let content = fs::read_to_string(&logo_path).unwrap_or_default();
-        if content.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(content))
-        }
-    } else {
-        Ok(None)
-    }
+pub async fn get_logo(state: State<'_, AppState>) -> Result<Option<String>, FlowError> {
+    let logo = AssetRepository::new(state.db()).get(asset_repo::LOGO).await?;
+    Ok(logo.filter(|data| !data.is_empty()))
 }
 
 #[tauri::command]
-pub async fn delete_logo(state: State<'_, AppState>) -> Result<(), String> {
-    use std::fs;
-    let logo_path = state.app_data_dir.join("logo.txt");
-    if logo_path.exists() {
-        fs::remove_file(&logo_path).map_err(|e| e.to_string())?;
-    }
-    Ok(())
+pub async fn delete_logo(state: State<'_, AppState>) -> Result<(), FlowError> {
+    AssetRepository::new(state.db()).delete(asset_repo::LOGO).await
 }
 
 // ─── QR Code Commands ──────────────────────────────────────────
 
 #[tauri::command]
-pub async fn save_qr(state: State<'_, AppState>, base64_data: String) -> Result<(), String> {
-    use std::fs;
-    let qr_path = state.app_data_dir.join("qr_code.txt");
-    fs::write(&qr_path, base64_data).map_err(|e| e.to_string())
+pub async fn save_qr(state: State<'_, AppState>, base64_data: String) -> Result<(), FlowError> {
+    AssetRepository::new(state.db()).save(asset_repo::QR_CODE, &base64_data).await
+}
+
+#[tauri::command]
+pub async fn get_qr(state: State<'_, AppState>) -> Result<Option<String>, FlowError> {
+    let qr = AssetRepository::new(state.db()).get(asset_repo::QR_CODE).await?;
+    Ok(qr.filter(|data| !data.is_empty()))
 }
 
 #[tauri::command]
-pub async fn get_qr(state: State<'_, AppState>) -> Result<Option<String>, String> {
-    use std::fs;
-    let qr_path = state.app_data_dir.join("qr_code.txt");
-    if qr_path.exists() {
-        let content = fs::read_to_string(&qr_path).unwrap_or_default();
-        if content.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(content))
+pub async fn delete_qr(state: State<'_, AppState>) -> Result<(), FlowError> {
+    AssetRepository::new(state.db()).delete(asset_repo::QR_CODE).await
+}
+
+/// Moves the logo and QR images kept in `logo.txt` and `qr_code.txt` by earlier versions into
+/// the database. An image already in the database wins over the file.
+pub async fn import_legacy_assets(db: &DbPool, app_data_dir: &std::path::Path) -> Result<(), FlowError> {
+    let repo = AssetRepository::new(db.clone());
+    for (file, name) in [("logo.txt", asset_repo::LOGO), ("qr_code.txt", asset_repo::QR_CODE)] {
+        let path = app_data_dir.join(file);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if !content.is_empty() && repo.get(name).await?.is_none() {
+            repo.save(name, &content).await?;
         }
-    } else {
-        Ok(None)
+        std::fs::remove_file(&path)?;
     }
+    Ok(())
+}
+
+/// SVG of the UPI QR the PDF will carry for this UPI ID and amount; `None` for an invalid UPI ID.
+#[tauri::command]
+pub async fn preview_upi_qr(upi_id: String, payee: String, amount: f64, note: String) -> Result<Option<String>, FlowError> {
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    let amount = Decimal::from_str(&amount.to_string()).unwrap_or_default();
+    Ok(flow_pdf::payment::upi_qr_svg(&upi_id, &payee, amount, &note))
+}
+
+// ─── Bank Account Commands ─────────────────────────────────────
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BankAccountRequest {
+    /// Absent when creating a new account.
+    pub id: Option<String>,
+    pub label: String,
+    pub account_holder: String,
+    #[serde(default)]
+    pub bank_name: String,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub account_number: Option<String>,
+    #[serde(default)]
+    pub iban: Option<String>,
+    #[serde(default)]
+    pub swift_bic: Option<String>,
+    #[serde(default)]
+    pub ifsc_code: Option<String>,
+    #[serde(default)]
+    pub routing_number: Option<String>,
+    #[serde(default)]
+    pub upi_id: Option<String>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[tauri::command]
+pub async fn get_bank_accounts(state: State<'_, AppState>) -> Result<Vec<BankAccount>, FlowError> {
+    let repo = BankAccountRepository::new(state.db());
+    repo.list_all().await
+}
+
+/// Validates and stores the account, returning it as saved.
+#[tauri::command]
+pub async fn save_bank_account(state: State<'_, AppState>, request: BankAccountRequest) -> Result<BankAccount, FlowError> {
+    use flow_core::bank::normalize_iban;
+    use std::str::FromStr;
+
+    let repo = BankAccountRepository::new(state.db());
+    let existing = match &request.id {
+        Some(id) => repo.get_by_id(id).await?,
+        None => None,
+    };
+    let now = chrono::Utc::now();
+    let text = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let account = BankAccount {
+        id: existing.as_ref().map(|a| a.id).unwrap_or_else(uuid::Uuid::new_v4),
+        label: request.label.trim().to_string(),
+        account_holder: request.account_holder.trim().to_string(),
+        bank_name: request.bank_name.trim().to_string(),
+        branch: text(request.branch),
+        account_number: text(request.account_number),
+        iban: text(request.iban).map(|iban| normalize_iban(&iban)),
+        swift_bic: text(request.swift_bic).map(|bic| bic.to_ascii_uppercase()),
+        ifsc_code: text(request.ifsc_code).map(|ifsc| ifsc.to_ascii_uppercase()),
+        routing_number: text(request.routing_number),
+        upi_id: text(request.upi_id),
+        currency: text(request.currency).map(|c| flow_core::types::Currency::from_str(&c).unwrap_or_default()),
+        is_default: request.is_default,
+        created_at: existing.as_ref().map(|a| a.created_at).unwrap_or(now),
+        updated_at: now,
+    };
+    account.validate()?;
+
+    repo.save(&account).await?;
+    Ok(account)
 }
 
 #[tauri::command]
-pub async fn delete_qr(state: State<'_, AppState>) -> Result<(), String> {
-    use std::fs;
-    let qr_path = state.app_data_dir.join("qr_code.txt");
-    if qr_path.exists() {
-        fs::remove_file(&qr_path).map_err(|e| e.to_string())?;
+pub async fn delete_bank_account(state: State<'_, AppState>, id: String) -> Result<(), FlowError> {
+    let repo = BankAccountRepository::new(state.db());
+    repo.delete(&id).await
+}
+
+/// Moves the single account kept in `bank_details.json` by earlier versions into the database.
+/// The file is deleted once the account is saved, and left in place if that fails.
+pub async fn import_legacy_bank_details(db: &DbPool, app_data_dir: &std::path::Path) -> Result<(), FlowError> {
+    let bank_path = app_data_dir.join("bank_details.json");
+    let Ok(content) = std::fs::read_to_string(&bank_path) else {
+        return Ok(());
+    };
+
+    let repo = BankAccountRepository::new(db.clone());
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap_or_default();
+    let field = |key: &str| json.get(key).and_then(|v| v.as_str()).map(str::trim).filter(|v| !v.is_empty()).map(String::from);
+    let has_details = ["accountNumber", "iban", "upiId"].iter().any(|key| field(key).is_some());
+    if has_details && repo.list_all().await?.is_empty() {
+        let now = chrono::Utc::now();
+        let account = BankAccount {
+            id: uuid::Uuid::new_v4(),
+            label: "Primary account".to_string(),
+            account_holder: field("accountHolder").unwrap_or_default(),
+            bank_name: field("bankName").unwrap_or_default(),
+            branch: field("branch"),
+            account_number: field("accountNumber"),
+            iban: field("iban").map(|iban| flow_core::bank::normalize_iban(&iban)),
+            swift_bic: field("bic"),
+            ifsc_code: field("ifscCode"),
+            routing_number: None,
+            upi_id: field("upiId"),
+            currency: None,
+            is_default: true,
+            created_at: now,
+            updated_at: now,
+        };
+        // Imported as-is: details that fail validation are flagged when the account is next edited.
+        repo.save(&account).await?;
     }
-    Ok(())
+
+    Ok(std::fs::remove_file(&bank_path)?)
 }
 
-// ─── Bank Details Commands ─────────────────────────────────────
+// ─── Custom Field Commands ─────────────────────────────────────
+
+use flow_core::models::CustomFieldDefinition;
+use flow_core::types::{CustomFieldEntity, CustomFieldType};
+use flow_db::repositories::CustomFieldRepository;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomFieldRequest {
+    /// Absent when creating a new field.
+    pub id: Option<String>,
+    pub name: String,
+    pub field_type: String,
+    pub entity: String,
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub default_value: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+}
 
 #[tauri::command]
-pub async fn save_bank_details(state: State<'_, AppState>, json_data: String) -> Result<(), String> {
-    use std::fs;
-    let bank_path = state.app_data_dir.join("bank_details.json");
-    fs::write(&bank_path, json_data).map_err(|e| e.to_string())
+pub async fn get_custom_fields(state: State<'_, AppState>, entity: Option<String>) -> Result<Vec<CustomFieldDefinition>, FlowError> {
+    use std::str::FromStr;
+
+    let entity = entity.map(|e| CustomFieldEntity::from_str(&e)).transpose().map_err(FlowError::Validation)?;
+    let repo = CustomFieldRepository::new(state.db());
+    repo.list_definitions(entity.as_ref()).await
 }
 
+/// Validates and stores the field definition, returning it as saved.
 #[tauri::command]
-pub async fn get_bank_details(state: State<'_, AppState>) -> Result<Option<String>, String> {
-    use std::fs;
-    let bank_path = state.app_data_dir.join("bank_details.json");
-    if bank_path.exists() {
-        let content = fs::read_to_string(&bank_path).unwrap_or_default();
-        if content.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(content))
-        }
-    } else {
-        Ok(None)
+pub async fn save_custom_field(state: State<'_, AppState>, request: CustomFieldRequest) -> Result<CustomFieldDefinition, FlowError> {
+    use std::str::FromStr;
+
+    let repo = CustomFieldRepository::new(state.db());
+    let existing = repo.list_definitions(None).await?;
+    let previous = existing.iter().find(|d| Some(d.id.to_string()) == request.id);
+    let now = chrono::Utc::now();
+    let mut definition = CustomFieldDefinition {
+        id: previous.map(|d| d.id).unwrap_or_else(uuid::Uuid::new_v4),
+        name: request.name.trim().to_string(),
+        field_type: CustomFieldType::from_str(&request.field_type).map_err(|e| FlowError::invalid_field("field_type", e))?,
+        entity: CustomFieldEntity::from_str(&request.entity).map_err(|e| FlowError::invalid_field("entity", e))?,
+        options: request.options.iter().map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect(),
+        required: request.required,
+        default_value: request.default_value.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()),
+        sort_order: request.sort_order,
+        created_at: previous.map(|d| d.created_at).unwrap_or(now),
+        updated_at: now,
+    };
+    definition.validate()?;
+    if let Some(default) = &definition.default_value {
+        definition.default_value = Some(definition.normalize_value(default)?);
+    }
+    if existing
+        .iter()
+        .any(|d| d.id != definition.id && d.entity == definition.entity && d.name.eq_ignore_ascii_case(&definition.name))
+    {
+        return Err(FlowError::invalid_field("name", format!("A {} field named \"{}\" already exists.", definition.entity, definition.name)));
     }
+
+    repo.save_definition(&definition).await?;
+    Ok(definition)
+}
+
+/// Deletes the field and every value recorded for it.
+#[tauri::command]
+pub async fn delete_custom_field(state: State<'_, AppState>, id: String) -> Result<(), FlowError> {
+    let repo = CustomFieldRepository::new(state.db());
+    repo.delete_definition(&id).await
+}
+
+/// Ids of the clients or invoices whose custom field values contain `query`.
+#[tauri::command]
+pub async fn search_custom_fields(state: State<'_, AppState>, entity: String, query: String) -> Result<Vec<String>, FlowError> {
+    use std::str::FromStr;
+
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let entity = CustomFieldEntity::from_str(&entity).map_err(FlowError::Validation)?;
+    let repo = CustomFieldRepository::new(state.db());
+    repo.search(&entity, &query).await
+}
+
+async fn resolve_custom_fields(
+    db: &DbPool,
+    entity: CustomFieldEntity,
+    input: &HashMap<String, String>,
+) -> Result<Vec<CustomFieldValue>, FlowError> {
+    let definitions = CustomFieldRepository::new(db.clone())
+        .list_definitions(Some(&entity))
+        .await?;
+    resolve_with(&definitions, entity, input)
+}
+
+/// Checks request values (keyed by field id) against the definitions for `entity`.
+fn resolve_with(
+    definitions: &[CustomFieldDefinition],
+    entity: CustomFieldEntity,
+    input: &HashMap<String, String>,
+) -> Result<Vec<CustomFieldValue>, FlowError> {
+    let input = input
+        .iter()
+        .map(|(id, value)| {
+            uuid::Uuid::parse_str(id)
+                .map(|id| (id, value.clone()))
+                .map_err(|_| FlowError::Validation(format!("Unknown custom field: {}", id)))
+        })
+        .collect::<Result<HashMap<_, _>, FlowError>>()?;
+    flow_core::custom_field::resolve_values(definitions, &entity, &input)
 }
 
 // ─── Settings / Business Profile Commands ──────────────────────
 
-use flow_core::models::BusinessProfile;
-use flow_db::repositories::BusinessProfileRepository;
+use flow_core::models::{BankAccount, BusinessProfile, CorruptRow};
+use flow_db::repositories::{BankAccountRepository, BusinessProfileRepository};
 
 #[tauri::command]
-pub async fn get_settings(state: State<'_, AppState>) -> Result<BusinessProfile, String> {
-    let repo = BusinessProfileRepository::new(state.db.clone());
-    repo.get_profile().await.map_err(|e| e.to_string())
+pub async fn get_settings(state: State<'_, AppState>) -> Result<BusinessProfile, FlowError> {
+    let repo = BusinessProfileRepository::new(state.db());
+    repo.get_profile().await
 }
 
 #[tauri::command]
 pub async fn save_settings(
     state: State<'_, AppState>,
     profile: BusinessProfile,
-) -> Result<(), String> {
-    let repo = BusinessProfileRepository::new(state.db.clone());
-    repo.update_profile(&profile).await.map_err(|e| e.to_string())
+) -> Result<(), FlowError> {
+    let repo = BusinessProfileRepository::new(state.db());
+    repo.update_profile(&profile).await
+}
+
+// ─── Database Check ────────────────────────────────────────────
+
+/// Scans the whole database and lists the rows the app cannot load; empty when all is well.
+#[tauri::command]
+pub async fn verify_database(state: State<'_, AppState>) -> Result<Vec<CorruptRow>, FlowError> {
+    flow_db::verify::verify_database(&state.db()).await
 }
 
 // ─── Reset Command ─────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn reset_database(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn reset_database(state: State<'_, AppState>) -> Result<(), FlowError> {
     use flow_db::repositories::BusinessProfileRepository;
-    use std::fs;
-
-    // 1. Delete all invoices and clients
-    sqlx::query("DELETE FROM invoice_items")
-        .execute(&state.db)
-        .await
-        .map_err(|e| format!("Failed to delete invoice items: {}", e))?;
-
-    sqlx::query("DELETE FROM invoices")
-        .execute(&state.db)
-        .await
-        .map_err(|e| format!("Failed to delete invoices: {}", e))?;
-
-    sqlx::query("DELETE FROM clients")
-        .execute(&state.db)
-        .await
-        .map_err(|e| format!("Failed to delete clients: {}", e))?;
-
-    // 2. Reset the business profile to the default "My Company" state without deleting the ID wrapper
-    let profile_repo = BusinessProfileRepository::new(state.db.clone());
-    let mut profile = profile_repo.get_profile().await.map_err(|e| e.to_string())?;
-    
+
+    // Read (or create) the profile before the unit of work starts writing
+    let profile_repo = BusinessProfileRepository::new(state.db());
+    let mut profile = profile_repo.get_profile().await?;
+
     // Wipe profile fields back to the "first-run" state
     profile.name = "My Company".to_string();
     profile.email = None;
@@ -450,71 +1003,324 @@ pub async fn reset_database(state: State<'_, AppState>) -> Result<(), String> {
     profile.logo_path = None;
     profile.default_currency = flow_core::types::Currency::USD;
     profile.default_payment_terms = flow_core::types::PaymentTerms::Net30;
-    
-    profile_repo.update_profile(&profile).await.map_err(|e| e.to_string())?;
 
-    // 3. Clear stored files
-    let logo_path = state.app_data_dir.join("logo.txt");
-    if logo_path.exists() {
-        fs::remove_file(&logo_path).ok();
+    // Delete all data, logo and QR included, and reset the business profile to the default
+    // "My Company" state without deleting the ID wrapper, together or not at all
+    let mut uow = UnitOfWork::begin(&state.db()).await?;
+    uow.delete_all_data().await?;
+    uow.update_profile(&profile).await?;
+    uow.commit().await
+}
+
+// ─── Encryption Commands ───────────────────────────────────────
+
+use crate::encryption::{self, KeySource};
+
+/// Opens the database with `key`, imports what earlier versions kept in side files and makes
+/// [`AppLock`] and then [`AppState`] available to the commands.
+pub async fn open_database(app: &tauri::AppHandle, key: Option<&DatabaseKey>) -> Result<(), FlowError> {
+    use tauri::Manager;
+
+    let app_data_dir = app.path().app_data_dir().map_err(|e| FlowError::Internal(e.to_string()))?;
+    let db_path = app_data_dir.join("invoiceflow.db");
+    let pool = flow_db::database::init_db(&db_path, key).await?;
+
+    let mut imported = true;
+    if let Err(e) = import_legacy_bank_details(&pool, &app_data_dir).await {
+        eprintln!("❌ Failed to import bank details: {}", e);
+        imported = false;
     }
-    
-    let qr_path = state.app_data_dir.join("qr_code.txt");
-    if qr_path.exists() {
-        fs::remove_file(&qr_path).ok();
+    if let Err(e) = import_legacy_assets(&pool, &app_data_dir).await {
+        eprintln!("❌ Failed to import logo and QR code: {}", e);
+        imported = false;
     }
-
-    let bank_path = state.app_data_dir.join("bank_details.json");
-    if bank_path.exists() {
-        fs::remove_file(&bank_path).ok();
+    // Bank details and images are kept in the database only; the files go once their contents are in
+    if imported {
+        encryption::remove_plaintext_side_files(&app_data_dir)?;
     }
 
+    // The lock goes in first, so a locked app never exposes the state to a command
+    let lock_settings = AppLockRepository::new(pool.clone()).get().await?;
+    app.manage(AppLock::new(&lock_settings));
+    app.manage(AppState { db: RwLock::new((pool, key.cloned())), db_path: db_path.clone(), app_data_dir });
+    println!("✅ Database initialized at {:?}", db_path);
     Ok(())
 }
 
-// ─── Export Command ──────────────────────────────────────────
+#[derive(Debug, Serialize)]
+pub struct EncryptionStatus {
+    pub key_source: KeySource,
+    /// Whether the database is open; `false` while a passphrase is awaited.
+    pub unlocked: bool,
+}
 
 #[tauri::command]
-pub async fn export_data(state: State<'_, AppState>, path: String) -> Result<(), String> {
-    use flow_db::repositories::{BusinessProfileRepository, ClientRepository, InvoiceRepository};
-    use serde_json::json;
-    use std::fs;
+pub async fn get_encryption_status(app: tauri::AppHandle) -> Result<EncryptionStatus, FlowError> {
+    use tauri::Manager;
 
-    // Fetch clients
-    let client_repo = ClientRepository::new(state.db.clone());
-    let clients = client_repo.list_all().await.map_err(|e| e.to_string())?;
+    let app_data_dir = app.path().app_data_dir().map_err(|e| FlowError::Internal(e.to_string()))?;
+    Ok(EncryptionStatus {
+        key_source: encryption::load_key_source(&app_data_dir),
+        unlocked: app.try_state::<AppState>().is_some(),
+    })
+}
 
-    // Fetch invoices
-    let invoice_repo = InvoiceRepository::new(state.db.clone());
-    let invoices = invoice_repo.list_all().await.map_err(|e| e.to_string())?;
+/// Opens a passphrase-encrypted database; a no-op once it is open.
+#[tauri::command]
+pub async fn unlock_database(app: tauri::AppHandle, passphrase: String) -> Result<(), FlowError> {
+    use tauri::Manager;
 
-    // Fetch profile
-    let profile_repo = BusinessProfileRepository::new(state.db.clone());
-    let profile = profile_repo.get_profile().await.map_err(|e| e.to_string())?;
+    if app.try_state::<AppState>().is_some() {
+        return Ok(());
+    }
+    match open_database(&app, Some(&DatabaseKey::Passphrase(passphrase))).await {
+        Err(FlowError::Validation(message)) => Err(FlowError::invalid_field("passphrase", message)),
+        result => result,
+    }
+}
 
-    // Fetch bank details
-    let bank_path = state.app_data_dir.join("bank_details.json");
-    let bank_details = if bank_path.exists() {
-        fs::read_to_string(&bank_path).unwrap_or_else(|_| "{}".to_string())
-    } else {
-        "{}".to_string()
+/// Re-encrypts the database for `key_source`: a new random key in the OS keyring, the given
+/// passphrase, or no encryption at all.
+#[tauri::command]
+pub async fn change_database_key(state: State<'_, AppState>, key_source: KeySource, passphrase: Option<String>) -> Result<(), FlowError> {
+    let new_key = match key_source {
+        KeySource::None => None,
+        KeySource::Keyring => Some(DatabaseKey::generate()?),
+        KeySource::Passphrase => {
+            let passphrase = passphrase.unwrap_or_default();
+            if passphrase.chars().count() < 8 {
+                return Err(FlowError::invalid_field("passphrase", "Passphrase must be at least 8 characters"));
+            }
+            Some(DatabaseKey::Passphrase(passphrase))
+        }
     };
-    let bank_json: serde_json::Value = serde_json::from_str(&bank_details).unwrap_or_else(|_| json!({}));
 
-    // Construct export payload
-    let export_payload = json!({
-        "version": "1.0",
-        "exported_at": chrono::Utc::now().to_rfc3339(),
-        "profile": profile,
-        "bank_details": bank_json,
-        "clients": clients,
-        "invoices": invoices,
-    });
+    // A new keyring key is stored before the file is rewritten with it, so it cannot be lost
+    // in between; the old one is put back if the rewrite fails
+    let current_source = encryption::load_key_source(&state.app_data_dir);
+    let old_keyring_key = if current_source == KeySource::Keyring { encryption::keyring_key()? } else { None };
+    if key_source == KeySource::Keyring {
+        encryption::set_keyring_key(new_key.as_ref())?;
+    }
+
+    match flow_db::database::change_key(&state.db(), &state.db_path, new_key.as_ref()).await {
+        Ok(pool) => state.replace_db(pool, new_key.clone()),
+        Err(e) => {
+            if key_source == KeySource::Keyring {
+                encryption::set_keyring_key(old_keyring_key.as_ref()).ok();
+            }
+            return Err(e);
+        }
+    }
+    encryption::save_key_source(&state.app_data_dir, key_source)?;
+
+    if current_source == KeySource::Keyring && key_source != KeySource::Keyring {
+        encryption::set_keyring_key(None)?;
+    }
+    Ok(())
+}
+
+// ─── App Lock Commands ─────────────────────────────────────────
+
+use crate::app_lock::{AppLock, LockStatus};
+use flow_db::repositories::AppLockRepository;
 
-    let json_string = serde_json::to_string_pretty(&export_payload).map_err(|e| e.to_string())?;
+/// Why the database could not be opened at start-up.
+pub struct StartupError(pub String);
 
-    // Write to selected path
-    fs::write(&path, json_string).map_err(|e| format!("Failed to write export file: {}", e))?;
+#[tauri::command]
+pub async fn get_lock_status(app: tauri::AppHandle) -> Result<LockStatus, FlowError> {
+    use tauri::Manager;
+
+    match (app.try_state::<AppLock>(), app.try_state::<StartupError>()) {
+        (Some(lock), _) => Ok(lock.status()),
+        (None, Some(error)) => Err(FlowError::Internal(format!("The database could not be opened. {}", error.0))),
+        // Retried by the frontend until start-up has read the lock settings
+        (None, None) => Err(FlowError::Busy("The database is still opening".to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn unlock_app(state: State<'_, AppState>, lock: State<'_, AppLock>, passphrase: String) -> Result<(), FlowError> {
+    if !AppLockRepository::new(state.db()).verify(&passphrase).await? {
+        return Err(FlowError::invalid_field("passphrase", "Wrong passphrase"));
+    }
+    lock.unlock();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn lock_app(lock: State<'_, AppLock>) -> Result<(), FlowError> {
+    lock.lock();
+    Ok(())
+}
+
+/// Tells the auto-lock the user is still there; like every command that gets through the
+/// lock, it resets the inactivity timer.
+#[tauri::command]
+pub async fn report_activity() -> Result<(), FlowError> {
+    Ok(())
+}
+
+/// Sets, changes or, for no `new_passphrase`, removes the lock passphrase. Once one is set,
+/// the current one is needed to change it.
+#[tauri::command]
+pub async fn set_app_passphrase(
+    state: State<'_, AppState>,
+    lock: State<'_, AppLock>,
+    current_passphrase: Option<String>,
+    new_passphrase: Option<String>,
+) -> Result<LockStatus, FlowError> {
+    let repo = AppLockRepository::new(state.db());
+    if !repo.verify(current_passphrase.as_deref().unwrap_or_default()).await? {
+        return Err(FlowError::invalid_field("current_passphrase", "Wrong passphrase"));
+    }
+    repo.set_passphrase(new_passphrase.as_deref()).await?;
+    lock.apply(&repo.get().await?);
+    Ok(lock.status())
+}
+
+/// Sets the inactivity timeout in minutes; `None` locks only at start-up.
+#[tauri::command]
+pub async fn set_auto_lock(state: State<'_, AppState>, lock: State<'_, AppLock>, minutes: Option<u32>) -> Result<LockStatus, FlowError> {
+    let repo = AppLockRepository::new(state.db());
+    repo.set_auto_lock(minutes).await?;
+    lock.apply(&repo.get().await?);
+    Ok(lock.status())
+}
+
+// ─── Backup Commands ───────────────────────────────────────────
+
+use crate::backups::{self, BackupConfig};
+use flow_db::backup::{BackupInfo, BackupManifest};
+
+#[tauri::command]
+pub async fn get_backup_config(state: State<'_, AppState>) -> Result<BackupConfig, FlowError> {
+    Ok(backups::load_config(&state.app_data_dir))
+}
+
+#[tauri::command]
+pub async fn save_backup_config(state: State<'_, AppState>, config: BackupConfig) -> Result<BackupConfig, FlowError> {
+    if config.interval_hours == 0 {
+        return Err(FlowError::invalid_field("interval_hours", "Back up at least every so many hours, not 0"));
+    }
+    let config = BackupConfig { directory: config.directory.filter(|dir| !dir.as_os_str().is_empty()), ..config };
+    backups::save_config(&state.app_data_dir, &config)?;
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn list_backups(state: State<'_, AppState>) -> Result<Vec<BackupInfo>, FlowError> {
+    let config = backups::load_config(&state.app_data_dir);
+    flow_db::backup::list_backups(&backups::backup_dir(&state.app_data_dir, &config))
+}
+
+/// Backs up now, then prunes old backups by the configured retention.
+#[tauri::command]
+pub async fn create_backup(state: State<'_, AppState>) -> Result<BackupInfo, FlowError> {
+    backups::back_up(&state).await
+}
 
+#[tauri::command]
+pub async fn verify_backup(state: State<'_, AppState>, path: String) -> Result<BackupManifest, FlowError> {
+    flow_db::backup::verify_backup(&state.db(), std::path::Path::new(&path)).await
+}
+
+/// Replaces the live database with a verified backup. The app lock takes over the settings
+/// stored in the restored database.
+#[tauri::command]
+pub async fn restore_backup(state: State<'_, AppState>, lock: State<'_, AppLock>, path: String) -> Result<(), FlowError> {
+    let key = state.db_key();
+    let pool = flow_db::backup::restore_backup(&state.db(), &state.db_path, std::path::Path::new(&path), key.as_ref()).await?;
+    state.replace_db(pool.clone(), key);
+    lock.apply(&AppLockRepository::new(pool).get().await?);
     Ok(())
 }
+
+// ─── Export & Import Commands ────────────────────────────────
+
+use flow_db::export::{ConflictPolicy, ImportReport};
+
+#[tauri::command]
+pub async fn export_data(state: State<'_, AppState>, path: String) -> Result<(), FlowError> {
+    let archive = flow_db::export::export_data(&state.db()).await?;
+    flow_db::export::write_archive(&archive, std::path::Path::new(&path))
+}
+
+/// Imports an export archive, or with `dry_run` only reports what importing would change.
+#[tauri::command]
+pub async fn import_data(state: State<'_, AppState>, path: String, policy: ConflictPolicy, dry_run: bool) -> Result<ImportReport, FlowError> {
+    let archive = flow_db::export::read_archive(std::path::Path::new(&path))?;
+    flow_db::export::import_data(&state.db(), &archive, policy, dry_run).await
+}
+
+// ─── Spreadsheet Import Commands ─────────────────────────────
+
+use flow_db::repositories::{ImportPreset, ImportPresetRepository};
+use flow_db::spreadsheet::{ColumnMapping, ImportField, ImportPlan, ImportTarget};
+
+#[derive(Debug, Serialize)]
+pub struct SpreadsheetField {
+    pub field: ImportField,
+    pub label: &'static str,
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpreadsheetInfo {
+    pub headers: Vec<String>,
+    pub row_count: usize,
+    /// The first rows, to help pick the columns.
+    pub sample: Vec<Vec<String>>,
+    pub fields: Vec<SpreadsheetField>,
+    /// Columns whose header names a field.
+    pub suggested_mapping: ColumnMapping,
+}
+
+/// Reads the headers of a CSV or Excel file and suggests how to map them for `target`.
+#[tauri::command]
+pub async fn read_spreadsheet(path: String, target: ImportTarget) -> Result<SpreadsheetInfo, FlowError> {
+    let sheet = flow_db::spreadsheet::read_sheet(std::path::Path::new(&path))?;
+    let required = ImportField::required(target);
+    Ok(SpreadsheetInfo {
+        suggested_mapping: flow_db::spreadsheet::guess_mapping(&sheet.headers, target),
+        row_count: sheet.rows.len(),
+        sample: sheet.rows.iter().take(5).map(|row| row.cells.clone()).collect(),
+        fields: ImportField::fields_for(target)
+            .map(|field| SpreadsheetField { field, label: field.label(), required: required.contains(&field) })
+            .collect(),
+        headers: sheet.headers,
+    })
+}
+
+/// What importing the spreadsheet with `mapping` would create, and the rows it would reject.
+#[tauri::command]
+pub async fn preview_spreadsheet_import(state: State<'_, AppState>, path: String, mapping: ColumnMapping) -> Result<ImportPlan, FlowError> {
+    let sheet = flow_db::spreadsheet::read_sheet(std::path::Path::new(&path))?;
+    flow_db::spreadsheet::plan_import(&state.db(), &sheet, &mapping).await
+}
+
+/// Imports the spreadsheet; refused unless every row is valid.
+#[tauri::command]
+pub async fn import_spreadsheet(state: State<'_, AppState>, path: String, mapping: ColumnMapping) -> Result<ImportPlan, FlowError> {
+    let sheet = flow_db::spreadsheet::read_sheet(std::path::Path::new(&path))?;
+    let plan = flow_db::spreadsheet::plan_import(&state.db(), &sheet, &mapping).await?;
+    flow_db::spreadsheet::commit_import(&state.db(), &plan).await?;
+    Ok(plan)
+}
+
+#[tauri::command]
+pub async fn list_import_presets(state: State<'_, AppState>) -> Result<Vec<ImportPreset>, FlowError> {
+    ImportPresetRepository::new(state.db()).list_all().await
+}
+
+/// Saves `mapping` under `name`, replacing a preset of the same name.
+#[tauri::command]
+pub async fn save_import_preset(state: State<'_, AppState>, name: String, mapping: ColumnMapping) -> Result<ImportPreset, FlowError> {
+    ImportPresetRepository::new(state.db()).save(&name, &mapping).await
+}
+
+#[tauri::command]
+pub async fn delete_import_preset(state: State<'_, AppState>, id: String) -> Result<(), FlowError> {
+    ImportPresetRepository::new(state.db()).delete(&id).await
+}
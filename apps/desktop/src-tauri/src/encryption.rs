@@ -0,0 +1,84 @@
+//! Where the database key comes from, and the OS keyring entry holding it.
+//!
+//! The choice is kept in `encryption.json` beside the database, since it has to be read
+//! before the database can be opened. It never contains the key itself.
+
+use flow_core::error::{FlowError, FlowResult};
+use flow_db::database::DatabaseKey;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const CONFIG_FILE: &str = "encryption.json";
+const KEYRING_SERVICE: &str = "InvoiceFlow";
+const KEYRING_USER: &str = "database-key";
+
+/// Files earlier versions kept in plaintext beside the database, left behind after their
+/// contents were imported. An unimported `bank_details.json` is not among them.
+const PLAINTEXT_SIDE_FILES: &[&str] = &["bank_details.json.imported", "logo.txt", "qr_code.txt"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Unencrypted.
+    #[default]
+    None,
+    /// A random key kept in the OS keyring; opens without asking.
+    Keyring,
+    /// Typed by the user at every start.
+    Passphrase,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct EncryptionConfig {
+    key_source: KeySource,
+}
+
+pub fn load_key_source(app_data_dir: &Path) -> KeySource {
+    std::fs::read_to_string(app_data_dir.join(CONFIG_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<EncryptionConfig>(&content).ok())
+        .map(|config| config.key_source)
+        .unwrap_or_default()
+}
+
+pub fn save_key_source(app_data_dir: &Path, key_source: KeySource) -> FlowResult<()> {
+    let content = serde_json::to_string_pretty(&EncryptionConfig { key_source })?;
+    Ok(std::fs::write(app_data_dir.join(CONFIG_FILE), content)?)
+}
+
+fn keyring_entry() -> FlowResult<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| FlowError::Internal(format!("OS keyring unavailable: {}", e)))
+}
+
+/// The key stored in the OS keyring, if there is one.
+pub fn keyring_key() -> FlowResult<Option<DatabaseKey>> {
+    match keyring_entry()?.get_password() {
+        Ok(hex) => DatabaseKey::from_hex(&hex).map(Some),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(FlowError::Internal(format!("Could not read the database key from the OS keyring: {}", e))),
+    }
+}
+
+/// Stores a raw key in the OS keyring, or removes the entry for `None`.
+pub fn set_keyring_key(key: Option<&DatabaseKey>) -> FlowResult<()> {
+    let entry = keyring_entry()?;
+    let result = match key.and_then(DatabaseKey::to_hex) {
+        Some(hex) => entry.set_password(&hex),
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            other => other,
+        },
+    };
+    result.map_err(|e| FlowError::Internal(format!("Could not update the database key in the OS keyring: {}", e)))
+}
+
+/// Deletes the plaintext side files; only called once the database holds their contents.
+pub fn remove_plaintext_side_files(app_data_dir: &Path) -> FlowResult<()> {
+    for name in PLAINTEXT_SIDE_FILES {
+        match std::fs::remove_file(app_data_dir.join(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
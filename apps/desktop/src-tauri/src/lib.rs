@@ -1,6 +1,12 @@
 use tauri::Manager;
 
+mod app_lock;
+mod backups;
 mod commands;
+mod encryption;
+
+use encryption::KeySource;
+use flow_core::error::FlowError;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -17,49 +23,96 @@ pub fn run() {
                     .path()
                     .app_data_dir()
                     .expect("Failed to get app data dir");
-                let db_path = app_dir.join("invoiceflow.db");
 
-                match flow_db::database::init_db(&db_path).await {
-                    Ok(pool) => {
-                        app_handle.manage(commands::AppState { 
-                            db: pool,
-                            db_path: db_path.clone(),
-                            app_data_dir: app_dir,
-                        });
-                        println!("✅ Database initialized at {:?}", db_path);
-                    }
-                    Err(e) => {
-                        eprintln!("❌ Failed to initialize database: {}", e);
-                    }
+                let key = match encryption::load_key_source(&app_dir) {
+                    KeySource::None => Ok(None),
+                    KeySource::Keyring => encryption::keyring_key().and_then(|key| {
+                        key.map(Some).ok_or_else(|| FlowError::Internal("The OS keyring has no key for the encrypted database".to_string()))
+                    }),
+                    // Opened by `unlock_database` once the user typed it
+                    KeySource::Passphrase => return,
+                };
+
+                // Reported by `get_lock_status`, which the frontend polls until the database is open
+                let opened = match key {
+                    Ok(key) => commands::open_database(&app_handle, key.as_ref()).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = opened {
+                    app_handle.manage(commands::StartupError(e.to_string()));
                 }
             });
+            tauri::async_runtime::spawn(backups::run_scheduler(app.handle().clone()));
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(app_lock::guard(tauri::generate_handler![
             commands::get_clients,
+            commands::get_client,
+            commands::query_clients,
             commands::create_client,
             commands::delete_client,
+            commands::set_client_archived,
             commands::update_client,
+            commands::find_duplicate_clients,
+            commands::merge_clients,
+            commands::get_audit_log,
             commands::get_invoices,
+            commands::query_invoices,
             commands::create_invoice,
             commands::delete_invoice,
+            commands::set_invoice_archived,
             commands::update_invoice_status,
             commands::get_analytics,
             commands::generate_pdf,
             commands::open_pdf,
+            commands::detect_pdf_browser,
+            commands::export_ubl,
+            commands::validate_einvoice,
+            commands::export_gst_einvoice,
+            commands::validate_gst_einvoice,
+            commands::record_gst_registration,
             commands::save_logo,
             commands::get_logo,
             commands::delete_logo,
             commands::save_qr,
+            commands::preview_upi_qr,
             commands::get_qr,
             commands::delete_qr,
-            commands::save_bank_details,
-            commands::get_bank_details,
+            commands::get_bank_accounts,
+            commands::save_bank_account,
+            commands::delete_bank_account,
+            commands::get_custom_fields,
+            commands::save_custom_field,
+            commands::delete_custom_field,
+            commands::search_custom_fields,
             commands::get_settings,
             commands::save_settings,
+            commands::verify_database,
             commands::reset_database,
             commands::export_data,
-        ])
+            commands::import_data,
+            commands::read_spreadsheet,
+            commands::preview_spreadsheet_import,
+            commands::import_spreadsheet,
+            commands::list_import_presets,
+            commands::save_import_preset,
+            commands::delete_import_preset,
+            commands::get_encryption_status,
+            commands::unlock_database,
+            commands::change_database_key,
+            commands::get_lock_status,
+            commands::unlock_app,
+            commands::lock_app,
+            commands::report_activity,
+            commands::set_app_passphrase,
+            commands::set_auto_lock,
+            commands::get_backup_config,
+            commands::save_backup_config,
+            commands::list_backups,
+            commands::create_backup,
+            commands::verify_backup,
+            commands::restore_backup,
+        ]))
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
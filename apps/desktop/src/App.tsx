@@ -10,20 +10,74 @@ import { useEffect, useState } from "react";
 import { useSettingsStore } from "./store/settingsStore";
 import { OnboardingWizard } from "./components/OnboardingWizard";
 import { UpdaterNotification } from "./components/ui/UpdaterNotification";
+import { UnlockScreen } from "./components/UnlockScreen";
+import { getEncryptionStatus, unlockDatabase, unlockApp, reportActivity, APP_LOCKED_EVENT } from "./lib/api";
+
+/** Activity is reported at most this often; the backend only needs it well within the timeout. */
+const ACTIVITY_REPORT_INTERVAL_MS = 30_000;
+/** How often an unlocked app asks whether the auto-lock has engaged. */
+const LOCK_POLL_INTERVAL_MS = 15_000;
+const ACTIVITY_EVENTS = ["mousemove", "mousedown", "keydown", "wheel", "touchstart"] as const;
 
 function App() {
     const fetchSettings = useSettingsStore(state => state.fetchSettings);
-    const fetchBankDetails = useSettingsStore(state => state.fetchBankDetails);
+    const fetchBankAccounts = useSettingsStore(state => state.fetchBankAccounts);
+    const fetchLockStatus = useSettingsStore(state => state.fetchLockStatus);
+    const setLockStatus = useSettingsStore(state => state.setLockStatus);
+    const lockStatus = useSettingsStore(state => state.lockStatus);
+    const startupError = useSettingsStore(state => state.startupError);
 
     // Global Settings
     const profile = useSettingsStore(state => state.profile);
     const isLoading = useSettingsStore(state => state.isLoading);
     const [showWizard, setShowWizard] = useState(false);
+    // `null` until known; a passphrase-encrypted database stays closed until unlocked
+    const [isDatabaseLocked, setIsDatabaseLocked] = useState<boolean | null>(null);
+    const isAppLocked = lockStatus?.locked ?? false;
+    const isUnlocked = lockStatus !== null && !lockStatus.locked;
+
+    useEffect(() => {
+        getEncryptionStatus()
+            .then(status => setIsDatabaseLocked(status.key_source === 'passphrase' && !status.unlocked))
+            .catch(() => setIsDatabaseLocked(false));
+    }, []);
+
+    // The app lock is only known once the database is open, which may take a moment at start-up
+    const hasLockStatus = lockStatus !== null;
+    useEffect(() => {
+        if (isDatabaseLocked !== false || hasLockStatus || startupError) return;
+        fetchLockStatus();
+        const retry = setInterval(fetchLockStatus, 1000);
+        return () => clearInterval(retry);
+    }, [isDatabaseLocked, hasLockStatus, startupError, fetchLockStatus]);
 
     useEffect(() => {
+        if (!isUnlocked) return;
         fetchSettings();
-        fetchBankDetails();
-    }, [fetchSettings, fetchBankDetails]);
+        fetchBankAccounts();
+    }, [isUnlocked, fetchSettings, fetchBankAccounts]);
+
+    // Auto-lock: report user activity to the backend, and notice when it has locked the app
+    useEffect(() => {
+        if (!lockStatus?.enabled || !isUnlocked) return;
+
+        let lastReport = Date.now();
+        const onActivity = () => {
+            if (Date.now() - lastReport < ACTIVITY_REPORT_INTERVAL_MS) return;
+            lastReport = Date.now();
+            reportActivity().catch(() => { });
+        };
+        const onLocked = () => fetchLockStatus();
+        const poll = setInterval(fetchLockStatus, LOCK_POLL_INTERVAL_MS);
+
+        ACTIVITY_EVENTS.forEach(event => window.addEventListener(event, onActivity, { passive: true }));
+        window.addEventListener(APP_LOCKED_EVENT, onLocked);
+        return () => {
+            clearInterval(poll);
+            ACTIVITY_EVENTS.forEach(event => window.removeEventListener(event, onActivity));
+            window.removeEventListener(APP_LOCKED_EVENT, onLocked);
+        };
+    }, [lockStatus?.enabled, isUnlocked, fetchLockStatus]);
 
     // Check onboarding status
     useEffect(() => {
@@ -62,12 +116,41 @@ function App() {
         }
     }, [profile?.theme_preference]);
 
+    if (startupError) {
+        return (
+            <div className="fixed inset-0 z-[100] flex items-center justify-center bg-[var(--background)] px-6">
+                <div className="w-full max-w-md text-center">
+                    <h1 className="text-3xl font-black text-[var(--foreground)] mb-3 tracking-tight">InvoiceFlow could not start</h1>
+                    <p className="text-sm text-red-400 break-words">{startupError}</p>
+                </div>
+            </div>
+        );
+    }
+
+    if (isDatabaseLocked) {
+        return (
+            <UnlockScreen
+                unlock={unlockDatabase}
+                onUnlock={() => setIsDatabaseLocked(false)}
+                message="Enter the passphrase that encrypts your data."
+            />
+        );
+    }
+
     return (
         <BrowserRouter>
             {showWizard && (
                 <OnboardingWizard onComplete={() => setShowWizard(false)} />
             )}
             <UpdaterNotification />
+            {/* Drawn over the app rather than replacing it, so unsaved work survives a lock */}
+            {isAppLocked && lockStatus && (
+                <UnlockScreen
+                    unlock={unlockApp}
+                    onUnlock={() => setLockStatus({ ...lockStatus, locked: false })}
+                    message="Enter your passphrase or PIN to continue."
+                />
+            )}
             <div style={{ display: 'flex', minHeight: '100vh', width: '100%', position: 'relative' }}>
                 <div className="aurora-bg" />
                 <Routes>
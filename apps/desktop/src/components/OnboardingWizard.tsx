@@ -10,7 +10,7 @@ import {
     ArrowLeft,
     Loader2
 } from "lucide-react";
-import { useSettingsStore, BusinessProfile, BankDetails } from "../store/settingsStore";
+import { useSettingsStore, BusinessProfile, BankAccount, emptyBankAccount } from "../store/settingsStore";
 
 interface OnboardingWizardProps {
     onComplete: () => void;
@@ -35,17 +35,10 @@ export function OnboardingWizard({ onComplete }: OnboardingWizardProps) {
         default_payment_terms: "Net30"
     });
 
-    const [bankData, setBankData] = useState<BankDetails>({
-        accountHolder: "",
-        accountNumber: "",
-        ifscCode: "",
-        bankName: "",
-        branch: "",
-        upiId: ""
-    });
+    const [bankData, setBankData] = useState<BankAccount>({ ...emptyBankAccount(), is_default: true });
 
     const updateSettings = useSettingsStore(state => state.updateSettings);
-    const updateBankDetails = useSettingsStore(state => state.updateBankDetails);
+    const saveBankAccount = useSettingsStore(state => state.saveBankAccount);
     const profile = useSettingsStore(state => state.profile);
 
     const handleNext = () => setStep(s => s + 1);
@@ -74,7 +67,15 @@ export function OnboardingWizard({ onComplete }: OnboardingWizardProps) {
                 };
                 await updateSettings(completeProfile);
             }
-            await updateBankDetails(bankData);
+            if (bankData.account_holder.trim() && bankData.account_number?.trim()) {
+                try {
+                    await saveBankAccount({ ...bankData, label: bankData.bank_name || "Primary account" });
+                } catch (error) {
+                    alert(`Bank account not saved: ${error}`);
+                    setIsSaving(false);
+                    return;
+                }
+            }
 
             // Advance to success step
             setStep(4);
@@ -354,8 +355,8 @@ export function OnboardingWizard({ onComplete }: OnboardingWizardProps) {
                                     <input
                                         type="text"
                                         placeholder="John Doe"
-                                        value={bankData.accountHolder}
-                                        onChange={e => setBankData({ ...bankData, accountHolder: e.target.value })}
+                                        value={bankData.account_holder}
+                                        onChange={e => setBankData({ ...bankData, account_holder: e.target.value })}
                                         className="w-full bg-[var(--background)] border border-[var(--premium-border)] rounded-xl px-4 py-3 text-[var(--foreground)] focus:border-[var(--primary)] focus:outline-none transition-colors"
                                     />
                                 </div>
@@ -365,18 +366,18 @@ export function OnboardingWizard({ onComplete }: OnboardingWizardProps) {
                                         <input
                                             type="text"
                                             placeholder="XXXX XXXX XXXX"
-                                            value={bankData.accountNumber}
-                                            onChange={e => setBankData({ ...bankData, accountNumber: e.target.value })}
+                                            value={bankData.account_number || ""}
+                                            onChange={e => setBankData({ ...bankData, account_number: e.target.value })}
                                             className="w-full bg-[var(--background)] border border-[var(--premium-border)] rounded-xl px-4 py-3 text-[var(--foreground)] focus:border-[var(--primary)] focus:outline-none transition-colors"
                                         />
                                     </div>
                                     <div>
-                                        <label className="block text-xs font-bold uppercase text-[var(--text-muted)] mb-2 tracking-wider">IFSC / Routing</label>
+                                        <label className="block text-xs font-bold uppercase text-[var(--text-muted)] mb-2 tracking-wider">IFSC Code</label>
                                         <input
                                             type="text"
                                             placeholder="IFSC0001234"
-                                            value={bankData.ifscCode}
-                                            onChange={e => setBankData({ ...bankData, ifscCode: e.target.value })}
+                                            value={bankData.ifsc_code || ""}
+                                            onChange={e => setBankData({ ...bankData, ifsc_code: e.target.value })}
                                             className="w-full bg-[var(--background)] border border-[var(--premium-border)] rounded-xl px-4 py-3 text-[var(--foreground)] focus:border-[var(--primary)] focus:outline-none transition-colors"
                                         />
                                     </div>
@@ -386,8 +387,8 @@ export function OnboardingWizard({ onComplete }: OnboardingWizardProps) {
                                     <input
                                         type="text"
                                         placeholder="Global Secure Bank"
-                                        value={bankData.bankName}
-                                        onChange={e => setBankData({ ...bankData, bankName: e.target.value })}
+                                        value={bankData.bank_name}
+                                        onChange={e => setBankData({ ...bankData, bank_name: e.target.value })}
                                         className="w-full bg-[var(--background)] border border-[var(--premium-border)] rounded-xl px-4 py-3 text-[var(--foreground)] focus:border-[var(--primary)] focus:outline-none transition-colors"
                                     />
                                 </div>
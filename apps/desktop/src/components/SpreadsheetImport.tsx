@@ -0,0 +1,266 @@
+import { useEffect, useState } from "react";
+import { motion } from "framer-motion";
+import { FileSpreadsheet, Loader2, Save, Trash2, X } from "lucide-react";
+import {
+    isApiError,
+    readSpreadsheet,
+    previewSpreadsheetImport,
+    importSpreadsheet,
+    listImportPresets,
+    saveImportPreset,
+    deleteImportPreset,
+    type ColumnMapping,
+    type DateFormat,
+    type ImportPlan,
+    type ImportPreset,
+    type ImportTarget,
+    type SpreadsheetInfo,
+} from "../lib/api";
+
+interface SpreadsheetImportProps {
+    path: string;
+    onClose: () => void;
+    onImported: () => void;
+}
+
+const selectClass = "w-full bg-[var(--premium-bg)] border border-[var(--premium-border)] rounded-lg px-3 py-2 text-sm text-[var(--foreground)] focus:outline-none focus:border-[var(--primary)] transition-all";
+const buttonClass = "flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] disabled:opacity-50 transition-all";
+
+const errorText = (err: unknown) => (isApiError(err) ? err.message : String(err));
+
+/** Maps spreadsheet columns to client or invoice fields, previews the result and imports it. */
+export function SpreadsheetImport({ path, onClose, onImported }: SpreadsheetImportProps) {
+    const [target, setTarget] = useState<ImportTarget>("clients");
+    const [info, setInfo] = useState<SpreadsheetInfo | null>(null);
+    const [mapping, setMapping] = useState<ColumnMapping | null>(null);
+    const [presets, setPresets] = useState<ImportPreset[]>([]);
+    const [presetName, setPresetName] = useState("");
+    const [plan, setPlan] = useState<ImportPlan | null>(null);
+    const [isBusy, setIsBusy] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    useEffect(() => {
+        listImportPresets().then(setPresets).catch((err) => console.error("Failed to load import presets:", err));
+    }, []);
+
+    useEffect(() => {
+        setPlan(null);
+        setError(null);
+        readSpreadsheet(path, target)
+            .then((sheet) => {
+                setInfo(sheet);
+                setMapping(sheet.suggested_mapping);
+            })
+            .catch((err) => setError(errorText(err)));
+    }, [path, target]);
+
+    const updateMapping = (next: ColumnMapping) => {
+        setMapping(next);
+        setPlan(null);
+    };
+
+    const handleColumnChange = (field: string, header: string) => {
+        if (!mapping) return;
+        const columns = { ...mapping.columns };
+        if (header) columns[field] = header;
+        else delete columns[field];
+        updateMapping({ ...mapping, columns });
+    };
+
+    const handleApplyPreset = (id: string) => {
+        const preset = presets.find((p) => p.id === id);
+        if (!preset) return;
+        setPresetName(preset.name);
+        updateMapping(preset.mapping);
+    };
+
+    const handleSavePreset = async () => {
+        if (!mapping) return;
+        try {
+            const saved = await saveImportPreset(presetName, mapping);
+            setPresets((current) => [...current.filter((p) => p.id !== saved.id), saved].sort((a, b) => a.name.localeCompare(b.name)));
+        } catch (err) {
+            setError(errorText(err));
+        }
+    };
+
+    const handleDeletePreset = async () => {
+        const preset = presets.find((p) => p.name === presetName.trim());
+        if (!preset || !confirm(`Delete the preset "${preset.name}"?`)) return;
+        try {
+            await deleteImportPreset(preset.id);
+            setPresets((current) => current.filter((p) => p.id !== preset.id));
+            setPresetName("");
+        } catch (err) {
+            setError(errorText(err));
+        }
+    };
+
+    const handlePreview = async () => {
+        if (!mapping) return;
+        setIsBusy(true);
+        setError(null);
+        try {
+            setPlan(await previewSpreadsheetImport(path, mapping));
+        } catch (err) {
+            setError(errorText(err));
+        } finally {
+            setIsBusy(false);
+        }
+    };
+
+    const handleImport = async () => {
+        if (!mapping) return;
+        setIsBusy(true);
+        setError(null);
+        try {
+            await importSpreadsheet(path, mapping);
+            onImported();
+        } catch (err) {
+            setError(errorText(err));
+            setIsBusy(false);
+        }
+    };
+
+    const fieldLabel = (field: string | null) => info?.fields.find((f) => f.field === field)?.label;
+    const targetPresets = presets.filter((p) => p.mapping.target === target);
+
+    return (
+        <div className="fixed inset-0 z-[100] flex items-center justify-center p-4">
+            <motion.div
+                initial={{ opacity: 0 }}
+                animate={{ opacity: 1 }}
+                className="absolute inset-0 bg-black/80"
+                onClick={() => !isBusy && onClose()}
+            />
+            <motion.div
+                initial={{ scale: 0.95, opacity: 0, y: 20 }}
+                animate={{ scale: 1, opacity: 1, y: 0 }}
+                className="relative w-full max-w-3xl max-h-[90vh] overflow-y-auto bg-[var(--background)] border border-[var(--premium-border)] rounded-2xl p-8 flex flex-col gap-6"
+            >
+                <div className="flex items-start justify-between gap-4">
+                    <div className="flex items-center gap-4">
+                        <div className="p-3 rounded-lg bg-teal-500/10 text-[var(--primary)]">
+                            <FileSpreadsheet size={20} />
+                        </div>
+                        <div>
+                            <h3 className="text-xl font-bold text-[var(--foreground)]">Import from Spreadsheet</h3>
+                            <p className="text-xs text-[var(--text-muted)] break-all">
+                                {path}{info ? ` · ${info.row_count} rows` : ''}
+                            </p>
+                        </div>
+                    </div>
+                    <button onClick={onClose} disabled={isBusy} className="p-2 text-[var(--text-muted)] hover:text-[var(--foreground)] disabled:opacity-50">
+                        <X size={18} />
+                    </button>
+                </div>
+
+                <div className="grid grid-cols-1 md:grid-cols-3 gap-4">
+                    <label className="flex flex-col gap-1 text-xs font-semibold text-[var(--text-muted)]">
+                        Each row is
+                        <select value={target} onChange={(e) => setTarget(e.target.value as ImportTarget)} className={selectClass}>
+                            <option value="clients">A client</option>
+                            <option value="invoices">An invoice line item</option>
+                        </select>
+                    </label>
+                    {mapping && target === "invoices" && (
+                        <label className="flex flex-col gap-1 text-xs font-semibold text-[var(--text-muted)]">
+                            Dates written as
+                            <select value={mapping.date_format} onChange={(e) => updateMapping({ ...mapping, date_format: e.target.value as DateFormat })} className={selectClass}>
+                                <option value="iso">2024-03-31</option>
+                                <option value="day_month_year">31/03/2024</option>
+                                <option value="month_day_year">03/31/2024</option>
+                            </select>
+                        </label>
+                    )}
+                    {targetPresets.length > 0 && (
+                        <label className="flex flex-col gap-1 text-xs font-semibold text-[var(--text-muted)]">
+                            Preset
+                            <select value="" onChange={(e) => handleApplyPreset(e.target.value)} className={selectClass}>
+                                <option value="">Apply a preset…</option>
+                                {targetPresets.map((p) => <option key={p.id} value={p.id}>{p.name}</option>)}
+                            </select>
+                        </label>
+                    )}
+                </div>
+
+                {info && mapping && (
+                    <div className="grid grid-cols-1 md:grid-cols-2 gap-x-6 gap-y-3">
+                        {info.fields.map(({ field, label, required }) => (
+                            <label key={field} className="flex items-center justify-between gap-3 text-sm text-[var(--foreground)]">
+                                <span>{label}{required && <span className="text-red-400"> *</span>}</span>
+                                <select value={mapping.columns[field] ?? ''} onChange={(e) => handleColumnChange(field, e.target.value)} className={`${selectClass} max-w-[55%]`}>
+                                    <option value="">— Not imported —</option>
+                                    {info.headers.map((header) => <option key={header} value={header}>{header}</option>)}
+                                </select>
+                            </label>
+                        ))}
+                    </div>
+                )}
+
+                {mapping && (
+                    <div className="flex items-center gap-2">
+                        <input
+                            value={presetName}
+                            onChange={(e) => setPresetName(e.target.value)}
+                            placeholder="Preset name"
+                            className={`${selectClass} flex-1`}
+                        />
+                        <button onClick={handleSavePreset} disabled={!presetName.trim()} className={buttonClass}>
+                            <Save size={14} /> Save Preset
+                        </button>
+                        {presets.some((p) => p.name === presetName.trim()) && (
+                            <button onClick={handleDeletePreset} className="p-2 text-red-400 hover:text-red-500">
+                                <Trash2 size={16} />
+                            </button>
+                        )}
+                    </div>
+                )}
+
+                {plan && (
+                    <div className="flex flex-col gap-3 p-4 rounded-xl bg-[var(--premium-bg)] border border-[var(--premium-border)]">
+                        <p className="text-sm font-semibold text-[var(--foreground)]">
+                            {plan.clients.length} new client{plan.clients.length === 1 ? '' : 's'}
+                            {target === "invoices" && `, ${plan.invoices.length} invoice${plan.invoices.length === 1 ? '' : 's'}`}
+                            {plan.errors.length > 0 && ` · ${plan.errors.length} problem${plan.errors.length === 1 ? '' : 's'} to fix first`}
+                        </p>
+                        {plan.errors.length > 0 && (
+                            <ul className="flex flex-col gap-1 max-h-48 overflow-y-auto text-xs">
+                                {plan.errors.map((e, i) => (
+                                    <li key={i} className="text-red-400">
+                                        <span className="font-mono font-bold">Line {e.line}</span>
+                                        {fieldLabel(e.field) && ` · ${fieldLabel(e.field)}`}: {e.message}
+                                    </li>
+                                ))}
+                            </ul>
+                        )}
+                        {plan.errors.length === 0 && target === "invoices" && plan.invoices.length > 0 && (
+                            <ul className="flex flex-col gap-1 max-h-48 overflow-y-auto text-xs text-[var(--text-muted)]">
+                                {plan.invoices.map((inv) => (
+                                    <li key={inv.id}>{inv.number} · {inv.items.length} item{inv.items.length === 1 ? '' : 's'} · {inv.currency} {inv.total}</li>
+                                ))}
+                            </ul>
+                        )}
+                    </div>
+                )}
+
+                {error && <p className="text-xs text-red-400">{error}</p>}
+
+                <div className="flex justify-end gap-2">
+                    <button onClick={handlePreview} disabled={isBusy || !mapping} className={buttonClass}>
+                        {isBusy && !plan ? <Loader2 size={14} className="animate-spin" /> : null}
+                        Preview
+                    </button>
+                    <button
+                        onClick={handleImport}
+                        disabled={isBusy || !plan || plan.errors.length > 0 || (plan.clients.length === 0 && plan.invoices.length === 0)}
+                        className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider bg-[var(--foreground)] text-[var(--background)] rounded-lg disabled:opacity-50 transition-all"
+                    >
+                        {isBusy && plan ? <Loader2 size={14} className="animate-spin" /> : null}
+                        {isBusy && plan ? 'Importing...' : 'Import'}
+                    </button>
+                </div>
+            </motion.div>
+        </div>
+    );
+}
@@ -0,0 +1,75 @@
+import { useState } from "react";
+import { motion } from "framer-motion";
+import { Lock, ArrowRight, Loader2 } from "lucide-react";
+import { isApiError } from "../lib/api";
+
+interface UnlockScreenProps {
+    /** Checks the passphrase, rejecting with a validation error when it is wrong. */
+    unlock: (passphrase: string) => Promise<void>;
+    onUnlock: () => void;
+    message: string;
+}
+
+/** Full-screen passphrase prompt, for the encrypted database and for the app lock. */
+export function UnlockScreen({ unlock, onUnlock, message }: UnlockScreenProps) {
+    const [passphrase, setPassphrase] = useState("");
+    const [error, setError] = useState<string | null>(null);
+    const [isUnlocking, setIsUnlocking] = useState(false);
+
+    const handleUnlock = async (e: React.FormEvent) => {
+        e.preventDefault();
+        setIsUnlocking(true);
+        setError(null);
+        try {
+            await unlock(passphrase);
+            setPassphrase("");
+            onUnlock();
+        } catch (err) {
+            setError(isApiError(err, "validation") ? "Wrong passphrase." : String(err));
+        } finally {
+            setIsUnlocking(false);
+        }
+    };
+
+    return (
+        <div className="fixed inset-0 z-[100] flex items-center justify-center bg-[var(--background)] overflow-hidden">
+            <div className="absolute inset-0 z-0">
+                <div className="absolute top-1/4 left-1/4 w-96 h-96 bg-[var(--primary)]/20 rounded-full blur-[120px]" />
+                <div className="absolute bottom-1/4 right-1/4 w-96 h-96 bg-[var(--secondary)]/20 rounded-full blur-[120px]" />
+            </div>
+
+            <motion.form
+                onSubmit={handleUnlock}
+                initial={{ opacity: 0, y: 20 }}
+                animate={{ opacity: 1, y: 0 }}
+                transition={{ duration: 0.5, ease: [0.16, 1, 0.3, 1] }}
+                className="relative z-10 w-full max-w-md px-6 text-center"
+            >
+                <div className="w-20 h-20 mx-auto bg-gradient-to-br from-[var(--primary)] to-[var(--secondary)] rounded-3xl flex items-center justify-center mb-8 shadow-[0_0_50px_rgba(45,212,191,0.4)]">
+                    <Lock size={32} className="text-white" />
+                </div>
+                <h1 className="text-3xl font-black text-[var(--foreground)] mb-3 tracking-tight">InvoiceFlow is locked</h1>
+                <p className="text-[var(--text-muted)] mb-8">{message}</p>
+
+                <input
+                    type="password"
+                    autoFocus
+                    value={passphrase}
+                    onChange={(e) => setPassphrase(e.target.value)}
+                    placeholder="Passphrase"
+                    className={`w-full bg-[var(--premium-bg)] border rounded-xl px-4 py-3 text-[var(--foreground)] focus:outline-none focus:border-[var(--primary)] transition-all ${error ? 'border-red-500' : 'border-[var(--premium-border)]'}`}
+                />
+                {error && <p className="mt-2 text-xs text-red-400 text-left">{error}</p>}
+
+                <button
+                    type="submit"
+                    disabled={isUnlocking || !passphrase}
+                    className="mt-6 w-full flex items-center justify-center gap-2 bg-[var(--foreground)] text-[var(--background)] font-bold py-3 rounded-xl hover:scale-[1.02] disabled:opacity-50 disabled:hover:scale-100 transition-all"
+                >
+                    {isUnlocking ? <Loader2 size={18} className="animate-spin" /> : <ArrowRight size={18} />}
+                    {isUnlocking ? 'Unlocking...' : 'Unlock'}
+                </button>
+            </motion.form>
+        </div>
+    );
+}
@@ -1,17 +1,24 @@
 import { useState, useImperativeHandle, forwardRef, useEffect, useRef } from "react";
 import { Plus, Trash2, ImagePlus, X } from "lucide-react";
-import { invoke } from "@tauri-apps/api/core";
-import { useSettingsStore } from "../../store/settingsStore";
-import type { ClientResponse } from "../../lib/api";
+import { useSettingsStore, defaultBankAccount, type BankAccount } from "../../store/settingsStore";
+import { call, previewUpiQr, getCustomFields, customFieldValues, type ClientResponse, type CustomFieldDefinition, type ProjectDetail } from "../../lib/api";
+import { CustomFieldInput } from "../ui/CustomFieldInput";
 
 export interface InvoiceData {
     invoiceNumber: string | null;
     clientId: string;
-    items: { description: string; quantity: number; unit_price: number }[];
-    notes: string;
+    items: { description: string; quantity: number; unit_price: number; hsn_code: string | null; custom_fields: Record<string, string> }[];
+    notes: string | null;
     status: string;
     issueDate: string | null;
     dueDate: string | null;
+    bankAccountId: string | null;
+    developer: string;
+    logoPath: string | null;
+    qrCodeUrl: string | null;
+    paymentNote: string;
+    projectDetails: ProjectDetail[];
+    customFields: Record<string, string>;
 }
 
 export interface InvoiceEditorRef {
@@ -50,8 +57,7 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
         if (initialData?.issueDate) return formatDate(new Date(initialData.issueDate));
         return formatDate(new Date());
     });
-    const customNotes = initialData?.notes ? JSON.parse(initialData.notes) : null;
-    const [developer, setDeveloper] = useState(customNotes?.developer || "");
+    const [developer, setDeveloper] = useState(initialData?.developer || "");
     const [dueDate, setDueDate] = useState(() => {
         if (initialData?.dueDate) return formatDate(new Date(initialData.dueDate));
         return "";
@@ -62,33 +68,53 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
     const selectedClient = clients.find((c) => c.id === selectedClientId);
 
     const profile = useSettingsStore(state => state.profile);
-    const bankDetailsFromStore = useSettingsStore(state => state.bankDetails);
+    const bankAccounts = useSettingsStore(state => state.bankAccounts);
+    const [bankAccountId, setBankAccountId] = useState<string>(initialData?.bankAccountId || "");
+    const bankAccount: BankAccount | undefined = bankAccounts.find((a) => a.id === bankAccountId);
 
-    const [logoPreview, setLogoPreview] = useState<string | null>(null);
-    const [qrCodeUrl, setQrCodeUrl] = useState<string | null>(null);
+    const [logoPreview, setLogoPreview] = useState<string | null>(initialData?.logoPath || null);
+    const [qrCodeUrl, setQrCodeUrl] = useState<string | null>(initialData?.qrCodeUrl || null);
     const fileInputRef = useRef<HTMLInputElement>(null);
 
+    // Custom fields defined in Settings; values are keyed by field id
+    const [fieldDefs, setFieldDefs] = useState<CustomFieldDefinition[]>([]);
+    const invoiceFieldDefs = fieldDefs.filter((f) => f.entity === "Invoice");
+    const itemFieldDefs = fieldDefs.filter((f) => f.entity === "InvoiceItem");
+    const [invoiceFields, setInvoiceFields] = useState<Record<string, string>>(initialData?.customFields || {});
+
     // Fetch initial settings & data on mount
     useEffect(() => {
         let mounted = true;
 
-        // Fetch Bank Details to store
-        useSettingsStore.getState().fetchBankDetails();
+        // Fetch bank accounts to store
+        useSettingsStore.getState().fetchBankAccounts();
+
+        // Fetch custom field definitions; fields without a value start at their default
+        getCustomFields()
+            .then((defs) => {
+                if (!mounted) return;
+                setFieldDefs(defs);
+                const invoiceDefaults = customFieldValues(defs.filter((f) => f.entity === "Invoice"));
+                const itemDefaults = customFieldValues(defs.filter((f) => f.entity === "InvoiceItem"));
+                setInvoiceFields((current) => ({ ...invoiceDefaults, ...current }));
+                setLineItems((items) => items.map((item) => ({ ...item, customFields: { ...itemDefaults, ...item.customFields } })));
+            })
+            .catch(console.error);
 
         // Fetch Logo
-        invoke<string | null>('get_logo')
+        call<string | null>('get_logo')
             .then((data) => {
                 if (mounted && data) {
-                    setLogoPreview(data);
+                    setLogoPreview((current) => current || data);
                 }
             })
             .catch(console.error);
 
         // Fetch QR
-        invoke<string | null>('get_qr')
+        call<string | null>('get_qr')
             .then((data) => {
                 if (mounted && data) {
-                    setQrCodeUrl(data);
+                    setQrCodeUrl((current) => current || data);
                 }
             })
             .catch(console.error);
@@ -110,20 +136,16 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
         setDueDate(computeDueDate(profile?.default_payment_terms));
     }, [profile?.default_payment_terms]);
 
-    // Auto-populate Bank Details
+    // Auto-select the default account for the invoice currency
     useEffect(() => {
-        if (bankDetailsFromStore) {
-            setAccountHolder((prev: string) => prev || bankDetailsFromStore.accountHolder);
-            setAccountNumber((prev: string) => prev || bankDetailsFromStore.accountNumber);
-            setIfscCode((prev: string) => prev || bankDetailsFromStore.ifscCode);
-            setBankName((prev: string) => prev || bankDetailsFromStore.bankName);
-            setBranch((prev: string) => prev || bankDetailsFromStore.branch);
-            setUpiId((prev: string) => prev || bankDetailsFromStore.upiId);
-        }
-    }, [bankDetailsFromStore]);
+        if (bankAccountId || bankAccounts.length === 0) return;
+        const account = defaultBankAccount(bankAccounts, profile?.default_currency);
+        if (account?.id) setBankAccountId(account.id);
+    }, [bankAccounts, profile?.default_currency]);
 
     // Project Details (Dynamic Key-Value Pairs)
-    const [projectDetails, setProjectDetails] = useState(customNotes?.projectDetails || [
+    const [projectDetails, setProjectDetails] = useState<{ id: number, label: string, value: string }[]>(() =>
+        initialData?.projectDetails?.map((d: ProjectDetail, index: number) => ({ id: index + 1, ...d })) || [
         { id: 1, label: "Website Name", value: "" },
         { id: 2, label: "Tech Stack", value: "" },
         { id: 3, label: "Domain Name", value: "" },
@@ -133,37 +155,57 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
     ]);
 
     // Line Items
-    const [lineItems, setLineItems] = useState<{ id: number, description: string, amount: number }[]>(() => {
+    const [lineItems, setLineItems] = useState<{ id: number, description: string, amount: number, hsnCode: string, customFields: Record<string, string> }[]>(() => {
         if (initialData?.items && initialData.items.length > 0) {
             return initialData.items.map((i: any, index: number) => ({
                 id: index + 1,
                 description: i.description,
-                amount: i.unit_price
+                amount: i.unit_price,
+                hsnCode: i.hsn_code || "",
+                customFields: i.custom_fields || {}
             }));
         }
         return [
             {
                 id: 1,
                 description: "",
-                amount: 0
+                amount: 0,
+                hsnCode: "",
+                customFields: {}
             }
         ];
     });
 
-    // Bank Details
-    const [accountHolder, setAccountHolder] = useState(customNotes?.bankDetails?.accountHolder || "");
-    const [accountNumber, setAccountNumber] = useState(customNotes?.bankDetails?.accountNumber || "");
-    const [ifscCode, setIfscCode] = useState(customNotes?.bankDetails?.ifscCode || "");
-    const [bankName, setBankName] = useState(customNotes?.bankDetails?.bankName || "");
-    const [branch, setBranch] = useState(customNotes?.bankDetails?.branch || "");
-    const [upiId, setUpiId] = useState(customNotes?.bankDetails?.upiId || "");
+    // Bank Details (read-only view of the selected account)
+    const accountHolder = bankAccount?.account_holder || "";
+    const upiId = bankAccount?.upi_id || "";
+    const accountRows: [string, string | null | undefined][] = bankAccount ? [
+        ["Account Holder:", bankAccount.account_holder],
+        ["Account Number:", bankAccount.account_number],
+        [bankAccount.ifsc_code || !bankAccount.routing_number ? "IFSC Code:" : "Routing Number:", bankAccount.ifsc_code || bankAccount.routing_number],
+        ["Bank:", bankAccount.bank_name],
+        ["Branch:", bankAccount.branch],
+        ["UPI ID:", bankAccount.upi_id],
+        ["IBAN:", bankAccount.iban],
+        ["BIC / SWIFT:", bankAccount.swift_bic],
+    ] : [];
 
     // Notes / Payment Term
-    const [paymentTermsNote, setPaymentTermsNote] = useState(customNotes?.paymentTermsNote || "");
+    const [paymentTermsNote, setPaymentTermsNote] = useState(initialData?.paymentNote || "");
 
     // Calculations
     const totalAmount = lineItems.reduce((sum, item) => sum + item.amount, 0);
 
+    // UPI QR for the exact amount, as it will print on the PDF
+    const [upiQrSvg, setUpiQrSvg] = useState<string | null>(null);
+    useEffect(() => {
+        let cancelled = false;
+        previewUpiQr(upiId, accountHolder || developer, totalAmount, invoiceNumber ? `Invoice ${invoiceNumber}` : "")
+            .then((svg) => { if (!cancelled) setUpiQrSvg(svg); })
+            .catch(() => { if (!cancelled) setUpiQrSvg(null); });
+        return () => { cancelled = true; };
+    }, [upiId, accountHolder, developer, totalAmount, invoiceNumber]);
+
     // General Helpers
     const adjustTextareaHeight = (e: React.ChangeEvent<HTMLTextAreaElement>) => {
         e.target.style.height = "auto";
@@ -185,13 +227,17 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
 
     // Line Items Helpers
     const addItem = () => {
-        setLineItems([...lineItems, { id: Date.now(), description: "", amount: 0 }]);
+        setLineItems([...lineItems, { id: Date.now(), description: "", amount: 0, hsnCode: "", customFields: customFieldValues(itemFieldDefs) }]);
     };
 
-    const updateItem = (id: number, field: "description" | "amount", value: string | number) => {
+    const updateItem = (id: number, field: "description" | "amount" | "hsnCode", value: string | number) => {
         setLineItems(lineItems.map(item => item.id === id ? { ...item, [field]: value } : item));
     };
 
+    const updateItemField = (id: number, fieldId: string, value: string) => {
+        setLineItems(lineItems.map(item => item.id === id ? { ...item, customFields: { ...item.customFields, [fieldId]: value } } : item));
+    };
+
     const removeItem = (id: number) => {
         setLineItems(lineItems.filter(item => item.id !== id));
     };
@@ -209,17 +255,7 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
     };
 
     // Serialize data function
-    const getCurrentData = () => {
-        const customData = {
-            developer,
-            logoPath: logoPreview,
-            qrCodeUrl,
-            projectDetails,
-            bankDetails: { accountHolder, accountNumber, ifscCode, bankName, branch, upiId },
-            paymentTermsNote,
-            status
-        };
-
+    const getCurrentData = (): InvoiceData => {
         return {
             invoiceNumber: invoiceNumber.trim() || null,
             clientId: selectedClientId,
@@ -227,11 +263,20 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
                 description: li.description,
                 quantity: 1,
                 unit_price: li.amount,
+                hsn_code: li.hsnCode.trim() || null,
+                custom_fields: li.customFields,
             })),
-            notes: JSON.stringify(customData),
+            notes: initialData?.notes || null,
             status: status,
             issueDate: parseDateToISO(invoiceDate),
-            dueDate: parseDateToISO(dueDate)
+            dueDate: parseDateToISO(dueDate),
+            bankAccountId: bankAccountId || null,
+            developer,
+            logoPath: logoPreview,
+            qrCodeUrl,
+            paymentNote: paymentTermsNote,
+            projectDetails: projectDetails.map(({ label, value }) => ({ label, value })),
+            customFields: invoiceFields
         };
     };
 
@@ -247,8 +292,7 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
         onChange,
         invoiceNumber, selectedClientId, lineItems, status, invoiceDate, dueDate,
         developer, logoPreview, qrCodeUrl, projectDetails,
-        accountHolder, accountNumber, ifscCode, bankName, branch, upiId,
-        paymentTermsNote
+        bankAccountId, paymentTermsNote, invoiceFields
     ]);
 
     // Expose data to parent via ref
@@ -350,7 +394,7 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
                             className="bg-transparent text-gray-900 font-bold outline-none w-full text-lg cursor-pointer hover:bg-gray-50 mb-1"
                         >
                             <option value="" disabled>Select a client...</option>
-                            {clients.map((c) => (
+                            {clients.filter((c) => !c.archived_at || c.id === selectedClientId).map((c) => (
                                 <option key={c.id} value={c.id}>
                                     {c.name} {c.company ? `(${c.company})` : ""}
                                 </option>
@@ -393,6 +437,17 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
                                 </div>
                             </div>
                         ))}
+                        {invoiceFieldDefs.map((field) => (
+                            <div key={field.id} className="grid grid-cols-[160px_1fr] items-center">
+                                <span className="font-bold px-1">{field.name}{field.required && <span className="text-red-500"> *</span>}</span>
+                                <CustomFieldInput
+                                    field={field}
+                                    value={invoiceFields[field.id || ""] ?? ""}
+                                    onChange={(value) => setInvoiceFields({ ...invoiceFields, [field.id || ""]: value })}
+                                    className="bg-transparent outline-none hover:bg-black/5 px-1 rounded transition-colors placeholder:text-gray-400 placeholder:italic"
+                                />
+                            </div>
+                        ))}
                     </div>
                     {/* Add Project Detail Button */}
                     <div className="absolute -bottom-7 right-0 opacity-0 group-hover/projectTable:opacity-100 transition-opacity">
@@ -426,6 +481,21 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
                                             placeholder="Enter service description, features, or project scope..."
                                             className="w-full bg-transparent outline-none resize-none overflow-hidden leading-relaxed text-sm text-gray-900 min-h-[140px] hover:bg-black/5 p-1 -m-1 rounded transition-colors placeholder:text-gray-400 placeholder:italic"
                                         />
+                                        {itemFieldDefs.length > 0 && (
+                                            <div className="flex flex-wrap gap-x-4 gap-y-1 mt-2 text-xs text-gray-500">
+                                                {itemFieldDefs.map((field) => (
+                                                    <label key={field.id} className="flex items-center gap-1">
+                                                        <span className="font-semibold">{field.name}:</span>
+                                                        <CustomFieldInput
+                                                            field={field}
+                                                            value={item.customFields[field.id || ""] ?? ""}
+                                                            onChange={(value) => updateItemField(item.id, field.id || "", value)}
+                                                            className="bg-transparent outline-none hover:bg-black/5 px-1 rounded placeholder:text-gray-300"
+                                                        />
+                                                    </label>
+                                                ))}
+                                            </div>
+                                        )}
                                     </td>
                                     <td className="py-3 px-4 align-top text-right">
                                         <input
@@ -434,6 +504,14 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
                                             onChange={(e) => updateItem(item.id, "amount", parseFloat(e.target.value) || 0)}
                                             className="w-full bg-transparent outline-none text-right font-bold text-gray-900 text-base"
                                         />
+                                        <input
+                                            type="text"
+                                            value={item.hsnCode}
+                                            onChange={(e) => updateItem(item.id, "hsnCode", e.target.value)}
+                                            placeholder="HSN/SAC"
+                                            title="HSN or SAC code, required for GST e-invoices"
+                                            className="w-full bg-transparent outline-none text-right text-xs text-gray-500 mt-2 placeholder:text-gray-300"
+                                        />
                                     </td>
                                     {/* Delete Button (Hover) */}
                                     <div className="absolute right-[-32px] top-1/2 -translate-y-1/2 opacity-0 group-hover/row:opacity-100 transition-opacity">
@@ -471,14 +549,28 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
                     {/* Bank Details */}
                     <div>
                         <div className="bg-[#fffbeb] p-4 text-sm h-full">
-                            <h3 className="font-bold text-[#3e546c] text-base mb-3">Bank Account Details</h3>
+                            <div className="flex items-center justify-between mb-3">
+                                <h3 className="font-bold text-[#3e546c] text-base">Bank Account Details</h3>
+                                {bankAccounts.length > 0 && (
+                                    <select
+                                        value={bankAccountId}
+                                        onChange={(e) => setBankAccountId(e.target.value)}
+                                        className="text-xs bg-transparent border border-gray-300 rounded px-2 py-1 outline-none text-gray-700"
+                                    >
+                                        {!bankAccountId && <option value="">Select account…</option>}
+                                        {bankAccounts.map((a) => (
+                                            <option key={a.id} value={a.id || ""}>{a.label || a.bank_name || "Account"}</option>
+                                        ))}
+                                    </select>
+                                )}
+                            </div>
                             <div className="flex flex-col gap-1 text-gray-800">
-                                <div className="flex"><span className="font-bold w-[120px]">Account Holder:</span><input value={accountHolder} onChange={(e) => setAccountHolder(e.target.value)} placeholder="Full Name" className="flex-1 bg-transparent outline-none hover:bg-black/5 px-1 -mx-1 rounded placeholder:text-gray-400 placeholder:italic" /></div>
-                                <div className="flex"><span className="font-bold w-[120px]">Account Number:</span><input value={accountNumber} onChange={(e) => setAccountNumber(e.target.value)} placeholder="0000000000" className="flex-1 bg-transparent outline-none hover:bg-black/5 px-1 -mx-1 rounded placeholder:text-gray-400 placeholder:italic" /></div>
-                                <div className="flex"><span className="font-bold w-[120px]">IFSC Code:</span><input value={ifscCode} onChange={(e) => setIfscCode(e.target.value)} placeholder="BANK0000000" className="flex-1 text-[#3b82f6] bg-transparent outline-none hover:bg-black/5 px-1 -mx-1 rounded placeholder:text-[#3b82f6]/50 placeholder:italic" /></div>
-                                <div className="flex"><span className="font-bold w-[120px]">Bank:</span><input value={bankName} onChange={(e) => setBankName(e.target.value)} placeholder="Bank Name" className="flex-1 bg-transparent outline-none hover:bg-black/5 px-1 -mx-1 rounded placeholder:text-gray-400 placeholder:italic" /></div>
-                                <div className="flex"><span className="font-bold w-[120px]">Branch:</span><input value={branch} onChange={(e) => setBranch(e.target.value)} placeholder="Branch Name" className="flex-1 bg-transparent outline-none hover:bg-black/5 px-1 -mx-1 rounded placeholder:text-gray-400 placeholder:italic" /></div>
-                                <div className="flex"><span className="font-bold w-[120px]">UPI ID:</span><input value={upiId} onChange={(e) => setUpiId(e.target.value)} placeholder="user@upi" className="flex-1 text-[#3b82f6] bg-transparent outline-none hover:bg-black/5 px-1 -mx-1 rounded placeholder:text-[#3b82f6]/50 placeholder:italic" /></div>
+                                {accountRows.filter(([, value]) => value).map(([label, value]) => (
+                                    <div key={label} className="flex"><span className="font-bold w-[120px]">{label}</span><span className="flex-1">{value}</span></div>
+                                ))}
+                                {!bankAccount && (
+                                    <p className="text-gray-400 italic">{bankAccounts.length > 0 ? "No account selected." : "Add a bank account in Settings to print payment details."}</p>
+                                )}
                                 <div className="mt-3">
                                     <span className="font-bold block mb-1">Payment Methods:</span>
                                     <p className="leading-snug text-gray-700">• Bank Transfer (NEFT/RTGS/IMPS)<br />• UPI Payment (Scan QR Code →)<br />• Cash</p>
@@ -490,7 +582,9 @@ export const InvoiceEditor = forwardRef<InvoiceEditorRef, Props>(({ clients, ini
                     <div className="flex flex-col items-center justify-center border-l border-gray-200 pl-6 relative">
                         <h3 className="font-bold text-[#3e546c] mb-2 text-sm text-center">Scan to Pay via UPI</h3>
                         <div className="w-48 h-48 bg-[#1e293b] rounded-lg border-2 border-dashed border-gray-300 flex items-center justify-center text-white text-xs opacity-90 cursor-default hover:opacity-100 transition-opacity overflow-hidden group relative">
-                            {qrCodeUrl ? (
+                            {upiQrSvg ? (
+                                <img src={`data:image/svg+xml;utf8,${encodeURIComponent(upiQrSvg)}`} alt="UPI QR Code" className="w-full h-full object-contain bg-white" />
+                            ) : qrCodeUrl ? (
                                 <img src={qrCodeUrl} alt="UPI QR Code" className="w-full h-full object-contain bg-white" />
                             ) : (
                                 <>
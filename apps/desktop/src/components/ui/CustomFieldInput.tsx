@@ -0,0 +1,45 @@
+import type { CustomFieldDefinition } from '../../lib/api';
+
+interface CustomFieldInputProps {
+    field: CustomFieldDefinition;
+    value: string;
+    onChange: (value: string) => void;
+    className?: string;
+    style?: React.CSSProperties;
+}
+
+/** Input matching a custom field's type; values are kept as the strings the backend stores. */
+export function CustomFieldInput({ field, value, onChange, className, style }: CustomFieldInputProps) {
+    const placeholder = field.required ? `${field.name} (required)` : field.name;
+
+    switch (field.field_type) {
+        case 'Boolean':
+            return (
+                <input
+                    type="checkbox"
+                    checked={value === 'true'}
+                    onChange={(e) => onChange(e.target.checked ? 'true' : 'false')}
+                />
+            );
+        case 'Select':
+            return (
+                <select value={value} onChange={(e) => onChange(e.target.value)} className={className} style={style}>
+                    <option value="">{field.required ? 'Select…' : '—'}</option>
+                    {field.options.map((option) => (
+                        <option key={option} value={option}>{option}</option>
+                    ))}
+                </select>
+            );
+        default:
+            return (
+                <input
+                    type={field.field_type === 'Number' ? 'number' : field.field_type === 'Date' ? 'date' : 'text'}
+                    value={value}
+                    onChange={(e) => onChange(e.target.value)}
+                    placeholder={placeholder}
+                    className={className}
+                    style={style}
+                />
+            );
+    }
+}
@@ -1,18 +1,98 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { Address } from "../store/settingsStore";
 
 // ─── Types ───────────────────────────────────────────────────
 
+export interface ClientContact {
+    /** Null for contacts not saved yet. */
+    id: string | null;
+    name: string;
+    email: string | null;
+    phone: string | null;
+    role: string | null;
+    is_primary: boolean;
+}
+
+export interface TaxIdentifier {
+    scheme: string;
+    value: string;
+}
+
 export interface ClientResponse {
     id: string;
     name: string;
     email: string | null;
+    phone: string | null;
     company: string | null;
+    address: Address;
+    /** Null when goods ship to the billing address. */
+    shipping_address: Address | null;
+    tax_id: string | null;
+    tax_ids: TaxIdentifier[];
+    contacts: ClientContact[];
+    notes: string | null;
+    custom_fields: CustomFieldValue[];
+    /** Set when the client is archived. */
+    archived_at: string | null;
 }
 
 export interface CreateClientRequest {
     name: string;
     email: string | null;
     company: string | null;
+    phone?: string | null;
+    address?: Address;
+    shipping_address?: Address | null;
+    tax_id?: string | null;
+    tax_ids?: TaxIdentifier[];
+    contacts?: ClientContact[];
+    notes?: string | null;
+    /** Values keyed by custom field id. */
+    custom_fields?: Record<string, string>;
+}
+
+export type DuplicateReason = "SameName" | "SimilarName" | "SameEmailDomain" | "SameTaxId" | "SimilarTaxId";
+
+export interface DuplicateMatch {
+    client_id: string;
+    duplicate_id: string;
+    reasons: DuplicateReason[];
+    /** 0–100; higher is more likely the same customer. */
+    score: number;
+}
+
+export interface AuditEntry {
+    id: number;
+    action: string;
+    entity: string;
+    entity_id: string;
+    details: Record<string, unknown>;
+    created_at: string;
+}
+
+export const emptyAddress = (): Address => ({
+    line1: "", line2: null, city: "", state: null, postal_code: "", country: "",
+});
+
+export type CustomFieldType = "Text" | "Number" | "Date" | "Boolean" | "Select";
+export type CustomFieldEntity = "Client" | "Invoice" | "InvoiceItem";
+
+export interface CustomFieldDefinition {
+    id: string | null;
+    name: string;
+    field_type: CustomFieldType;
+    entity: CustomFieldEntity;
+    options: string[];
+    required: boolean;
+    default_value: string | null;
+    sort_order: number;
+}
+
+export interface CustomFieldValue {
+    field_id: string;
+    name: string;
+    field_type: CustomFieldType;
+    value: string;
 }
 
 export interface InvoiceSummary {
@@ -25,12 +105,56 @@ export interface InvoiceSummary {
     currency: string;
     total: string;
     amount_due: string;
+    /** Set when the invoice is archived. */
+    archived_at: string | null;
+}
+
+/** One page of a list query; pass `next_cursor` back as `cursor` for the following page. */
+export interface Page<T> {
+    items: T[];
+    next_cursor: string | null;
+    total: number;
+}
+
+export type SortDirection = "Asc" | "Desc";
+
+/** Which rows a query lists by archive state; defaults to "Active". */
+export type ArchiveFilter = "Active" | "Archived" | "All";
+
+export interface ClientQuery {
+    /** Words matched as prefixes against name, company, email, phone and notes. */
+    search?: string | null;
+    archived?: ArchiveFilter;
+    sort?: "Name" | "CreatedAt" | "UpdatedAt";
+    direction?: SortDirection;
+    cursor?: string | null;
+    limit?: number;
+}
+
+export interface InvoiceQuery {
+    /** Words matched as prefixes against number, notes, item descriptions and the client. */
+    search?: string | null;
+    statuses?: string[];
+    client_id?: string | null;
+    currency?: string | null;
+    /** Issue date range (YYYY-MM-DD), both ends inclusive. */
+    issued_from?: string | null;
+    issued_to?: string | null;
+    min_total?: string | null;
+    max_total?: string | null;
+    archived?: ArchiveFilter;
+    sort?: "IssueDate" | "DueDate" | "Number" | "Total" | "CreatedAt";
+    direction?: SortDirection;
+    cursor?: string | null;
+    limit?: number;
 }
 
 export interface InvoiceItemRequest {
     description: string;
     quantity: number;
     unit_price: number;
+    hsn_code?: string | null;
+    custom_fields?: Record<string, string>;
 }
 
 export interface CreateInvoiceRequest {
@@ -41,6 +165,19 @@ export interface CreateInvoiceRequest {
     status: string | null;
     issue_date: string | null;
     due_date: string | null;
+    /** Defaults to the default account for the invoice currency. */
+    bank_account_id?: string | null;
+    developer?: string | null;
+    logo_path?: string | null;
+    qr_code_url?: string | null;
+    payment_note?: string | null;
+    project_details?: ProjectDetail[];
+    custom_fields?: Record<string, string>;
+}
+
+export interface ProjectDetail {
+    label: string;
+    value: string;
 }
 
 export interface RevenueMetrics {
@@ -50,72 +187,468 @@ export interface RevenueMetrics {
     overdue: number;
 }
 
+// ─── Errors ──────────────────────────────────────────────────
+
+export type ApiErrorCode =
+    | "database"
+    | "busy"
+    | "validation"
+    | "not_found"
+    | "client_has_invoices"
+    | "pdf_generation"
+    | "serialization"
+    | "io"
+    | "locked"
+    | "internal";
+
+/** A failed command, as serialized by the backend's `FlowError`. */
+export class ApiError extends Error {
+    code: ApiErrorCode;
+    /** The request field the error is about, for validation errors. */
+    field: string | null;
+    retryable: boolean;
+
+    constructor(code: ApiErrorCode, message: string, field: string | null = null, retryable = false) {
+        super(message);
+        this.name = "ApiError";
+        this.code = code;
+        this.field = field;
+        this.retryable = retryable;
+    }
+
+    toString(): string {
+        return this.message;
+    }
+}
+
+export function isApiError(err: unknown, code?: ApiErrorCode): err is ApiError {
+    return err instanceof ApiError && (code === undefined || err.code === code);
+}
+
+function toApiError(err: unknown): ApiError {
+    if (err instanceof ApiError) return err;
+    if (err && typeof err === "object" && "code" in err && "message" in err) {
+        const { code, message, field, retryable } = err as { code: ApiErrorCode; message: string; field?: string | null; retryable?: boolean };
+        return new ApiError(code, message, field ?? null, retryable ?? false);
+    }
+    return new ApiError("internal", String(err));
+}
+
+const RETRY_DELAYS_MS = [200, 800];
+
+/** Dispatched on `window` whenever a command is rejected because the app lock engaged. */
+export const APP_LOCKED_EVENT = "invoiceflow:locked";
+
+/** Invokes a backend command, retrying while the database is busy and rejecting with an `ApiError`. */
+export async function call<T>(command: string, args?: Record<string, unknown>): Promise<T> {
+    for (let attempt = 0; ; attempt++) {
+        try {
+            return await invoke<T>(command, args);
+        } catch (err) {
+            const error = toApiError(err);
+            if (error.code === "locked") window.dispatchEvent(new Event(APP_LOCKED_EVENT));
+            if (!error.retryable || attempt >= RETRY_DELAYS_MS.length) throw error;
+            await new Promise((resolve) => setTimeout(resolve, RETRY_DELAYS_MS[attempt]));
+        }
+    }
+}
+
 // ─── Client API ──────────────────────────────────────────────
 
 export async function getClients(): Promise<ClientResponse[]> {
-    return invoke<ClientResponse[]>("get_clients");
+    return call<ClientResponse[]>("get_clients");
+}
+
+export async function queryClients(query: ClientQuery): Promise<Page<ClientResponse>> {
+    return call<Page<ClientResponse>>("query_clients", { query });
+}
+
+export async function getClient(id: string): Promise<ClientResponse> {
+    return call<ClientResponse>("get_client", { id });
 }
 
 export async function createClient(request: CreateClientRequest): Promise<ClientResponse> {
-    return invoke<ClientResponse>("create_client", { request });
+    return call<ClientResponse>("create_client", { request });
 }
 
+/** Rejects with a `client_has_invoices` ApiError for clients that have invoices; archive those instead. */
 export async function deleteClient(id: string): Promise<void> {
-    return invoke<void>("delete_client", { id });
+    return call<void>("delete_client", { id });
 }
 
-export interface UpdateClientRequest {
+export async function setClientArchived(id: string, archived: boolean): Promise<void> {
+    return call<void>("set_client_archived", { id, archived });
+}
+
+export interface UpdateClientRequest extends CreateClientRequest {
     id: string;
-    name: string;
-    email: string | null;
-    company: string | null;
 }
 
 export async function updateClient(request: UpdateClientRequest): Promise<void> {
-    return invoke<void>("update_client", { request });
+    return call<void>("update_client", { request });
+}
+
+export async function findDuplicateClients(): Promise<DuplicateMatch[]> {
+    return call<DuplicateMatch[]>("find_duplicate_clients");
+}
+
+/** Moves the source's invoices, notes and details onto the target and deletes the source. */
+export async function mergeClients(sourceId: string, targetId: string): Promise<ClientResponse> {
+    return call<ClientResponse>("merge_clients", { sourceId, targetId });
+}
+
+export async function getAuditLog(entityId?: string, limit?: number): Promise<AuditEntry[]> {
+    return call<AuditEntry[]>("get_audit_log", { entityId: entityId ?? null, limit: limit ?? null });
+}
+
+// ─── Custom Field API ────────────────────────────────────────
+
+export async function getCustomFields(entity?: CustomFieldEntity): Promise<CustomFieldDefinition[]> {
+    return call<CustomFieldDefinition[]>("get_custom_fields", { entity: entity ?? null });
+}
+
+export async function saveCustomField(request: CustomFieldDefinition): Promise<CustomFieldDefinition> {
+    return call<CustomFieldDefinition>("save_custom_field", { request });
+}
+
+export async function deleteCustomField(id: string): Promise<void> {
+    return call<void>("delete_custom_field", { id });
+}
+
+/** Ids of the clients or invoices with a custom field value containing `query`. */
+export async function searchCustomFields(entity: "Client" | "Invoice", query: string): Promise<string[]> {
+    return call<string[]>("search_custom_fields", { entity, query });
+}
+
+/** Initial form values: the record's stored values, else each field's default. */
+export function customFieldValues(definitions: CustomFieldDefinition[], values: CustomFieldValue[] = []): Record<string, string> {
+    return Object.fromEntries(definitions.map((d) => [
+        d.id || "",
+        values.find((v) => v.field_id === d.id)?.value ?? d.default_value ?? (d.field_type === "Boolean" ? "false" : ""),
+    ]));
 }
 
 // ─── Invoice API ─────────────────────────────────────────────
 
 export async function getInvoices(): Promise<InvoiceSummary[]> {
-    return invoke<InvoiceSummary[]>("get_invoices");
+    return call<InvoiceSummary[]>("get_invoices");
+}
+
+export async function queryInvoices(query: InvoiceQuery): Promise<Page<InvoiceSummary>> {
+    return call<Page<InvoiceSummary>>("query_invoices", { query });
 }
 
 export async function createInvoice(request: CreateInvoiceRequest): Promise<string> {
-    return invoke<string>("create_invoice", { request });
+    return call<string>("create_invoice", { request });
 }
 
 export async function deleteInvoice(id: string): Promise<void> {
-    return invoke<void>("delete_invoice", { id });
+    return call<void>("delete_invoice", { id });
+}
+
+export async function setInvoiceArchived(id: string, archived: boolean): Promise<void> {
+    return call<void>("set_invoice_archived", { id, archived });
 }
 
 export async function updateInvoiceStatus(id: string, status: string): Promise<void> {
-    return invoke<void>("update_invoice_status", { id, status });
+    return call<void>("update_invoice_status", { id, status });
 }
 
 // ─── Analytics API ───────────────────────────────────────────
 
 export async function getAnalytics(): Promise<RevenueMetrics> {
-    return invoke<RevenueMetrics>("get_analytics");
+    return call<RevenueMetrics>("get_analytics");
 }
 
 // ─── PDF API ─────────────────────────────────────────────────
 
 export async function generatePdf(id: string): Promise<string> {
-    return invoke<string>("generate_pdf", { invoiceId: id });
+    return call<string>("generate_pdf", { invoiceId: id });
 }
 
 export async function openPdf(path: string): Promise<void> {
-    return invoke<void>("open_pdf", { path });
+    return call<void>("open_pdf", { path });
+}
+
+export interface ValidationIssue {
+    rule: string;
+    field: string;
+    message: string;
+}
+
+export async function exportUbl(id: string): Promise<string> {
+    return call<string>("export_ubl", { invoiceId: id });
+}
+
+export async function validateEinvoice(id: string): Promise<ValidationIssue[]> {
+    return call<ValidationIssue[]>("validate_einvoice", { invoiceId: id });
+}
+
+export interface GstRegistration {
+    irn: string;
+    ack_no: string;
+    ack_date: string;
+    signed_qr: string;
+}
+
+export async function exportGstEinvoice(id: string): Promise<string> {
+    return call<string>("export_gst_einvoice", { invoiceId: id });
+}
+
+export async function validateGstEinvoice(id: string): Promise<ValidationIssue[]> {
+    return call<ValidationIssue[]>("validate_gst_einvoice", { invoiceId: id });
+}
+
+export async function recordGstRegistration(id: string, response: string): Promise<GstRegistration> {
+    return call<GstRegistration>("record_gst_registration", { invoiceId: id, response });
+}
+
+export async function previewUpiQr(upiId: string, payee: string, amount: number, note: string): Promise<string | null> {
+    return call<string | null>("preview_upi_qr", { upiId, payee, amount, note });
+}
+
+export interface BrowserDetection {
+    /** Absent when no browser was found. */
+    path: string | null;
+    /** Locations probed before giving up, or the configured path when it is missing. */
+    searched: string[];
+}
+
+export async function detectPdfBrowser(): Promise<BrowserDetection> {
+    return call<BrowserDetection>("detect_pdf_browser");
 }
 
 // ─── System & Data API ───────────────────────────────────────
 
+export interface CorruptRow {
+    table: string;
+    id: string;
+    problem: string;
+}
+
+export async function verifyDatabase(): Promise<CorruptRow[]> {
+    return call<CorruptRow[]>("verify_database");
+}
+
 export async function resetDatabase(): Promise<void> {
-    return invoke<void>("reset_database");
+    return call<void>("reset_database");
 }
 
 export async function exportData(path: string): Promise<void> {
-    return invoke<void>("export_data", { path });
+    return call<void>("export_data", { path });
+}
+
+/** What to do with an archived record that is already in the database. `rename` imports invoices whose number is taken under the next free number. */
+export type ConflictPolicy = "skip" | "overwrite" | "rename";
+
+export interface ImportChanges {
+    added: string[];
+    updated: string[];
+    skipped: string[];
+    renamed: { from: string; to: string }[];
+}
+
+export interface ImportReport {
+    dry_run: boolean;
+    profile: ImportChanges;
+    bank_accounts: ImportChanges;
+    custom_fields: ImportChanges;
+    clients: ImportChanges;
+    invoices: ImportChanges;
+    templates: ImportChanges;
+    assets: ImportChanges;
+}
+
+/** Imports an export file; with `dryRun` nothing is written and the report is a preview. */
+export async function importData(path: string, policy: ConflictPolicy, dryRun: boolean): Promise<ImportReport> {
+    return call<ImportReport>("import_data", { path, policy, dryRun });
+}
+
+
+// ─── Spreadsheet Import API ──────────────────────────────────
+
+/** `clients`: one client per row. `invoices`: one line item per row, grouped into invoices by number. */
+export type ImportTarget = "clients" | "invoices";
+export type DateFormat = "iso" | "day_month_year" | "month_day_year";
+
+export interface ColumnMapping {
+    target: ImportTarget;
+    /** Column header for each mapped field, keyed by field name such as `client_name`. */
+    columns: Record<string, string>;
+    date_format: DateFormat;
+}
+
+export interface SpreadsheetField {
+    field: string;
+    label: string;
+    required: boolean;
 }
 
+export interface SpreadsheetInfo {
+    headers: string[];
+    row_count: number;
+    sample: string[][];
+    fields: SpreadsheetField[];
+    suggested_mapping: ColumnMapping;
+}
+
+export interface RowError {
+    /** Spreadsheet line; the header line for mapping problems. */
+    line: number;
+    field: string | null;
+    message: string;
+}
+
+export interface ImportPlan {
+    clients: { id: string; name: string }[];
+    invoices: { id: string; number: string; currency: string; total: string; items: unknown[] }[];
+    errors: RowError[];
+}
+
+export interface ImportPreset {
+    id: string;
+    name: string;
+    mapping: ColumnMapping;
+}
+
+export async function readSpreadsheet(path: string, target: ImportTarget): Promise<SpreadsheetInfo> {
+    return call<SpreadsheetInfo>("read_spreadsheet", { path, target });
+}
+
+export async function previewSpreadsheetImport(path: string, mapping: ColumnMapping): Promise<ImportPlan> {
+    return call<ImportPlan>("preview_spreadsheet_import", { path, mapping });
+}
+
+/** Rejected with a validation error unless every row is valid. */
+export async function importSpreadsheet(path: string, mapping: ColumnMapping): Promise<ImportPlan> {
+    return call<ImportPlan>("import_spreadsheet", { path, mapping });
+}
+
+export async function listImportPresets(): Promise<ImportPreset[]> {
+    return call<ImportPreset[]>("list_import_presets");
+}
+
+export async function saveImportPreset(name: string, mapping: ColumnMapping): Promise<ImportPreset> {
+    return call<ImportPreset>("save_import_preset", { name, mapping });
+}
+
+export async function deleteImportPreset(id: string): Promise<void> {
+    return call<void>("delete_import_preset", { id });
+}
+
+
+// ─── Encryption API ──────────────────────────────────────────
+
+/** Where the database key comes from: nowhere (unencrypted), the OS keyring, or a passphrase typed at start. */
+export type KeySource = "none" | "keyring" | "passphrase";
+
+export interface EncryptionStatus {
+    key_source: KeySource;
+    /** `false` while the app waits for the passphrase. */
+    unlocked: boolean;
+}
+
+export async function getEncryptionStatus(): Promise<EncryptionStatus> {
+    return call<EncryptionStatus>("get_encryption_status");
+}
+
+export async function unlockDatabase(passphrase: string): Promise<void> {
+    return call<void>("unlock_database", { passphrase });
+}
+
+export async function changeDatabaseKey(keySource: KeySource, passphrase?: string): Promise<void> {
+    return call<void>("change_database_key", { keySource, passphrase: passphrase ?? null });
+}
+
+// ─── App Lock API ────────────────────────────────────────────
+
+export interface LockStatus {
+    /** Whether a lock passphrase or PIN is set. */
+    enabled: boolean;
+    locked: boolean;
+    /** Minutes of inactivity before the app locks; `null` locks only at start-up. */
+    auto_lock_minutes: number | null;
+}
+
+export async function getLockStatus(): Promise<LockStatus> {
+    return call<LockStatus>("get_lock_status");
+}
+
+export async function unlockApp(passphrase: string): Promise<void> {
+    return call<void>("unlock_app", { passphrase });
+}
+
+export async function lockApp(): Promise<void> {
+    return call<void>("lock_app");
+}
+
+export async function reportActivity(): Promise<void> {
+    return call<void>("report_activity");
+}
+
+/** Sets, changes or, with no `newPassphrase`, removes the lock passphrase. */
+export async function setAppPassphrase(currentPassphrase: string | null, newPassphrase: string | null): Promise<LockStatus> {
+    return call<LockStatus>("set_app_passphrase", { currentPassphrase, newPassphrase });
+}
+
+export async function setAutoLock(minutes: number | null): Promise<LockStatus> {
+    return call<LockStatus>("set_auto_lock", { minutes });
+}
+
+// ─── Backup API ──────────────────────────────────────────────
+
+export interface BackupRetention {
+    /** Newest backup of each of this many recent days is kept; likewise for weeks and months. */
+    daily: number;
+    weekly: number;
+    monthly: number;
+}
+
+export interface BackupConfig {
+    /** Whether backups are made on schedule. */
+    enabled: boolean;
+    /** `null` for the app data directory. */
+    directory: string | null;
+    interval_hours: number;
+    retention: BackupRetention;
+}
+
+export interface BackupManifest {
+    format_version: number;
+    schema_version: number;
+    created_at: string;
+    encrypted: boolean;
+    database_size: number;
+    database_sha256: string;
+}
+
+export interface BackupInfo {
+    path: string;
+    archive_size: number;
+    manifest: BackupManifest;
+}
+
+export async function getBackupConfig(): Promise<BackupConfig> {
+    return call<BackupConfig>("get_backup_config");
+}
+
+export async function saveBackupConfig(config: BackupConfig): Promise<BackupConfig> {
+    return call<BackupConfig>("save_backup_config", { config });
+}
+
+export async function listBackups(): Promise<BackupInfo[]> {
+    return call<BackupInfo[]>("list_backups");
+}
+
+export async function createBackup(): Promise<BackupInfo> {
+    return call<BackupInfo>("create_backup");
+}
+
+export async function verifyBackup(path: string): Promise<BackupManifest> {
+    return call<BackupManifest>("verify_backup", { path });
+}
+
+/** Replaces all data with the backup; reload the app afterwards. */
+export async function restoreBackup(path: string): Promise<void> {
+    return call<void>("restore_backup", { path });
+}
@@ -13,9 +13,17 @@ import {
     Briefcase,
     ArrowRight,
     Pencil,
-    AlertTriangle
+    AlertTriangle,
+    Hash,
+    Phone,
+    MapPin,
+    Archive,
+    ArchiveRestore,
+    GitMerge
 } from "lucide-react";
-import { getClients, createClient, deleteClient, updateClient, type ClientResponse } from "../lib/api";
+import { isApiError, getClients, createClient, deleteClient, updateClient, setClientArchived, findDuplicateClients, mergeClients, getCustomFields, searchCustomFields, customFieldValues, emptyAddress, type ClientResponse, type CreateClientRequest, type CustomFieldDefinition, type DuplicateMatch, type DuplicateReason } from "../lib/api";
+import type { Address } from "../store/settingsStore";
+import { CustomFieldInput } from "../components/ui/CustomFieldInput";
 
 const containerVariants = {
     hidden: { opacity: 0 },
@@ -27,6 +35,46 @@ const cardVariants = {
     visible: { opacity: 1, scale: 1, y: 0, transition: { duration: 0.5, type: 'spring', bounce: 0.4 } },
 };
 
+/** Form state for the parts of a client edited as structured values rather than refs. */
+type ClientDetails = Required<Pick<CreateClientRequest, "phone" | "address" | "shipping_address" | "tax_ids" | "contacts" | "notes">>;
+
+const detailsOf = (client: ClientResponse | null): ClientDetails => ({
+    phone: client?.phone ?? null,
+    address: client?.address ?? emptyAddress(),
+    shipping_address: client?.shipping_address ?? null,
+    tax_ids: client?.tax_ids ?? [],
+    contacts: client?.contacts ?? [],
+    notes: client?.notes ?? null,
+});
+
+const REASON_LABELS: Record<DuplicateReason, string> = {
+    SameName: "Same name",
+    SimilarName: "Similar name",
+    SameEmailDomain: "Same email domain",
+    SameTaxId: "Same tax ID",
+    SimilarTaxId: "Similar tax ID",
+};
+
+const rowStyle: React.CSSProperties = { display: 'flex', gap: '0.5rem', alignItems: 'center' };
+const linkButtonStyle: React.CSSProperties = { background: 'none', border: 'none', color: 'var(--primary)', cursor: 'pointer', fontSize: '0.85rem', display: 'flex', alignItems: 'center', gap: '4px', padding: 0 };
+const removeButtonStyle: React.CSSProperties = { background: 'none', border: 'none', color: 'var(--foreground)', opacity: 0.5, cursor: 'pointer', flexShrink: 0 };
+
+function AddressFields({ address, onChange }: { address: Address; onChange: (address: Address) => void }) {
+    const set = (key: keyof Address, optional = false) => (e: React.ChangeEvent<HTMLInputElement>) =>
+        onChange({ ...address, [key]: optional ? e.target.value || null : e.target.value });
+
+    return (
+        <div style={{ display: 'grid', gridTemplateColumns: '1fr 1fr', gap: '0.75rem' }}>
+            <input className="form-input" placeholder="Street address" value={address.line1} onChange={set('line1')} style={{ gridColumn: 'span 2' }} />
+            <input className="form-input" placeholder="Apartment, suite, floor" value={address.line2 || ''} onChange={set('line2', true)} style={{ gridColumn: 'span 2' }} />
+            <input className="form-input" placeholder="City" value={address.city} onChange={set('city')} />
+            <input className="form-input" placeholder="State / Region" value={address.state || ''} onChange={set('state', true)} />
+            <input className="form-input" placeholder="Postal code" value={address.postal_code} onChange={set('postal_code')} />
+            <input className="form-input" placeholder="Country" value={address.country} onChange={set('country')} />
+        </div>
+    );
+}
+
 export function Clients() {
     const [clients, setClients] = useState<ClientResponse[]>([]);
     const [loading, setLoading] = useState(true);
@@ -39,11 +87,19 @@ export function Clients() {
     const [saving, setSaving] = useState(false);
     const [clientToDelete, setClientToDelete] = useState<ClientResponse | null>(null);
     const [isDeleting, setIsDeleting] = useState(false);
+    const [fieldDefs, setFieldDefs] = useState<CustomFieldDefinition[]>([]);
+    const [fieldValues, setFieldValues] = useState<Record<string, string>>({});
+    const [fieldMatches, setFieldMatches] = useState<string[]>([]);
+    const [details, setDetails] = useState<ClientDetails>(detailsOf(null));
+    const [showArchived, setShowArchived] = useState(false);
+    const [duplicates, setDuplicates] = useState<DuplicateMatch[] | null>(null);
+    const [merging, setMerging] = useState(false);
 
     // Form refs
     const nameRef = useRef<HTMLInputElement>(null);
     const emailRef = useRef<HTMLInputElement>(null);
     const companyRef = useRef<HTMLInputElement>(null);
+    const taxIdRef = useRef<HTMLInputElement>(null);
 
     const fetchClients = async () => {
         try {
@@ -58,8 +114,35 @@ export function Clients() {
 
     useEffect(() => {
         fetchClients();
+        getCustomFields("Client").then(setFieldDefs).catch(console.error);
     }, []);
 
+    // Start the form from the client's custom field values, or the field defaults
+    useEffect(() => {
+        setFieldValues(customFieldValues(fieldDefs, editClient?.custom_fields));
+    }, [showCreate, editClient, fieldDefs]);
+
+    useEffect(() => {
+        setDetails(detailsOf(editClient));
+    }, [showCreate, editClient]);
+
+    const updateContact = (index: number, patch: Partial<ClientDetails["contacts"][number]>) => {
+        setDetails({
+            ...details,
+            contacts: details.contacts.map((c, i) =>
+                i === index ? { ...c, ...patch } : patch.is_primary ? { ...c, is_primary: false } : c
+            ),
+        });
+    };
+
+    // Custom field values are searched in the database
+    useEffect(() => {
+        const timeoutId = setTimeout(() => {
+            searchCustomFields("Client", search).then(setFieldMatches).catch(() => setFieldMatches([]));
+        }, 250);
+        return () => clearTimeout(timeoutId);
+    }, [search]);
+
     const handleCreate = async () => {
         const name = nameRef.current?.value?.trim();
         if (!name) return;
@@ -70,16 +153,20 @@ export function Clients() {
                 name,
                 email: emailRef.current?.value?.trim() || null,
                 company: companyRef.current?.value?.trim() || null,
+                tax_id: taxIdRef.current?.value?.trim() || null,
+                ...details,
+                custom_fields: fieldValues,
             });
             // Reset form fields
             if (nameRef.current) nameRef.current.value = '';
             if (emailRef.current) emailRef.current.value = '';
             if (companyRef.current) companyRef.current.value = '';
+            if (taxIdRef.current) taxIdRef.current.value = '';
             setShowCreate(false);
             await fetchClients();
         } catch (err) {
             console.error("Failed to create client:", err);
-            alert("Failed to create client. Please try again.");
+            alert(`Failed to create client: ${err}`);
         } finally {
             setCreating(false);
         }
@@ -101,12 +188,55 @@ export function Clients() {
             setClientToDelete(null);
         } catch (err) {
             console.error("Failed to delete client:", err);
-            alert("Failed to delete client. It may have invoices attached.");
+            if (isApiError(err, "client_has_invoices")) {
+                if (confirm(`${err.message}.\n\nArchive "${clientToDelete.name}" now?`)) {
+                    await setClientArchived(clientToDelete.id, true).catch((e) => alert(`Failed to archive client: ${e}`));
+                    await fetchClients();
+                }
+            } else {
+                alert(`${err}`);
+            }
+            setClientToDelete(null);
         } finally {
             setIsDeleting(false);
         }
     };
 
+    const handleToggleArchived = async (client: ClientResponse, e: React.MouseEvent) => {
+        e.stopPropagation();
+        setMenuId(null);
+        try {
+            await setClientArchived(client.id, !client.archived_at);
+            await fetchClients();
+        } catch (err) {
+            alert(`Failed to ${client.archived_at ? "restore" : "archive"} client: ${err}`);
+        }
+    };
+
+    const handleFindDuplicates = async () => {
+        try {
+            setDuplicates(await findDuplicateClients());
+        } catch (err) {
+            alert(`Failed to look for duplicates: ${err}`);
+        }
+    };
+
+    /** Keeps `target` and folds `source` (its invoices, notes and contacts) into it. */
+    const handleMerge = async (source: ClientResponse, target: ClientResponse) => {
+        if (!confirm(`Merge "${source.name}" into "${target.name}"? "${source.name}" will be deleted and its invoices moved.`)) return;
+        setMerging(true);
+        try {
+            await mergeClients(source.id, target.id);
+            if (editClient?.id === source.id) setEditClient(null);
+            await fetchClients();
+            setDuplicates(await findDuplicateClients());
+        } catch (err) {
+            alert(`Failed to merge clients: ${err}`);
+        } finally {
+            setMerging(false);
+        }
+    };
+
     const handleStartEdit = (client: ClientResponse, e: React.MouseEvent) => {
         e.stopPropagation();
         setEditClient(client);
@@ -125,22 +255,28 @@ export function Clients() {
                 name,
                 email: emailRef.current?.value?.trim() || null,
                 company: companyRef.current?.value?.trim() || null,
+                tax_id: taxIdRef.current?.value?.trim() || null,
+                ...details,
+                custom_fields: fieldValues,
             });
             setEditClient(null);
             await fetchClients();
         } catch (err) {
             console.error("Failed to update client:", err);
-            alert("Failed to update client. Please try again.");
+            alert(`Failed to update client: ${err}`);
         } finally {
             setSaving(false);
         }
     };
 
-    const filtered = clients.filter(
+    const activeClients = clients.filter((c) => !c.archived_at);
+    const archivedCount = clients.length - activeClients.length;
+    const filtered = (showArchived ? clients.filter((c) => c.archived_at) : activeClients).filter(
         (c) =>
             c.name.toLowerCase().includes(search.toLowerCase()) ||
             (c.company || "").toLowerCase().includes(search.toLowerCase()) ||
-            (c.email || "").toLowerCase().includes(search.toLowerCase())
+            (c.email || "").toLowerCase().includes(search.toLowerCase()) ||
+            fieldMatches.includes(c.id)
     );
 
     // Close context menu when clicking outside
@@ -172,7 +308,7 @@ export function Clients() {
             >
                 <div>
                     <h1 className="text-gradient" style={{ fontSize: '2.5rem', fontWeight: 800, letterSpacing: '-0.02em', marginBottom: '0.5rem' }}>Client Hub</h1>
-                    <p style={{ color: 'var(--text-tertiary)', fontSize: '1.1rem' }}>Manage your professional network. <span style={{ color: 'var(--primary)', fontWeight: 600 }}>{clients.length} Active Connections</span></p>
+                    <p style={{ color: 'var(--text-tertiary)', fontSize: '1.1rem' }}>Manage your professional network. <span style={{ color: 'var(--primary)', fontWeight: 600 }}>{activeClients.length} Active Connections</span></p>
                 </div>
                 <div className="flex flex-col sm:flex-row gap-4 w-full sm:w-auto items-stretch sm:items-center">
                     <div className="relative w-full sm:w-[300px]">
@@ -182,12 +318,28 @@ export function Clients() {
                         />
                         <input
                             className="form-input glass-panel w-full"
-                            placeholder="Search by name, company, or email..."
+                            placeholder="Search by name, company, email or custom field..."
                             value={search}
                             onChange={(e) => setSearch(e.target.value)}
                             style={{ paddingLeft: '44px', borderRadius: 'var(--radius-xl)', height: '3rem', border: '1px solid rgba(255,255,255,0.08)' }}
                         />
                     </div>
+                    <button
+                        className="btn btn-ghost glass-panel"
+                        onClick={handleFindDuplicates}
+                        style={{ height: '3rem', padding: '0 1.25rem', borderRadius: 'var(--radius-xl)', gap: '8px' }}
+                    >
+                        <GitMerge size={16} /> Duplicates
+                    </button>
+                    {(archivedCount > 0 || showArchived) && (
+                        <button
+                            className="btn btn-ghost glass-panel"
+                            onClick={() => setShowArchived(!showArchived)}
+                            style={{ height: '3rem', padding: '0 1.25rem', borderRadius: 'var(--radius-xl)', gap: '8px' }}
+                        >
+                            <Archive size={16} /> {showArchived ? "Back to active" : `Archived (${archivedCount})`}
+                        </button>
+                    )}
                     <motion.button
                         className="btn btn-primary glass-panel"
                         onClick={() => setShowCreate(true)}
@@ -332,6 +484,21 @@ export function Clients() {
                                                             >
                                                                 <Pencil size={16} /> Edit Client
                                                             </button>
+                                                            <button
+                                                                className="btn btn-ghost"
+                                                                style={{
+                                                                    width: "100%",
+                                                                    justifyContent: "flex-start",
+                                                                    color: "var(--foreground)",
+                                                                    fontSize: "0.9rem",
+                                                                    gap: '10px'
+                                                                }}
+                                                                onClick={(e) => handleToggleArchived(client, e)}
+                                                            >
+                                                                {client.archived_at
+                                                                    ? <><ArchiveRestore size={16} /> Restore Client</>
+                                                                    : <><Archive size={16} /> Archive Client</>}
+                                                            </button>
                                                             <button
                                                                 className="btn btn-ghost"
                                                                 style={{
@@ -426,7 +593,7 @@ export function Clients() {
                             animate={{ opacity: 1, scale: 1, y: 0, rotateX: 0 }}
                             exit={{ opacity: 0, scale: 0.9, y: 40, rotateX: -10 }}
                             transition={{ duration: 0.4, type: 'spring', bounce: 0.3 }}
-                            style={{ width: '100%', maxWidth: '500px', borderRadius: 'var(--radius-2xl)', overflow: 'hidden', boxShadow: '0 25px 50px -12px rgba(0,0,0,0.7)', perspective: '1000px' }}
+                            style={{ width: '100%', maxWidth: '560px', maxHeight: 'calc(100vh - 2rem)', display: 'flex', flexDirection: 'column', borderRadius: 'var(--radius-2xl)', overflow: 'hidden', boxShadow: '0 25px 50px -12px rgba(0,0,0,0.7)', perspective: '1000px' }}
                             onClick={(e) => e.stopPropagation()}
                         >
                             <div style={{ padding: '2rem 2.5rem', borderBottom: '1px solid color-mix(in srgb, var(--foreground) 10%, transparent)', position: 'relative' }}>
@@ -454,7 +621,7 @@ export function Clients() {
                                 </div>
                             </div>
 
-                            <div style={{ padding: '2rem 2.5rem', display: 'flex', flexDirection: 'column', gap: '1.5rem', background: 'color-mix(in srgb, var(--foreground) 3%, transparent)' }}>
+                            <div style={{ padding: '2rem 2.5rem', display: 'flex', flexDirection: 'column', gap: '1.5rem', background: 'color-mix(in srgb, var(--foreground) 3%, transparent)', overflowY: 'auto' }}>
                                 <div className="form-group" style={{ marginBottom: 0 }}>
                                     <label className="form-label">Client / Contact Name <span style={{ color: 'var(--primary)' }}>*</span></label>
                                     <div style={{ position: 'relative' }}>
@@ -476,6 +643,103 @@ export function Clients() {
                                         <input ref={companyRef} className="form-input" placeholder="e.g. Cyberdyne Systems" style={{ paddingLeft: '44px' }} defaultValue={editClient?.company || ''} key={editClient ? `edit-company-${editClient.id}` : 'create-company'} />
                                     </div>
                                 </div>
+                                <div className="form-group" style={{ marginBottom: 0 }}>
+                                    <label className="form-label">Tax ID (VAT / GSTIN)</label>
+                                    <div style={{ position: 'relative' }}>
+                                        <Hash size={16} style={{ position: 'absolute', left: 16, top: '50%', transform: 'translateY(-50%)', color: 'var(--foreground)', opacity: 0.5 }} />
+                                        <input ref={taxIdRef} className="form-input" placeholder="e.g. 27AAPFU0939F1ZV" style={{ paddingLeft: '44px' }} defaultValue={editClient?.tax_id || ''} key={editClient ? `edit-taxid-${editClient.id}` : 'create-taxid'} />
+                                    </div>
+                                </div>
+                                <div className="form-group" style={{ marginBottom: 0 }}>
+                                    <label className="form-label">Other Tax IDs</label>
+                                    <div style={{ display: 'flex', flexDirection: 'column', gap: '0.5rem' }}>
+                                        {details.tax_ids.map((taxId, index) => (
+                                            <div style={rowStyle} key={index}>
+                                                <input className="form-input" placeholder="Scheme, e.g. PAN" value={taxId.scheme} style={{ flex: 1 }}
+                                                    onChange={(e) => setDetails({ ...details, tax_ids: details.tax_ids.map((t, i) => i === index ? { ...t, scheme: e.target.value } : t) })} />
+                                                <input className="form-input" placeholder="Number" value={taxId.value} style={{ flex: 2 }}
+                                                    onChange={(e) => setDetails({ ...details, tax_ids: details.tax_ids.map((t, i) => i === index ? { ...t, value: e.target.value } : t) })} />
+                                                <button type="button" style={removeButtonStyle} onClick={() => setDetails({ ...details, tax_ids: details.tax_ids.filter((_, i) => i !== index) })}>
+                                                    <X size={16} />
+                                                </button>
+                                            </div>
+                                        ))}
+                                        <button type="button" style={linkButtonStyle} onClick={() => setDetails({ ...details, tax_ids: [...details.tax_ids, { scheme: '', value: '' }] })}>
+                                            <Plus size={14} /> Add tax ID
+                                        </button>
+                                    </div>
+                                </div>
+                                <div className="form-group" style={{ marginBottom: 0 }}>
+                                    <label className="form-label">Phone</label>
+                                    <div style={{ position: 'relative' }}>
+                                        <Phone size={16} style={{ position: 'absolute', left: 16, top: '50%', transform: 'translateY(-50%)', color: 'var(--foreground)', opacity: 0.5 }} />
+                                        <input className="form-input" type="tel" placeholder="+1 555 0100" style={{ paddingLeft: '44px' }} value={details.phone || ''} onChange={(e) => setDetails({ ...details, phone: e.target.value || null })} />
+                                    </div>
+                                </div>
+                                <div className="form-group" style={{ marginBottom: 0 }}>
+                                    <label className="form-label" style={{ display: 'flex', alignItems: 'center', gap: '6px' }}><MapPin size={14} /> Billing Address</label>
+                                    <AddressFields address={details.address} onChange={(address) => setDetails({ ...details, address })} />
+                                </div>
+                                <div className="form-group" style={{ marginBottom: 0 }}>
+                                    <label className="form-label" style={{ display: 'flex', alignItems: 'center', gap: '8px', cursor: 'pointer' }}>
+                                        <input
+                                            type="checkbox"
+                                            checked={details.shipping_address !== null}
+                                            onChange={(e) => setDetails({ ...details, shipping_address: e.target.checked ? emptyAddress() : null })}
+                                        />
+                                        Ship to a different address
+                                    </label>
+                                    {details.shipping_address && (
+                                        <AddressFields address={details.shipping_address} onChange={(shipping_address) => setDetails({ ...details, shipping_address })} />
+                                    )}
+                                </div>
+                                <div className="form-group" style={{ marginBottom: 0 }}>
+                                    <label className="form-label">Contacts</label>
+                                    <div style={{ display: 'flex', flexDirection: 'column', gap: '1rem' }}>
+                                        {details.contacts.map((contact, index) => (
+                                            <div key={contact.id ?? `new-${index}`} style={{ display: 'grid', gridTemplateColumns: '1fr 1fr auto', gap: '0.5rem', alignItems: 'center' }}>
+                                                <input className="form-input" placeholder="Name" value={contact.name} onChange={(e) => updateContact(index, { name: e.target.value })} />
+                                                <input className="form-input" placeholder="Role, e.g. Accounts payable" value={contact.role || ''} onChange={(e) => updateContact(index, { role: e.target.value || null })} />
+                                                <button type="button" style={removeButtonStyle} onClick={() => setDetails({ ...details, contacts: details.contacts.filter((_, i) => i !== index) })}>
+                                                    <X size={16} />
+                                                </button>
+                                                <input className="form-input" type="email" placeholder="Email" value={contact.email || ''} onChange={(e) => updateContact(index, { email: e.target.value || null })} />
+                                                <input className="form-input" type="tel" placeholder="Phone" value={contact.phone || ''} onChange={(e) => updateContact(index, { phone: e.target.value || null })} />
+                                                <label title="Primary contact" style={{ display: 'flex', alignItems: 'center', gap: '4px', fontSize: '0.75rem', opacity: 0.8, cursor: 'pointer' }}>
+                                                    <input type="radio" name="primary-contact" checked={contact.is_primary} onChange={() => updateContact(index, { is_primary: true })} />
+                                                    Primary
+                                                </label>
+                                            </div>
+                                        ))}
+                                        <button
+                                            type="button"
+                                            style={linkButtonStyle}
+                                            onClick={() => setDetails({
+                                                ...details,
+                                                contacts: [...details.contacts, { id: null, name: '', email: null, phone: null, role: null, is_primary: details.contacts.length === 0 }],
+                                            })}
+                                        >
+                                            <Plus size={14} /> Add contact
+                                        </button>
+                                    </div>
+                                </div>
+                                <div className="form-group" style={{ marginBottom: 0 }}>
+                                    <label className="form-label">Notes</label>
+                                    <textarea className="form-input" rows={3} placeholder="Internal notes about this client" value={details.notes || ''} onChange={(e) => setDetails({ ...details, notes: e.target.value || null })} />
+                                </div>
+                                {fieldDefs.map((field) => (
+                                    <div className="form-group" style={{ marginBottom: 0 }} key={field.id}>
+                                        <label className="form-label">
+                                            {field.name} {field.required && <span style={{ color: 'var(--primary)' }}>*</span>}
+                                        </label>
+                                        <CustomFieldInput
+                                            field={field}
+                                            value={fieldValues[field.id || ""] ?? ""}
+                                            onChange={(value) => setFieldValues({ ...fieldValues, [field.id || ""]: value })}
+                                            className="form-input"
+                                        />
+                                    </div>
+                                ))}
                             </div>
 
                             <div style={{ padding: '1.5rem 2.5rem', borderTop: '1px solid color-mix(in srgb, var(--foreground) 10%, transparent)', display: 'flex', justifyContent: 'flex-end', gap: '1rem', background: 'color-mix(in srgb, var(--foreground) 2%, transparent)' }}>
@@ -504,6 +768,60 @@ export function Clients() {
                     </motion.div>
                 )}
             </AnimatePresence>
+            {/* Duplicate Clients Modal */}
+            <AnimatePresence>
+                {duplicates && (
+                    <motion.div
+                        className="modal-overlay"
+                        initial={{ opacity: 0 }}
+                        animate={{ opacity: 1 }}
+                        exit={{ opacity: 0 }}
+                        style={{ position: 'fixed', inset: 0, background: 'color-mix(in srgb, var(--background) 85%, transparent)', zIndex: 200, display: 'flex', alignItems: 'center', justifyContent: 'center', padding: '1rem' }}
+                        onClick={() => !merging && setDuplicates(null)}
+                    >
+                        <motion.div
+                            className="glass-panel"
+                            initial={{ opacity: 0, scale: 0.95, y: 20 }}
+                            animate={{ opacity: 1, scale: 1, y: 0 }}
+                            exit={{ opacity: 0, scale: 0.95, y: 20 }}
+                            style={{ width: '100%', maxWidth: '640px', maxHeight: '85vh', overflowY: 'auto', borderRadius: 'var(--radius-3xl)', padding: '2rem', background: 'color-mix(in srgb, var(--background) 60%, transparent)', backdropFilter: 'blur(20px)' }}
+                            onClick={(e) => e.stopPropagation()}
+                        >
+                            <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center', marginBottom: '1.5rem' }}>
+                                <h2 style={{ fontSize: '1.5rem', fontWeight: 800, margin: 0, color: 'var(--foreground)' }}>Possible Duplicates</h2>
+                                <button className="btn btn-ghost" onClick={() => setDuplicates(null)} disabled={merging}><X size={18} /></button>
+                            </div>
+                            {duplicates.length === 0 && (
+                                <p style={{ color: 'var(--text-tertiary)', margin: 0 }}>No clients look like duplicates of each other.</p>
+                            )}
+                            {duplicates.map((match) => {
+                                const first = clients.find((c) => c.id === match.client_id);
+                                const second = clients.find((c) => c.id === match.duplicate_id);
+                                if (!first || !second) return null;
+                                return (
+                                    <div key={`${match.client_id}-${match.duplicate_id}`} style={{ padding: '1rem', marginBottom: '0.75rem', borderRadius: 'var(--radius-xl)', border: '1px solid rgba(255,255,255,0.08)' }}>
+                                        <div style={{ display: 'flex', justifyContent: 'space-between', gap: '1rem', marginBottom: '0.5rem' }}>
+                                            <span style={{ fontWeight: 600, color: 'var(--foreground)' }}>{first.name} · {second.name}</span>
+                                            <span style={{ color: 'var(--text-tertiary)', whiteSpace: 'nowrap' }}>{match.score}% match</span>
+                                        </div>
+                                        <div style={{ color: 'var(--text-tertiary)', fontSize: '0.85rem', marginBottom: '0.75rem' }}>
+                                            {match.reasons.map((reason) => REASON_LABELS[reason]).join(', ')}
+                                        </div>
+                                        <div style={{ display: 'flex', gap: '0.5rem', flexWrap: 'wrap' }}>
+                                            <button className="btn btn-secondary" disabled={merging} onClick={() => handleMerge(second, first)}>
+                                                {merging ? <Loader2 size={14} className="animate-spin" /> : <GitMerge size={14} />} Keep {first.name}
+                                            </button>
+                                            <button className="btn btn-secondary" disabled={merging} onClick={() => handleMerge(first, second)}>
+                                                {merging ? <Loader2 size={14} className="animate-spin" /> : <GitMerge size={14} />} Keep {second.name}
+                                            </button>
+                                        </div>
+                                    </div>
+                                );
+                            })}
+                        </motion.div>
+                    </motion.div>
+                )}
+            </AnimatePresence>
             {/* Premium Delete Confirmation Modal */}
             <AnimatePresence>
                 {clientToDelete && (
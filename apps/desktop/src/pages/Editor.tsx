@@ -27,8 +27,19 @@ export function Editor() {
         if (savedDraft) {
             try {
                 const parsed = JSON.parse(savedDraft);
-                const parsedNotes = parsed.notes ? JSON.parse(parsed.notes) : null;
-                const hasProjectDetailsChanges = parsedNotes?.projectDetails?.some((detail: any) => detail.value !== "");
+                // Drafts saved by older versions kept the invoice metadata as JSON in notes
+                if (typeof parsed.notes === "string" && parsed.notes.startsWith("{")) {
+                    const legacy = JSON.parse(parsed.notes);
+                    Object.assign(parsed, {
+                        notes: legacy.notes || null,
+                        developer: legacy.developer,
+                        logoPath: legacy.logoPath,
+                        qrCodeUrl: legacy.qrCodeUrl,
+                        paymentNote: legacy.paymentTermsNote,
+                        projectDetails: legacy.projectDetails,
+                    });
+                }
+                const hasProjectDetailsChanges = parsed.projectDetails?.some((detail: any) => detail.value !== "");
 
                 // Check if the draft actually has meaningful changes compared to a brand new one
                 const hasChanges =
@@ -74,7 +85,14 @@ export function Editor() {
                 notes: data.notes || null,
                 status: status,
                 issue_date: data.issueDate,
-                due_date: data.dueDate
+                due_date: data.dueDate,
+                bank_account_id: data.bankAccountId,
+                developer: data.developer || null,
+                logo_path: data.logoPath,
+                qr_code_url: data.qrCodeUrl,
+                payment_note: data.paymentNote || null,
+                project_details: data.projectDetails.filter((d) => d.value.trim() !== ""),
+                custom_fields: data.customFields
             });
             setSaved(true);
             localStorage.removeItem("invoice_draft");
@@ -83,7 +101,7 @@ export function Editor() {
             }, 1200);
         } catch (err) {
             console.error("Save failed:", err);
-            alert("Failed to save invoice. Please try again.");
+            alert(`Failed to save invoice: ${err}`);
         } finally {
             setSaving(false);
         }
@@ -7,6 +7,8 @@ import {
     MoreHorizontal,
     FileText,
     Trash2,
+    Archive,
+    ArchiveRestore,
     X,
     Loader2,
     Download,
@@ -15,16 +17,24 @@ import {
     Clock,
     CheckCircle2,
     AlertCircle,
-    FilePlus2
+    FilePlus2,
+    FileCode,
+    QrCode
 } from "lucide-react";
 import {
     getInvoices,
     getClients,
     createInvoice,
     deleteInvoice,
+    setInvoiceArchived,
     updateInvoiceStatus,
     generatePdf,
+    exportUbl,
+    exportGstEinvoice,
+    recordGstRegistration,
     openPdf,
+    searchCustomFields,
+    queryInvoices,
     type InvoiceSummary,
     type ClientResponse,
     type CreateInvoiceRequest,
@@ -59,6 +69,7 @@ export function Invoices() {
     const [creating, setCreating] = useState(false);
     const [menuId, setMenuId] = useState<string | null>(null);
     const [previewInvoice, setPreviewInvoice] = useState<InvoiceSummary | null>(null);
+    const [fieldMatches, setFieldMatches] = useState<string[]>([]);
     const currency = useSettingsStore(state => state.profile?.default_currency) || "USD";
 
     // Download Modal State
@@ -99,6 +110,23 @@ export function Invoices() {
         fetchData();
     }, []);
 
+    // Notes, item descriptions and custom field values are searched in the database
+    useEffect(() => {
+        const timeoutId = setTimeout(() => {
+            if (!search.trim()) {
+                setFieldMatches([]);
+                return;
+            }
+            Promise.all([
+                searchCustomFields("Invoice", search),
+                queryInvoices({ search, limit: 500 }).then((page) => page.items.map((inv) => inv.id)),
+            ])
+                .then(([fieldIds, textIds]) => setFieldMatches([...fieldIds, ...textIds]))
+                .catch(() => setFieldMatches([]));
+        }, 250);
+        return () => clearTimeout(timeoutId);
+    }, [search]);
+
     // Click outside handler for more options menu
     useEffect(() => {
         const handleClickOutside = (e: MouseEvent) => {
@@ -165,6 +193,16 @@ export function Invoices() {
         }
     };
 
+    const handleToggleArchived = async (inv: InvoiceSummary) => {
+        try {
+            await setInvoiceArchived(inv.id, !inv.archived_at);
+            await fetchData();
+            setMenuId(null);
+        } catch (err) {
+            alert(`Failed to ${inv.archived_at ? "restore" : "archive"} invoice: ${err}`);
+        }
+    };
+
     const handleStatusChange = async (id: string, newStatus: string) => {
         try {
             await updateInvoiceStatus(id, newStatus);
@@ -204,6 +242,41 @@ export function Invoices() {
         }
     };
 
+    const handleExportUbl = async (id: string) => {
+        setMenuId(null);
+        try {
+            const path = await exportUbl(id);
+            alert(`Peppol UBL invoice saved to ${path}`);
+        } catch (err) {
+            console.error("UBL export failed:", err);
+            alert(`Could not export e-invoice:\n${err}`);
+        }
+    };
+
+    const handleExportGst = async (id: string) => {
+        setMenuId(null);
+        try {
+            const path = await exportGstEinvoice(id);
+            alert(`GST e-invoice JSON saved to ${path}`);
+        } catch (err) {
+            console.error("GST e-invoice export failed:", err);
+            alert(`Could not export GST e-invoice:\n${err}`);
+        }
+    };
+
+    const handleRecordIrn = async (id: string) => {
+        setMenuId(null);
+        const response = window.prompt("Paste the registration response from the IRP (JSON with Irn, AckNo, AckDt and SignedQRCode):");
+        if (!response?.trim()) return;
+        try {
+            const registration = await recordGstRegistration(id, response);
+            alert(`IRN recorded (Ack No ${registration.ack_no}). The signed QR will print on the invoice PDF.`);
+        } catch (err) {
+            console.error("Recording IRN failed:", err);
+            alert(`Could not record IRN:\n${err}`);
+        }
+    };
+
     const getDisplayStatus = (inv: InvoiceSummary) => {
         if (inv.status !== 'Paid' && inv.status !== 'Cancelled') {
             if (inv.due_date) {
@@ -252,8 +325,11 @@ export function Invoices() {
     const filtered = invoices.filter((inv) => {
         const matchesSearch =
             inv.number.toLowerCase().includes(search.toLowerCase()) ||
-            clientName(inv.client_id).toLowerCase().includes(search.toLowerCase());
-        const matchesFilter = !filterStatus || getDisplayStatus(inv).toLowerCase() === filterStatus;
+            clientName(inv.client_id).toLowerCase().includes(search.toLowerCase()) ||
+            fieldMatches.includes(inv.id);
+        const matchesFilter = filterStatus === "archived"
+            ? !!inv.archived_at
+            : !inv.archived_at && (!filterStatus || getDisplayStatus(inv).toLowerCase() === filterStatus);
         return matchesSearch && matchesFilter;
     });
 
@@ -264,6 +340,7 @@ export function Invoices() {
         { id: "pending", label: "Pending", icon: Clock },
         { id: "overdue", label: "Overdue", icon: AlertCircle },
         { id: "draft", label: "Draft", icon: FilePlus2 },
+        { id: "archived", label: "Archived", icon: Archive },
     ];
 
     const statusConfig = (status: string) => {
@@ -342,7 +419,7 @@ export function Invoices() {
                         />
                         <input
                             className="form-input"
-                            placeholder="Search by invoice number or client name..."
+                            placeholder="Search by number, client, item, notes or custom field..."
                             value={search}
                             onChange={(e) => setSearch(e.target.value)}
                             style={{ paddingLeft: '44px', borderRadius: 'var(--radius-xl)' }}
@@ -526,6 +603,27 @@ export function Invoices() {
                                                                     >
                                                                         <Download size={16} /> Download PDF
                                                                     </button>
+                                                                    <button
+                                                                        className="btn btn-ghost"
+                                                                        style={{ width: "100%", justifyContent: "flex-start", fontSize: "0.9rem", height: 36, gap: '10px', color: 'var(--text-primary)' }}
+                                                                        onClick={() => handleExportUbl(inv.id)}
+                                                                    >
+                                                                        <FileCode size={16} /> Export UBL (Peppol)
+                                                                    </button>
+                                                                    <button
+                                                                        className="btn btn-ghost"
+                                                                        style={{ width: "100%", justifyContent: "flex-start", fontSize: "0.9rem", height: 36, gap: '10px', color: 'var(--text-primary)' }}
+                                                                        onClick={() => handleExportGst(inv.id)}
+                                                                    >
+                                                                        <FileCode size={16} /> Export GST e-Invoice
+                                                                    </button>
+                                                                    <button
+                                                                        className="btn btn-ghost"
+                                                                        style={{ width: "100%", justifyContent: "flex-start", fontSize: "0.9rem", height: 36, gap: '10px', color: 'var(--text-primary)' }}
+                                                                        onClick={() => handleRecordIrn(inv.id)}
+                                                                    >
+                                                                        <QrCode size={16} /> Record IRN
+                                                                    </button>
 
                                                                     <div style={{ height: '1px', background: 'rgba(255,255,255,0.05)', margin: '4px 0' }} />
 
@@ -547,6 +645,15 @@ export function Invoices() {
 
                                                                     <div style={{ height: '1px', background: 'rgba(255,255,255,0.05)', margin: '4px 0' }} />
 
+                                                                    <button
+                                                                        className="btn btn-ghost"
+                                                                        style={{ width: "100%", justifyContent: "flex-start", color: "var(--text-secondary)", fontSize: "0.9rem", height: 36, gap: '10px' }}
+                                                                        onClick={() => handleToggleArchived(inv)}
+                                                                    >
+                                                                        {inv.archived_at
+                                                                            ? <><ArchiveRestore size={16} /> Restore Invoice</>
+                                                                            : <><Archive size={16} /> Archive Invoice</>}
+                                                                    </button>
                                                                     <button
                                                                         className="btn btn-ghost"
                                                                         style={{ width: "100%", justifyContent: "flex-start", color: "var(--color-soft-coral)", fontSize: "0.9rem", height: 36, gap: '10px' }}
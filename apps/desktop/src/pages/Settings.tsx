@@ -1,4 +1,4 @@
-import { useState, ReactNode } from "react";
+import { useState, useEffect, ReactNode } from "react";
 import { motion, AnimatePresence, useMotionTemplate, useMotionValue } from "framer-motion";
 import {
     Building2,
@@ -29,12 +29,19 @@ import {
     Moon,
     Sun,
     Monitor,
-    FolderOpen
+    FolderOpen,
+    Plus,
+    Trash2,
+    ListPlus,
+    Lock,
+    KeyRound,
+    Download,
+    FileSpreadsheet
 } from "lucide-react";
-import { invoke } from "@tauri-apps/api/core";
 import { open, save } from '@tauri-apps/plugin-dialog';
-import { useSettingsStore, BusinessProfile, BankDetails } from "../store/settingsStore";
-import { resetDatabase, exportData } from "../lib/api";
+import { SpreadsheetImport } from "../components/SpreadsheetImport";
+import { useSettingsStore, BusinessProfile, BankAccount, emptyBankAccount, defaultBankAccount } from "../store/settingsStore";
+import { call, isApiError, resetDatabase, exportData, verifyDatabase, getCustomFields, saveCustomField, deleteCustomField, getEncryptionStatus, changeDatabaseKey, setAppPassphrase, setAutoLock, lockApp, getBackupConfig, saveBackupConfig, listBackups, createBackup, verifyBackup, restoreBackup, importData, type CustomFieldDefinition, type CorruptRow, type KeySource, type BackupConfig, type BackupInfo, type BackupRetention, type ConflictPolicy, type ImportReport, type ImportChanges } from "../lib/api";
 
 // --- Types ---
 interface SectionHeader {
@@ -82,7 +89,7 @@ function SpotlightCard({ children, className = "", spotlightColor = "var(--prima
     );
 }
 
-function PremiumInput({ label, type = "text", placeholder, value, onChange, rows, icon: Icon }: { label: string; type?: string; placeholder?: string; value?: string; onChange?: (e: React.ChangeEvent<HTMLInputElement | HTMLTextAreaElement>) => void; rows?: number; icon?: any }) {
+function PremiumInput({ label, type = "text", placeholder, value, onChange, rows, icon: Icon, error }: { label: string; type?: string; placeholder?: string; value?: string; onChange?: (e: React.ChangeEvent<HTMLInputElement | HTMLTextAreaElement>) => void; rows?: number; icon?: any; error?: string }) {
     const [focused, setFocused] = useState(false);
     const hasValue = !!value;
 
@@ -115,7 +122,7 @@ function PremiumInput({ label, type = "text", placeholder, value, onChange, rows
                             onChange={onChange}
                             onFocus={() => setFocused(true)}
                             onBlur={() => setFocused(false)}
-                            style={focused ? { borderColor: 'var(--primary)', boxShadow: '0 0 0 1px var(--primary), 0 0 15px color-mix(in srgb, var(--primary) 20%, transparent)' } : {}}
+                            style={focused ? { borderColor: 'var(--primary)', boxShadow: '0 0 0 1px var(--primary), 0 0 15px color-mix(in srgb, var(--primary) 20%, transparent)' } : error ? { borderColor: 'rgb(239 68 68)' } : {}}
                         />
                         {Icon && (
                             <Icon
@@ -134,6 +141,7 @@ function PremiumInput({ label, type = "text", placeholder, value, onChange, rows
                     {label}
                 </label>
             </div>
+            {error && <p className="mt-1 px-1 text-xs text-red-400">{error}</p>}
         </motion.div>
     );
 }
@@ -196,7 +204,7 @@ function LogoUpload() {
 
     // Fetch existing logo on mount
     useState(() => {
-        invoke<string | null>('get_logo').then((data) => {
+        call<string | null>('get_logo').then((data) => {
             if (data) setPreviewUrl(data);
         }).catch(console.error);
     });
@@ -209,7 +217,7 @@ function LogoUpload() {
             const base64String = reader.result as string;
             setPreviewUrl(base64String);
             try {
-                await invoke('save_logo', { base64Data: base64String });
+                await call('save_logo', { base64Data: base64String });
             } catch (error) {
                 console.error("Failed to save logo:", error);
             }
@@ -229,7 +237,7 @@ function LogoUpload() {
         e.stopPropagation();
         setPreviewUrl(null);
         try {
-            await invoke('delete_logo');
+            await call('delete_logo');
         } catch (error) {
             console.error("Failed to delete logo:", error);
         }
@@ -305,7 +313,7 @@ function QRUpload() {
 
     // Fetch existing QR on mount
     useState(() => {
-        invoke<string | null>('get_qr').then((data) => {
+        call<string | null>('get_qr').then((data) => {
             if (data) setPreviewUrl(data);
         }).catch(console.error);
     });
@@ -318,7 +326,7 @@ function QRUpload() {
             const base64String = reader.result as string;
             setPreviewUrl(base64String);
             try {
-                await invoke('save_qr', { base64Data: base64String });
+                await call('save_qr', { base64Data: base64String });
             } catch (error) {
                 console.error("Failed to save QR:", error);
             }
@@ -338,7 +346,7 @@ function QRUpload() {
         e.stopPropagation();
         setPreviewUrl(null);
         try {
-            await invoke('delete_qr');
+            await call('delete_qr');
         } catch (error) {
             console.error("Failed to delete QR:", error);
         }
@@ -414,9 +422,33 @@ const sectionHeaders: SectionHeader[] = [
     { id: 'profile', icon: Building2, title: 'Business Profile', desc: 'Identity & Contact Info' },
     { id: 'invoicing', icon: CreditCard, title: 'Invoicing Data', desc: 'Currencies & Taxes' },
     { id: 'payments', icon: Wallet, title: 'Payments & Bank', desc: 'Accounts & QR Codes' },
+    { id: 'fields', icon: ListPlus, title: 'Custom Fields', desc: 'PO Numbers & Codes' },
     { id: 'system', icon: Database, title: 'System & Data', desc: 'Backup & Reset' },
 ];
 
+type ImportSection = Exclude<keyof ImportReport, 'dry_run'>;
+
+const IMPORT_SECTIONS: { key: ImportSection; label: string }[] = [
+    { key: 'profile', label: 'Business profile' },
+    { key: 'bank_accounts', label: 'Bank accounts' },
+    { key: 'custom_fields', label: 'Custom fields' },
+    { key: 'clients', label: 'Clients' },
+    { key: 'invoices', label: 'Invoices' },
+    { key: 'templates', label: 'Templates' },
+    { key: 'assets', label: 'Logo & QR code' },
+];
+
+/** "3 new, 1 replaced" style summary of one section of an import preview. */
+function describeChanges(changes: ImportChanges): string {
+    const parts = [
+        [changes.added.length, 'new'],
+        [changes.updated.length, 'replaced'],
+        [changes.renamed.length, 'renumbered'],
+        [changes.skipped.length, 'kept'],
+    ].filter(([count]) => count).map(([count, what]) => `${count} ${what}`);
+    return parts.length ? parts.join(', ') : 'nothing';
+}
+
 export function Settings() {
     const [activeSection, setActiveSection] = useState('preferences');
     const [isSaving, setIsSaving] = useState(false);
@@ -430,14 +462,62 @@ export function Settings() {
     // Export State
     const [isExporting, setIsExporting] = useState(false);
 
+    // Import State
+    const [importPath, setImportPath] = useState<string | null>(null);
+    const [importPolicy, setImportPolicy] = useState<ConflictPolicy>('skip');
+    const [importPreview, setImportPreview] = useState<ImportReport | null>(null);
+    const [isImporting, setIsImporting] = useState(false);
+    const [spreadsheetPath, setSpreadsheetPath] = useState<string | null>(null);
+
+    // Database Check State
+    const [isVerifying, setIsVerifying] = useState(false);
+    const [corruptRows, setCorruptRows] = useState<CorruptRow[] | null>(null);
+
+    // Encryption State
+    const [keySource, setKeySource] = useState<KeySource | null>(null);
+    const [keySourceDraft, setKeySourceDraft] = useState<KeySource>('none');
+    const [newPassphrase, setNewPassphrase] = useState('');
+    const [passphraseError, setPassphraseError] = useState<string | undefined>(undefined);
+    const [isChangingKey, setIsChangingKey] = useState(false);
+
+    // App Lock State
+    const lockStatus = useSettingsStore(state => state.lockStatus);
+    const setLockStatus = useSettingsStore(state => state.setLockStatus);
+    const [lockCurrent, setLockCurrent] = useState('');
+    const [lockNew, setLockNew] = useState('');
+    const [lockError, setLockError] = useState<{ field: string; message: string } | null>(null);
+    const [isSavingLock, setIsSavingLock] = useState(false);
+
+    // Backup State
+    const [backupConfig, setBackupConfig] = useState<BackupConfig | null>(null);
+    const [backups, setBackups] = useState<BackupInfo[]>([]);
+    const [isBackingUp, setIsBackingUp] = useState(false);
+    const [backupBusyPath, setBackupBusyPath] = useState<string | null>(null);
+    const [backupMessage, setBackupMessage] = useState<{ path: string; text: string; ok: boolean } | null>(null);
+
     const profile = useSettingsStore(state => state.profile);
-    const bankDetails = useSettingsStore(state => state.bankDetails);
+    const bankAccounts = useSettingsStore(state => state.bankAccounts);
     const updateSettings = useSettingsStore(state => state.updateSettings);
-    const updateBankDetails = useSettingsStore(state => state.updateBankDetails);
+    const saveBankAccount = useSettingsStore(state => state.saveBankAccount);
+    const deleteBankAccount = useSettingsStore(state => state.deleteBankAccount);
+
+    // Account being edited; until the user picks or edits one, the default account is shown
+    const [bankDraft, setBankDraft] = useState<BankAccount | null>(null);
+    // Validation error the backend reported for one of the bank account fields
+    const [bankFieldError, setBankFieldError] = useState<{ field: string; message: string } | null>(null);
+    const bankAccount = bankDraft ?? defaultBankAccount(bankAccounts, profile?.default_currency) ?? emptyBankAccount();
+
+    // Custom field definitions and the one being added
+    const emptyCustomField = (): CustomFieldDefinition => ({
+        id: null, name: "", field_type: "Text", entity: "Invoice", options: [], required: false, default_value: null, sort_order: 0,
+    });
+    const [customFields, setCustomFields] = useState<CustomFieldDefinition[]>([]);
+    const [fieldDraft, setFieldDraft] = useState<CustomFieldDefinition>(emptyCustomField());
 
     // Fetch initial data
     useState(() => {
-        useSettingsStore.getState().fetchBankDetails();
+        useSettingsStore.getState().fetchBankAccounts();
+        getCustomFields().then(setCustomFields).catch(console.error);
     });
 
     const handleSave = async () => {
@@ -445,13 +525,18 @@ export function Settings() {
         setIsSaving(true);
         try {
             await updateSettings(profile);
-            if (bankDetails) {
-                await updateBankDetails(bankDetails);
-            } else {
-                // Save empty bank details structure to signify no details initially
-                await updateBankDetails({
-                    accountHolder: "", accountNumber: "", ifscCode: "", bankName: "", branch: "", upiId: ""
-                });
+            if (bankDraft) {
+                try {
+                    setBankDraft(await saveBankAccount(bankDraft));
+                    setBankFieldError(null);
+                } catch (error) {
+                    if (isApiError(error, "validation") && error.field) {
+                        setBankFieldError({ field: error.field, message: error.message });
+                    } else {
+                        alert(`Bank account not saved: ${error}`);
+                    }
+                    return;
+                }
             }
             setShowSuccess(true);
             setTimeout(() => setShowSuccess(false), 2000);
@@ -462,7 +547,7 @@ export function Settings() {
         }
     };
 
-    const handleUpdateField = (field: keyof BusinessProfile, value: string) => {
+    const handleUpdateField = (field: keyof BusinessProfile, value: string | boolean) => {
         if (!profile) return;
         useSettingsStore.setState({ profile: { ...profile, [field]: value } });
     };
@@ -486,9 +571,52 @@ export function Settings() {
         useSettingsStore.setState({ profile: { ...profile, address: { ...profile.address, [field]: value } } });
     };
 
-    const handleUpdateBankField = (field: keyof BankDetails, value: string) => {
-        const currentDetails = bankDetails || { accountHolder: "", accountNumber: "", ifscCode: "", bankName: "", branch: "", upiId: "" };
-        useSettingsStore.setState({ bankDetails: { ...currentDetails, [field]: value } });
+    const handleUpdateBankField = (field: keyof BankAccount, value: string | boolean) => {
+        setBankDraft({ ...bankAccount, [field]: value });
+        if (bankFieldError?.field === field) setBankFieldError(null);
+    };
+
+    const bankError = (field: keyof BankAccount) => (bankFieldError?.field === field ? bankFieldError.message : undefined);
+
+    const handleDeleteBankAccount = async () => {
+        if (!bankAccount.id || !window.confirm(`Delete the bank account "${bankAccount.label || bankAccount.bank_name}"? Invoices already issued keep their PDF details.`)) return;
+        try {
+            await deleteBankAccount(bankAccount.id);
+            setBankDraft(null);
+        } catch (error) {
+            alert(`Failed to delete bank account: ${error}`);
+        }
+    };
+
+    const handleAddCustomField = async () => {
+        if (!fieldDraft.name.trim()) return;
+        try {
+            const sortOrder = customFields.filter((f) => f.entity === fieldDraft.entity).length;
+            await saveCustomField({ ...fieldDraft, sort_order: sortOrder });
+            setCustomFields(await getCustomFields());
+            setFieldDraft(emptyCustomField());
+        } catch (error) {
+            alert(`Custom field not saved: ${error}`);
+        }
+    };
+
+    const handleToggleRequired = async (field: CustomFieldDefinition) => {
+        try {
+            await saveCustomField({ ...field, required: !field.required });
+            setCustomFields(await getCustomFields());
+        } catch (error) {
+            alert(`Custom field not saved: ${error}`);
+        }
+    };
+
+    const handleDeleteCustomField = async (field: CustomFieldDefinition) => {
+        if (!field.id || !window.confirm(`Delete the custom field "${field.name}"? Its values are removed from every ${field.entity === "InvoiceItem" ? "line item" : field.entity.toLowerCase()}.`)) return;
+        try {
+            await deleteCustomField(field.id);
+            setCustomFields(await getCustomFields());
+        } catch (error) {
+            alert(`Failed to delete custom field: ${error}`);
+        }
     };
 
     const handleSelectPdfExportDir = async () => {
@@ -524,6 +652,191 @@ export function Settings() {
         }
     };
 
+    /** Previews importing `path` under `policy`; nothing is written until the import is confirmed. */
+    const previewImport = async (path: string, policy: ConflictPolicy) => {
+        setIsImporting(true);
+        setImportPreview(null);
+        try {
+            setImportPreview(await importData(path, policy, true));
+        } catch (err) {
+            alert(`Could not read the export: ${isApiError(err) ? err.message : err}`);
+            setImportPath(null);
+        } finally {
+            setIsImporting(false);
+        }
+    };
+
+    const handleChooseImport = async () => {
+        const selectedPath = await open({
+            title: "Import InvoiceFlow Data",
+            filters: [{ name: "JSON Export", extensions: ["json"] }],
+            multiple: false,
+        });
+        if (typeof selectedPath === 'string') {
+            setImportPath(selectedPath);
+            await previewImport(selectedPath, importPolicy);
+        }
+    };
+
+    const handleImportPolicyChange = async (policy: ConflictPolicy) => {
+        setImportPolicy(policy);
+        if (importPath) await previewImport(importPath, policy);
+    };
+
+    const handleConfirmImport = async () => {
+        if (!importPath) return;
+        setIsImporting(true);
+        try {
+            await importData(importPath, importPolicy, false);
+            window.location.reload();
+        } catch (err) {
+            alert(`Import failed, nothing was changed: ${isApiError(err) ? err.message : err}`);
+            setIsImporting(false);
+        }
+    };
+
+    const handleChooseSpreadsheet = async () => {
+        const selectedPath = await open({
+            title: "Import from Spreadsheet",
+            filters: [{ name: "Spreadsheet", extensions: ["csv", "xlsx", "xls", "ods"] }],
+            multiple: false,
+        });
+        if (typeof selectedPath === 'string') setSpreadsheetPath(selectedPath);
+    };
+
+    useEffect(() => {
+        getEncryptionStatus()
+            .then((status) => {
+                setKeySource(status.key_source);
+                setKeySourceDraft(status.key_source);
+            })
+            .catch((err) => console.error("Failed to load encryption status:", err));
+    }, []);
+
+    const handleChangeDatabaseKey = async () => {
+        setIsChangingKey(true);
+        setPassphraseError(undefined);
+        try {
+            await changeDatabaseKey(keySourceDraft, keySourceDraft === 'passphrase' ? newPassphrase : undefined);
+            setKeySource(keySourceDraft);
+            setNewPassphrase('');
+        } catch (err) {
+            if (isApiError(err) && err.field === 'passphrase') {
+                setPassphraseError(err.message);
+            } else {
+                alert(`Could not change the database key: ${err}`);
+            }
+        } finally {
+            setIsChangingKey(false);
+        }
+    };
+
+    useEffect(() => {
+        getBackupConfig().then(setBackupConfig).catch((err) => console.error("Failed to load backup settings:", err));
+        listBackups().then(setBackups).catch((err) => console.error("Failed to list backups:", err));
+    }, []);
+
+    const handleBackupConfigChange = async (changes: Partial<BackupConfig>) => {
+        if (!backupConfig) return;
+        try {
+            setBackupConfig(await saveBackupConfig({ ...backupConfig, ...changes }));
+            if ('directory' in changes) setBackups(await listBackups());
+        } catch (err) {
+            alert(`Could not save the backup settings: ${err}`);
+        }
+    };
+
+    const handleRetentionChange = (period: keyof BackupRetention, value: string) => {
+        if (!backupConfig) return;
+        const count = Math.max(0, Math.floor(Number(value) || 0));
+        handleBackupConfigChange({ retention: { ...backupConfig.retention, [period]: count } });
+    };
+
+    const handleSelectBackupDir = async () => {
+        const selectedPath = await open({ directory: true, multiple: false, title: "Select Backup Location" });
+        if (selectedPath) handleBackupConfigChange({ directory: selectedPath as string });
+    };
+
+    const handleBackupNow = async () => {
+        setIsBackingUp(true);
+        try {
+            await createBackup();
+            setBackups(await listBackups());
+        } catch (err) {
+            alert(`Backup failed: ${err}`);
+        } finally {
+            setIsBackingUp(false);
+        }
+    };
+
+    const handleVerifyBackup = async (path: string) => {
+        setBackupBusyPath(path);
+        try {
+            await verifyBackup(path);
+            setBackupMessage({ path, text: 'Backup is intact.', ok: true });
+        } catch (err) {
+            setBackupMessage({ path, text: String(err), ok: false });
+        } finally {
+            setBackupBusyPath(null);
+        }
+    };
+
+    const handleRestoreBackup = async (backup: BackupInfo) => {
+        const when = new Date(backup.manifest.created_at).toLocaleString();
+        if (!confirm(`Replace all current data with the backup from ${when}? The current database is kept as invoiceflow.db.pre-restore.`)) return;
+        setBackupBusyPath(backup.path);
+        try {
+            await restoreBackup(backup.path);
+            window.location.reload();
+        } catch (err) {
+            setBackupMessage({ path: backup.path, text: String(err), ok: false });
+            setBackupBusyPath(null);
+        }
+    };
+
+    /** Sets or changes the lock passphrase, or removes the lock when `remove` is set. */
+    const handleSaveLockPassphrase = async (remove: boolean) => {
+        setIsSavingLock(true);
+        setLockError(null);
+        try {
+            setLockStatus(await setAppPassphrase(lockStatus?.enabled ? lockCurrent : null, remove ? null : lockNew));
+            setLockCurrent('');
+            setLockNew('');
+        } catch (err) {
+            if (isApiError(err) && err.field) {
+                setLockError({ field: err.field, message: err.message });
+            } else {
+                alert(`Could not update the app lock: ${err}`);
+            }
+        } finally {
+            setIsSavingLock(false);
+        }
+    };
+
+    const handleAutoLockChange = async (value: string) => {
+        try {
+            setLockStatus(await setAutoLock(value === 'off' ? null : Number(value)));
+        } catch (err) {
+            alert(`Could not update the auto-lock: ${err}`);
+        }
+    };
+
+    const handleLockNow = async () => {
+        await lockApp();
+        if (lockStatus) setLockStatus({ ...lockStatus, locked: true });
+    };
+
+    const handleVerifyDatabase = async () => {
+        setIsVerifying(true);
+        try {
+            setCorruptRows(await verifyDatabase());
+        } catch (err) {
+            alert(`Database check failed: ${err}`);
+        } finally {
+            setIsVerifying(false);
+        }
+    };
+
     if (!profile) {
         return (
             <div className="flex-1 min-h-screen bg-[var(--background)] p-8 flex items-center justify-center">
@@ -722,8 +1035,8 @@ export function Settings() {
                                                     <PremiumInput
                                                         label="Save Invoices To"
                                                         icon={HardDrive}
-                                                        placeholder="C:\Users\jabir\Downloads"
-                                                        value={profile.pdf_export_dir || 'C:\\Users\\jabir\\Downloads'}
+                                                        placeholder="Downloads folder"
+                                                        value={profile.pdf_export_dir || ''}
                                                         onChange={(e) => handleUpdateField('pdf_export_dir', e.target.value)}
                                                     />
                                                 </div>
@@ -737,8 +1050,36 @@ export function Settings() {
                                                 </button>
                                             </div>
                                             <p className="text-xs text-[var(--text-muted)] mt-2">
-                                                Leave blank to default to your Downloads folder: <code>C:\Users\jabir\Downloads</code>.
+                                                Leave blank to default to your system Downloads folder.
                                             </p>
+
+                                            <div className="mt-6">
+                                                <PremiumInput
+                                                    label="File Name Pattern"
+                                                    icon={FileText}
+                                                    placeholder="Invoice_{number}.pdf"
+                                                    value={profile.pdf_filename_pattern || ''}
+                                                    onChange={(e) => handleUpdateField('pdf_filename_pattern', e.target.value)}
+                                                />
+                                            </div>
+                                            <p className="text-xs text-[var(--text-muted)] mt-2">
+                                                Use <code>{'{number}'}</code>, <code>{'{client}'}</code>, <code>{'{company}'}</code>, <code>{'{year}'}</code>, <code>{'{month}'}</code>, <code>{'{day}'}</code> or <code>{'{date}'}</code>. Slashes create sub-folders, e.g. <code>{'{client}/{year}/{number}.pdf'}</code>. Existing files are never overwritten.
+                                            </p>
+
+                                            <label className="mt-6 flex items-start gap-3 cursor-pointer">
+                                                <input
+                                                    type="checkbox"
+                                                    className="mt-1 h-4 w-4 accent-[var(--primary)]"
+                                                    checked={profile.pdf_archival}
+                                                    onChange={(e) => handleUpdateField('pdf_archival', e.target.checked)}
+                                                />
+                                                <span>
+                                                    <span className="block text-sm font-semibold text-[var(--foreground)]">Archival PDF/A-3 with Factur-X data</span>
+                                                    <span className="block text-xs text-[var(--text-muted)]">
+                                                        Embeds a machine-readable <code>factur-x.xml</code> (ZUGFeRD, EN 16931) so accounting systems can import invoices automatically. Requires the native PDF backend.
+                                                    </span>
+                                                </span>
+                                            </label>
                                         </div>
 
                                     </div>
@@ -915,7 +1256,7 @@ export function Settings() {
                                             <div className="flex-1">
                                                 <h4 className="font-semibold text-[var(--foreground)] mb-1">Scan to Pay QR Code</h4>
                                                 <p className="text-xs text-[var(--text-muted)] mb-3 leading-relaxed">
-                                                    Upload your UPI or Bank QR Code. <br />Shown at the bottom of the invoice.
+                                                    Invoices with a UPI ID get a QR for the exact amount due. <br />Upload a QR to show when no UPI ID is set.
                                                 </p>
                                                 <button
                                                     type="button"
@@ -928,48 +1269,261 @@ export function Settings() {
                                             </div>
                                         </div>
 
+                                        <div className="md:col-span-2 flex flex-wrap items-center gap-2">
+                                            {bankAccounts.map((account) => (
+                                                <button
+                                                    key={account.id}
+                                                    type="button"
+                                                    onClick={() => { setBankDraft(account); setBankFieldError(null); }}
+                                                    className={`px-4 py-2 rounded-xl text-sm font-semibold border transition-colors ${account.id === bankAccount.id ? 'border-[var(--primary)] text-[var(--primary)] bg-[var(--premium-bg-hover)]' : 'border-[var(--premium-border)] text-[var(--text-muted)] hover:text-[var(--foreground)]'}`}
+                                                >
+                                                    {account.label || account.bank_name || "Account"}
+                                                    {account.is_default && <span className="ml-2 text-[10px] uppercase tracking-wider">Default{account.currency ? ` · ${account.currency}` : ""}</span>}
+                                                </button>
+                                            ))}
+                                            <button
+                                                type="button"
+                                                onClick={() => { setBankDraft(emptyBankAccount()); setBankFieldError(null); }}
+                                                className="px-4 py-2 rounded-xl text-sm font-semibold border border-dashed border-[var(--premium-border)] text-[var(--text-muted)] hover:text-[var(--foreground)] flex items-center gap-2"
+                                            >
+                                                <Plus size={14} /> Add account
+                                            </button>
+                                            {bankAccount.id && (
+                                                <button
+                                                    type="button"
+                                                    onClick={handleDeleteBankAccount}
+                                                    className="ml-auto px-3 py-2 rounded-xl text-sm text-red-400 hover:text-red-300 flex items-center gap-2"
+                                                >
+                                                    <Trash2 size={14} /> Delete
+                                                </button>
+                                            )}
+                                        </div>
+
+                                        <PremiumInput
+                                            label="Account Label"
+                                            icon={Wallet}
+                                            placeholder="Main business account"
+                                            value={bankAccount.label}
+                                            onChange={(e) => handleUpdateBankField('label', e.target.value)}
+                                        />
                                         <PremiumInput
                                             label="Account Holder Name"
                                             icon={UserCircle2}
                                             placeholder="John Doe"
-                                            value={bankDetails?.accountHolder || ''}
-                                            onChange={(e) => handleUpdateBankField('accountHolder', e.target.value)}
+                                            value={bankAccount.account_holder}
+                                            onChange={(e) => handleUpdateBankField('account_holder', e.target.value)}
+                                            error={bankError('account_holder')}
                                         />
                                         <PremiumInput
                                             label="Account Number"
                                             icon={Hash}
                                             placeholder="XXXX XXXX XXXX"
-                                            value={bankDetails?.accountNumber || ''}
-                                            onChange={(e) => handleUpdateBankField('accountNumber', e.target.value)}
+                                            value={bankAccount.account_number || ''}
+                                            onChange={(e) => handleUpdateBankField('account_number', e.target.value)}
+                                            error={bankError('account_number')}
                                         />
                                         <PremiumInput
-                                            label="IFSC Code / Routing Number"
+                                            label="IFSC Code"
                                             icon={ShieldCheck}
                                             placeholder="IFSC0001234"
-                                            value={bankDetails?.ifscCode || ''}
-                                            onChange={(e) => handleUpdateBankField('ifscCode', e.target.value)}
+                                            value={bankAccount.ifsc_code || ''}
+                                            onChange={(e) => handleUpdateBankField('ifsc_code', e.target.value)}
+                                            error={bankError('ifsc_code')}
+                                        />
+                                        <PremiumInput
+                                            label="Routing Number (ABA)"
+                                            icon={ShieldCheck}
+                                            placeholder="021000021"
+                                            value={bankAccount.routing_number || ''}
+                                            onChange={(e) => handleUpdateBankField('routing_number', e.target.value)}
+                                            error={bankError('routing_number')}
                                         />
                                         <PremiumInput
                                             label="Bank Name"
                                             icon={Landmark}
                                             placeholder="Global Secure Bank"
-                                            value={bankDetails?.bankName || ''}
-                                            onChange={(e) => handleUpdateBankField('bankName', e.target.value)}
+                                            value={bankAccount.bank_name}
+                                            onChange={(e) => handleUpdateBankField('bank_name', e.target.value)}
                                         />
                                         <PremiumInput
                                             label="Branch Name"
                                             icon={MapPin}
                                             placeholder="Downtown Branch"
-                                            value={bankDetails?.branch || ''}
+                                            value={bankAccount.branch || ''}
                                             onChange={(e) => handleUpdateBankField('branch', e.target.value)}
                                         />
                                         <PremiumInput
                                             label="UPI ID"
                                             icon={Phone}
                                             placeholder="username@bank"
-                                            value={bankDetails?.upiId || ''}
-                                            onChange={(e) => handleUpdateBankField('upiId', e.target.value)}
+                                            value={bankAccount.upi_id || ''}
+                                            onChange={(e) => handleUpdateBankField('upi_id', e.target.value)}
+                                            error={bankError('upi_id')}
+                                        />
+                                        <PremiumInput
+                                            label="IBAN"
+                                            icon={CreditCard}
+                                            placeholder="DE89 3704 0044 0532 0130 00"
+                                            value={bankAccount.iban || ''}
+                                            onChange={(e) => handleUpdateBankField('iban', e.target.value)}
+                                            error={bankError('iban')}
+                                        />
+                                        <PremiumInput
+                                            label="BIC / SWIFT"
+                                            icon={Globe}
+                                            placeholder="COBADEFFXXX"
+                                            value={bankAccount.swift_bic || ''}
+                                            onChange={(e) => handleUpdateBankField('swift_bic', e.target.value)}
+                                            error={bankError('swift_bic')}
+                                        />
+                                        <PremiumSelect
+                                            label="Default For Currency"
+                                            icon={CreditCard}
+                                            options={[
+                                                { value: "", label: "Any currency" },
+                                                { value: "USD", label: "USD - US Dollar ($)" },
+                                                { value: "EUR", label: "EUR - Euro (€)" },
+                                                { value: "GBP", label: "GBP - British Pound (£)" },
+                                                { value: "INR", label: "INR - Indian Rupee (₹)" },
+                                                { value: "AED", label: "AED - UAE Dirham" },
+                                            ]}
+                                            value={bankAccount.currency || ''}
+                                            onChange={(e) => handleUpdateBankField('currency', e.target.value)}
+                                        />
+                                        <label className="flex items-center gap-3 text-sm text-[var(--foreground)] cursor-pointer">
+                                            <input
+                                                type="checkbox"
+                                                checked={bankAccount.is_default}
+                                                onChange={(e) => handleUpdateBankField('is_default', e.target.checked)}
+                                                className="w-4 h-4 accent-[var(--primary)]"
+                                            />
+                                            Use by default for new invoices
+                                        </label>
+                                    </div>
+                                </SpotlightCard>
+                            </motion.div>
+                        )}
+
+                        {activeSection === 'fields' && (
+                            <motion.div
+                                key="fields"
+                                initial={{ opacity: 0, filter: "blur(10px)", y: 20 }}
+                                animate={{ opacity: 1, filter: "blur(0px)", y: 0 }}
+                                exit={{ opacity: 0, filter: "blur(10px)", y: -20 }}
+                                transition={{ duration: 0.4 }}
+                            >
+                                <SpotlightCard className="p-8 md:p-10">
+                                    <div className="flex items-center gap-4 mb-10 pb-6 border-b border-[var(--premium-border)]">
+                                        <div className="p-3 bg-[var(--premium-bg)] rounded-xl border border-[var(--premium-border)]">
+                                            <ListPlus size={24} className="text-amber-400" />
+                                        </div>
+                                        <div>
+                                            <h2 className="text-xl font-bold text-[var(--foreground)]">Custom Fields</h2>
+                                            <p className="text-sm text-[var(--text-muted)]">Extra details for clients, invoices and line items, printed on the PDF</p>
+                                        </div>
+                                    </div>
+
+                                    <div className="flex flex-col gap-2 mb-10">
+                                        {customFields.length === 0 && (
+                                            <p className="text-sm text-[var(--text-muted)]">No custom fields yet. Add one below, e.g. "PO Number" for invoices.</p>
+                                        )}
+                                        {customFields.map((field) => (
+                                            <div key={field.id} className="flex items-center gap-4 px-4 py-3 rounded-xl bg-[var(--premium-bg)] border border-[var(--premium-border)] text-sm">
+                                                <span className="font-semibold text-[var(--foreground)] flex-1">{field.name}</span>
+                                                <span className="text-[10px] uppercase tracking-wider text-[var(--text-muted)]">
+                                                    {field.entity === "InvoiceItem" ? "Line item" : field.entity} · {field.field_type}
+                                                    {field.options.length > 0 && ` (${field.options.join(", ")})`}
+                                                    {field.default_value && ` · default ${field.default_value}`}
+                                                </span>
+                                                <label className="flex items-center gap-2 text-xs text-[var(--text-muted)] cursor-pointer">
+                                                    <input
+                                                        type="checkbox"
+                                                        checked={field.required}
+                                                        onChange={() => handleToggleRequired(field)}
+                                                        className="w-4 h-4 accent-[var(--primary)]"
+                                                    />
+                                                    Required
+                                                </label>
+                                                <button
+                                                    type="button"
+                                                    onClick={() => handleDeleteCustomField(field)}
+                                                    className="text-red-400 hover:text-red-300"
+                                                >
+                                                    <Trash2 size={14} />
+                                                </button>
+                                            </div>
+                                        ))}
+                                    </div>
+
+                                    <div className="grid grid-cols-1 md:grid-cols-2 gap-8">
+                                        <PremiumInput
+                                            label="Field Name"
+                                            icon={Hash}
+                                            placeholder="PO Number"
+                                            value={fieldDraft.name}
+                                            onChange={(e) => setFieldDraft({ ...fieldDraft, name: e.target.value })}
+                                        />
+                                        <PremiumSelect
+                                            label="Shown On"
+                                            icon={FileText}
+                                            options={[
+                                                { value: "Invoice", label: "Invoices" },
+                                                { value: "InvoiceItem", label: "Line items" },
+                                                { value: "Client", label: "Clients" },
+                                            ]}
+                                            value={fieldDraft.entity}
+                                            onChange={(e) => setFieldDraft({ ...fieldDraft, entity: e.target.value as CustomFieldDefinition["entity"] })}
+                                        />
+                                        <PremiumSelect
+                                            label="Type"
+                                            icon={ListPlus}
+                                            options={[
+                                                { value: "Text", label: "Text" },
+                                                { value: "Number", label: "Number" },
+                                                { value: "Date", label: "Date" },
+                                                { value: "Boolean", label: "Yes / No" },
+                                                { value: "Select", label: "Choice list" },
+                                            ]}
+                                            value={fieldDraft.field_type}
+                                            onChange={(e) => setFieldDraft({ ...fieldDraft, field_type: e.target.value as CustomFieldDefinition["field_type"] })}
                                         />
+                                        <PremiumInput
+                                            label="Default Value"
+                                            icon={FileText}
+                                            placeholder={fieldDraft.field_type === "Date" ? "YYYY-MM-DD" : "Optional"}
+                                            value={fieldDraft.default_value || ''}
+                                            onChange={(e) => setFieldDraft({ ...fieldDraft, default_value: e.target.value || null })}
+                                        />
+                                        {fieldDraft.field_type === "Select" && (
+                                            <div className="md:col-span-2">
+                                                <PremiumInput
+                                                    label="Choices (comma separated)"
+                                                    icon={ListPlus}
+                                                    placeholder="Retail, Wholesale"
+                                                    value={fieldDraft.options.join(",")}
+                                                    onChange={(e) => setFieldDraft({ ...fieldDraft, options: e.target.value.split(",") })}
+                                                />
+                                            </div>
+                                        )}
+                                        <label className="flex items-center gap-3 text-sm text-[var(--foreground)] cursor-pointer">
+                                            <input
+                                                type="checkbox"
+                                                checked={fieldDraft.required}
+                                                onChange={(e) => setFieldDraft({ ...fieldDraft, required: e.target.checked })}
+                                                className="w-4 h-4 accent-[var(--primary)]"
+                                            />
+                                            Required
+                                        </label>
+                                        <div className="flex justify-end">
+                                            <button
+                                                type="button"
+                                                onClick={handleAddCustomField}
+                                                disabled={!fieldDraft.name.trim()}
+                                                className="px-4 py-2 rounded-xl text-sm font-semibold border border-[var(--primary)] text-[var(--primary)] hover:bg-[var(--premium-bg-hover)] disabled:opacity-50 flex items-center gap-2"
+                                            >
+                                                <Plus size={14} /> Add field
+                                            </button>
+                                        </div>
                                     </div>
                                 </SpotlightCard>
                             </motion.div>
@@ -1017,6 +1571,339 @@ export function Settings() {
                                             </div>
                                         </div>
 
+                                        <div className="group p-6 rounded-xl bg-gradient-to-r from-[rgba(45,212,191,0.05)] to-transparent border border-[var(--premium-border)] hover:border-[var(--primary)] transition-all">
+                                            <div className="flex items-center justify-between gap-4">
+                                                <div className="flex items-center gap-4">
+                                                    <div className="p-3 rounded-lg bg-teal-500/10 text-[var(--primary)]">
+                                                        <Download size={20} />
+                                                    </div>
+                                                    <div>
+                                                        <h4 className="font-semibold text-[var(--foreground)]">Import Data</h4>
+                                                        <p className="text-xs text-[var(--text-muted)] break-all">{importPath ?? 'Merge an export into this database.'}</p>
+                                                    </div>
+                                                </div>
+                                                <button
+                                                    onClick={handleChooseImport}
+                                                    disabled={isImporting}
+                                                    className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] disabled:opacity-50 transition-all"
+                                                >
+                                                    {isImporting && !importPreview ? <Loader2 size={14} className="animate-spin" /> : null}
+                                                    Choose File
+                                                </button>
+                                            </div>
+                                            {importPath && (
+                                                <div className="mt-4 flex flex-col gap-4">
+                                                    <PremiumSelect
+                                                        label="Records already here"
+                                                        value={importPolicy}
+                                                        onChange={(e) => handleImportPolicyChange(e.target.value as ConflictPolicy)}
+                                                        options={[
+                                                            { value: 'skip', label: 'Keep mine' },
+                                                            { value: 'overwrite', label: 'Replace with imported' },
+                                                            { value: 'rename', label: 'Keep both (renumber invoices)' },
+                                                        ]}
+                                                    />
+                                                    {importPreview && (
+                                                        <>
+                                                            <ul className="flex flex-col gap-1 text-xs">
+                                                                {IMPORT_SECTIONS.map(({ key, label }) => (
+                                                                    <li key={key} className="flex items-center justify-between p-2 rounded-lg bg-[var(--premium-bg)] border border-[var(--premium-border)]">
+                                                                        <span className="font-semibold text-[var(--foreground)]">{label}</span>
+                                                                        <span className="text-[var(--text-muted)]">{describeChanges(importPreview[key])}</span>
+                                                                    </li>
+                                                                ))}
+                                                            </ul>
+                                                            {importPreview.invoices.renamed.length > 0 && (
+                                                                <p className="text-xs text-[var(--text-muted)]">
+                                                                    Renumbered: {importPreview.invoices.renamed.map(r => `${r.from} → ${r.to}`).join(', ')}
+                                                                </p>
+                                                            )}
+                                                            <div className="flex justify-end gap-2">
+                                                                <button
+                                                                    onClick={() => { setImportPath(null); setImportPreview(null); }}
+                                                                    disabled={isImporting}
+                                                                    className="px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--text-muted)] hover:text-[var(--foreground)] disabled:opacity-50 transition-all"
+                                                                >
+                                                                    Cancel
+                                                                </button>
+                                                                <button
+                                                                    onClick={handleConfirmImport}
+                                                                    disabled={isImporting}
+                                                                    className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] disabled:opacity-50 transition-all"
+                                                                >
+                                                                    {isImporting ? <Loader2 size={14} className="animate-spin" /> : null}
+                                                                    {isImporting ? 'Importing...' : 'Import'}
+                                                                </button>
+                                                            </div>
+                                                        </>
+                                                    )}
+                                                </div>
+                                            )}
+                                        </div>
+
+                                        <div className="group p-6 rounded-xl bg-gradient-to-r from-[rgba(45,212,191,0.05)] to-transparent border border-[var(--premium-border)] hover:border-[var(--primary)] transition-all">
+                                            <div className="flex items-center justify-between gap-4">
+                                                <div className="flex items-center gap-4">
+                                                    <div className="p-3 rounded-lg bg-teal-500/10 text-[var(--primary)]">
+                                                        <FileSpreadsheet size={20} />
+                                                    </div>
+                                                    <div>
+                                                        <h4 className="font-semibold text-[var(--foreground)]">Import from Spreadsheet</h4>
+                                                        <p className="text-xs text-[var(--text-muted)]">Bring in clients or invoices from a CSV or Excel file.</p>
+                                                    </div>
+                                                </div>
+                                                <button
+                                                    onClick={handleChooseSpreadsheet}
+                                                    className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] transition-all"
+                                                >
+                                                    Choose File
+                                                </button>
+                                            </div>
+                                        </div>
+
+                                        {backupConfig && (
+                                            <div className="group p-6 rounded-xl bg-gradient-to-r from-[rgba(45,212,191,0.05)] to-transparent border border-[var(--premium-border)] hover:border-[var(--primary)] transition-all">
+                                                <div className="flex items-center justify-between gap-4">
+                                                    <div className="flex items-center gap-4">
+                                                        <div className="p-3 rounded-lg bg-teal-500/10 text-[var(--primary)]">
+                                                            <HardDrive size={20} />
+                                                        </div>
+                                                        <div>
+                                                            <h4 className="font-semibold text-[var(--foreground)]">Backups</h4>
+                                                            <p className="text-xs text-[var(--text-muted)] break-all">
+                                                                {backupConfig.directory ?? 'App data folder'}
+                                                            </p>
+                                                        </div>
+                                                    </div>
+                                                    <div className="flex items-center gap-2">
+                                                        <button
+                                                            onClick={handleSelectBackupDir}
+                                                            className="p-2 text-[var(--text-muted)] border border-[var(--premium-border)] rounded-lg hover:text-[var(--primary)] hover:border-[var(--primary)] transition-all"
+                                                            title="Choose backup folder"
+                                                        >
+                                                            <FolderOpen size={14} />
+                                                        </button>
+                                                        <button
+                                                            onClick={handleBackupNow}
+                                                            disabled={isBackingUp}
+                                                            className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] disabled:opacity-50 transition-all"
+                                                        >
+                                                            {isBackingUp ? <Loader2 size={14} className="animate-spin" /> : null}
+                                                            {isBackingUp ? 'Backing up...' : 'Back Up Now'}
+                                                        </button>
+                                                    </div>
+                                                </div>
+                                                <div className="mt-4 grid grid-cols-1 md:grid-cols-4 gap-4">
+                                                    <PremiumSelect
+                                                        label="Automatic"
+                                                        value={backupConfig.enabled ? String(backupConfig.interval_hours) : 'off'}
+                                                        onChange={(e) => handleBackupConfigChange(e.target.value === 'off'
+                                                            ? { enabled: false }
+                                                            : { enabled: true, interval_hours: Number(e.target.value) })}
+                                                        options={[
+                                                            { value: 'off', label: 'Off' },
+                                                            { value: '6', label: 'Every 6 hours' },
+                                                            { value: '24', label: 'Daily' },
+                                                            { value: '168', label: 'Weekly' },
+                                                        ]}
+                                                    />
+                                                    <PremiumInput label="Keep daily" type="number" value={String(backupConfig.retention.daily)} onChange={(e) => handleRetentionChange('daily', e.target.value)} />
+                                                    <PremiumInput label="Keep weekly" type="number" value={String(backupConfig.retention.weekly)} onChange={(e) => handleRetentionChange('weekly', e.target.value)} />
+                                                    <PremiumInput label="Keep monthly" type="number" value={String(backupConfig.retention.monthly)} onChange={(e) => handleRetentionChange('monthly', e.target.value)} />
+                                                </div>
+                                                {backups.length > 0 && (
+                                                    <ul className="mt-4 flex flex-col gap-2 max-h-60 overflow-y-auto">
+                                                        {backups.map((backup) => (
+                                                            <li key={backup.path} className="flex flex-col gap-1 text-xs">
+                                                                <div className="flex items-center justify-between gap-4">
+                                                                    <span className="text-[var(--foreground)]">
+                                                                        {new Date(backup.manifest.created_at).toLocaleString()}
+                                                                        <span className="ml-2 text-[var(--text-muted)]">
+                                                                            {(backup.archive_size / 1024 / 1024).toFixed(1)} MB{backup.manifest.encrypted ? ' · encrypted' : ''}
+                                                                        </span>
+                                                                    </span>
+                                                                    <span className="flex items-center gap-3">
+                                                                        {backupBusyPath === backup.path && <Loader2 size={12} className="animate-spin text-[var(--primary)]" />}
+                                                                        <button onClick={() => handleVerifyBackup(backup.path)} disabled={backupBusyPath !== null} className="font-bold uppercase tracking-wider text-[var(--primary)] hover:underline disabled:opacity-50">Verify</button>
+                                                                        <button onClick={() => handleRestoreBackup(backup)} disabled={backupBusyPath !== null} className="font-bold uppercase tracking-wider text-red-500 hover:underline disabled:opacity-50">Restore</button>
+                                                                    </span>
+                                                                </div>
+                                                                {backupMessage?.path === backup.path && (
+                                                                    <p className={backupMessage.ok ? 'flex items-center gap-2 text-[var(--primary)]' : 'text-red-400 break-all'}>
+                                                                        {backupMessage.ok && <CheckCircle2 size={12} />} {backupMessage.text}
+                                                                    </p>
+                                                                )}
+                                                            </li>
+                                                        ))}
+                                                    </ul>
+                                                )}
+                                            </div>
+                                        )}
+
+                                        <div className="group p-6 rounded-xl bg-gradient-to-r from-[rgba(45,212,191,0.05)] to-transparent border border-[var(--premium-border)] hover:border-[var(--primary)] transition-all">
+                                            <div className="flex items-center justify-between gap-4">
+                                                <div className="flex items-center gap-4">
+                                                    <div className="p-3 rounded-lg bg-teal-500/10 text-[var(--primary)]">
+                                                        <ShieldCheck size={20} />
+                                                    </div>
+                                                    <div>
+                                                        <h4 className="font-semibold text-[var(--foreground)]">Check Database</h4>
+                                                        <p className="text-xs text-[var(--text-muted)]">Find records that can no longer be opened.</p>
+                                                    </div>
+                                                </div>
+                                                <button
+                                                    onClick={handleVerifyDatabase}
+                                                    disabled={isVerifying}
+                                                    className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] disabled:opacity-50 transition-all"
+                                                >
+                                                    {isVerifying ? <Loader2 size={14} className="animate-spin" /> : null}
+                                                    {isVerifying ? 'Checking...' : 'Check'}
+                                                </button>
+                                            </div>
+                                            {corruptRows && (
+                                                corruptRows.length === 0 ? (
+                                                    <p className="mt-4 flex items-center gap-2 text-xs text-[var(--primary)]">
+                                                        <CheckCircle2 size={14} /> No problems found.
+                                                    </p>
+                                                ) : (
+                                                    <ul className="mt-4 flex flex-col gap-2 max-h-60 overflow-y-auto">
+                                                        {corruptRows.map((row, i) => (
+                                                            <li key={`${row.table}-${row.id}-${i}`} className="text-xs font-mono text-red-400 break-all">
+                                                                {row.problem}
+                                                            </li>
+                                                        ))}
+                                                    </ul>
+                                                )
+                                            )}
+                                        </div>
+
+                                        <div className="group p-6 rounded-xl bg-gradient-to-r from-[rgba(45,212,191,0.05)] to-transparent border border-[var(--premium-border)] hover:border-[var(--primary)] transition-all">
+                                            <div className="flex items-center justify-between gap-4">
+                                                <div className="flex items-center gap-4">
+                                                    <div className="p-3 rounded-lg bg-teal-500/10 text-[var(--primary)]">
+                                                        <Lock size={20} />
+                                                    </div>
+                                                    <div>
+                                                        <h4 className="font-semibold text-[var(--foreground)]">Database Encryption</h4>
+                                                        <p className="text-xs text-[var(--text-muted)]">Encrypt invoices, clients, bank details, logo and QR code on disk.</p>
+                                                    </div>
+                                                </div>
+                                                <button
+                                                    onClick={handleChangeDatabaseKey}
+                                                    disabled={isChangingKey || keySource === null || (keySourceDraft === keySource && keySourceDraft !== 'passphrase')}
+                                                    className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] disabled:opacity-50 transition-all"
+                                                >
+                                                    {isChangingKey ? <Loader2 size={14} className="animate-spin" /> : null}
+                                                    {isChangingKey ? 'Applying...' : 'Apply'}
+                                                </button>
+                                            </div>
+                                            <div className="mt-4 grid grid-cols-1 md:grid-cols-2 gap-4">
+                                                <PremiumSelect
+                                                    label="Key"
+                                                    value={keySourceDraft}
+                                                    onChange={(e) => {
+                                                        setKeySourceDraft(e.target.value as KeySource);
+                                                        setPassphraseError(undefined);
+                                                    }}
+                                                    options={[
+                                                        { value: 'none', label: 'Not encrypted' },
+                                                        { value: 'keyring', label: 'Key in system keychain' },
+                                                        { value: 'passphrase', label: 'Passphrase at every start' },
+                                                    ]}
+                                                />
+                                                {keySourceDraft === 'passphrase' && (
+                                                    <PremiumInput
+                                                        label={keySource === 'passphrase' ? 'New passphrase' : 'Passphrase'}
+                                                        type="password"
+                                                        value={newPassphrase}
+                                                        onChange={(e) => setNewPassphrase(e.target.value)}
+                                                        error={passphraseError}
+                                                    />
+                                                )}
+                                            </div>
+                                            {keySourceDraft === 'passphrase' && (
+                                                <p className="mt-3 text-xs text-[var(--text-muted)]">A forgotten passphrase cannot be recovered; keep an exported backup.</p>
+                                            )}
+                                        </div>
+
+                                        {lockStatus && (
+                                            <div className="group p-6 rounded-xl bg-gradient-to-r from-[rgba(45,212,191,0.05)] to-transparent border border-[var(--premium-border)] hover:border-[var(--primary)] transition-all">
+                                                <div className="flex items-center justify-between gap-4">
+                                                    <div className="flex items-center gap-4">
+                                                        <div className="p-3 rounded-lg bg-teal-500/10 text-[var(--primary)]">
+                                                            <KeyRound size={20} />
+                                                        </div>
+                                                        <div>
+                                                            <h4 className="font-semibold text-[var(--foreground)]">App Lock</h4>
+                                                            <p className="text-xs text-[var(--text-muted)]">
+                                                                {lockStatus.enabled ? 'A passphrase or PIN is needed to open InvoiceFlow.' : 'Ask for a passphrase or PIN at start and after inactivity.'}
+                                                            </p>
+                                                        </div>
+                                                    </div>
+                                                    {lockStatus.enabled && (
+                                                        <button
+                                                            onClick={handleLockNow}
+                                                            className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] transition-all"
+                                                        >
+                                                            <Lock size={14} /> Lock Now
+                                                        </button>
+                                                    )}
+                                                </div>
+                                                <div className="mt-4 grid grid-cols-1 md:grid-cols-2 gap-4">
+                                                    {lockStatus.enabled && (
+                                                        <PremiumInput
+                                                            label="Current passphrase or PIN"
+                                                            type="password"
+                                                            value={lockCurrent}
+                                                            onChange={(e) => setLockCurrent(e.target.value)}
+                                                            error={lockError?.field === 'current_passphrase' ? lockError.message : undefined}
+                                                        />
+                                                    )}
+                                                    <PremiumInput
+                                                        label={lockStatus.enabled ? 'New passphrase or PIN' : 'Passphrase or PIN'}
+                                                        type="password"
+                                                        value={lockNew}
+                                                        onChange={(e) => setLockNew(e.target.value)}
+                                                        error={lockError?.field === 'new_passphrase' ? lockError.message : undefined}
+                                                    />
+                                                    {lockStatus.enabled && (
+                                                        <PremiumSelect
+                                                            label="Auto-lock"
+                                                            value={lockStatus.auto_lock_minutes === null ? 'off' : String(lockStatus.auto_lock_minutes)}
+                                                            onChange={(e) => handleAutoLockChange(e.target.value)}
+                                                            options={[
+                                                                { value: 'off', label: 'Only at start' },
+                                                                { value: '1', label: 'After 1 minute' },
+                                                                { value: '5', label: 'After 5 minutes' },
+                                                                { value: '15', label: 'After 15 minutes' },
+                                                                { value: '30', label: 'After 30 minutes' },
+                                                                { value: '60', label: 'After 1 hour' },
+                                                            ]}
+                                                        />
+                                                    )}
+                                                </div>
+                                                <div className="mt-4 flex justify-end gap-3">
+                                                    {lockStatus.enabled && (
+                                                        <button
+                                                            onClick={() => handleSaveLockPassphrase(true)}
+                                                            disabled={isSavingLock || !lockCurrent}
+                                                            className="px-4 py-2 text-xs font-bold uppercase tracking-wider text-red-500 border border-red-500/30 rounded-lg hover:bg-red-500 hover:text-white disabled:opacity-50 transition-all"
+                                                        >
+                                                            Remove Lock
+                                                        </button>
+                                                    )}
+                                                    <button
+                                                        onClick={() => handleSaveLockPassphrase(false)}
+                                                        disabled={isSavingLock || !lockNew}
+                                                        className="flex items-center gap-2 px-4 py-2 text-xs font-bold uppercase tracking-wider text-[var(--primary)] border border-[var(--primary)]/30 rounded-lg hover:bg-[var(--primary)] hover:text-[var(--background)] disabled:opacity-50 transition-all"
+                                                    >
+                                                        {isSavingLock ? <Loader2 size={14} className="animate-spin" /> : null}
+                                                        {lockStatus.enabled ? 'Change' : 'Enable'}
+                                                    </button>
+                                                </div>
+                                            </div>
+                                        )}
+
                                         <div className="group p-6 rounded-xl bg-gradient-to-r from-[rgba(244,63,94,0.05)] to-transparent border border-[var(--premium-border)] hover:border-red-500/30 transition-all">
                                             <div className="flex items-center justify-between gap-4">
                                                 <div className="flex items-center gap-4">
@@ -1133,6 +2020,14 @@ export function Settings() {
                     </div>
                 )}
             </AnimatePresence>
+
+            {spreadsheetPath && (
+                <SpreadsheetImport
+                    path={spreadsheetPath}
+                    onClose={() => setSpreadsheetPath(null)}
+                    onImported={() => window.location.reload()}
+                />
+            )}
         </div>
     );
 }
@@ -1,5 +1,5 @@
 import { create } from 'zustand';
-import { invoke } from '@tauri-apps/api/core';
+import { call, getLockStatus, isApiError, type LockStatus } from '../lib/api';
 
 export interface Address {
     line1: string;
@@ -24,43 +24,81 @@ export interface BusinessProfile {
     // Preferences
     theme_preference: "system" | "dark" | "light";
     pdf_export_dir?: string | null;
+    pdf_filename_pattern: string;
+    pdf_backend: "native" | "browser";
+    pdf_archival: boolean;
+    browser_path?: string | null;
 
     created_at: string;
     updated_at: string;
 }
 
-export interface BankDetails {
-    accountHolder: string;
-    accountNumber: string;
-    ifscCode: string;
-    bankName: string;
-    branch: string;
-    upiId: string;
+/** A saved bank account; mirrors `flow_core::models::BankAccount`. */
+export interface BankAccount {
+    /** `null` until the account has been saved. */
+    id: string | null;
+    label: string;
+    account_holder: string;
+    bank_name: string;
+    branch?: string | null;
+    account_number?: string | null;
+    iban?: string | null;
+    swift_bic?: string | null;
+    ifsc_code?: string | null;
+    routing_number?: string | null;
+    upi_id?: string | null;
+    /** Currency this account is the default for; `null` means any currency. */
+    currency?: string | null;
+    is_default: boolean;
 }
 
+export const emptyBankAccount = (): BankAccount => ({
+    id: null,
+    label: "",
+    account_holder: "",
+    bank_name: "",
+    is_default: false,
+});
+
+/** The account new invoices in `currency` use: the default for that currency, else the default for any currency. */
+export const defaultBankAccount = (accounts: BankAccount[], currency?: string): BankAccount | undefined =>
+    accounts.find(a => a.is_default && a.currency === currency)
+    ?? accounts.find(a => a.is_default && !a.currency)
+    ?? accounts[0];
+
 interface SettingsState {
     profile: BusinessProfile | null;
-    bankDetails: BankDetails | null;
+    bankAccounts: BankAccount[];
+    /** `null` until the database is open. */
+    lockStatus: LockStatus | null;
+    /** Why the database could not be opened at start-up; it stays closed for this session. */
+    startupError: string | null;
     isLoading: boolean;
     error: string | null;
 
     // Actions
     fetchSettings: () => Promise<void>;
     updateSettings: (profile: BusinessProfile) => Promise<void>;
-    fetchBankDetails: () => Promise<void>;
-    updateBankDetails: (details: BankDetails) => Promise<void>;
+    fetchBankAccounts: () => Promise<void>;
+    /** Rejects with the validation message when an identifier is malformed. */
+    saveBankAccount: (account: BankAccount) => Promise<BankAccount>;
+    deleteBankAccount: (id: string) => Promise<void>;
+    fetchLockStatus: () => Promise<void>;
+    setLockStatus: (status: LockStatus) => void;
 }
 
-export const useSettingsStore = create<SettingsState>((set) => ({
+export const useSettingsStore = create<SettingsState>((set, get) => ({
     profile: null,
-    bankDetails: null,
+    bankAccounts: [],
+    lockStatus: null,
+    startupError: null,
     isLoading: true,
     error: null,
 
     fetchSettings: async () => {
         set({ isLoading: true, error: null });
         try {
-            const data = await invoke<BusinessProfile>('get_settings');
+            const data = await call<BusinessProfile>('get_settings');
             set({ profile: data, isLoading: false });
         } catch (error) {
             console.error("Failed to fetch settings:", error);
@@ -71,7 +109,7 @@ export const useSettingsStore = create<SettingsState>((set) => ({
     updateSettings: async (profile: BusinessProfile) => {
         set({ isLoading: true, error: null });
         try {
-            await invoke('save_settings', { profile });
+            await call('save_settings', { profile });
             set({ profile, isLoading: false });
         } catch (error) {
             console.error("Failed to save settings:", error);
@@ -79,23 +117,35 @@ export const useSettingsStore = create<SettingsState>((set) => ({
         }
     },
 
-    fetchBankDetails: async () => {
+    fetchBankAccounts: async () => {
         try {
-            const data: string | null = await invoke('get_bank_details');
-            if (data) {
-                set({ bankDetails: JSON.parse(data) });
-            }
+            const accounts = await call<BankAccount[]>('get_bank_accounts');
+            set({ bankAccounts: accounts });
         } catch (error) {
-            console.error("Failed to fetch bank details:", error);
+            console.error("Failed to fetch bank accounts:", error);
         }
     },
 
-    updateBankDetails: async (details: BankDetails) => {
+    saveBankAccount: async (account: BankAccount) => {
+        const saved = await call<BankAccount>('save_bank_account', { request: account });
+        await get().fetchBankAccounts();
+        return saved;
+    },
+
+    deleteBankAccount: async (id: string) => {
+        await call('delete_bank_account', { id });
+        set({ bankAccounts: get().bankAccounts.filter(a => a.id !== id) });
+    },
+
+    fetchLockStatus: async () => {
         try {
-            await invoke('save_bank_details', { jsonData: JSON.stringify(details) });
-            set({ bankDetails: details });
+            set({ lockStatus: await getLockStatus() });
         } catch (error) {
-            console.error("Failed to save bank details:", error);
+            // Busy while the database is still opening; anything else means it never will
+            if (isApiError(error, "busy")) console.error("Failed to fetch lock status:", error);
+            else set({ startupError: String(error) });
         }
-    }
+    },
+
+    setLockStatus: (status: LockStatus) => set({ lockStatus: status })
 }));
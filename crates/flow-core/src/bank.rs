@@ -0,0 +1,182 @@
+//! Format checks for bank account identifiers.
+
+use crate::error::{FlowError, FlowResult};
+use crate::models::BankAccount;
+
+/// IBAN lengths for the countries InvoiceFlow users bill from most; others are checked by checksum only.
+const IBAN_LENGTHS: &[(&str, usize)] = &[
+    ("AE", 23), ("AT", 20), ("BE", 16), ("CH", 21), ("CY", 28), ("CZ", 24), ("DE", 22), ("DK", 18),
+    ("EE", 20), ("ES", 24), ("FI", 18), ("FR", 27), ("GB", 22), ("GR", 27), ("HR", 21), ("HU", 28),
+    ("IE", 22), ("IT", 27), ("LI", 21), ("LT", 20), ("LU", 20), ("LV", 21), ("MT", 31), ("NL", 18),
+    ("NO", 15), ("PL", 28), ("PT", 25), ("RO", 24), ("SA", 24), ("SE", 24), ("SI", 19), ("SK", 24),
+];
+
+type IdentifierCheck = fn(&str) -> bool;
+
+impl BankAccount {
+    /// Checks that the account can be paid into and that every identifier present is well-formed.
+    pub fn validate(&self) -> FlowResult<()> {
+        if self.account_holder.trim().is_empty() {
+            return Err(FlowError::invalid_field("account_holder", "Account holder is required"));
+        }
+        let present = |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        if present(&self.account_number).is_none() && present(&self.iban).is_none() && present(&self.upi_id).is_none() {
+            return Err(FlowError::invalid_field("account_number", "An account number, IBAN or UPI ID is required"));
+        }
+
+        let checks: [(&str, &str, &Option<String>, IdentifierCheck); 6] = [
+            ("account_number", "account number", &self.account_number, is_valid_account_number),
+            ("iban", "IBAN", &self.iban, is_valid_iban),
+            ("swift_bic", "SWIFT/BIC", &self.swift_bic, is_valid_bic),
+            ("ifsc_code", "IFSC code", &self.ifsc_code, is_valid_ifsc),
+            ("routing_number", "routing number", &self.routing_number, is_valid_routing_number),
+            ("upi_id", "UPI ID", &self.upi_id, is_valid_upi_id),
+        ];
+        for (field, name, value, check) in checks {
+            if let Some(value) = present(value) {
+                if !check(&value) {
+                    return Err(FlowError::invalid_field(field, format!("Invalid {}: {}", name, value)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Uppercase IBAN without spaces.
+pub fn normalize_iban(iban: &str) -> String {
+    iban.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase()
+}
+
+/// ISO 13616 structure, country length and mod-97 check digits; spaces are ignored.
+pub fn is_valid_iban(iban: &str) -> bool {
+    let iban = normalize_iban(iban);
+    let bytes = iban.as_bytes();
+    let length_ok = match IBAN_LENGTHS.iter().find(|(country, _)| iban.starts_with(country)) {
+        Some((_, length)) => bytes.len() == *length,
+        None => (15..=34).contains(&bytes.len()),
+    };
+    length_ok
+        && bytes[..2].iter().all(u8::is_ascii_uppercase)
+        && bytes[2..4].iter().all(u8::is_ascii_digit)
+        && bytes.iter().all(u8::is_ascii_alphanumeric)
+        && mod97(iban[4..].chars().chain(iban[..4].chars())) == 1
+}
+
+/// ISO 9362 business identifier code: bank (4 letters), country (2 letters),
+/// location (2 alphanumerics) and an optional 3-character branch.
+pub fn is_valid_bic(bic: &str) -> bool {
+    let bic = bic.trim().to_ascii_uppercase();
+    let bytes = bic.as_bytes();
+    matches!(bytes.len(), 8 | 11)
+        && bytes[..6].iter().all(u8::is_ascii_alphabetic)
+        && bytes[6..].iter().all(u8::is_ascii_alphanumeric)
+}
+
+/// Indian Financial System Code: 4 letters, a zero, then 6 alphanumerics (e.g. `SBIN0000001`).
+pub fn is_valid_ifsc(ifsc: &str) -> bool {
+    let ifsc = ifsc.trim().to_ascii_uppercase();
+    let bytes = ifsc.as_bytes();
+    bytes.len() == 11
+        && bytes[..4].iter().all(u8::is_ascii_alphabetic)
+        && bytes[4] == b'0'
+        && bytes[5..].iter().all(u8::is_ascii_alphanumeric)
+}
+
+/// ABA routing transit number: 9 digits with the 3-7-1 weighted checksum.
+pub fn is_valid_routing_number(routing: &str) -> bool {
+    let digits: Vec<u32> = routing.trim().chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() != 9 || routing.trim().len() != 9 {
+        return false;
+    }
+    let weighted: u32 = digits.iter().zip([3, 7, 1].iter().cycle()).map(|(d, w)| d * w).sum();
+    weighted.is_multiple_of(10)
+}
+
+/// Domestic account numbers: 4–34 letters or digits, optionally grouped with spaces or hyphens.
+pub fn is_valid_account_number(number: &str) -> bool {
+    let compact: Vec<char> = number.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+    (4..=34).contains(&compact.len())
+        && compact.iter().all(char::is_ascii_alphanumeric)
+        && compact.iter().any(char::is_ascii_digit)
+}
+
+/// `handle@provider`, as accepted by UPI apps.
+pub fn is_valid_upi_id(upi_id: &str) -> bool {
+    let Some((handle, provider)) = upi_id.split_once('@') else {
+        return false;
+    };
+    (2..=256).contains(&handle.len())
+        && handle.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        && (3..=64).contains(&provider.len())
+        && provider.starts_with(|c: char| c.is_ascii_alphabetic())
+        && provider.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// ISO 7064 MOD 97-10 remainder of `chars`, reading letters as 10..35.
+pub fn mod97(chars: impl Iterator<Item = char>) -> u32 {
+    chars.fold(0, |rem, c| {
+        let value = c.to_digit(36).unwrap_or(0);
+        if value < 10 {
+            (rem * 10 + value) % 97
+        } else {
+            (rem * 100 + value) % 97
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn account() -> BankAccount {
+        BankAccount {
+            id: Uuid::nil(),
+            label: "Main".to_string(),
+            account_holder: "Jabir Studio".to_string(),
+            bank_name: "State Bank of India".to_string(),
+            branch: None,
+            account_number: Some("000123456789".to_string()),
+            iban: None,
+            swift_bic: Some("SBININBB".to_string()),
+            ifsc_code: Some("SBIN0000001".to_string()),
+            routing_number: None,
+            upi_id: Some("jabir@upi".to_string()),
+            currency: None,
+            is_default: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_identifier_formats() {
+        assert!(is_valid_iban("DE89 3704 0044 0532 0130 00"));
+        assert!(!is_valid_iban("DE89 3704 0044 0532 0130 0"));
+        assert!(!is_valid_iban("GB82WEST12345698765431"));
+        assert!(is_valid_bic("COBADEFFXXX"));
+        assert!(!is_valid_bic("COBA1EFF"));
+        assert!(is_valid_ifsc("HDFC0001234"));
+        assert!(!is_valid_ifsc("HDFC1001234"));
+        assert!(is_valid_routing_number("021000021"));
+        assert!(!is_valid_routing_number("021000022"));
+        assert!(is_valid_upi_id("studio.pay@okhdfc"));
+        assert!(!is_valid_upi_id("studio@1bank"));
+    }
+
+    #[test]
+    fn test_validate_reports_the_bad_field() {
+        assert!(account().validate().is_ok());
+
+        let mut bad = account();
+        bad.ifsc_code = Some("SBIN000001".to_string());
+        assert_eq!(bad.validate().unwrap_err().to_string(), "Validation error: Invalid IFSC code: SBIN000001");
+
+        let mut empty = account();
+        empty.account_number = Some(" ".to_string());
+        empty.upi_id = None;
+        assert!(empty.validate().is_err());
+    }
+}
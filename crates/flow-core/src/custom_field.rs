@@ -0,0 +1,146 @@
+//! Type checks and defaults for user-defined custom fields.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::error::{FlowError, FlowResult};
+use crate::models::{CustomFieldDefinition, CustomFieldValue};
+use crate::types::{CustomFieldEntity, CustomFieldType};
+
+impl CustomFieldDefinition {
+    /// Checks the definition itself: a name, choices for select fields and a valid default.
+    pub fn validate(&self) -> FlowResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(FlowError::invalid_field("name", "Custom field name is required"));
+        }
+        if self.field_type == CustomFieldType::Select && self.options.iter().all(|o| o.trim().is_empty()) {
+            return Err(FlowError::invalid_field("options", format!("{} needs at least one option", self.name)));
+        }
+        if let Some(default) = self.default_value.as_deref().filter(|d| !d.trim().is_empty()) {
+            self.normalize_value(default).map_err(|e| FlowError::invalid_field("default_value", e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Parses `value` by the field type and returns it in its stored form. A value that does
+    /// not parse is reported against the field's id.
+    pub fn normalize_value(&self, value: &str) -> FlowResult<String> {
+        let value = value.trim();
+        let invalid = |expected: &str| FlowError::invalid_field(self.id.to_string(), format!("Invalid {}: expected {}, got {}", self.name, expected, value));
+        match self.field_type {
+            CustomFieldType::Text => Ok(value.to_string()),
+            CustomFieldType::Number => Decimal::from_str(&value.replace(',', ""))
+                .map(|n| n.normalize().to_string())
+                .map_err(|_| invalid("a number")),
+            CustomFieldType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|d| d.to_string())
+                .map_err(|_| invalid("a date (YYYY-MM-DD)")),
+            CustomFieldType::Boolean => match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok("true".to_string()),
+                "false" | "no" | "0" => Ok("false".to_string()),
+                _ => Err(invalid("yes or no")),
+            },
+            CustomFieldType::Select => self
+                .options
+                .iter()
+                .find(|option| option.trim().eq_ignore_ascii_case(value))
+                .map(|option| option.trim().to_string())
+                .ok_or_else(|| invalid(&format!("one of {}", self.options.join(", ")))),
+        }
+    }
+}
+
+/// Builds the values stored on a record of `entity` from user input keyed by field id: fills
+/// defaults, enforces required fields and normalizes every value. Blank optional fields are dropped.
+pub fn resolve_values(
+    definitions: &[CustomFieldDefinition],
+    entity: &CustomFieldEntity,
+    input: &HashMap<Uuid, String>,
+) -> FlowResult<Vec<CustomFieldValue>> {
+    let definitions: Vec<&CustomFieldDefinition> = definitions.iter().filter(|d| &d.entity == entity).collect();
+    if let Some(unknown) = input.keys().find(|id| !definitions.iter().any(|d| &d.id == *id)) {
+        return Err(FlowError::Validation(format!("Unknown custom field: {}", unknown)));
+    }
+
+    let mut values = Vec::new();
+    for definition in definitions {
+        let raw = input
+            .get(&definition.id)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .or_else(|| definition.default_value.as_deref().map(str::trim).filter(|v| !v.is_empty()));
+        let Some(raw) = raw else {
+            if definition.required {
+                return Err(FlowError::invalid_field(definition.id.to_string(), format!("{} is required", definition.name)));
+            }
+            continue;
+        };
+        values.push(CustomFieldValue {
+            field_id: definition.id,
+            name: definition.name.clone(),
+            field_type: definition.field_type.clone(),
+            value: definition.normalize_value(raw)?,
+        });
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn definition(n: u128, name: &str, field_type: CustomFieldType, required: bool) -> CustomFieldDefinition {
+        CustomFieldDefinition {
+            id: Uuid::from_u128(n),
+            name: name.to_string(),
+            field_type,
+            entity: CustomFieldEntity::Invoice,
+            options: vec!["Retail".to_string(), "Wholesale".to_string()],
+            required,
+            default_value: None,
+            sort_order: n as i32,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_values_are_normalized_by_type() {
+        let number = definition(1, "Budget", CustomFieldType::Number, false);
+        assert_eq!(number.normalize_value("1,250.50").unwrap(), "1250.5");
+        assert!(number.normalize_value("twelve").is_err());
+        let date = definition(2, "Delivery", CustomFieldType::Date, false);
+        assert_eq!(date.normalize_value("2025-03-01").unwrap(), "2025-03-01");
+        assert!(date.normalize_value("01/03/2025").is_err());
+        let flag = definition(3, "Billable", CustomFieldType::Boolean, false);
+        assert_eq!(flag.normalize_value("Yes").unwrap(), "true");
+        let channel = definition(4, "Channel", CustomFieldType::Select, false);
+        assert_eq!(channel.normalize_value("wholesale").unwrap(), "Wholesale");
+        assert!(channel.normalize_value("Online").is_err());
+    }
+
+    #[test]
+    fn test_resolve_values() {
+        let mut cost_centre = definition(2, "Cost Centre", CustomFieldType::Text, false);
+        cost_centre.default_value = Some("CC-100".to_string());
+        let mut client_only = definition(3, "Vendor ID", CustomFieldType::Text, true);
+        client_only.entity = CustomFieldEntity::Client;
+        let definitions = [definition(1, "PO Number", CustomFieldType::Text, true), cost_centre, client_only];
+
+        let missing = resolve_values(&definitions, &CustomFieldEntity::Invoice, &HashMap::new());
+        assert!(missing.unwrap_err().to_string().contains("PO Number is required"));
+
+        let input = HashMap::from([(Uuid::from_u128(1), " PO-42 ".to_string())]);
+        let values = resolve_values(&definitions, &CustomFieldEntity::Invoice, &input).unwrap();
+        let pairs: Vec<(&str, &str)> = values.iter().map(|v| (v.name.as_str(), v.value.as_str())).collect();
+        assert_eq!(pairs, [("PO Number", "PO-42"), ("Cost Centre", "CC-100")]);
+
+        let foreign = HashMap::from([(Uuid::from_u128(3), "V-1".to_string())]);
+        assert!(resolve_values(&definitions, &CustomFieldEntity::Invoice, &foreign).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Finds clients that are probably the same customer entered twice.
+
+use crate::models::{Client, DuplicateMatch};
+use crate::types::DuplicateReason;
+
+/// Pairs scoring below this are not reported; a shared email domain alone never reaches it.
+const REPORT_SCORE: u8 = 50;
+
+/// Legal-form words ignored when comparing names ("Acme Ltd." and "ACME Limited" match).
+const LEGAL_SUFFIXES: &[&str] = &[
+    "co", "company", "corp", "corporation", "inc", "incorporated", "llc", "llp", "ltd", "limited",
+    "plc", "gmbh", "ag", "bv", "sa", "sarl", "srl", "pvt", "private", "pte", "pty", "the",
+];
+
+/// Mail providers whose domain says nothing about the customer.
+const FREE_MAIL_DOMAINS: &[&str] = &[
+    "gmail.com", "googlemail.com", "outlook.com", "hotmail.com", "live.com", "yahoo.com", "icloud.com",
+    "me.com", "aol.com", "proton.me", "protonmail.com", "gmx.de", "gmx.net", "web.de", "rediffmail.com",
+];
+
+/// Likely duplicates among `clients`, most likely first.
+pub fn find_duplicates(clients: &[Client]) -> Vec<DuplicateMatch> {
+    let keys: Vec<Keys> = clients.iter().map(Keys::of).collect();
+    let mut matches = Vec::new();
+
+    for (i, a) in keys.iter().enumerate() {
+        for (j, b) in keys.iter().enumerate().skip(i + 1) {
+            let reasons = compare(a, b);
+            let score = reasons.iter().map(|r| r.weight() as u32).sum::<u32>().min(100) as u8;
+            if score >= REPORT_SCORE {
+                matches.push(DuplicateMatch {
+                    client_id: clients[i].id,
+                    duplicate_id: clients[j].id,
+                    reasons,
+                    score,
+                });
+            }
+        }
+    }
+
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches
+}
+
+impl DuplicateReason {
+    fn weight(&self) -> u8 {
+        match self {
+            DuplicateReason::SameTaxId => 60,
+            DuplicateReason::SameName => 50,
+            DuplicateReason::SimilarTaxId => 40,
+            DuplicateReason::SimilarName => 30,
+            DuplicateReason::SameEmailDomain => 20,
+        }
+    }
+}
+
+struct Keys {
+    names: Vec<String>,
+    email_domain: Option<String>,
+    tax_ids: Vec<String>,
+}
+
+impl Keys {
+    fn of(client: &Client) -> Self {
+        let names = [Some(client.name.as_str()), client.company.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(normalize_name)
+            .filter(|n| !n.is_empty())
+            .collect();
+        let tax_ids = client
+            .tax_id
+            .iter()
+            .chain(client.tax_ids.iter().map(|t| &t.value))
+            .map(|t| normalize_tax_id(t))
+            .filter(|t| t.len() >= 5)
+            .collect();
+        Keys { names, email_domain: client.email.as_deref().and_then(email_domain), tax_ids }
+    }
+}
+
+fn compare(a: &Keys, b: &Keys) -> Vec<DuplicateReason> {
+    let mut reasons = Vec::new();
+
+    let pairs = |x: &[String], y: &[String]| -> Vec<(String, String)> {
+        x.iter().flat_map(|p| y.iter().map(move |q| (p.clone(), q.clone()))).collect()
+    };
+    let names = pairs(&a.names, &b.names);
+    if names.iter().any(|(x, y)| x == y) {
+        reasons.push(DuplicateReason::SameName);
+    } else if names.iter().any(|(x, y)| is_similar(x, y)) {
+        reasons.push(DuplicateReason::SimilarName);
+    }
+
+    let tax_ids = pairs(&a.tax_ids, &b.tax_ids);
+    if tax_ids.iter().any(|(x, y)| x == y) {
+        reasons.push(DuplicateReason::SameTaxId);
+    } else if tax_ids.iter().any(|(x, y)| x.len() >= 8 && levenshtein(x, y) == 1) {
+        reasons.push(DuplicateReason::SimilarTaxId);
+    }
+
+    if a.email_domain.is_some() && a.email_domain == b.email_domain {
+        reasons.push(DuplicateReason::SameEmailDomain);
+    }
+    reasons
+}
+
+/// Lowercase words without punctuation or legal-form suffixes.
+pub fn normalize_name(name: &str) -> String {
+    let cleaned: String = name
+        .to_lowercase()
+        .chars()
+        .filter(|c| *c != '.') // "L.L.C." -> "llc"
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    cleaned
+        .split_whitespace()
+        .filter(|word| !LEGAL_SUFFIXES.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Tax numbers compared without spaces, dashes, dots or letter case.
+pub fn normalize_tax_id(tax_id: &str) -> String {
+    tax_id.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_uppercase()
+}
+
+/// The domain of a company email address; `None` for free mail providers.
+fn email_domain(email: &str) -> Option<String> {
+    let domain = email.trim().rsplit_once('@')?.1.to_lowercase();
+    (!domain.is_empty() && !FREE_MAIL_DOMAINS.contains(&domain.as_str())).then_some(domain)
+}
+
+/// Names of at least 6 characters differing by a typo or two.
+fn is_similar(a: &str, b: &str) -> bool {
+    let shorter = a.chars().count().min(b.chars().count());
+    shorter >= 6 && levenshtein(a, b) <= if shorter >= 12 { 2 } else { 1 }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = (previous + (ca != *cb) as usize).min(row[j] + 1).min(current + 1);
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Address, TaxIdentifier};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn client(n: u128, name: &str, email: Option<&str>, tax_id: Option<&str>) -> Client {
+        Client {
+            id: Uuid::from_u128(n),
+            name: name.to_string(),
+            email: email.map(String::from),
+            phone: None,
+            company: None,
+            address: Address::default(),
+            shipping_address: None,
+            tax_id: tax_id.map(String::from),
+            tax_ids: vec![],
+            contacts: vec![],
+            notes: None,
+            custom_fields: vec![],
+            archived_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_names_are_normalized() {
+        assert_eq!(normalize_name("ACME Holdings Ltd."), "acme holdings");
+        assert_eq!(normalize_name("Smith & Sons, Inc"), "smith sons");
+        assert_eq!(normalize_tax_id("de 123-456.789"), "DE123456789");
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let mut other_vat = client(5, "Globex", None, None);
+        other_vat.tax_ids.push(TaxIdentifier { scheme: "VAT".to_string(), value: "DE 123456789".to_string() });
+        let clients = [
+            client(1, "Acme Holdings Ltd", Some("ap@acme.example"), None),
+            client(2, "ACME Holdings Limited", Some("billing@acme.example"), None),
+            client(3, "Initech", Some("a@gmail.com"), Some("DE123456789")),
+            client(4, "Initrode", Some("b@gmail.com"), None),
+            other_vat,
+        ];
+
+        let matches = find_duplicates(&clients);
+        let found: Vec<(u128, u128, u8)> = matches.iter().map(|m| (m.client_id.as_u128(), m.duplicate_id.as_u128(), m.score)).collect();
+        assert_eq!(found, [(1, 2, 70), (3, 5, 60)]);
+        assert_eq!(matches[0].reasons, [DuplicateReason::SameName, DuplicateReason::SameEmailDomain]);
+    }
+}
@@ -5,12 +5,23 @@ pub enum FlowError {
     #[error("Database error: {0}")]
     Database(String),
 
+    /// The database was locked by another connection; the same call may succeed later.
+    #[error("Database is busy: {0}")]
+    Busy(String),
+
     #[error("Validation error: {0}")]
     Validation(String),
 
+    /// A validation failure tied to one input field, so the UI can point at it.
+    #[error("Validation error: {message}")]
+    InvalidField { field: String, message: String },
+
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Client \"{name}\" has {invoice_count} invoice(s) and cannot be deleted; archive it instead")]
+    ClientHasInvoices { name: String, invoice_count: i64 },
+
     #[error("PDF generation error: {0}")]
     PdfGeneration(String),
 
@@ -20,18 +31,104 @@ pub enum FlowError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// The application lock is engaged; nothing but unlocking is allowed.
+    #[error("InvoiceFlow is locked")]
+    Locked,
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
 pub type FlowResult<T> = Result<T, FlowError>;
 
-// Implement conversion for Tauri command error handling
+impl FlowError {
+    pub fn invalid_field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FlowError::InvalidField { field: field.into(), message: message.into() }
+    }
+
+    /// Stable, machine-readable name of the error kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FlowError::Database(_) => "database",
+            FlowError::Busy(_) => "busy",
+            FlowError::Validation(_) | FlowError::InvalidField { .. } => "validation",
+            FlowError::NotFound(_) => "not_found",
+            FlowError::ClientHasInvoices { .. } => "client_has_invoices",
+            FlowError::PdfGeneration(_) => "pdf_generation",
+            FlowError::Serialization(_) => "serialization",
+            FlowError::Io(_) => "io",
+            FlowError::Locked => "locked",
+            FlowError::Internal(_) => "internal",
+        }
+    }
+
+    /// The input field the error is about, if any.
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            FlowError::InvalidField { field, .. } => Some(field),
+            _ => None,
+        }
+    }
+
+    /// Whether repeating the same call unchanged may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, FlowError::Busy(_))
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl From<sqlx::Error> for FlowError {
+    fn from(err: sqlx::Error) -> Self {
+        // SQLITE_BUSY (5) and SQLITE_LOCKED (6), including their extended codes.
+        let locked = |code: &str| code.parse::<i64>().map(|c| matches!(c & 0xff, 5 | 6)).unwrap_or(false);
+        match err {
+            sqlx::Error::RowNotFound => FlowError::NotFound("Record not found".to_string()),
+            sqlx::Error::PoolTimedOut => FlowError::Busy(err.to_string()),
+            sqlx::Error::Database(ref db) if db.code().as_deref().is_some_and(locked) => FlowError::Busy(db.message().to_string()),
+            sqlx::Error::Io(e) => FlowError::Io(e),
+            other => FlowError::Database(other.to_string()),
+        }
+    }
+}
+
+/// Tauri commands hand errors to the frontend as `{ code, message, field, retryable }`.
 impl serde::Serialize for FlowError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("FlowError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("field", &self.field())?;
+        state.serialize_field("retryable", &self.is_retryable())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_as_structured_object() {
+        let err = FlowError::invalid_field("name", "Client name is required");
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": "validation",
+                "message": "Validation error: Client name is required",
+                "field": "name",
+                "retryable": false,
+            })
+        );
+
+        let json = serde_json::to_value(FlowError::Busy("database is locked".into())).unwrap();
+        assert_eq!(json["code"], "busy");
+        assert_eq!(json["field"], serde_json::Value::Null);
+        assert_eq!(json["retryable"], true);
     }
 }
@@ -1,3 +1,6 @@
+pub mod bank;
+pub mod custom_field;
+pub mod duplicates;
 pub mod error;
 pub mod models;
 pub mod types;
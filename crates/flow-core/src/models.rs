@@ -3,7 +3,7 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::types::{Currency, DiscountType, InvoiceStatus, PaymentTerms, TaxRate};
+use crate::types::{Currency, CustomFieldEntity, CustomFieldType, DiscountType, DuplicateReason, InvoiceStatus, PaymentTerms, TaxRate};
 
 /// Business / company profile
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +21,10 @@ pub struct BusinessProfile {
     // Preferences
     pub theme_preference: String, // "system", "dark", "light"
     pub pdf_export_dir: Option<String>,
+    pub pdf_filename_pattern: String, // e.g. "{client}/{year}/{number}.pdf"
+    pub pdf_backend: String, // "native", "browser"
+    pub pdf_archival: bool, // PDF/A-3b with embedded Factur-X XML
+    pub browser_path: Option<String>,
     
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -34,12 +38,36 @@ pub struct Client {
     pub email: Option<String>,
     pub phone: Option<String>,
     pub company: Option<String>,
-    pub address: Address,
+    pub address: Address, // billing address
+    pub shipping_address: Option<Address>, // None ships to the billing address
+    pub tax_id: Option<String>, // VAT number or GSTIN
+    pub tax_ids: Vec<TaxIdentifier>, // further registrations beside tax_id
+    pub contacts: Vec<ClientContact>,
     pub notes: Option<String>,
+    pub custom_fields: Vec<CustomFieldValue>,
+    pub archived_at: Option<DateTime<Utc>>, // hidden from lists, kept for its invoices
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Person to contact at a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientContact {
+    pub id: Uuid,
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub role: Option<String>, // e.g. "Accounts payable"
+    pub is_primary: bool,
+}
+
+/// Tax registration number with its scheme, e.g. PAN, EORI or a second VAT number
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TaxIdentifier {
+    pub scheme: String,
+    pub value: String,
+}
+
 /// Address
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Address {
@@ -80,8 +108,23 @@ pub struct Invoice {
     pub payment_terms: PaymentTerms,
     pub notes: Option<String>,
     pub terms_and_conditions: Option<String>,
+    pub payment_note: Option<String>, // printed under "Payment Terms"
+
+    // Presentation
+    pub developer: Option<String>, // contact named in the header and footer
+    pub logo_path: Option<String>, // file path or data URL
+    pub qr_code_url: Option<String>, // uploaded payment QR, used when no QR can be generated
+    pub project_details: Vec<ProjectDetail>,
+    pub custom_fields: Vec<CustomFieldValue>,
+
+    // Registration returned by the GST invoice registration portal
+    pub gst_registration: Option<GstRegistration>,
+
+    // Account payments go to; stored as `bank_account_id` and resolved on load
+    pub bank_account: Option<BankAccount>,
 
     // Metadata
+    pub archived_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -96,7 +139,69 @@ pub struct InvoiceItem {
     pub unit_price: Decimal,
     pub amount: Decimal,
     pub tax_rate_name: Option<String>,
+    pub hsn_code: Option<String>, // HSN (goods) or SAC (services) classification
     pub sort_order: i32,
+    pub custom_fields: Vec<CustomFieldValue>,
+}
+
+/// User-defined field (PO number, cost centre, vendor ID…) offered on clients, invoices or items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomFieldDefinition {
+    pub id: Uuid,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub entity: CustomFieldEntity,
+    pub options: Vec<String>, // choices for Select fields
+    pub required: bool,
+    pub default_value: Option<String>,
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Custom field value attached to a client, invoice or item
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CustomFieldValue {
+    pub field_id: Uuid,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub value: String, // normalized: decimal, YYYY-MM-DD or true/false
+}
+
+/// Label/value pair listed under "Project Details"
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProjectDetail {
+    pub label: String,
+    pub value: String,
+}
+
+/// IRN details issued by the GST invoice registration portal for an e-invoice
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GstRegistration {
+    pub irn: String,
+    pub ack_no: String,
+    pub ack_date: String,
+    pub signed_qr: String,
+}
+
+/// A bank account clients can pay into. Identifiers are checked by [`BankAccount::validate`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BankAccount {
+    pub id: Uuid,
+    pub label: String, // e.g. "EUR business account"
+    pub account_holder: String,
+    pub bank_name: String,
+    pub branch: Option<String>,
+    pub account_number: Option<String>,
+    pub iban: Option<String>,
+    pub swift_bic: Option<String>,
+    pub ifsc_code: Option<String>,
+    pub routing_number: Option<String>, // ABA routing transit number
+    pub upi_id: Option<String>,
+    pub currency: Option<Currency>, // None = usable for any currency
+    pub is_default: bool, // default for `currency` (or for all currencies when None)
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 /// A saved invoice template
@@ -110,3 +215,31 @@ pub struct InvoiceTemplate {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// Two clients that are probably the same customer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateMatch {
+    pub client_id: Uuid,
+    pub duplicate_id: Uuid,
+    pub reasons: Vec<DuplicateReason>,
+    pub score: u8, // 0-100
+}
+
+/// A recorded change that cannot be read back from the data itself (e.g. a client merge)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: String,    // e.g. "merge"
+    pub entity: String,    // e.g. "client"
+    pub entity_id: String, // record the action left in place
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A stored row that cannot be read back, found by the database check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorruptRow {
+    pub table: String,
+    pub id: String,
+    pub problem: String,
+}
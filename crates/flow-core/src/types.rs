@@ -3,8 +3,9 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Currency representation
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum Currency {
+    #[default]
     USD,
     EUR,
     GBP,
@@ -26,12 +27,6 @@ impl fmt::Display for Currency {
     }
 }
 
-impl Default for Currency {
-    fn default() -> Self {
-        Currency::USD
-    }
-}
-
 impl std::str::FromStr for Currency {
     type Err = String;
 
@@ -47,9 +42,27 @@ impl std::str::FromStr for Currency {
     }
 }
 
+impl Currency {
+    /// Decimal places of the currency's minor unit (ISO 4217): 2 for cents, 0 for yen,
+    /// 3 for fils. Unknown codes are assumed to use cents.
+    pub fn minor_units(&self) -> u32 {
+        match self {
+            Currency::USD | Currency::EUR | Currency::GBP | Currency::INR | Currency::AED => 2,
+            Currency::Custom(code) => match code.to_uppercase().as_str() {
+                "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" | "VND" | "VUV"
+                | "XAF" | "XOF" | "XPF" => 0,
+                "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+                "CLF" | "UYW" => 4,
+                _ => 2,
+            },
+        }
+    }
+}
+
 /// Invoice status lifecycle
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum InvoiceStatus {
+    #[default]
     Draft,
     Pending,
     Sent,
@@ -59,17 +72,29 @@ pub enum InvoiceStatus {
     Cancelled,
 }
 
-impl Default for InvoiceStatus {
-    fn default() -> Self {
-        InvoiceStatus::Draft
+impl std::str::FromStr for InvoiceStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Draft" => Ok(InvoiceStatus::Draft),
+            "Pending" => Ok(InvoiceStatus::Pending),
+            "Sent" => Ok(InvoiceStatus::Sent),
+            "Viewed" => Ok(InvoiceStatus::Viewed),
+            "Paid" => Ok(InvoiceStatus::Paid),
+            "Overdue" => Ok(InvoiceStatus::Overdue),
+            "Cancelled" => Ok(InvoiceStatus::Cancelled),
+            _ => Err(format!("Unknown invoice status: {}", s)),
+        }
     }
 }
 
 /// Payment terms
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum PaymentTerms {
     DueOnReceipt,
     Net15,
+    #[default]
     Net30,
     Net60,
     Net90,
@@ -104,9 +129,62 @@ impl std::str::FromStr for PaymentTerms {
     }
 }
 
-impl Default for PaymentTerms {
-    fn default() -> Self {
-        PaymentTerms::Net30
+/// Value type of a user-defined custom field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum CustomFieldType {
+    #[default]
+    Text,
+    Number,
+    Date,
+    Boolean,
+    Select,
+}
+
+impl fmt::Display for CustomFieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::str::FromStr for CustomFieldType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Text" => Ok(CustomFieldType::Text),
+            "Number" => Ok(CustomFieldType::Number),
+            "Date" => Ok(CustomFieldType::Date),
+            "Boolean" => Ok(CustomFieldType::Boolean),
+            "Select" => Ok(CustomFieldType::Select),
+            _ => Err(format!("Unknown custom field type: {}", s)),
+        }
+    }
+}
+
+/// Record a custom field is attached to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CustomFieldEntity {
+    Client,
+    Invoice,
+    InvoiceItem,
+}
+
+impl fmt::Display for CustomFieldEntity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::str::FromStr for CustomFieldEntity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Client" => Ok(CustomFieldEntity::Client),
+            "Invoice" => Ok(CustomFieldEntity::Invoice),
+            "InvoiceItem" => Ok(CustomFieldEntity::InvoiceItem),
+            _ => Err(format!("Unknown custom field entity: {}", s)),
+        }
     }
 }
 
@@ -124,3 +202,13 @@ pub enum DiscountType {
     Percentage(Decimal),
     FixedAmount(Decimal),
 }
+
+/// Why two clients look like the same customer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DuplicateReason {
+    SameName,
+    SimilarName,
+    SameEmailDomain,
+    SameTaxId,
+    SimilarTaxId,
+}
@@ -0,0 +1,90 @@
+CREATE TABLE business_profiles (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT,
+    phone TEXT,
+    address_line1 TEXT NOT NULL DEFAULT '',
+    address_line2 TEXT,
+    address_city TEXT NOT NULL DEFAULT '',
+    address_state TEXT,
+    address_postal_code TEXT NOT NULL DEFAULT '',
+    address_country TEXT NOT NULL DEFAULT '',
+    tax_id TEXT,
+    logo_path TEXT,
+    default_currency TEXT NOT NULL DEFAULT 'USD',
+    default_payment_terms TEXT NOT NULL DEFAULT 'Net30',
+    theme_preference TEXT NOT NULL DEFAULT 'system',
+    pdf_export_dir TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE clients (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT,
+    phone TEXT,
+    company TEXT,
+    address_line1 TEXT NOT NULL DEFAULT '',
+    address_line2 TEXT,
+    address_city TEXT NOT NULL DEFAULT '',
+    address_state TEXT,
+    address_postal_code TEXT NOT NULL DEFAULT '',
+    address_country TEXT NOT NULL DEFAULT '',
+    notes TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE invoices (
+    id TEXT PRIMARY KEY NOT NULL,
+    number TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL DEFAULT 'Draft',
+    client_id TEXT NOT NULL,
+    business_profile_id TEXT NOT NULL,
+    issue_date TEXT NOT NULL,
+    due_date TEXT NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'USD',
+    subtotal TEXT NOT NULL DEFAULT '0',
+    tax_total TEXT NOT NULL DEFAULT '0',
+    discount_total TEXT NOT NULL DEFAULT '0',
+    total TEXT NOT NULL DEFAULT '0',
+    amount_paid TEXT NOT NULL DEFAULT '0',
+    amount_due TEXT NOT NULL DEFAULT '0',
+    payment_terms TEXT NOT NULL DEFAULT 'Net30',
+    notes TEXT,
+    terms_and_conditions TEXT,
+    tax_rates_json TEXT DEFAULT '[]',
+    discount_json TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (client_id) REFERENCES clients(id),
+    FOREIGN KEY (business_profile_id) REFERENCES business_profiles(id)
+);
+
+CREATE TABLE invoice_items (
+    id TEXT PRIMARY KEY NOT NULL,
+    invoice_id TEXT NOT NULL,
+    description TEXT NOT NULL,
+    quantity TEXT NOT NULL DEFAULT '1',
+    unit_price TEXT NOT NULL DEFAULT '0',
+    amount TEXT NOT NULL DEFAULT '0',
+    tax_rate_name TEXT,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE CASCADE
+);
+
+CREATE TABLE invoice_templates (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    layout_json TEXT NOT NULL DEFAULT '{}',
+    is_default INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX idx_invoices_client_id ON invoices(client_id);
+CREATE INDEX idx_invoices_status ON invoices(status);
+CREATE INDEX idx_invoices_due_date ON invoices(due_date);
+CREATE INDEX idx_invoice_items_invoice_id ON invoice_items(invoice_id);
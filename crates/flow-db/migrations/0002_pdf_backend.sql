@@ -0,0 +1 @@
+ALTER TABLE business_profiles ADD COLUMN pdf_backend TEXT NOT NULL DEFAULT 'native';
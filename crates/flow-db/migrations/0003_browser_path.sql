@@ -0,0 +1 @@
+ALTER TABLE business_profiles ADD COLUMN browser_path TEXT;
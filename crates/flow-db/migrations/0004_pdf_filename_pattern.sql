@@ -0,0 +1 @@
+ALTER TABLE business_profiles ADD COLUMN pdf_filename_pattern TEXT NOT NULL DEFAULT 'Invoice_{number}.pdf';
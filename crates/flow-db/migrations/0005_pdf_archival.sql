@@ -0,0 +1 @@
+ALTER TABLE business_profiles ADD COLUMN pdf_archival INTEGER NOT NULL DEFAULT 0;
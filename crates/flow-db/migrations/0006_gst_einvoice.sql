@@ -0,0 +1,6 @@
+ALTER TABLE clients ADD COLUMN tax_id TEXT;
+ALTER TABLE invoice_items ADD COLUMN hsn_code TEXT;
+ALTER TABLE invoices ADD COLUMN irn TEXT;
+ALTER TABLE invoices ADD COLUMN irn_ack_no TEXT;
+ALTER TABLE invoices ADD COLUMN irn_ack_date TEXT;
+ALTER TABLE invoices ADD COLUMN irn_signed_qr TEXT;
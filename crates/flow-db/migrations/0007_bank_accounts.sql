@@ -0,0 +1,19 @@
+CREATE TABLE bank_accounts (
+    id TEXT PRIMARY KEY NOT NULL,
+    label TEXT NOT NULL DEFAULT '',
+    account_holder TEXT NOT NULL,
+    bank_name TEXT NOT NULL DEFAULT '',
+    branch TEXT,
+    account_number TEXT,
+    iban TEXT,
+    swift_bic TEXT,
+    ifsc_code TEXT,
+    routing_number TEXT,
+    upi_id TEXT,
+    currency TEXT,
+    is_default INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+ALTER TABLE invoices ADD COLUMN bank_account_id TEXT REFERENCES bank_accounts(id) ON DELETE SET NULL;
@@ -0,0 +1,17 @@
+-- The values still stored as JSON in invoices.notes are moved into these columns
+-- by the data step that runs with this migration.
+ALTER TABLE invoices ADD COLUMN payment_note TEXT;
+ALTER TABLE invoices ADD COLUMN developer TEXT;
+ALTER TABLE invoices ADD COLUMN logo_path TEXT;
+ALTER TABLE invoices ADD COLUMN qr_code_url TEXT;
+
+CREATE TABLE invoice_project_details (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    invoice_id TEXT NOT NULL,
+    label TEXT NOT NULL DEFAULT '',
+    value TEXT NOT NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_invoice_project_details_invoice_id ON invoice_project_details(invoice_id);
@@ -0,0 +1,24 @@
+CREATE TABLE custom_field_definitions (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL DEFAULT 'Text',
+    entity TEXT NOT NULL,
+    options TEXT NOT NULL DEFAULT '[]',
+    required INTEGER NOT NULL DEFAULT 0,
+    default_value TEXT,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (entity, name)
+);
+
+-- entity_id is a client, invoice or invoice item id
+CREATE TABLE custom_field_values (
+    field_id TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (field_id, entity_id),
+    FOREIGN KEY (field_id) REFERENCES custom_field_definitions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_custom_field_values_entity_id ON custom_field_values(entity_id);
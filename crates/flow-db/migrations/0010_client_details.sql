@@ -0,0 +1,30 @@
+ALTER TABLE clients ADD COLUMN shipping_line1 TEXT;
+ALTER TABLE clients ADD COLUMN shipping_line2 TEXT;
+ALTER TABLE clients ADD COLUMN shipping_city TEXT;
+ALTER TABLE clients ADD COLUMN shipping_state TEXT;
+ALTER TABLE clients ADD COLUMN shipping_postal_code TEXT;
+ALTER TABLE clients ADD COLUMN shipping_country TEXT;
+
+CREATE TABLE client_contacts (
+    id TEXT PRIMARY KEY NOT NULL,
+    client_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT,
+    phone TEXT,
+    role TEXT,
+    is_primary INTEGER NOT NULL DEFAULT 0,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (client_id) REFERENCES clients(id) ON DELETE CASCADE
+);
+
+CREATE TABLE client_tax_ids (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    client_id TEXT NOT NULL,
+    scheme TEXT NOT NULL,
+    value TEXT NOT NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (client_id) REFERENCES clients(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_client_contacts_client_id ON client_contacts(client_id);
+CREATE INDEX idx_client_tax_ids_client_id ON client_tax_ids(client_id);
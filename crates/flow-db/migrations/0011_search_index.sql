@@ -0,0 +1,52 @@
+-- Full-text indexes searched by the list queries, kept in sync with their source table by triggers.
+
+CREATE VIRTUAL TABLE clients_fts USING fts5(id UNINDEXED, name, company, email, phone, notes);
+
+CREATE TRIGGER clients_fts_ai AFTER INSERT ON clients BEGIN
+    INSERT INTO clients_fts (id, name, company, email, phone, notes) VALUES (new.id, new.name, new.company, new.email, new.phone, new.notes);
+END;
+
+CREATE TRIGGER clients_fts_au AFTER UPDATE ON clients BEGIN
+    DELETE FROM clients_fts WHERE id = old.id;
+    INSERT INTO clients_fts (id, name, company, email, phone, notes) VALUES (new.id, new.name, new.company, new.email, new.phone, new.notes);
+END;
+
+CREATE TRIGGER clients_fts_ad AFTER DELETE ON clients BEGIN
+    DELETE FROM clients_fts WHERE id = old.id;
+END;
+
+INSERT INTO clients_fts (id, name, company, email, phone, notes) SELECT id, name, company, email, phone, notes FROM clients;
+
+CREATE VIRTUAL TABLE invoices_fts USING fts5(id UNINDEXED, number, notes, payment_note);
+
+CREATE TRIGGER invoices_fts_ai AFTER INSERT ON invoices BEGIN
+    INSERT INTO invoices_fts (id, number, notes, payment_note) VALUES (new.id, new.number, new.notes, new.payment_note);
+END;
+
+CREATE TRIGGER invoices_fts_au AFTER UPDATE ON invoices BEGIN
+    DELETE FROM invoices_fts WHERE id = old.id;
+    INSERT INTO invoices_fts (id, number, notes, payment_note) VALUES (new.id, new.number, new.notes, new.payment_note);
+END;
+
+CREATE TRIGGER invoices_fts_ad AFTER DELETE ON invoices BEGIN
+    DELETE FROM invoices_fts WHERE id = old.id;
+END;
+
+INSERT INTO invoices_fts (id, number, notes, payment_note) SELECT id, number, notes, payment_note FROM invoices;
+
+CREATE VIRTUAL TABLE invoice_items_fts USING fts5(id UNINDEXED, invoice_id UNINDEXED, description);
+
+CREATE TRIGGER invoice_items_fts_ai AFTER INSERT ON invoice_items BEGIN
+    INSERT INTO invoice_items_fts (id, invoice_id, description) VALUES (new.id, new.invoice_id, new.description);
+END;
+
+CREATE TRIGGER invoice_items_fts_au AFTER UPDATE ON invoice_items BEGIN
+    DELETE FROM invoice_items_fts WHERE id = old.id;
+    INSERT INTO invoice_items_fts (id, invoice_id, description) VALUES (new.id, new.invoice_id, new.description);
+END;
+
+CREATE TRIGGER invoice_items_fts_ad AFTER DELETE ON invoice_items BEGIN
+    DELETE FROM invoice_items_fts WHERE id = old.id;
+END;
+
+INSERT INTO invoice_items_fts (id, invoice_id, description) SELECT id, invoice_id, description FROM invoice_items;
@@ -0,0 +1,2 @@
+ALTER TABLE clients ADD COLUMN archived_at TEXT;
+ALTER TABLE invoices ADD COLUMN archived_at TEXT;
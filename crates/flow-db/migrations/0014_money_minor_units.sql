@@ -0,0 +1,13 @@
+-- Amounts move to INTEGER minor units at the invoice's currency_scale. The data step that
+-- runs with this migration fills them from the TEXT columns and fails on any value that is
+-- not a number; the TEXT columns are dropped by the next migration.
+ALTER TABLE invoices ADD COLUMN currency_scale INTEGER NOT NULL DEFAULT 2;
+ALTER TABLE invoices ADD COLUMN subtotal_minor INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoices ADD COLUMN tax_total_minor INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoices ADD COLUMN discount_total_minor INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoices ADD COLUMN total_minor INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoices ADD COLUMN amount_paid_minor INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoices ADD COLUMN amount_due_minor INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE invoice_items ADD COLUMN unit_price_minor INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoice_items ADD COLUMN amount_minor INTEGER NOT NULL DEFAULT 0;
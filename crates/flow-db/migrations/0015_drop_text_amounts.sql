@@ -0,0 +1,9 @@
+ALTER TABLE invoices DROP COLUMN subtotal;
+ALTER TABLE invoices DROP COLUMN tax_total;
+ALTER TABLE invoices DROP COLUMN discount_total;
+ALTER TABLE invoices DROP COLUMN total;
+ALTER TABLE invoices DROP COLUMN amount_paid;
+ALTER TABLE invoices DROP COLUMN amount_due;
+
+ALTER TABLE invoice_items DROP COLUMN unit_price;
+ALTER TABLE invoice_items DROP COLUMN amount;
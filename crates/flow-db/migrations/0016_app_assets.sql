@@ -0,0 +1,7 @@
+-- Logo and QR images, kept with the rest of the data instead of as files in the app data
+-- directory so they are encrypted along with it
+CREATE TABLE app_assets (
+    name TEXT PRIMARY KEY,
+    data TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,8 @@
+-- Application lock: a single row holding the Argon2 hash of the unlock passphrase or PIN and
+-- the inactivity timeout. Not user data, so a reset or an import leaves it alone.
+CREATE TABLE app_lock (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    passphrase_hash TEXT,
+    auto_lock_minutes INTEGER,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Saved column mappings for spreadsheet imports, by name. The mapping is the JSON of
+-- `spreadsheet::ColumnMapping`. Settings rather than user data, so a reset leaves them.
+CREATE TABLE import_presets (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    mapping TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,5 @@
+-- Unit prices may have more decimal places than the currency's minor unit (0.125 per kWh),
+-- so each item stores its own scale. Existing prices are at their invoice's currency_scale.
+ALTER TABLE invoice_items ADD COLUMN unit_price_scale INTEGER NOT NULL DEFAULT 2;
+UPDATE invoice_items
+SET unit_price_scale = COALESCE((SELECT currency_scale FROM invoices WHERE invoices.id = invoice_items.invoice_id), 2);
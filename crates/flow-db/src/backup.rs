@@ -0,0 +1,412 @@
+//! Backup archives of the database.
+//!
+//! A backup is a zip archive holding `manifest.json` and a `VACUUM INTO` snapshot of the
+//! database, taken while the app keeps running. Bank accounts, logo and QR code live in the
+//! database, so the snapshot covers them. An encrypted database is snapshotted encrypted under
+//! the same key, and only a pool opened with that key can verify or restore it.
+
+use crate::database::{self, blocking, DatabaseKey, DbPool};
+use crate::migrations;
+use chrono::{DateTime, Datelike, Utc};
+use flow_core::error::{FlowError, FlowResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Version of the archive layout; archives with a newer layout are refused.
+pub const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "invoiceflow.db";
+const FILE_PREFIX: &str = "invoiceflow-backup-";
+const FILE_EXTENSION: &str = "zip";
+
+/// What an archive holds, written next to the snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    /// Latest migration applied to the snapshot.
+    pub schema_version: i64,
+    pub created_at: DateTime<Utc>,
+    pub encrypted: bool,
+    pub database_size: u64,
+    pub database_sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub archive_size: u64,
+    pub manifest: BackupManifest,
+}
+
+/// Which backups [`prune_backups`] keeps: the newest one of each of the last `daily` days,
+/// `weekly` ISO weeks and `monthly` months that have a backup. The newest backup is always
+/// kept. Periods are counted in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupRetention {
+    pub daily: u32,
+    pub weekly: u32,
+    pub monthly: u32,
+}
+
+impl Default for BackupRetention {
+    fn default() -> Self {
+        BackupRetention { daily: 7, weekly: 4, monthly: 12 }
+    }
+}
+
+/// Snapshots the database behind `pool` into a new archive in `backup_dir`. The snapshot is
+/// checked before it is archived.
+pub async fn create_backup(pool: &DbPool, backup_dir: &Path) -> FlowResult<BackupInfo> {
+    std::fs::create_dir_all(backup_dir)?;
+    let created_at = Utc::now();
+    let archive = backup_dir.join(format!("{}{}.{}", FILE_PREFIX, created_at.format("%Y%m%d-%H%M%S"), FILE_EXTENSION));
+    let snapshot = database::sibling(&archive, ".db");
+    database::remove_database_files(&snapshot)?;
+
+    let result = async {
+        sqlx::query("VACUUM INTO ?").bind(snapshot.to_string_lossy().into_owned()).execute(pool).await?;
+        let schema_version = check_snapshot(pool, &snapshot).await?;
+        let (database_size, database_sha256) = digest_file(&snapshot)?;
+        let manifest = BackupManifest {
+            format_version: FORMAT_VERSION,
+            schema_version,
+            created_at,
+            encrypted: database::is_encrypted(&snapshot)?,
+            database_size,
+            database_sha256,
+        };
+        let (archive, snapshot, written) = (archive.clone(), snapshot.clone(), manifest.clone());
+        blocking(move || write_archive(&archive, &snapshot, &written)).await?;
+        Ok::<_, FlowError>(manifest)
+    }
+    .await;
+    database::remove_database_files(&snapshot).ok();
+
+    let manifest = result?;
+    Ok(BackupInfo { archive_size: std::fs::metadata(&archive)?.len(), path: archive, manifest })
+}
+
+/// The backups in `backup_dir`, newest first. Files that are not readable archives are left
+/// out.
+pub fn list_backups(backup_dir: &Path) -> FlowResult<Vec<BackupInfo>> {
+    let entries = match std::fs::read_dir(backup_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let is_backup = path.extension().is_some_and(|ext| ext == FILE_EXTENSION)
+            && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(FILE_PREFIX));
+        if !is_backup {
+            continue;
+        }
+        let Ok(manifest) = File::open(&path).map_err(FlowError::from).and_then(|file| read_manifest(&mut open_archive(file)?)) else {
+            continue;
+        };
+        backups.push(BackupInfo { archive_size: std::fs::metadata(&path)?.len(), path, manifest });
+    }
+    backups.sort_by_key(|b| Reverse(b.manifest.created_at));
+    Ok(backups)
+}
+
+/// Checks an archive end to end: manifest, checksum, SQLite integrity and schema version.
+pub async fn verify_backup(pool: &DbPool, archive: &Path) -> FlowResult<BackupManifest> {
+    let snapshot = std::env::temp_dir().join(format!("invoiceflow-verify-{}.db", uuid::Uuid::new_v4()));
+    let result = extract_checked(pool, archive, &snapshot).await;
+    database::remove_database_files(&snapshot).ok();
+    result
+}
+
+/// Replaces the database at `db_path` with the snapshot in `archive` and returns a pool opened
+/// on it with `key`, which must be the key `pool` was opened with.
+///
+/// The archive is extracted, checked and migrated to the current schema beside the live
+/// database before `pool` is closed; on error up to then nothing has changed and `pool` stays
+/// usable. The replaced database is kept as `<db>.pre-restore`.
+pub async fn restore_backup(pool: &DbPool, db_path: &Path, archive: &Path, key: Option<&DatabaseKey>) -> FlowResult<DbPool> {
+    let restored = database::sibling(db_path, ".restore");
+    database::remove_database_files(&restored)?;
+
+    let prepared = async {
+        extract_checked(pool, archive, &restored).await?;
+        // Opening runs the migrations, so an older snapshot that cannot be migrated fails here
+        database::init_db(&restored, key).await?.close().await;
+        Ok::<_, FlowError>(())
+    }
+    .await;
+    if let Err(e) = prepared {
+        database::remove_database_files(&restored).ok();
+        return Err(e);
+    }
+
+    let previous = database::sibling(db_path, ".pre-restore");
+    database::remove_database_files(&previous)?;
+    pool.close().await;
+    // The live file stays in place until the rename below replaces it in one step, so an
+    // interruption leaves either the old or the restored database, never none
+    if std::fs::hard_link(db_path, &previous).is_err() {
+        std::fs::copy(db_path, &previous)?;
+    }
+    // The WAL was checkpointed on close; what is left of it belongs to the replaced file
+    database::remove_journal_files(db_path)?;
+    std::fs::rename(&restored, db_path)?;
+    database::remove_journal_files(&restored)?;
+
+    database::init_db(db_path, key).await
+}
+
+/// Deletes the backups in `backup_dir` that `retention` does not keep and returns their paths.
+pub fn prune_backups(backup_dir: &Path, retention: &BackupRetention) -> FlowResult<Vec<PathBuf>> {
+    let backups = list_backups(backup_dir)?;
+    let created: Vec<DateTime<Utc>> = backups.iter().map(|b| b.manifest.created_at).collect();
+    let keep = retained(&created, retention);
+
+    let mut removed = Vec::new();
+    for (i, backup) in backups.into_iter().enumerate() {
+        if !keep.contains(&i) {
+            std::fs::remove_file(&backup.path)?;
+            removed.push(backup.path);
+        }
+    }
+    Ok(removed)
+}
+
+/// A day, ISO week or month, as year and number within the year.
+type Period = (i32, u32);
+type PeriodOf = fn(&DateTime<Utc>) -> Period;
+
+/// Indices into `created` of the backups `retention` keeps.
+fn retained(created: &[DateTime<Utc>], retention: &BackupRetention) -> HashSet<usize> {
+    let mut newest_first: Vec<usize> = (0..created.len()).collect();
+    newest_first.sort_by_key(|&i| Reverse(created[i]));
+
+    let periods: [(u32, PeriodOf); 3] = [
+        (retention.daily, |t| (t.year(), t.ordinal())),
+        (retention.weekly, |t| (t.iso_week().year(), t.iso_week().week())),
+        (retention.monthly, |t| (t.year(), t.month())),
+    ];
+
+    let mut keep: HashSet<usize> = newest_first.first().copied().into_iter().collect();
+    for (count, period_of) in periods {
+        let mut periods_seen = HashSet::new();
+        for &i in &newest_first {
+            let period = period_of(&created[i]);
+            if periods_seen.contains(&period) {
+                continue;
+            }
+            if periods_seen.len() >= count as usize {
+                break;
+            }
+            periods_seen.insert(period);
+            keep.insert(i);
+        }
+    }
+    keep
+}
+
+/// Extracts the snapshot in `archive` to `dest` and checks it against its manifest and this
+/// app's schema.
+async fn extract_checked(pool: &DbPool, archive: &Path, dest: &Path) -> FlowResult<BackupManifest> {
+    let (source, target) = (archive.to_path_buf(), dest.to_path_buf());
+    let manifest = blocking(move || extract_archive(&source, &target)).await?;
+
+    let schema_version = check_snapshot(pool, dest).await?;
+    if schema_version != manifest.schema_version {
+        return Err(FlowError::Validation("The backup is damaged: its schema version does not match its manifest".to_string()));
+    }
+    if schema_version > migrations::latest_version() {
+        return Err(FlowError::Validation(format!(
+            "The backup has schema version {}, newer than this app supports ({}); update the app first",
+            schema_version,
+            migrations::latest_version()
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Checks the SQLite file at `path` with the key of `pool` and returns its schema version.
+async fn check_snapshot(pool: &DbPool, path: &Path) -> FlowResult<i64> {
+    let mut conn = pool.acquire().await?;
+    // Without a KEY clause SQLCipher attaches with the key of the main database
+    sqlx::query("ATTACH DATABASE ? AS snapshot")
+        .bind(path.to_string_lossy().into_owned())
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.message().contains("file is not a database") => {
+                FlowError::Validation("The backup cannot be read with the current database key".to_string())
+            }
+            e => e.into(),
+        })?;
+
+    let result = async {
+        let problems: Vec<String> = sqlx::query_scalar("PRAGMA snapshot.integrity_check").fetch_all(&mut *conn).await?;
+        if problems != ["ok"] {
+            return Err(FlowError::Validation(format!("The backup is damaged: {}", problems.join("; "))));
+        }
+        let version = sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM snapshot.schema_migrations")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|_| FlowError::Validation("The backup is not an InvoiceFlow database".to_string()))?;
+        Ok(version)
+    }
+    .await;
+
+    sqlx::query("DETACH DATABASE snapshot").execute(&mut *conn).await?;
+    result
+}
+
+fn write_archive(archive: &Path, snapshot: &Path, manifest: &BackupManifest) -> FlowResult<()> {
+    // Written under another name first, so an interrupted backup never looks complete
+    let partial = database::sibling(archive, ".partial");
+    let written = (|| -> FlowResult<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated).large_file(true);
+        let mut zip = ZipWriter::new(File::create(&partial)?);
+        zip.start_file(MANIFEST_ENTRY, options).map_err(archive_error)?;
+        zip.write_all(&serde_json::to_vec_pretty(manifest)?)?;
+        zip.start_file(DATABASE_ENTRY, options).map_err(archive_error)?;
+        io::copy(&mut File::open(snapshot)?, &mut zip)?;
+        zip.finish().map_err(archive_error)?.sync_all()?;
+        Ok(())
+    })();
+
+    match written {
+        Ok(()) => Ok(std::fs::rename(&partial, archive)?),
+        Err(e) => {
+            std::fs::remove_file(&partial).ok();
+            Err(e)
+        }
+    }
+}
+
+/// Extracts the snapshot to `dest` and checks its size and checksum against the manifest.
+fn extract_archive(archive: &Path, dest: &Path) -> FlowResult<BackupManifest> {
+    let mut zip = open_archive(File::open(archive)?)?;
+    let manifest = read_manifest(&mut zip)?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(FlowError::Validation("The backup was made by a newer version of InvoiceFlow; update the app first".to_string()));
+    }
+
+    let mut entry = zip.by_name(DATABASE_ENTRY).map_err(archive_error)?;
+    io::copy(&mut entry, &mut File::create(dest)?)?;
+    let (size, sha256) = digest_file(dest)?;
+    if size != manifest.database_size || sha256 != manifest.database_sha256 {
+        return Err(FlowError::Validation("The backup is damaged: its database does not match its manifest".to_string()));
+    }
+    Ok(manifest)
+}
+
+fn open_archive(file: File) -> FlowResult<ZipArchive<File>> {
+    ZipArchive::new(file).map_err(archive_error)
+}
+
+fn read_manifest(zip: &mut ZipArchive<File>) -> FlowResult<BackupManifest> {
+    let entry = zip.by_name(MANIFEST_ENTRY).map_err(archive_error)?;
+    serde_json::from_reader(entry).map_err(|e| FlowError::Validation(format!("The backup manifest is unreadable: {}", e)))
+}
+
+fn archive_error(err: ZipError) -> FlowError {
+    match err {
+        ZipError::Io(e) => FlowError::Io(e),
+        other => FlowError::Validation(format!("Not a readable backup archive: {}", other)),
+    }
+}
+
+fn digest_file(path: &Path) -> FlowResult<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok((size, hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("invoiceflow-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn audit_count(pool: &DbPool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM audit_log").fetch_one(pool).await.unwrap()
+    }
+
+    async fn audit(pool: &DbPool, entity_id: &str) {
+        sqlx::query("INSERT INTO audit_log (action, entity, entity_id) VALUES ('create', 'client', ?)")
+            .bind(entity_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_retention_keeps_newest_per_period() {
+        let at = |y, m, d, h| Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap();
+        let created = [
+            at(2026, 3, 10, 18), // 0: newest
+            at(2026, 3, 10, 9),  // 1: same day as 0
+            at(2026, 3, 9, 9),   // 2: previous day
+            at(2026, 3, 2, 9),   // 3: previous ISO week
+            at(2026, 2, 20, 9),  // 4: previous month
+            at(2026, 1, 5, 9),   // 5: two months back
+        ];
+        let keep = |daily, weekly, monthly| {
+            let mut kept: Vec<usize> = retained(&created, &BackupRetention { daily, weekly, monthly }).into_iter().collect();
+            kept.sort();
+            kept
+        };
+
+        assert_eq!(keep(0, 0, 0), vec![0]);
+        assert_eq!(keep(2, 0, 0), vec![0, 2]);
+        assert_eq!(keep(1, 2, 0), vec![0, 3]);
+        assert_eq!(keep(0, 0, 2), vec![0, 4]);
+        assert_eq!(keep(7, 4, 12), vec![0, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_backup_verifies_and_restores() {
+        let dir = temp_dir("backup");
+        let db_path = dir.join("invoiceflow.db");
+        let key = DatabaseKey::Passphrase("backup key".to_string());
+        let pool = database::init_db(&db_path, Some(&key)).await.unwrap();
+        audit(&pool, "before").await;
+
+        let backup = create_backup(&pool, &dir.join("backups")).await.unwrap();
+        assert!(backup.manifest.encrypted);
+        assert_eq!(backup.manifest.schema_version, migrations::latest_version());
+        assert_eq!(verify_backup(&pool, &backup.path).await.unwrap(), backup.manifest);
+        assert_eq!(list_backups(&dir.join("backups")).unwrap().len(), 1);
+
+        audit(&pool, "after").await;
+        let pool = restore_backup(&pool, &db_path, &backup.path, Some(&key)).await.unwrap();
+        assert_eq!(audit_count(&pool).await, 1);
+        // The replaced database is kept, with the row added after the backup
+        let previous = database::init_db(&database::sibling(&db_path, ".pre-restore"), Some(&key)).await.unwrap();
+        assert_eq!(audit_count(&previous).await, 2);
+        previous.close().await;
+
+        // A truncated archive is refused and the live database stays as it is
+        let damaged = dir.join("backups").join(format!("{}damaged.{}", FILE_PREFIX, FILE_EXTENSION));
+        let bytes = std::fs::read(&backup.path).unwrap();
+        std::fs::write(&damaged, &bytes[..bytes.len() / 2]).unwrap();
+        assert!(restore_backup(&pool, &db_path, &damaged, Some(&key)).await.is_err());
+        assert_eq!(audit_count(&pool).await, 1);
+
+        pool.close().await;
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
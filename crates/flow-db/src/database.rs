@@ -1,34 +1,285 @@
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
-use std::path::Path;
+use flow_core::error::{FlowError, FlowResult};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Pool, Sqlite};
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 pub type DbPool = Pool<Sqlite>;
 
+/// First bytes of every unencrypted SQLite file; an encrypted file starts with random salt.
+const PLAINTEXT_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Key of an encrypted database file.
+#[derive(Clone, PartialEq, Eq)]
+pub enum DatabaseKey {
+    /// Typed by the user; SQLCipher derives the key from it.
+    Passphrase(String),
+    /// 256 random bits, e.g. kept in the OS keyring, used without derivation.
+    Raw([u8; 32]),
+}
+
+impl DatabaseKey {
+    /// A new random raw key.
+    pub fn generate() -> FlowResult<Self> {
+        let mut key = [0u8; 32];
+        getrandom::getrandom(&mut key).map_err(|e| FlowError::Internal(format!("No random source for the database key: {}", e)))?;
+        Ok(DatabaseKey::Raw(key))
+    }
+
+    /// Reads a raw key stored with [`DatabaseKey::to_hex`].
+    pub fn from_hex(hex: &str) -> FlowResult<Self> {
+        let invalid = || FlowError::Validation("Stored database key is not 64 hex digits".to_string());
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(DatabaseKey::Raw(key))
+    }
+
+    /// Hex form of a raw key for storage; `None` for a passphrase, which is never stored.
+    pub fn to_hex(&self) -> Option<String> {
+        match self {
+            DatabaseKey::Raw(key) => Some(key.iter().map(|b| format!("{:02x}", b)).collect()),
+            DatabaseKey::Passphrase(_) => None,
+        }
+    }
+
+    /// The key as SQLCipher reads it in `PRAGMA key` and `ATTACH ... KEY`: a raw key is
+    /// written as a blob literal inside the string.
+    fn sqlcipher_text(&self) -> String {
+        match self {
+            DatabaseKey::Passphrase(passphrase) => passphrase.clone(),
+            DatabaseKey::Raw(_) => format!("x'{}'", self.to_hex().unwrap_or_default()),
+        }
+    }
+}
+
+impl fmt::Debug for DatabaseKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseKey::Passphrase(_) => f.write_str("DatabaseKey::Passphrase(..)"),
+            DatabaseKey::Raw(_) => f.write_str("DatabaseKey::Raw(..)"),
+        }
+    }
+}
+
 /// Initialize the SQLite database, creating the file if it doesn't exist.
-pub async fn init_db(db_path: &Path) -> Result<DbPool, sqlx::Error> {
+///
+/// With a key the file is opened, or created, encrypted with SQLCipher. A wrong key, or a key
+/// for a plaintext file, fails here rather than on the first query. A missing file is not
+/// created while a [`change_key`] copy is left next to it, which would hide the data.
+pub async fn init_db(db_path: &Path, key: Option<&DatabaseKey>) -> FlowResult<DbPool> {
+    let rekeyed_path = sibling(db_path, ".rekey");
+    if !db_path.exists() && rekeyed_path.exists() {
+        return Err(FlowError::Internal(format!(
+            "{} is missing, but an interrupted key change left {}; rename it to {} to recover it",
+            db_path.display(),
+            rekeyed_path.display(),
+            db_path.display()
+        )));
+    }
+
     // Ensure parent dir exists
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent).ok();
     }
 
-    let db_url = format!("sqlite:{}?mode=rwc", db_path.display());
-
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
-        .connect(&db_url)
-        .await?;
-
-    // Enable WAL mode for better concurrent read performance
-    sqlx::query("PRAGMA journal_mode=WAL;")
-        .execute(&pool)
-        .await?;
-
-    // Enable foreign keys
-    sqlx::query("PRAGMA foreign_keys=ON;")
-        .execute(&pool)
-        .await?;
+        .connect_with(connect_options(db_path, key))
+        .await
+        .map_err(|e| match (key, e) {
+            (Some(_), sqlx::Error::Database(db)) if db.message().contains("file is not a database") => {
+                FlowError::Validation("The database key is wrong or the database is not encrypted".to_string())
+            }
+            (_, e) => e.into(),
+        })?;
 
     // Run migrations
     crate::migrations::run_migrations(&pool).await?;
 
     Ok(pool)
 }
+
+/// Every connection gets the key first, then WAL mode for better concurrent read
+/// performance and foreign keys.
+fn connect_options(db_path: &Path, key: Option<&DatabaseKey>) -> SqliteConnectOptions {
+    let options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true);
+    match key {
+        Some(key) => options.pragma("key", quote(&key.sqlcipher_text())),
+        None => options,
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Whether the file at `db_path` is encrypted; `false` when it does not exist yet.
+pub fn is_encrypted(db_path: &Path) -> FlowResult<bool> {
+    let mut file = match std::fs::File::open(db_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    let mut header = [0u8; 16];
+    match file.read_exact(&mut header) {
+        Ok(()) => Ok(&header != PLAINTEXT_HEADER),
+        // An empty file is a database SQLite has not written to yet
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Rewrites the database at `db_path` under `new_key`, or unencrypted for `None`, and
+/// returns a pool opened with it.
+///
+/// The data is exported into a new file first; `pool` is only closed, and the original
+/// replaced, once the export succeeded. On error the original file is left untouched and
+/// `pool` stays usable.
+pub async fn change_key(pool: &DbPool, db_path: &Path, new_key: Option<&DatabaseKey>) -> FlowResult<DbPool> {
+    let rekeyed_path = sibling(db_path, ".rekey");
+    remove_database_files(&rekeyed_path)?;
+
+    let exported = export_copy(pool, &rekeyed_path, new_key).await;
+    if let Err(e) = exported {
+        remove_database_files(&rekeyed_path).ok();
+        return Err(e);
+    }
+
+    // Closing the last connection checkpoints the WAL into the old file, which the rename
+    // then replaces in one step; its emptied WAL must not be applied to the new file
+    pool.close().await;
+    remove_journal_files(db_path)?;
+    std::fs::rename(&rekeyed_path, db_path)?;
+
+    init_db(db_path, new_key).await
+}
+
+/// Copies every table, index, trigger and the schema version into a new database file.
+async fn export_copy(pool: &DbPool, path: &Path, key: Option<&DatabaseKey>) -> FlowResult<()> {
+    let mut conn = pool.acquire().await?;
+    // sqlcipher_export fails with "database schema has changed" on a connection that has not
+    // read the schema yet, which a fresh pool connection may not have
+    sqlx::query("SELECT count(*) FROM sqlite_master").execute(&mut *conn).await?;
+    let key_text = key.map(DatabaseKey::sqlcipher_text).unwrap_or_default();
+    sqlx::query("ATTACH DATABASE ? AS rekeyed KEY ?")
+        .bind(path.to_string_lossy().into_owned())
+        .bind(key_text)
+        .execute(&mut *conn)
+        .await?;
+
+    let result = async {
+        sqlx::raw_sql("SELECT sqlcipher_export('rekeyed')").execute(&mut *conn).await?;
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version").fetch_one(&mut *conn).await?;
+        sqlx::query(&format!("PRAGMA rekeyed.user_version = {}", version)).execute(&mut *conn).await?;
+        Ok::<_, sqlx::Error>(())
+    }
+    .await;
+
+    sqlx::query("DETACH DATABASE rekeyed").execute(&mut *conn).await?;
+    Ok(result?)
+}
+
+pub(crate) fn sibling(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    db_path.with_file_name(name)
+}
+
+/// Removes a database file together with its WAL and shared-memory files.
+pub(crate) fn remove_database_files(db_path: &Path) -> FlowResult<()> {
+    remove_file(db_path)?;
+    remove_journal_files(db_path)
+}
+
+/// Removes the WAL and shared-memory files of a closed database, leaving the database itself.
+pub(crate) fn remove_journal_files(db_path: &Path) -> FlowResult<()> {
+    remove_file(&sibling(db_path, "-wal"))?;
+    remove_file(&sibling(db_path, "-shm"))
+}
+
+fn remove_file(path: &Path) -> FlowResult<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Runs slow, synchronous work (hashing, compressing) off the async executor.
+pub(crate) async fn blocking<T: Send + 'static>(f: impl FnOnce() -> FlowResult<T> + Send + 'static) -> FlowResult<T> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| FlowError::Internal(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("invoiceflow-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("invoiceflow.db")
+    }
+
+    async fn audited_id(pool: &DbPool) -> String {
+        sqlx::query_scalar("SELECT entity_id FROM audit_log").fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_encrypts_rekeys_and_decrypts() {
+        let path = temp_db("rekey");
+        let pool = init_db(&path, None).await.unwrap();
+        sqlx::query("INSERT INTO audit_log (action, entity, entity_id) VALUES ('create', 'client', 'Acme')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(!is_encrypted(&path).unwrap());
+
+        let passphrase = DatabaseKey::Passphrase("correct horse's battery".to_string());
+        let pool = change_key(&pool, &path, Some(&passphrase)).await.unwrap();
+        assert!(is_encrypted(&path).unwrap());
+        assert_eq!(audited_id(&pool).await, "Acme");
+        pool.close().await;
+
+        let wrong = DatabaseKey::Passphrase("wrong".to_string());
+        assert!(matches!(init_db(&path, Some(&wrong)).await, Err(FlowError::Validation(_))));
+        assert!(init_db(&path, None).await.is_err());
+
+        let raw = DatabaseKey::generate().unwrap();
+        assert_eq!(DatabaseKey::from_hex(&raw.to_hex().unwrap()).unwrap(), raw);
+        let pool = init_db(&path, Some(&passphrase)).await.unwrap();
+        let pool = change_key(&pool, &path, Some(&raw)).await.unwrap();
+        assert_eq!(audited_id(&pool).await, "Acme");
+
+        let pool = change_key(&pool, &path, None).await.unwrap();
+        assert!(!is_encrypted(&path).unwrap());
+        assert_eq!(audited_id(&pool).await, "Acme");
+        pool.close().await;
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_does_not_replace_a_left_over_rekeyed_copy_with_an_empty_database() {
+        let path = temp_db("interrupted");
+        let pool = init_db(&path, None).await.unwrap();
+        pool.close().await;
+        // As if a key change had been interrupted after removing the original
+        std::fs::rename(&path, sibling(&path, ".rekey")).unwrap();
+
+        let err = init_db(&path, None).await.unwrap_err();
+        assert!(err.to_string().contains("interrupted key change"), "{}", err);
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,90 @@
+//! Checked conversions from stored columns to typed values. A value that does not parse
+//! is a [`FlowError::Database`] naming the table, row id and column, never a default.
+
+use crate::money;
+use chrono::{DateTime, NaiveDate, Utc};
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::CorruptRow;
+use rust_decimal::Decimal;
+use sqlx::sqlite::SqliteRow;
+use sqlx::FromRow;
+use std::fmt::Debug;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::database::DbPool;
+
+/// The row a column is being read from, for error messages.
+pub(crate) struct RowRef<'a> {
+    table: &'static str,
+    id: &'a str,
+}
+
+impl<'a> RowRef<'a> {
+    pub(crate) fn new(table: &'static str, id: &'a str) -> Self {
+        Self { table, id }
+    }
+
+    pub(crate) fn invalid(&self, column: &str, value: impl Debug) -> FlowError {
+        FlowError::Database(format!("{} row {}: invalid {} {:?}", self.table, self.id, column, value))
+    }
+
+    pub(crate) fn uuid(&self, column: &str, value: &str) -> FlowResult<Uuid> {
+        Uuid::parse_str(value).map_err(|_| self.invalid(column, value))
+    }
+
+    pub(crate) fn date(&self, column: &str, value: &str) -> FlowResult<NaiveDate> {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| self.invalid(column, value))
+    }
+
+    pub(crate) fn timestamp(&self, column: &str, value: &str) -> FlowResult<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|_| self.invalid(column, value))
+    }
+
+    pub(crate) fn optional_timestamp(&self, column: &str, value: Option<&str>) -> FlowResult<Option<DateTime<Utc>>> {
+        value.map(|v| self.timestamp(column, v)).transpose()
+    }
+
+    pub(crate) fn parse<T: FromStr>(&self, column: &str, value: &str) -> FlowResult<T> {
+        T::from_str(value).map_err(|_| self.invalid(column, value))
+    }
+
+    pub(crate) fn decimal(&self, column: &str, value: &str) -> FlowResult<Decimal> {
+        Decimal::from_str(value.trim()).map_err(|_| self.invalid(column, value))
+    }
+
+    /// An amount stored in minor units; only the scale can make it unreadable.
+    pub(crate) fn amount(&self, minor: i64, scale: i64) -> FlowResult<Decimal> {
+        money::from_minor(minor, scale).map_err(|_| self.invalid("currency_scale", scale))
+    }
+}
+
+/// Reads every row of `table` one at a time with `select`, which must end in a
+/// `rowid = ?` condition, and reports the rows that do not convert to `T`.
+pub(crate) async fn find_corrupt_rows<R, T>(pool: &DbPool, table: &'static str, select: &str) -> FlowResult<Vec<CorruptRow>>
+where
+    R: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    T: TryFrom<R, Error = FlowError>,
+{
+    let ids: Vec<(i64, Option<String>)> = sqlx::query_as(&format!("SELECT rowid, CAST(id AS TEXT) FROM {}", table))
+        .fetch_all(pool)
+        .await?;
+
+    let mut corrupt = Vec::new();
+    for (rowid, id) in ids {
+        let id = id.unwrap_or_else(|| format!("rowid {}", rowid));
+        let problem = match sqlx::query_as::<_, R>(select).bind(rowid).fetch_one(pool).await {
+            Ok(row) => match T::try_from(row) {
+                Ok(_) => continue,
+                Err(FlowError::Database(message)) => message,
+                Err(e) => e.to_string(),
+            },
+            Err(e @ (sqlx::Error::ColumnDecode { .. } | sqlx::Error::Decode(_))) => format!("{} row {}: {}", table, id, e),
+            Err(e) => return Err(e.into()),
+        };
+        corrupt.push(CorruptRow { table: table.to_string(), id, problem });
+    }
+    Ok(corrupt)
+}
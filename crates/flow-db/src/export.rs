@@ -0,0 +1,422 @@
+//! Export archives: everything entered into InvoiceFlow as one JSON file, and importing one.
+//!
+//! Unlike a backup, an export is readable and is imported record by record, so it can be
+//! merged into a database that already holds data. A record that is already there is a
+//! conflict, settled by the [`ConflictPolicy`]. An import runs in a single transaction; a dry
+//! run makes the same writes and rolls them back, so its [`ImportReport`] is the exact diff.
+
+use crate::database::DbPool;
+use crate::repositories::{
+    asset_repo, bank_account_repo, business_profile_repo, client_repo, custom_field_repo, invoice_repo, template_repo, AssetRepository,
+    BankAccountRepository, BusinessProfileRepository, ClientRepository, CustomFieldRepository, InvoiceRepository, TemplateRepository,
+};
+use chrono::{DateTime, Utc};
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::{BankAccount, BusinessProfile, Client, CustomFieldDefinition, Invoice, InvoiceTemplate};
+use serde::{Deserialize, Serialize};
+use sqlx::{Sqlite, Transaction};
+use std::collections::BTreeMap;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Version of the archive layout; archives with a newer layout are refused. Version 1 was the
+/// old export of invoice summaries, which cannot be imported.
+pub const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataArchive {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub profile: BusinessProfile,
+    pub bank_accounts: Vec<BankAccount>,
+    pub custom_fields: Vec<CustomFieldDefinition>,
+    pub clients: Vec<Client>,
+    /// Complete invoices with their items. Payments are recorded as each invoice's
+    /// `amount_paid`, so they travel with it.
+    pub invoices: Vec<Invoice>,
+    pub templates: Vec<InvoiceTemplate>,
+    /// Stored images by name, such as [`asset_repo::LOGO`].
+    pub assets: BTreeMap<String, String>,
+}
+
+/// What to do with an archived record that is already in the database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Keep the record in the database.
+    #[default]
+    Skip,
+    /// Replace the record in the database with the archived one.
+    Overwrite,
+    /// Import an invoice whose number is taken under the next free number. An invoice that is
+    /// already there is imported as a copy with new ids. Other records are kept as with `Skip`.
+    Rename,
+}
+
+/// An invoice imported under another number.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Renamed {
+    pub from: String,
+    pub to: String,
+}
+
+/// What an import did, or would do, to one kind of record, by name or number.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportChanges {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub skipped: Vec<String>,
+    pub renamed: Vec<Renamed>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Whether the changes were rolled back.
+    pub dry_run: bool,
+    pub profile: ImportChanges,
+    pub bank_accounts: ImportChanges,
+    pub custom_fields: ImportChanges,
+    pub clients: ImportChanges,
+    pub invoices: ImportChanges,
+    pub templates: ImportChanges,
+    pub assets: ImportChanges,
+}
+
+/// Reads everything out of the database.
+pub async fn export_data(pool: &DbPool) -> FlowResult<DataArchive> {
+    let invoice_repo = InvoiceRepository::new(pool.clone());
+    let mut invoices = Vec::new();
+    for summary in invoice_repo.list_all().await? {
+        if let Some(invoice) = invoice_repo.get_by_id(&summary.id).await? {
+            invoices.push(invoice);
+        }
+    }
+    // Oldest first, so numbers are taken in the order they were issued when renaming
+    invoices.reverse();
+
+    Ok(DataArchive {
+        format_version: FORMAT_VERSION,
+        exported_at: Utc::now(),
+        profile: BusinessProfileRepository::new(pool.clone()).get_profile().await?,
+        bank_accounts: BankAccountRepository::new(pool.clone()).list_all().await?,
+        custom_fields: CustomFieldRepository::new(pool.clone()).list_definitions(None).await?,
+        clients: ClientRepository::new(pool.clone()).list_all().await?,
+        invoices,
+        templates: TemplateRepository::new(pool.clone()).list_all().await?,
+        assets: AssetRepository::new(pool.clone()).list_all().await?.into_iter().collect(),
+    })
+}
+
+pub fn write_archive(archive: &DataArchive, path: &Path) -> FlowResult<()> {
+    Ok(std::fs::write(path, serde_json::to_string_pretty(archive)?)?)
+}
+
+/// Reads an archive, refusing files that are not one or have a layout this version cannot
+/// read.
+pub fn read_archive(path: &Path) -> FlowResult<DataArchive> {
+    let value: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|_| FlowError::Validation("The file is not an InvoiceFlow export".to_string()))?;
+    match value.get("format_version").and_then(|v| v.as_u64()) {
+        None => Err(FlowError::Validation(
+            "The file is not an InvoiceFlow export, or was exported by a version without line items and cannot be imported".to_string(),
+        )),
+        Some(version) if version > u64::from(FORMAT_VERSION) => Err(FlowError::Validation(format!(
+            "The export has format version {}; this version of InvoiceFlow reads up to {}",
+            version, FORMAT_VERSION
+        ))),
+        Some(_) => serde_json::from_value(value).map_err(|e| FlowError::Validation(format!("The export is damaged: {}", e))),
+    }
+}
+
+/// Imports the archive in one transaction, settling conflicts by `policy`. A `dry_run` rolls
+/// the transaction back and only reports.
+///
+/// The business profile is replaced only under [`ConflictPolicy::Overwrite`], and keeps its
+/// id; imported invoices are moved to it.
+pub async fn import_data(pool: &DbPool, archive: &DataArchive, policy: ConflictPolicy, dry_run: bool) -> FlowResult<ImportReport> {
+    let profile_id = BusinessProfileRepository::new(pool.clone()).get_profile().await?.id;
+    let overwrite = policy == ConflictPolicy::Overwrite;
+    let mut report = ImportReport { dry_run, ..Default::default() };
+    let mut tx = pool.begin().await?;
+
+    let label = archive.profile.name.clone();
+    if overwrite {
+        business_profile_repo::update_profile(&mut tx, &BusinessProfile { id: profile_id, ..archive.profile.clone() }).await?;
+        report.profile.updated.push(label);
+    } else {
+        report.profile.skipped.push(label);
+    }
+
+    for account in &archive.bank_accounts {
+        let exists = exists(&mut tx, "bank_accounts", "id", &account.id.to_string()).await?;
+        if !exists || overwrite {
+            bank_account_repo::save_bank_account(&mut tx, account).await?;
+        }
+        record(&mut report.bank_accounts, exists, overwrite, &account.label);
+    }
+
+    for definition in &archive.custom_fields {
+        let exists = exists(&mut tx, "custom_field_definitions", "id", &definition.id.to_string()).await?;
+        if !exists || overwrite {
+            custom_field_repo::save_definition(&mut tx, definition).await?;
+        }
+        record(&mut report.custom_fields, exists, overwrite, &definition.name);
+    }
+
+    for template in &archive.templates {
+        let exists = exists(&mut tx, "invoice_templates", "id", &template.id.to_string()).await?;
+        if !exists || overwrite {
+            template_repo::save_template(&mut tx, template).await?;
+        }
+        record(&mut report.templates, exists, overwrite, &template.name);
+    }
+
+    for (name, data) in &archive.assets {
+        let exists = exists(&mut tx, "app_assets", "name", name).await?;
+        if !exists || overwrite {
+            asset_repo::save_asset(&mut tx, name, data).await?;
+        }
+        record(&mut report.assets, exists, overwrite, name);
+    }
+
+    for client in &archive.clients {
+        let id = client.id.to_string();
+        let exists = exists(&mut tx, "clients", "id", &id).await?;
+        if !exists {
+            client_repo::insert_client(&mut tx, client).await?;
+        } else if overwrite {
+            client_repo::update_client(&mut tx, client).await?;
+            client_repo::set_client_archived(&mut tx, &id, client.archived_at.is_some()).await?;
+        }
+        record(&mut report.clients, exists, overwrite, &client.name);
+    }
+
+    for invoice in &archive.invoices {
+        import_invoice(&mut tx, Invoice { business_profile_id: profile_id, ..invoice.clone() }, policy, &mut report.invoices).await?;
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+    Ok(report)
+}
+
+async fn import_invoice(tx: &mut Transaction<'_, Sqlite>, mut invoice: Invoice, policy: ConflictPolicy, changes: &mut ImportChanges) -> FlowResult<()> {
+    let id = invoice.id.to_string();
+    let id_taken = exists(tx, "invoices", "id", &id).await?;
+    let holder: Option<String> = sqlx::query_scalar("SELECT id FROM invoices WHERE number = ?")
+        .bind(&invoice.number)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    if !id_taken && holder.is_none() {
+        insert_invoice(tx, &invoice).await?;
+        changes.added.push(invoice.number);
+        return Ok(());
+    }
+    match policy {
+        ConflictPolicy::Skip => changes.skipped.push(invoice.number),
+        ConflictPolicy::Overwrite => {
+            if id_taken {
+                invoice_repo::delete_invoice(tx, &id).await?;
+            }
+            if let Some(holder) = holder.filter(|holder| *holder != id) {
+                invoice_repo::delete_invoice(tx, &holder).await?;
+            }
+            insert_invoice(tx, &invoice).await?;
+            changes.updated.push(invoice.number);
+        }
+        ConflictPolicy::Rename => {
+            if id_taken {
+                invoice.id = Uuid::new_v4();
+                for item in &mut invoice.items {
+                    item.id = Uuid::new_v4();
+                    item.invoice_id = invoice.id;
+                }
+            }
+            if holder.is_none() {
+                insert_invoice(tx, &invoice).await?;
+                changes.added.push(invoice.number);
+                return Ok(());
+            }
+            let from = std::mem::take(&mut invoice.number);
+            invoice.number = free_number(tx, &from).await?;
+            insert_invoice(tx, &invoice).await?;
+            changes.renamed.push(Renamed { from, to: invoice.number });
+        }
+    }
+    Ok(())
+}
+
+/// Inserts the invoice along with its e-invoice registration, which is stored separately.
+async fn insert_invoice(tx: &mut Transaction<'_, Sqlite>, invoice: &Invoice) -> FlowResult<()> {
+    invoice_repo::insert_invoice(tx, invoice).await?;
+    if let Some(registration) = &invoice.gst_registration {
+        invoice_repo::set_invoice_gst_registration(tx, &invoice.id.to_string(), Some(registration)).await?;
+    }
+    Ok(())
+}
+
+/// `number` with the first `-2`, `-3`… suffix no invoice has.
+async fn free_number(tx: &mut Transaction<'_, Sqlite>, number: &str) -> FlowResult<String> {
+    let mut suffix = 2;
+    loop {
+        let candidate = format!("{}-{}", number, suffix);
+        if !exists(tx, "invoices", "number", &candidate).await? {
+            return Ok(candidate);
+        }
+        suffix += 1;
+    }
+}
+
+async fn exists(tx: &mut Transaction<'_, Sqlite>, table: &str, column: &str, value: &str) -> FlowResult<bool> {
+    Ok(sqlx::query_scalar(&format!("SELECT EXISTS (SELECT 1 FROM {} WHERE {} = ?)", table, column))
+        .bind(value)
+        .fetch_one(&mut **tx)
+        .await?)
+}
+
+/// Files a record under added, updated or skipped; it was written unless it existed and
+/// `overwrite` is off.
+fn record(changes: &mut ImportChanges, exists: bool, overwrite: bool, label: &str) {
+    let list = match (exists, overwrite) {
+        (false, _) => &mut changes.added,
+        (true, true) => &mut changes.updated,
+        (true, false) => &mut changes.skipped,
+    };
+    list.push(label.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{new_client, new_invoice, new_pool};
+    use flow_core::models::GstRegistration;
+    use rust_decimal::Decimal;
+
+    /// A part-paid invoice with an HSN-coded item and a note, so every column is carried.
+    fn part_paid_invoice(number: &str, client_id: Uuid, business_profile_id: Uuid) -> Invoice {
+        let mut invoice = new_invoice(number, client_id, business_profile_id, Decimal::new(15050, 2));
+        invoice.items[0].hsn_code = Some("998391".to_string());
+        invoice.amount_paid = Decimal::new(5000, 2);
+        invoice.amount_due = Decimal::new(10050, 2);
+        invoice.notes = Some("Thanks".to_string());
+        invoice
+    }
+
+    /// A database with one client, two invoices, a template and a logo.
+    async fn populated_pool() -> DbPool {
+        let pool = new_pool().await;
+        let profile_id = BusinessProfileRepository::new(pool.clone()).get_profile().await.unwrap().id;
+        let client = new_client("Acme");
+        ClientRepository::new(pool.clone()).create(&client).await.unwrap();
+        let invoices = InvoiceRepository::new(pool.clone());
+        let mut registered = part_paid_invoice("INV-1", client.id, profile_id);
+        registered.gst_registration = Some(GstRegistration {
+            irn: "irn".to_string(),
+            ack_no: "1".to_string(),
+            ack_date: "2024-01-01".to_string(),
+            signed_qr: "qr".to_string(),
+        });
+        invoices.create(&registered).await.unwrap();
+        invoices.set_gst_registration(&registered.id.to_string(), registered.gst_registration.as_ref()).await.unwrap();
+        invoices.create(&part_paid_invoice("INV-2", client.id, profile_id)).await.unwrap();
+        TemplateRepository::new(pool.clone())
+            .save(&InvoiceTemplate {
+                id: Uuid::new_v4(),
+                name: "Compact".to_string(),
+                description: None,
+                layout_json: "{\"columns\":2}".to_string(),
+                is_default: true,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        AssetRepository::new(pool.clone()).save(asset_repo::LOGO, "data:image/png;base64,AAAA").await.unwrap();
+        pool
+    }
+
+    /// Invoices as JSON, ignoring the business profile they belong to and the update time,
+    /// which storing the e-invoice registration moves.
+    fn invoices_json(archive: &DataArchive) -> serde_json::Value {
+        let invoices: Vec<Invoice> = archive
+            .invoices
+            .iter()
+            .map(|i| Invoice { business_profile_id: Uuid::nil(), updated_at: i.created_at, ..i.clone() })
+            .collect();
+        serde_json::to_value(invoices).unwrap()
+    }
+
+    async fn count(pool: &DbPool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table)).fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_archive_round_trips_into_an_empty_database() {
+        let source = populated_pool().await;
+        let archive = export_data(&source).await.unwrap();
+        assert_eq!(archive.invoices[0].items.len(), 1);
+
+        let path = std::env::temp_dir().join(format!("invoiceflow-export-{}.json", Uuid::new_v4()));
+        write_archive(&archive, &path).unwrap();
+        let read = read_archive(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let target = new_pool().await;
+        let report = import_data(&target, &read, ConflictPolicy::Skip, false).await.unwrap();
+        assert_eq!(report.invoices.added, vec!["INV-1", "INV-2"]);
+        assert_eq!(report.clients.added, vec!["Acme"]);
+
+        let again = export_data(&target).await.unwrap();
+        assert_eq!(invoices_json(&again), invoices_json(&archive));
+        assert_eq!(serde_json::to_value(&again.clients).unwrap(), serde_json::to_value(&archive.clients).unwrap());
+        assert_eq!(again.templates[0].layout_json, archive.templates[0].layout_json);
+        assert_eq!(again.assets, archive.assets);
+    }
+
+    #[tokio::test]
+    async fn test_import_settles_conflicts_by_policy() {
+        let pool = populated_pool().await;
+        let mut archive = export_data(&pool).await.unwrap();
+        archive.invoices[0].notes = Some("Changed".to_string());
+
+        // A dry run reports without writing
+        let report = import_data(&pool, &archive, ConflictPolicy::Rename, true).await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(
+            report.invoices.renamed,
+            vec![Renamed { from: "INV-1".to_string(), to: "INV-1-2".to_string() }, Renamed { from: "INV-2".to_string(), to: "INV-2-2".to_string() }]
+        );
+        assert_eq!(count(&pool, "invoices").await, 2);
+
+        let report = import_data(&pool, &archive, ConflictPolicy::Skip, false).await.unwrap();
+        assert_eq!(report.invoices.skipped, vec!["INV-1", "INV-2"]);
+        assert_eq!(report.templates.skipped, vec!["Compact"]);
+        assert_eq!(count(&pool, "invoices").await, 2);
+
+        let report = import_data(&pool, &archive, ConflictPolicy::Overwrite, false).await.unwrap();
+        assert_eq!(report.invoices.updated, vec!["INV-1", "INV-2"]);
+        let overwritten = InvoiceRepository::new(pool.clone()).get_by_id(&archive.invoices[0].id.to_string()).await.unwrap().unwrap();
+        assert_eq!(overwritten.notes.as_deref(), Some("Changed"));
+        assert!(overwritten.gst_registration.is_some());
+        assert_eq!(count(&pool, "invoice_items").await, 2);
+
+        // Renaming copies the invoices with new ids under free numbers
+        import_data(&pool, &archive, ConflictPolicy::Rename, false).await.unwrap();
+        let report = import_data(&pool, &archive, ConflictPolicy::Rename, false).await.unwrap();
+        assert_eq!(report.invoices.renamed[0].to, "INV-1-3");
+        assert_eq!((count(&pool, "invoices").await, count(&pool, "invoice_items").await), (6, 6));
+    }
+
+    #[test]
+    fn test_old_exports_are_refused() {
+        let path = std::env::temp_dir().join(format!("invoiceflow-export-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, r#"{"version":"1.0","invoices":[]}"#).unwrap();
+        let result = read_archive(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(FlowError::Validation(_))));
+    }
+}
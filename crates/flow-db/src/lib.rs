@@ -1,3 +1,13 @@
+pub mod backup;
 pub mod database;
+mod decode;
+pub mod export;
 pub mod migrations;
+pub mod money;
+pub mod query;
 pub mod repositories;
+pub mod spreadsheet;
+#[cfg(test)]
+mod test_support;
+pub mod unit_of_work;
+pub mod verify;
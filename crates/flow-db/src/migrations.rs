@@ -1,159 +1,596 @@
-use sqlx::{Pool, Sqlite};
+//! Versioned schema migrations.
+//!
+//! Each migration is a numbered SQL file under `migrations/`, applied once in its own
+//! transaction and recorded with a checksum in `schema_migrations`. Applied files must never
+//! be edited: a changed checksum stops start-up rather than leaving the schema in an unknown
+//! state. Change the schema by adding the next numbered file.
 
-/// Run all database migrations in order.
+use crate::money;
+use flow_core::types::Currency;
+use rust_decimal::RoundingStrategy;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite, Transaction};
+use std::str::FromStr;
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    sql: &'static str,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "initial", sql: include_str!("../migrations/0001_initial.sql") },
+    Migration { version: 2, name: "pdf_backend", sql: include_str!("../migrations/0002_pdf_backend.sql") },
+    Migration { version: 3, name: "browser_path", sql: include_str!("../migrations/0003_browser_path.sql") },
+    Migration { version: 4, name: "pdf_filename_pattern", sql: include_str!("../migrations/0004_pdf_filename_pattern.sql") },
+    Migration { version: 5, name: "pdf_archival", sql: include_str!("../migrations/0005_pdf_archival.sql") },
+    Migration { version: 6, name: "gst_einvoice", sql: include_str!("../migrations/0006_gst_einvoice.sql") },
+    Migration { version: 7, name: "bank_accounts", sql: include_str!("../migrations/0007_bank_accounts.sql") },
+    Migration { version: 8, name: "invoice_metadata", sql: include_str!("../migrations/0008_invoice_metadata.sql") },
+    Migration { version: 9, name: "custom_fields", sql: include_str!("../migrations/0009_custom_fields.sql") },
+    Migration { version: 10, name: "client_details", sql: include_str!("../migrations/0010_client_details.sql") },
+    Migration { version: 11, name: "search_index", sql: include_str!("../migrations/0011_search_index.sql") },
+    Migration { version: 12, name: "archiving", sql: include_str!("../migrations/0012_archiving.sql") },
+    Migration { version: 13, name: "audit_log", sql: include_str!("../migrations/0013_audit_log.sql") },
+    Migration { version: 14, name: "money_minor_units", sql: include_str!("../migrations/0014_money_minor_units.sql") },
+    Migration { version: 15, name: "drop_text_amounts", sql: include_str!("../migrations/0015_drop_text_amounts.sql") },
+    Migration { version: 16, name: "app_assets", sql: include_str!("../migrations/0016_app_assets.sql") },
+    Migration { version: 17, name: "app_lock", sql: include_str!("../migrations/0017_app_lock.sql") },
+    Migration { version: 18, name: "import_presets", sql: include_str!("../migrations/0018_import_presets.sql") },
+    Migration { version: 19, name: "unit_price_scale", sql: include_str!("../migrations/0019_unit_price_scale.sql") },
+];
+
+/// What each migration added, newest last. Databases created before migrations were
+/// recorded got every change of their app version at each start, so the newest change
+/// present tells which migrations they already have.
+const LEGACY_MARKERS: [(i64, &str, Option<&str>); 13] = [
+    (1, "business_profiles", None),
+    (2, "business_profiles", Some("pdf_backend")),
+    (3, "business_profiles", Some("browser_path")),
+    (4, "business_profiles", Some("pdf_filename_pattern")),
+    (5, "business_profiles", Some("pdf_archival")),
+    (6, "invoices", Some("irn")),
+    (7, "bank_accounts", None),
+    (8, "invoices", Some("payment_note")),
+    (9, "custom_field_definitions", None),
+    (10, "client_contacts", None),
+    (11, "clients_fts", None),
+    (12, "clients", Some("archived_at")),
+    (13, "audit_log", None),
+];
+
+/// Profile columns that predate the first recorded schema; the oldest databases lack them.
+const PRE_VERSIONING_COLUMNS: [(&str, &str); 2] = [
+    ("theme_preference", "TEXT NOT NULL DEFAULT 'system'"),
+    ("pdf_export_dir", "TEXT"),
+];
+
+/// The schema version this build migrates databases to.
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// Bring the database up to the latest schema version.
 pub async fn run_migrations(pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
-    // Create business_profiles table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS business_profiles (
-            id TEXT PRIMARY KEY NOT NULL,
-            name TEXT NOT NULL,
-            email TEXT,
-            phone TEXT,
-            address_line1 TEXT NOT NULL DEFAULT '',
-            address_line2 TEXT,
-            address_city TEXT NOT NULL DEFAULT '',
-            address_state TEXT,
-            address_postal_code TEXT NOT NULL DEFAULT '',
-            address_country TEXT NOT NULL DEFAULT '',
-            tax_id TEXT,
-            logo_path TEXT,
-            default_currency TEXT NOT NULL DEFAULT 'USD',
-            default_payment_terms TEXT NOT NULL DEFAULT 'Net30',
-            theme_preference TEXT NOT NULL DEFAULT 'system',
-            pdf_export_dir TEXT,
-            created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
-        );
-        "#,
-    )
-    .execute(pool)
-    .await?;
+    if !has_table(pool, "schema_migrations").await? {
+        adopt(pool).await?;
+    }
 
-    // Create clients table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS clients (
-            id TEXT PRIMARY KEY NOT NULL,
-            name TEXT NOT NULL,
-            email TEXT,
-            phone TEXT,
-            company TEXT,
-            address_line1 TEXT NOT NULL DEFAULT '',
-            address_line2 TEXT,
-            address_city TEXT NOT NULL DEFAULT '',
-            address_state TEXT,
-            address_postal_code TEXT NOT NULL DEFAULT '',
-            address_country TEXT NOT NULL DEFAULT '',
-            notes TEXT,
-            created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
-        );
-        "#,
-    )
-    .execute(pool)
-    .await?;
+    let applied: Vec<(i64, String, String)> =
+        sqlx::query_as("SELECT version, name, checksum FROM schema_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?;
+    for (version, name, recorded) in &applied {
+        let migration = MIGRATIONS.iter().find(|m| m.version == *version).ok_or_else(|| {
+            sqlx::Error::Protocol(format!(
+                "database schema version {} ({}) is newer than this app supports; update the app",
+                version, name
+            ))
+        })?;
+        if checksum(migration.sql) != *recorded {
+            return Err(sqlx::Error::Protocol(format!(
+                "migration {} ({}) was changed after it was applied",
+                version, name
+            )));
+        }
+    }
 
-    // Create invoices table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS invoices (
-            id TEXT PRIMARY KEY NOT NULL,
-            number TEXT NOT NULL UNIQUE,
-            status TEXT NOT NULL DEFAULT 'Draft',
-            client_id TEXT NOT NULL,
-            business_profile_id TEXT NOT NULL,
-            issue_date TEXT NOT NULL,
-            due_date TEXT NOT NULL,
-            currency TEXT NOT NULL DEFAULT 'USD',
-            subtotal TEXT NOT NULL DEFAULT '0',
-            tax_total TEXT NOT NULL DEFAULT '0',
-            discount_total TEXT NOT NULL DEFAULT '0',
-            total TEXT NOT NULL DEFAULT '0',
-            amount_paid TEXT NOT NULL DEFAULT '0',
-            amount_due TEXT NOT NULL DEFAULT '0',
-            payment_terms TEXT NOT NULL DEFAULT 'Net30',
-            notes TEXT,
-            terms_and_conditions TEXT,
-            tax_rates_json TEXT DEFAULT '[]',
-            discount_json TEXT,
-            created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
-            FOREIGN KEY (client_id) REFERENCES clients(id),
-            FOREIGN KEY (business_profile_id) REFERENCES business_profiles(id)
-        );
-        "#,
-    )
-    .execute(pool)
-    .await?;
+    let current = applied.last().map_or(0, |(version, _, _)| *version);
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        apply(pool, migration).await?;
+    }
+    Ok(())
+}
 
-    // Create invoice_items table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS invoice_items (
-            id TEXT PRIMARY KEY NOT NULL,
-            invoice_id TEXT NOT NULL,
-            description TEXT NOT NULL,
-            quantity TEXT NOT NULL DEFAULT '1',
-            unit_price TEXT NOT NULL DEFAULT '0',
-            amount TEXT NOT NULL DEFAULT '0',
-            tax_rate_name TEXT,
-            sort_order INTEGER NOT NULL DEFAULT 0,
-            FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE CASCADE
-        );
-        "#,
-    )
-    .execute(pool)
-    .await?;
+/// Creates `schema_migrations`, recording the migrations a database from before versioning
+/// already has (none for a new database).
+async fn adopt(pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
+    let mut version = 0;
+    for (marker_version, table, column) in LEGACY_MARKERS {
+        let present = match column {
+            Some(column) => has_column(pool, table, column).await?,
+            None => has_table(pool, table).await?,
+        };
+        if present {
+            version = marker_version;
+        }
+    }
 
-    // Create invoice_templates table
+    let mut missing = Vec::new();
+    for (column, definition) in PRE_VERSIONING_COLUMNS {
+        if version > 0 && !has_column(pool, "business_profiles", column).await? {
+            missing.push(format!("ALTER TABLE business_profiles ADD COLUMN {} {};", column, definition));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
     sqlx::query(
         r#"
-        CREATE TABLE IF NOT EXISTS invoice_templates (
-            id TEXT PRIMARY KEY NOT NULL,
+        CREATE TABLE schema_migrations (
+            version INTEGER PRIMARY KEY NOT NULL,
             name TEXT NOT NULL,
-            description TEXT,
-            layout_json TEXT NOT NULL DEFAULT '{}',
-            is_default INTEGER NOT NULL DEFAULT 0,
-            created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+            checksum TEXT NOT NULL,
+            applied_at TEXT NOT NULL
         );
         "#,
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await?;
+    for statement in missing {
+        sqlx::query(&statement).execute(&mut *tx).await?;
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+        record(&mut tx, migration).await?;
+    }
+    tx.commit().await
+}
+
+async fn apply(pool: &Pool<Sqlite>, migration: &Migration) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::raw_sql(migration.sql).execute(&mut *tx).await.map_err(|e| {
+        sqlx::Error::Protocol(format!("migration {} ({}) failed: {}", migration.version, migration.name, e))
+    })?;
+    // Data steps that belong to a schema change
+    match migration.version {
+        8 => migrate_notes_metadata(&mut tx).await?,
+        14 => convert_amounts_to_minor_units(&mut tx).await?,
+        19 => apply_legacy_unit_price_scales(&mut tx).await?,
+        _ => {}
+    }
+    record(&mut tx, migration).await?;
+    tx.commit().await
+}
+
+async fn record(tx: &mut Transaction<'_, Sqlite>, migration: &Migration) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?, ?, ?, ?)")
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(checksum(migration.sql))
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+fn checksum(sql: &str) -> String {
+    Sha256::digest(sql.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+async fn has_table(pool: &Pool<Sqlite>, table: &str) -> Result<bool, sqlx::Error> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(table)
+        .fetch_one(pool)
+        .await?;
+    Ok(count > 0)
+}
+
+pub(crate) async fn has_column(pool: &Pool<Sqlite>, table: &str, column: &str) -> Result<bool, sqlx::Error> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+        .bind(table)
+        .bind(column)
+        .fetch_one(pool)
+        .await?;
+    Ok(count > 0)
+}
+
+/// Fills the minor-unit columns from the TEXT amounts. A value that is not a number stops the
+/// migration, naming the invoice. Older versions computed amounts from floats without
+/// rounding, so amounts finer than the currency's minor unit are rounded and unit prices keep
+/// their own scale (up to [`money::MAX_SCALE`]); every value that changed is recorded in the
+/// audit log with its original text.
+async fn convert_amounts_to_minor_units(tx: &mut Transaction<'_, Sqlite>) -> Result<(), sqlx::Error> {
+    const INVOICE_AMOUNTS: [&str; 6] = ["subtotal", "tax_total", "discount_total", "total", "amount_paid", "amount_due"];
 
-    // Create indexes
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_invoices_client_id ON invoices(client_id);")
-        .execute(pool)
+    // `unit_price_scale` only arrives with migration 19, which takes the scales from here
+    sqlx::query("CREATE TABLE legacy_unit_price_scales (item_id TEXT PRIMARY KEY NOT NULL, scale INTEGER NOT NULL)")
+        .execute(&mut **tx)
         .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_invoices_status ON invoices(status);")
-        .execute(pool)
+
+    let invoices: Vec<(String, String, String)> = sqlx::query_as("SELECT id, number, currency FROM invoices")
+        .fetch_all(&mut **tx)
         .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_invoices_due_date ON invoices(due_date);")
-        .execute(pool)
+    for (id, number, currency) in invoices {
+        let scale = Currency::from_str(&currency).unwrap_or_default().minor_units();
+        let context = |e: &dyn std::fmt::Display| sqlx::Error::Protocol(format!("invoice {}: {}", number, e));
+        let mut rounded = Vec::new();
+        let mut convert = |column: String, value: &str, scale: u32| -> Result<i64, sqlx::Error> {
+            let original = money::parse_decimal(&column, value).map_err(|e| context(&e))?;
+            let stored = original.round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+            if stored != original {
+                rounded.push(json!({ "column": column, "original": value.trim(), "stored": stored.to_string() }));
+            }
+            money::to_minor(stored, scale).map_err(|e| context(&e))
+        };
+
+        sqlx::query("UPDATE invoices SET currency_scale = ? WHERE id = ?")
+            .bind(scale as i64)
+            .bind(&id)
+            .execute(&mut **tx)
+            .await?;
+        for column in INVOICE_AMOUNTS {
+            let value: String = sqlx::query_scalar(&format!("SELECT CAST({} AS TEXT) FROM invoices WHERE id = ?", column))
+                .bind(&id)
+                .fetch_one(&mut **tx)
+                .await?;
+            let minor = convert(column.to_string(), &value, scale)?;
+            sqlx::query(&format!("UPDATE invoices SET {}_minor = ? WHERE id = ?", column))
+                .bind(minor)
+                .bind(&id)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        let items: Vec<(String, String, String)> =
+            sqlx::query_as("SELECT id, CAST(unit_price AS TEXT), CAST(amount AS TEXT) FROM invoice_items WHERE invoice_id = ?")
+                .bind(&id)
+                .fetch_all(&mut **tx)
+                .await?;
+        for (item_id, unit_price, amount) in items {
+            let price_scale = money::parse_decimal("unit_price", &unit_price)
+                .map(|price| money::price_scale(price, scale))
+                .map_err(|e| context(&e))?;
+            let unit_price = convert(format!("items.{}.unit_price", item_id), &unit_price, price_scale)?;
+            let amount = convert(format!("items.{}.amount", item_id), &amount, scale)?;
+            sqlx::query("UPDATE invoice_items SET unit_price_minor = ?, amount_minor = ? WHERE id = ?")
+                .bind(unit_price)
+                .bind(amount)
+                .bind(&item_id)
+                .execute(&mut **tx)
+                .await?;
+            if price_scale != scale {
+                sqlx::query("INSERT INTO legacy_unit_price_scales (item_id, scale) VALUES (?, ?)")
+                    .bind(&item_id)
+                    .bind(price_scale as i64)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+
+        if !rounded.is_empty() {
+            sqlx::query("INSERT INTO audit_log (action, entity, entity_id, details, created_at) VALUES ('round', 'invoice', ?, ?, ?)")
+                .bind(&id)
+                .bind(json!({ "number": number, "reason": "stored in minor units", "values": rounded }).to_string())
+                .bind(chrono::Utc::now().to_rfc3339())
+                .execute(&mut **tx)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Gives unit prices converted by [`convert_amounts_to_minor_units`] at a finer scale than
+/// their currency that scale; every other item is already at its invoice's `currency_scale`.
+async fn apply_legacy_unit_price_scales(tx: &mut Transaction<'_, Sqlite>) -> Result<(), sqlx::Error> {
+    let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'legacy_unit_price_scales'")
+        .fetch_one(&mut **tx)
         .await?;
+    if pending == 0 {
+        return Ok(());
+    }
     sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_invoice_items_invoice_id ON invoice_items(invoice_id);",
+        r#"UPDATE invoice_items
+           SET unit_price_scale = (SELECT scale FROM legacy_unit_price_scales WHERE item_id = invoice_items.id)
+           WHERE id IN (SELECT item_id FROM legacy_unit_price_scales)"#,
     )
-    .execute(pool)
+    .execute(&mut **tx)
     .await?;
+    sqlx::query("DROP TABLE legacy_unit_price_scales").execute(&mut **tx).await?;
+    Ok(())
+}
+
+/// Older versions stored the invoice's presentation details as a JSON object in `notes`.
+/// Moves them into their columns, the project details into `invoice_project_details` and
+/// the bank details into a (deduplicated) `bank_accounts` row, then clears `notes`.
+async fn migrate_notes_metadata(tx: &mut Transaction<'_, Sqlite>) -> Result<(), sqlx::Error> {
+    let rows: Vec<(String, String, String, Option<String>)> =
+        sqlx::query_as("SELECT id, number, notes, bank_account_id FROM invoices WHERE notes LIKE '{%'")
+            .fetch_all(&mut **tx)
+            .await?;
+
+    for (id, number, notes, bank_account_id) in rows {
+        let Ok(Value::Object(json)) = serde_json::from_str::<Value>(&notes) else {
+            continue;
+        };
+        // Leave free-text notes that merely happen to be JSON alone.
+        const EDITOR_KEYS: [&str; 6] = ["developer", "logoPath", "qrCodeUrl", "paymentTermsNote", "bankDetails", "projectDetails"];
+        if !EDITOR_KEYS.iter().any(|key| json.contains_key(*key)) {
+            continue;
+        }
+        let text = |value: Option<&Value>| value.and_then(Value::as_str).map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+
+        sqlx::query(
+            "UPDATE invoices SET developer = ?, logo_path = ?, qr_code_url = ?, payment_note = ?, notes = ? WHERE id = ?",
+        )
+        .bind(text(json.get("developer")))
+        .bind(text(json.get("logoPath")))
+        .bind(text(json.get("qrCodeUrl")))
+        .bind(text(json.get("paymentTermsNote")))
+        .bind(text(json.get("notes")))
+        .bind(&id)
+        .execute(&mut **tx)
+        .await?;
+
+        let details = json.get("projectDetails").and_then(Value::as_array).into_iter().flatten();
+        for (sort_order, detail) in details.enumerate() {
+            let Some(value) = text(detail.get("value")) else {
+                continue;
+            };
+            sqlx::query("INSERT INTO invoice_project_details (invoice_id, label, value, sort_order) VALUES (?, ?, ?, ?)")
+                .bind(&id)
+                .bind(text(detail.get("label")).unwrap_or_default())
+                .bind(value)
+                .bind(sort_order as i64)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        if let (None, Some(bank)) = (bank_account_id, json.get("bankDetails")) {
+            let field = |key: &str| text(bank.get(key));
+            if let Some(account_id) = find_or_create_bank_account(tx, &number, &field).await? {
+                sqlx::query("UPDATE invoices SET bank_account_id = ? WHERE id = ?")
+                    .bind(account_id)
+                    .bind(&id)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reuses an account with the same holder and identifiers, so invoices that copied the same
+/// settings share one row. `None` when the details carry no payable identifier.
+async fn find_or_create_bank_account(
+    tx: &mut Transaction<'_, Sqlite>,
+    invoice_number: &str,
+    field: &dyn Fn(&str) -> Option<String>,
+) -> Result<Option<String>, sqlx::Error> {
+    let account_number = field("accountNumber");
+    let iban = field("iban").map(|iban| flow_core::bank::normalize_iban(&iban));
+    let upi_id = field("upiId");
+    if account_number.is_none() && iban.is_none() && upi_id.is_none() {
+        return Ok(None);
+    }
+    let holder = field("accountHolder").unwrap_or_default();
 
-    // --- Backwards Compatibility Migrations ---
-    
-    // Add theme_preference column if not exists
-    let _ = sqlx::query(
-        "ALTER TABLE business_profiles ADD COLUMN theme_preference TEXT NOT NULL DEFAULT 'system';"
+    let existing: Option<(String,)> = sqlx::query_as(
+        "SELECT id FROM bank_accounts WHERE account_holder = ? AND account_number IS ? AND iban IS ? AND upi_id IS ? LIMIT 1",
     )
-    .execute(pool)
-    .await;
+    .bind(&holder)
+    .bind(&account_number)
+    .bind(&iban)
+    .bind(&upi_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+    if let Some((id,)) = existing {
+        return Ok(Some(id));
+    }
 
-    // Add pdf_export_dir column if not exists
-    let _ = sqlx::query(
-        "ALTER TABLE business_profiles ADD COLUMN pdf_export_dir TEXT;"
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let bank_name = field("bankName").unwrap_or_default();
+    let label = if bank_name.is_empty() { format!("From invoice {}", invoice_number) } else { bank_name.clone() };
+    sqlx::query(
+        r#"INSERT INTO bank_accounts (id, label, account_holder, bank_name, branch, account_number, iban, swift_bic, ifsc_code, upi_id, is_default, created_at, updated_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?)"#,
     )
-    .execute(pool)
-    .await;
+    .bind(&id)
+    .bind(label)
+    .bind(&holder)
+    .bind(bank_name)
+    .bind(field("branch"))
+    .bind(&account_number)
+    .bind(&iban)
+    .bind(field("bic").or_else(|| field("swift")))
+    .bind(field("ifscCode"))
+    .bind(&upi_id)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut **tx)
+    .await?;
+    Ok(Some(id))
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn memory_db() -> Pool<Sqlite> {
+        SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap()
+    }
+
+    /// Every table, column (with type, nullability and default), index and trigger.
+    async fn schema(pool: &Pool<Sqlite>) -> Vec<String> {
+        sqlx::query_scalar(
+            r#"SELECT m.type || ' ' || m.name || COALESCE(' ' || p.name || ' ' || p.type || ' ' || p."notnull" || ' ' || COALESCE(p.dflt_value, ''), '')
+               FROM sqlite_master m LEFT JOIN pragma_table_info(m.name) p ON m.type = 'table'
+               WHERE m.name NOT LIKE 'sqlite_%' ORDER BY 1"#,
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn versions(pool: &Pool<Sqlite>) -> Vec<i64> {
+        sqlx::query_scalar("SELECT version FROM schema_migrations ORDER BY version").fetch_all(pool).await.unwrap()
+    }
+
+    /// A database as left by the app at `version`: migrated that far, from before or after
+    /// migrations were recorded.
+    async fn database_at(version: i64, recorded: bool) -> Pool<Sqlite> {
+        let pool = memory_db().await;
+        adopt(&pool).await.unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+            apply(&pool, migration).await.unwrap();
+        }
+        if !recorded {
+            sqlx::query("DROP TABLE schema_migrations").execute(&pool).await.unwrap();
+        }
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_upgrades_from_every_version() {
+        let fresh = memory_db().await;
+        run_migrations(&fresh).await.unwrap();
+        let latest = schema(&fresh).await;
+        let all: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert_eq!(versions(&fresh).await, all);
+
+        for version in 1..=MIGRATIONS.len() as i64 {
+            // Only the versions released before migrations were recorded exist unrecorded
+            let legacy = version <= LEGACY_MARKERS.len() as i64;
+            for recorded in [true, false].into_iter().filter(|recorded| *recorded || legacy) {
+                let pool = database_at(version, recorded).await;
+                run_migrations(&pool).await.unwrap();
+                assert_eq!(schema(&pool).await, latest, "upgrading from version {} (recorded: {})", version, recorded);
+                assert_eq!(versions(&pool).await, all);
+                // Running again on an up-to-date database changes nothing
+                run_migrations(&pool).await.unwrap();
+                assert_eq!(schema(&pool).await, latest);
+            }
+        }
+
+        // The oldest databases also lack two profile columns
+        let pool = database_at(1, false).await;
+        for (column, _) in PRE_VERSIONING_COLUMNS {
+            sqlx::query(&format!("ALTER TABLE business_profiles DROP COLUMN {}", column)).execute(&pool).await.unwrap();
+        }
+        run_migrations(&pool).await.unwrap();
+        assert_eq!(schema(&pool).await, latest);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_keeps_and_moves_data() {
+        let pool = database_at(7, false).await;
+        sqlx::query("INSERT INTO business_profiles (id, name) VALUES ('p', 'Me')").execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO clients (id, name) VALUES ('c', 'Acme')").execute(&pool).await.unwrap();
+        sqlx::query(
+            r#"INSERT INTO invoices (id, number, client_id, business_profile_id, issue_date, due_date, notes)
+               VALUES ('i', 'INV-1', 'c', 'p', '2026-01-01', '2026-01-31', '{"notes": "Thanks", "developer": "Dev Co"}')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE invoices SET currency = 'JPY', total = '1234', amount_due = '0' WHERE id = 'i'").execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO invoice_items (id, invoice_id, description, quantity, unit_price, amount) VALUES ('it', 'i', 'Work', '2', '617', '1234')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        run_migrations(&pool).await.unwrap();
+        let (notes, developer): (Option<String>, Option<String>) =
+            sqlx::query_as("SELECT notes, developer FROM invoices WHERE id = 'i'").fetch_one(&pool).await.unwrap();
+        assert_eq!((notes.as_deref(), developer.as_deref()), (Some("Thanks"), Some("Dev Co")));
+        let found: Vec<String> = sqlx::query_scalar("SELECT id FROM clients_fts WHERE clients_fts MATCH 'acme'").fetch_all(&pool).await.unwrap();
+        assert_eq!(found, ["c"]);
+
+        // Yen have no minor unit
+        let amounts: (i64, i64, i64, i64, i64, i64) = sqlx::query_as(
+            "SELECT i.currency_scale, i.total_minor, i.amount_due_minor, it.unit_price_minor, it.unit_price_scale, it.amount_minor FROM invoices i JOIN invoice_items it ON it.invoice_id = i.id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(amounts, (0, 1234, 0, 617, 0, 1234));
+    }
+
+    #[tokio::test]
+    async fn test_refuses_amounts_that_are_not_numbers() {
+        let pool = database_at(13, true).await;
+        sqlx::query("INSERT INTO business_profiles (id, name) VALUES ('p', 'Me')").execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO clients (id, name) VALUES ('c', 'Acme')").execute(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO invoices (id, number, client_id, business_profile_id, issue_date, due_date, total) VALUES ('i', 'INV-7', 'c', 'p', '2026-01-01', '2026-01-31', '12,50')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let err = run_migrations(&pool).await.unwrap_err().to_string();
+        assert!(err.contains("invoice INV-7: ") && err.contains(r#"invalid total "12,50""#), "{}", err);
+        assert_eq!(versions(&pool).await.last(), Some(&13));
+    }
+
+    #[tokio::test]
+    async fn test_rounds_float_amounts_and_keeps_fine_unit_prices() {
+        let pool = database_at(13, true).await;
+        sqlx::query("INSERT INTO business_profiles (id, name) VALUES ('p', 'Me')").execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO clients (id, name) VALUES ('c', 'Acme')").execute(&pool).await.unwrap();
+        // As older versions stored them: amount = quantity × price, unrounded
+        sqlx::query(
+            r#"INSERT INTO invoices (id, number, client_id, business_profile_id, issue_date, due_date, subtotal, total, amount_due)
+               VALUES ('i', 'INV-8', 'c', 'p', '2026-01-01', '2026-01-31', '50.12', '50.12', '50.12')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"INSERT INTO invoice_items (id, invoice_id, description, quantity, unit_price, amount)
+               VALUES ('a', 'i', 'Hours', '1.5', '33.33', '49.995'), ('b', 'i', 'Power', '1', '0.125', '0.125')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        run_migrations(&pool).await.unwrap();
+        let items: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+            "SELECT id, unit_price_minor, unit_price_scale, amount_minor FROM invoice_items ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(items, [("a".to_string(), 3333, 2, 5000), ("b".to_string(), 125, 3, 13)]);
+
+        let (entity_id, details): (String, String) =
+            sqlx::query_as("SELECT entity_id, details FROM audit_log WHERE action = 'round'").fetch_one(&pool).await.unwrap();
+        let details: Value = serde_json::from_str(&details).unwrap();
+        assert_eq!(entity_id, "i");
+        assert_eq!(
+            details["values"],
+            json!([
+                { "column": "items.a.amount", "original": "49.995", "stored": "50.00" },
+                { "column": "items.b.amount", "original": "0.125", "stored": "0.13" },
+            ])
+        );
+        assert!(!has_table(&pool, "legacy_unit_price_scales").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_refuses_changed_or_unknown_migrations() {
+        let pool = memory_db().await;
+        run_migrations(&pool).await.unwrap();
+
+        sqlx::query("UPDATE schema_migrations SET checksum = 'x' WHERE version = 3").execute(&pool).await.unwrap();
+        let err = run_migrations(&pool).await.unwrap_err().to_string();
+        assert!(err.contains("migration 3 (browser_path) was changed"), "{}", err);
+
+        sqlx::query("UPDATE schema_migrations SET checksum = ? WHERE version = 3").bind(checksum(MIGRATIONS[2].sql)).execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (999, 'future', '', '')").execute(&pool).await.unwrap();
+        let err = run_migrations(&pool).await.unwrap_err().to_string();
+        assert!(err.contains("newer than this app supports"), "{}", err);
+    }
 }
@@ -0,0 +1,43 @@
+//! Amounts are stored as INTEGER minor units (cents, paise, fils…) at the invoice's
+//! `currency_scale`, so SQL can sum, compare and order them; unit prices carry their own
+//! scale. Values that cannot be represented exactly are reported as errors rather than
+//! rounded, or read or written as zero.
+
+use flow_core::error::{FlowError, FlowResult};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// The most decimal places any currency's minor unit, or any unit price, has.
+pub const MAX_SCALE: u32 = 4;
+
+/// `amount` in minor units at `scale`. Amounts with more decimal places than `scale` are
+/// refused, never rounded: rounding is the caller's decision.
+pub(crate) fn to_minor(amount: Decimal, scale: u32) -> FlowResult<i64> {
+    let exact = amount.normalize();
+    if exact.scale() > scale {
+        return Err(FlowError::Validation(format!("Amount {} has more than {} decimal places", amount, scale)));
+    }
+    exact
+        .checked_mul(Decimal::from(10_i64.pow(scale)))
+        .and_then(|minor| minor.to_i64())
+        .ok_or_else(|| FlowError::Validation(format!("Amount {} is too large to store", amount)))
+}
+
+/// The scale a unit price is stored at: its own decimal places, but never fewer than the
+/// currency's, so 0.125 per kWh survives while 10 USD still reads back as 10.00.
+pub(crate) fn price_scale(price: Decimal, currency_scale: u32) -> u32 {
+    price.normalize().scale().clamp(currency_scale.min(MAX_SCALE), MAX_SCALE)
+}
+
+pub(crate) fn from_minor(minor: i64, scale: i64) -> Result<Decimal, sqlx::Error> {
+    match u32::try_from(scale) {
+        Ok(scale) if scale <= MAX_SCALE => Ok(Decimal::new(minor, scale)),
+        _ => Err(sqlx::Error::Decode(format!("invalid currency scale {}", scale).into())),
+    }
+}
+
+/// Parses a decimal stored as text, naming the column when it is not a number.
+pub(crate) fn parse_decimal(column: &str, value: &str) -> Result<Decimal, sqlx::Error> {
+    Decimal::from_str(value.trim()).map_err(|_| sqlx::Error::Decode(format!("invalid {} {:?}", column, value).into()))
+}
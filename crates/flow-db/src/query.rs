@@ -0,0 +1,247 @@
+//! Filters, sorting and cursor pagination for the list queries.
+//!
+//! A cursor is the sort value of the last row of a page followed by that row's id,
+//! so the next page continues after it even when rows are added in between.
+
+use chrono::NaiveDate;
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::types::{Currency, InvoiceStatus};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// One page of a list query.
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass back as `cursor` to get the following page; `None` on the last page.
+    pub next_cursor: Option<String>,
+    /// Rows matching the filters across all pages.
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Which rows to list by their `archived_at`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ArchiveFilter {
+    #[default]
+    Active,
+    Archived,
+    All,
+}
+
+impl ArchiveFilter {
+    /// SQL condition (starting with `AND`) on the `archived_at` column given.
+    pub(crate) fn condition(self, column: &str) -> String {
+        match self {
+            ArchiveFilter::Active => format!(" AND {} IS NULL", column),
+            ArchiveFilter::Archived => format!(" AND {} IS NOT NULL", column),
+            ArchiveFilter::All => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ClientSort {
+    #[default]
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientQuery {
+    /// Full-text search over name, company, email, phone and notes.
+    pub search: Option<String>,
+    pub archived: ArchiveFilter,
+    pub sort: ClientSort,
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+    /// Page size; 0 means [`DEFAULT_PAGE_SIZE`].
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum InvoiceSort {
+    IssueDate,
+    DueDate,
+    Number,
+    Total,
+    #[default]
+    CreatedAt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InvoiceQuery {
+    /// Full-text search over number, notes, item descriptions and the client's name and email.
+    pub search: Option<String>,
+    /// Any of these statuses; empty matches all.
+    pub statuses: Vec<InvoiceStatus>,
+    pub client_id: Option<String>,
+    pub currency: Option<Currency>,
+    /// Issue date range, both ends inclusive.
+    pub issued_from: Option<NaiveDate>,
+    pub issued_to: Option<NaiveDate>,
+    pub min_total: Option<Decimal>,
+    pub max_total: Option<Decimal>,
+    pub archived: ArchiveFilter,
+    pub sort: InvoiceSort,
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+    /// Page size; 0 means [`DEFAULT_PAGE_SIZE`].
+    pub limit: u32,
+}
+
+impl Default for InvoiceQuery {
+    fn default() -> Self {
+        Self {
+            search: None,
+            statuses: Vec::new(),
+            client_id: None,
+            currency: None,
+            issued_from: None,
+            issued_to: None,
+            min_total: None,
+            max_total: None,
+            archived: ArchiveFilter::default(),
+            sort: InvoiceSort::default(),
+            direction: SortDirection::Desc,
+            cursor: None,
+            limit: 0,
+        }
+    }
+}
+
+/// How a sort option orders rows: the SQL expression ordered by, the text the cursor
+/// stores, and whether cursor values compare as (integer) numbers.
+pub(crate) struct SortKey {
+    pub expr: &'static str,
+    pub key: &'static str,
+    pub numeric: bool,
+}
+
+impl ClientSort {
+    pub(crate) fn key(self) -> SortKey {
+        let (expr, key) = match self {
+            ClientSort::Name => ("c.name COLLATE NOCASE", "c.name"),
+            ClientSort::CreatedAt => ("c.created_at", "c.created_at"),
+            ClientSort::UpdatedAt => ("c.updated_at", "c.updated_at"),
+        };
+        SortKey { expr, key, numeric: false }
+    }
+}
+
+/// An invoice's total in units of 10^-[`MAX_SCALE`](crate::money::MAX_SCALE), so totals in
+/// currencies with different minor units compare exactly.
+macro_rules! total_at_common_scale {
+    () => {
+        "(i.total_minor * CASE i.currency_scale WHEN 0 THEN 10000 WHEN 1 THEN 1000 WHEN 2 THEN 100 WHEN 3 THEN 10 ELSE 1 END)"
+    };
+}
+pub(crate) use total_at_common_scale;
+
+impl InvoiceSort {
+    pub(crate) fn key(self) -> SortKey {
+        match self {
+            InvoiceSort::IssueDate => SortKey { expr: "i.issue_date", key: "i.issue_date", numeric: false },
+            InvoiceSort::DueDate => SortKey { expr: "i.due_date", key: "i.due_date", numeric: false },
+            InvoiceSort::Number => SortKey { expr: "i.number", key: "i.number", numeric: false },
+            InvoiceSort::Total => SortKey {
+                expr: total_at_common_scale!(),
+                key: concat!("CAST(", total_at_common_scale!(), " AS TEXT)"),
+                numeric: true,
+            },
+            InvoiceSort::CreatedAt => SortKey { expr: "i.created_at", key: "i.created_at", numeric: false },
+        }
+    }
+}
+
+pub(crate) fn page_size(limit: u32) -> u32 {
+    match limit {
+        0 => DEFAULT_PAGE_SIZE,
+        n => n.min(MAX_PAGE_SIZE),
+    }
+}
+
+pub(crate) fn encode_cursor(sort_value: &str, id: &str) -> String {
+    format!("{}|{}", sort_value, id)
+}
+
+/// Adds the keyset condition continuing after `cursor` (ids never contain `|`).
+pub(crate) fn push_cursor(qb: &mut QueryBuilder<'_, Sqlite>, sort: &SortKey, id_column: &str, direction: SortDirection, cursor: &str) -> FlowResult<()> {
+    let (value, id) = cursor
+        .rsplit_once('|')
+        .ok_or_else(|| FlowError::Validation(format!("Invalid page cursor: {}", cursor)))?;
+    let op = match direction {
+        SortDirection::Asc => ">",
+        SortDirection::Desc => "<",
+    };
+
+    for (i, comparison) in [op, "="].into_iter().enumerate() {
+        qb.push(if i == 0 { " AND ((" } else { ") OR (" });
+        qb.push(sort.expr).push(format!(" {} ", comparison));
+        if sort.numeric {
+            let number: i64 = value
+                .parse()
+                .map_err(|_| FlowError::Validation(format!("Invalid page cursor: {}", cursor)))?;
+            qb.push_bind(number);
+        } else {
+            qb.push_bind(value.to_string());
+        }
+    }
+    qb.push(format!(" AND {} {} ", id_column, op)).push_bind(id.to_string()).push("))");
+    Ok(())
+}
+
+pub(crate) fn push_order(qb: &mut QueryBuilder<'_, Sqlite>, sort: &SortKey, id_column: &str, direction: SortDirection) {
+    let dir = match direction {
+        SortDirection::Asc => "ASC",
+        SortDirection::Desc => "DESC",
+    };
+    qb.push(format!(" ORDER BY {} {}, {} {}", sort.expr, dir, id_column, dir));
+}
+
+/// Turns what the user typed into an FTS5 query: each word is matched as a prefix and all
+/// words must match. Returns `None` when there is nothing to search for.
+pub(crate) fn fts_query(search: Option<&str>) -> Option<String> {
+    let terms: Vec<String> = search?
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query_quotes_every_word_as_a_prefix() {
+        assert_eq!(fts_query(None), None);
+        assert_eq!(fts_query(Some(" \t ")), None);
+        assert_eq!(fts_query(Some("acme  OR")).as_deref(), Some(r#""acme"* "OR"*"#));
+        assert_eq!(fts_query(Some(r#"say "hi""#)).as_deref(), Some(r#""say"* """hi"""*"#));
+    }
+
+    #[test]
+    fn test_page_size_is_capped() {
+        assert_eq!(page_size(0), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_size(7), 7);
+        assert_eq!(page_size(MAX_PAGE_SIZE + 1), MAX_PAGE_SIZE);
+    }
+}
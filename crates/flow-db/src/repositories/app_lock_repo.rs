@@ -0,0 +1,155 @@
+use crate::database::{blocking, DbPool};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::Utc;
+use flow_core::error::{FlowError, FlowResult};
+use serde::{Deserialize, Serialize};
+
+/// Shortest accepted passphrase; four digits make a PIN.
+pub const MIN_PASSPHRASE_LEN: usize = 4;
+
+/// How the application lock is set up. The passphrase hash never leaves this module.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppLockSettings {
+    /// Whether a passphrase is set, i.e. the app starts locked.
+    pub enabled: bool,
+    /// Lock after this many minutes without activity; `None` locks only at start-up.
+    pub auto_lock_minutes: Option<u32>,
+}
+
+/// The single `app_lock` row: the Argon2 hash of the unlock passphrase or PIN and the
+/// inactivity timeout.
+pub struct AppLockRepository {
+    pool: DbPool,
+}
+
+impl AppLockRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self) -> FlowResult<AppLockSettings> {
+        let row: Option<(Option<String>, Option<i64>)> =
+            sqlx::query_as("SELECT passphrase_hash, auto_lock_minutes FROM app_lock WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        let (hash, minutes) = row.unwrap_or_default();
+        Ok(AppLockSettings {
+            enabled: hash.is_some(),
+            auto_lock_minutes: minutes.and_then(|m| u32::try_from(m).ok()),
+        })
+    }
+
+    /// Sets a new passphrase, or removes the lock for `None`.
+    pub async fn set_passphrase(&self, passphrase: Option<&str>) -> FlowResult<()> {
+        let hash = match passphrase {
+            Some(passphrase) => {
+                if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+                    return Err(FlowError::invalid_field(
+                        "new_passphrase",
+                        format!("Use at least {} characters", MIN_PASSPHRASE_LEN),
+                    ));
+                }
+                // Argon2 is deliberately slow
+                let passphrase = passphrase.to_string();
+                Some(blocking(move || hash_passphrase(&passphrase)).await?)
+            }
+            None => None,
+        };
+        sqlx::query(
+            "INSERT INTO app_lock (id, passphrase_hash, updated_at) VALUES (1, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET passphrase_hash = excluded.passphrase_hash, updated_at = excluded.updated_at",
+        )
+        .bind(hash)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_auto_lock(&self, minutes: Option<u32>) -> FlowResult<()> {
+        if minutes == Some(0) {
+            return Err(FlowError::invalid_field("auto_lock_minutes", "Auto-lock needs at least one minute"));
+        }
+        sqlx::query(
+            "INSERT INTO app_lock (id, auto_lock_minutes, updated_at) VALUES (1, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET auto_lock_minutes = excluded.auto_lock_minutes, updated_at = excluded.updated_at",
+        )
+        .bind(minutes.map(i64::from))
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Whether `passphrase` unlocks the app; always `true` when no passphrase is set.
+    pub async fn verify(&self, passphrase: &str) -> FlowResult<bool> {
+        let hash: Option<String> = sqlx::query_scalar("SELECT passphrase_hash FROM app_lock WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?
+            .flatten();
+        let Some(hash) = hash else {
+            return Ok(true);
+        };
+        let passphrase = passphrase.to_string();
+        blocking(move || {
+            let parsed = PasswordHash::new(&hash).map_err(|e| FlowError::Internal(format!("Stored passphrase hash is invalid: {}", e)))?;
+            Ok(Argon2::default().verify_password(passphrase.as_bytes(), &parsed).is_ok())
+        })
+        .await
+    }
+}
+
+fn hash_passphrase(passphrase: &str) -> FlowResult<String> {
+    let mut salt = [0u8; 16];
+    getrandom::getrandom(&mut salt).map_err(|e| FlowError::Internal(format!("No random source for the passphrase salt: {}", e)))?;
+    let salt = SaltString::encode_b64(&salt).map_err(|e| FlowError::Internal(e.to_string()))?;
+    let hash = Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map_err(|e| FlowError::Internal(format!("Could not hash the passphrase: {}", e)))?;
+    Ok(hash.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::new_pool;
+
+    #[tokio::test]
+    async fn test_passphrase_is_hashed_and_verified() {
+        let repo = AppLockRepository::new(new_pool().await);
+        assert_eq!(repo.get().await.unwrap(), AppLockSettings::default());
+        assert!(repo.verify("anything").await.unwrap());
+
+        let err = repo.set_passphrase(Some("123")).await.unwrap_err();
+        assert_eq!(err.field(), Some("new_passphrase"));
+        assert!(!repo.get().await.unwrap().enabled);
+
+        repo.set_passphrase(Some("1234")).await.unwrap();
+        assert!(repo.get().await.unwrap().enabled);
+        assert!(repo.verify("1234").await.unwrap());
+        assert!(!repo.verify("4321").await.unwrap());
+        assert!(!repo.verify("").await.unwrap());
+        let stored: String = sqlx::query_scalar("SELECT passphrase_hash FROM app_lock").fetch_one(&repo.pool).await.unwrap();
+        assert!(stored.starts_with("$argon2") && !stored.contains("1234"), "{}", stored);
+
+        repo.set_passphrase(None).await.unwrap();
+        assert!(!repo.get().await.unwrap().enabled);
+        assert!(repo.verify("4321").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_auto_lock_is_kept_apart_from_the_passphrase() {
+        let repo = AppLockRepository::new(new_pool().await);
+        let err = repo.set_auto_lock(Some(0)).await.unwrap_err();
+        assert_eq!(err.field(), Some("auto_lock_minutes"));
+
+        repo.set_auto_lock(Some(5)).await.unwrap();
+        repo.set_passphrase(Some("secret")).await.unwrap();
+        assert_eq!(repo.get().await.unwrap(), AppLockSettings { enabled: true, auto_lock_minutes: Some(5) });
+
+        repo.set_auto_lock(None).await.unwrap();
+        assert_eq!(repo.get().await.unwrap(), AppLockSettings { enabled: true, auto_lock_minutes: None });
+        assert!(repo.verify("secret").await.unwrap());
+    }
+}
@@ -0,0 +1,56 @@
+use crate::database::DbPool;
+use chrono::Utc;
+use flow_core::error::FlowResult;
+use sqlx::{Sqlite, Transaction};
+
+/// The business logo, as a `data:` URI.
+pub const LOGO: &str = "logo";
+/// The payment QR image, as a `data:` URI.
+pub const QR_CODE: &str = "qr_code";
+
+/// Named images stored in the database, so they are encrypted and backed up with the data.
+pub struct AssetRepository {
+    pool: DbPool,
+}
+
+impl AssetRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, name: &str) -> FlowResult<Option<String>> {
+        Ok(sqlx::query_scalar("SELECT data FROM app_assets WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?)
+    }
+
+    /// Every stored image as `(name, data)`, by name.
+    pub async fn list_all(&self) -> FlowResult<Vec<(String, String)>> {
+        Ok(sqlx::query_as("SELECT name, data FROM app_assets ORDER BY name").fetch_all(&self.pool).await?)
+    }
+
+    pub async fn save(&self, name: &str, data: &str) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        save_asset(&mut tx, name, data).await?;
+        Ok(tx.commit().await?)
+    }
+
+    pub async fn delete(&self, name: &str) -> FlowResult<()> {
+        sqlx::query("DELETE FROM app_assets WHERE name = ?").bind(name).execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+pub(crate) async fn save_asset(tx: &mut Transaction<'_, Sqlite>, name: &str, data: &str) -> FlowResult<()> {
+    sqlx::query(
+        "INSERT INTO app_assets (name, data, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(name) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+    )
+    .bind(name)
+    .bind(data)
+    .bind(Utc::now().to_rfc3339())
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
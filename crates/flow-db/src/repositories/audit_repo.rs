@@ -0,0 +1,74 @@
+use crate::database::DbPool;
+use chrono::Utc;
+use flow_core::error::FlowResult;
+use flow_core::models::AuditEntry;
+use sqlx::{Sqlite, Transaction};
+
+pub struct AuditLogRepository {
+    pool: DbPool,
+}
+
+impl AuditLogRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Most recent entries first, optionally only those about one record.
+    pub async fn list(&self, entity_id: Option<&str>, limit: u32) -> FlowResult<Vec<AuditEntry>> {
+        let rows = sqlx::query_as::<_, AuditRow>(
+            "SELECT id, action, entity, entity_id, details, created_at FROM audit_log WHERE ? IS NULL OR entity_id = ? ORDER BY id DESC LIMIT ?",
+        )
+        .bind(entity_id)
+        .bind(entity_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into_entry()).collect())
+    }
+}
+
+/// Records an entry inside the transaction making the change, so the log never
+/// mentions a change that was rolled back.
+pub(crate) async fn record(
+    tx: &mut Transaction<'_, Sqlite>,
+    action: &str,
+    entity: &str,
+    entity_id: &str,
+    details: &serde_json::Value,
+) -> FlowResult<()> {
+    sqlx::query("INSERT INTO audit_log (action, entity, entity_id, details, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(action)
+        .bind(entity)
+        .bind(entity_id)
+        .bind(details.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    id: i64,
+    action: String,
+    entity: String,
+    entity_id: String,
+    details: String,
+    created_at: String,
+}
+
+impl AuditRow {
+    fn into_entry(self) -> AuditEntry {
+        AuditEntry {
+            id: self.id,
+            action: self.action,
+            entity: self.entity,
+            entity_id: self.entity_id,
+            details: serde_json::from_str(&self.details).unwrap_or_default(),
+            created_at: chrono::DateTime::parse_from_rfc3339(&self.created_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::database::DbPool;
+use chrono::Utc;
+use flow_core::error::FlowResult;
+use flow_core::models::BankAccount;
+use flow_core::types::Currency;
+use std::str::FromStr;
+use sqlx::{Sqlite, Transaction};
+use uuid::Uuid;
+
+const COLUMNS: &str = "id, label, account_holder, bank_name, branch, account_number, iban, swift_bic, ifsc_code, routing_number, upi_id, currency, is_default, created_at, updated_at";
+
+pub struct BankAccountRepository {
+    pool: DbPool,
+}
+
+impl BankAccountRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_all(&self) -> FlowResult<Vec<BankAccount>> {
+        let rows = sqlx::query_as::<_, BankAccountRow>(&format!("SELECT {} FROM bank_accounts ORDER BY is_default DESC, label", COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|r| r.into_account()).collect())
+    }
+
+    pub async fn get_by_id(&self, id: &str) -> FlowResult<Option<BankAccount>> {
+        let row = sqlx::query_as::<_, BankAccountRow>(&format!("SELECT {} FROM bank_accounts WHERE id = ?", COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| r.into_account()))
+    }
+
+    /// The default account for invoices in `currency`: one marked default for that currency,
+    /// else the default for any currency.
+    pub async fn default_for_currency(&self, currency: &Currency) -> FlowResult<Option<BankAccount>> {
+        let row = sqlx::query_as::<_, BankAccountRow>(&format!(
+            "SELECT {} FROM bank_accounts WHERE is_default = 1 AND (currency = ? OR currency IS NULL) ORDER BY currency IS NULL LIMIT 1",
+            COLUMNS
+        ))
+        .bind(currency.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| r.into_account()))
+    }
+
+    /// Inserts or updates the account. Marking it default clears the flag on other accounts
+    /// for the same currency.
+    pub async fn save(&self, account: &BankAccount) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        save_bank_account(&mut tx, account).await?;
+        Ok(tx.commit().await?)
+    }
+
+    /// Invoices that referenced the account keep their number but lose the reference.
+    pub async fn delete(&self, id: &str) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        delete_bank_account(&mut tx, id).await?;
+        Ok(tx.commit().await?)
+    }
+}
+
+pub(crate) async fn save_bank_account(tx: &mut Transaction<'_, Sqlite>, account: &BankAccount) -> FlowResult<()> {
+    let now = Utc::now().to_rfc3339();
+    let currency = account.currency.as_ref().map(|c| c.to_string());
+
+    if account.is_default {
+        sqlx::query("UPDATE bank_accounts SET is_default = 0 WHERE id != ? AND currency IS ?")
+            .bind(account.id.to_string())
+            .bind(&currency)
+            .execute(&mut **tx)
+            .await?;
+    }
+    sqlx::query(
+        r#"INSERT INTO bank_accounts (id, label, account_holder, bank_name, branch, account_number, iban, swift_bic, ifsc_code, routing_number, upi_id, currency, is_default, created_at, updated_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(id) DO UPDATE SET
+               label = excluded.label, account_holder = excluded.account_holder, bank_name = excluded.bank_name,
+               branch = excluded.branch, account_number = excluded.account_number, iban = excluded.iban,
+               swift_bic = excluded.swift_bic, ifsc_code = excluded.ifsc_code, routing_number = excluded.routing_number,
+               upi_id = excluded.upi_id, currency = excluded.currency, is_default = excluded.is_default,
+               updated_at = excluded.updated_at"#,
+    )
+    .bind(account.id.to_string())
+    .bind(&account.label)
+    .bind(&account.account_holder)
+    .bind(&account.bank_name)
+    .bind(&account.branch)
+    .bind(&account.account_number)
+    .bind(&account.iban)
+    .bind(&account.swift_bic)
+    .bind(&account.ifsc_code)
+    .bind(&account.routing_number)
+    .bind(&account.upi_id)
+    .bind(&currency)
+    .bind(account.is_default)
+    .bind(account.created_at.to_rfc3339())
+    .bind(&now)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn delete_bank_account(tx: &mut Transaction<'_, Sqlite>, id: &str) -> FlowResult<()> {
+    sqlx::query("DELETE FROM bank_accounts WHERE id = ?")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct BankAccountRow {
+    id: String,
+    label: String,
+    account_holder: String,
+    bank_name: String,
+    branch: Option<String>,
+    account_number: Option<String>,
+    iban: Option<String>,
+    swift_bic: Option<String>,
+    ifsc_code: Option<String>,
+    routing_number: Option<String>,
+    upi_id: Option<String>,
+    currency: Option<String>,
+    is_default: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+impl BankAccountRow {
+    fn into_account(self) -> BankAccount {
+        BankAccount {
+            id: Uuid::parse_str(&self.id).unwrap_or_default(),
+            label: self.label,
+            account_holder: self.account_holder,
+            bank_name: self.bank_name,
+            branch: self.branch,
+            account_number: self.account_number,
+            iban: self.iban,
+            swift_bic: self.swift_bic,
+            ifsc_code: self.ifsc_code,
+            routing_number: self.routing_number,
+            upi_id: self.upi_id,
+            currency: self.currency.map(|c| Currency::from_str(&c).unwrap_or_default()),
+            is_default: self.is_default,
+            created_at: chrono::DateTime::parse_from_rfc3339(&self.created_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&self.updated_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use crate::database::DbPool;
-use flow_core::models::{Address, BusinessProfile};
+use crate::decode::{self, RowRef};
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::{Address, BusinessProfile, CorruptRow};
 use flow_core::types::{Currency, PaymentTerms};
 use chrono::Utc;
-use std::str::FromStr;
+use sqlx::{Sqlite, Transaction};
 use uuid::Uuid;
 
+const COLUMNS: &str = "id, name, email, phone, address_line1, address_line2, address_city, address_state, address_postal_code, address_country, tax_id, logo_path, default_currency, default_payment_terms, theme_preference, pdf_export_dir, pdf_filename_pattern, pdf_backend, pdf_archival, browser_path, created_at, updated_at";
+
 pub struct BusinessProfileRepository {
     pool: DbPool,
 }
@@ -15,19 +19,13 @@ impl BusinessProfileRepository {
     }
 
     /// Retrieve the first (and only) business profile, or create a default one if it doesn't exist
-    pub async fn get_profile(&self) -> Result<BusinessProfile, sqlx::Error> {
-        let row = sqlx::query_as::<_, BusinessProfileRow>(
-            "SELECT id, name, email, phone, address_line1, address_line2, address_city, 
-                    address_state, address_postal_code, address_country, tax_id, logo_path, 
-                    default_currency, default_payment_terms, theme_preference, pdf_export_dir, 
-                    created_at, updated_at 
-             FROM business_profiles ORDER BY updated_at DESC LIMIT 1"
-        )
+    pub async fn get_profile(&self) -> FlowResult<BusinessProfile> {
+        let row = sqlx::query_as::<_, BusinessProfileRow>(&format!("SELECT {} FROM business_profiles ORDER BY updated_at DESC LIMIT 1", COLUMNS))
         .fetch_optional(&self.pool)
         .await?;
 
         if let Some(r) = row {
-            Ok(r.into_profile())
+            BusinessProfile::try_from(r)
         } else {
             // Create a default profile
             let id = Uuid::new_v4();
@@ -37,8 +35,8 @@ impl BusinessProfileRepository {
 
             sqlx::query(
                 r#"INSERT INTO business_profiles 
-                   (id, name, address_line1, address_city, address_postal_code, address_country, default_currency, default_payment_terms, theme_preference, pdf_export_dir, created_at, updated_at)
-                   VALUES (?, 'My Company', '', '', '', '', 'USD', 'Net30', 'system', NULL, ?, ?)"#
+                   (id, name, address_line1, address_city, address_postal_code, address_country, default_currency, default_payment_terms, theme_preference, pdf_export_dir, pdf_filename_pattern, pdf_backend, created_at, updated_at)
+                   VALUES (?, 'My Company', '', '', '', '', 'USD', 'Net30', 'system', NULL, 'Invoice_{number}.pdf', 'native', ?, ?)"#
             )
             .bind(&id_str)
             .bind(&now_str)
@@ -58,6 +56,10 @@ impl BusinessProfileRepository {
                 default_payment_terms: PaymentTerms::Net30,
                 theme_preference: "system".to_string(),
                 pdf_export_dir: None,
+                pdf_filename_pattern: "Invoice_{number}.pdf".to_string(),
+                pdf_backend: "native".to_string(),
+                pdf_archival: false,
+                browser_path: None,
                 created_at: now,
                 updated_at: now,
             })
@@ -65,42 +67,57 @@ impl BusinessProfileRepository {
     }
 
     /// Update the business profile
-    pub async fn update_profile(&self, profile: &BusinessProfile) -> Result<(), sqlx::Error> {
-        let now = Utc::now().to_rfc3339();
-        
-        sqlx::query(
-            r#"UPDATE business_profiles 
-               SET name = ?, email = ?, phone = ?, 
-                   address_line1 = ?, address_line2 = ?, address_city = ?, 
-                   address_state = ?, address_postal_code = ?, address_country = ?, 
-                   tax_id = ?, logo_path = ?, default_currency = ?, default_payment_terms = ?, 
-                   theme_preference = ?, pdf_export_dir = ?, updated_at = ?
-               WHERE id = ?"#
-        )
-        .bind(&profile.name)
-        .bind(&profile.email)
-        .bind(&profile.phone)
-        .bind(&profile.address.line1)
-        .bind(&profile.address.line2)
-        .bind(&profile.address.city)
-        .bind(&profile.address.state)
-        .bind(&profile.address.postal_code)
-        .bind(&profile.address.country)
-        .bind(&profile.tax_id)
-        .bind(&profile.logo_path)
-        .bind(profile.default_currency.to_string())
-        .bind(profile.default_payment_terms.to_string())
-        .bind(&profile.theme_preference)
-        .bind(&profile.pdf_export_dir)
-        .bind(&now)
-        .bind(profile.id.to_string())
-        .execute(&self.pool)
-        .await?;
-
-        Ok(())
+    pub async fn update_profile(&self, profile: &BusinessProfile) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        update_profile(&mut tx, profile).await?;
+        Ok(tx.commit().await?)
     }
 }
 
+pub(crate) async fn update_profile(tx: &mut Transaction<'_, Sqlite>, profile: &BusinessProfile) -> FlowResult<()> {
+    let now = Utc::now().to_rfc3339();
+    
+    sqlx::query(
+        r#"UPDATE business_profiles 
+           SET name = ?, email = ?, phone = ?, 
+               address_line1 = ?, address_line2 = ?, address_city = ?, 
+               address_state = ?, address_postal_code = ?, address_country = ?, 
+               tax_id = ?, logo_path = ?, default_currency = ?, default_payment_terms = ?, 
+               theme_preference = ?, pdf_export_dir = ?, pdf_filename_pattern = ?, pdf_backend = ?, pdf_archival = ?, browser_path = ?, updated_at = ?
+           WHERE id = ?"#
+    )
+    .bind(&profile.name)
+    .bind(&profile.email)
+    .bind(&profile.phone)
+    .bind(&profile.address.line1)
+    .bind(&profile.address.line2)
+    .bind(&profile.address.city)
+    .bind(&profile.address.state)
+    .bind(&profile.address.postal_code)
+    .bind(&profile.address.country)
+    .bind(&profile.tax_id)
+    .bind(&profile.logo_path)
+    .bind(profile.default_currency.to_string())
+    .bind(profile.default_payment_terms.to_string())
+    .bind(&profile.theme_preference)
+    .bind(&profile.pdf_export_dir)
+    .bind(&profile.pdf_filename_pattern)
+    .bind(&profile.pdf_backend)
+    .bind(profile.pdf_archival)
+    .bind(&profile.browser_path)
+    .bind(&now)
+    .bind(profile.id.to_string())
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Business profile rows that cannot be read back.
+pub(crate) async fn find_corrupt_rows(pool: &DbPool) -> FlowResult<Vec<CorruptRow>> {
+    decode::find_corrupt_rows::<BusinessProfileRow, BusinessProfile>(pool, "business_profiles", &format!("SELECT {} FROM business_profiles WHERE rowid = ?", COLUMNS)).await
+}
+
 #[derive(sqlx::FromRow)]
 struct BusinessProfileRow {
     id: String,
@@ -119,37 +136,44 @@ struct BusinessProfileRow {
     default_payment_terms: String,
     theme_preference: String,
     pdf_export_dir: Option<String>,
+    pdf_filename_pattern: String,
+    pdf_backend: String,
+    pdf_archival: bool,
+    browser_path: Option<String>,
     created_at: String,
     updated_at: String,
 }
 
-impl BusinessProfileRow {
-    fn into_profile(self) -> BusinessProfile {
-        BusinessProfile {
-            id: Uuid::parse_str(&self.id).unwrap_or_default(),
-            name: self.name,
-            email: self.email,
-            phone: self.phone,
+impl TryFrom<BusinessProfileRow> for BusinessProfile {
+    type Error = FlowError;
+
+    fn try_from(row: BusinessProfileRow) -> FlowResult<Self> {
+        let r = RowRef::new("business_profiles", &row.id);
+        Ok(BusinessProfile {
+            id: r.uuid("id", &row.id)?,
+            name: row.name,
+            email: row.email,
+            phone: row.phone,
             address: Address {
-                line1: self.address_line1,
-                line2: self.address_line2,
-                city: self.address_city,
-                state: self.address_state,
-                postal_code: self.address_postal_code,
-                country: self.address_country,
+                line1: row.address_line1,
+                line2: row.address_line2,
+                city: row.address_city,
+                state: row.address_state,
+                postal_code: row.address_postal_code,
+                country: row.address_country,
             },
-            tax_id: self.tax_id,
-            logo_path: self.logo_path,
-            default_currency: Currency::from_str(&self.default_currency).unwrap_or(Currency::USD),
-            default_payment_terms: PaymentTerms::from_str(&self.default_payment_terms).unwrap_or(PaymentTerms::Net30),
-            theme_preference: self.theme_preference,
-            pdf_export_dir: self.pdf_export_dir,
-            created_at: chrono::DateTime::parse_from_rfc3339(&self.created_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now()),
-            updated_at: chrono::DateTime::parse_from_rfc3339(&self.updated_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now()),
-        }
+            tax_id: row.tax_id,
+            logo_path: row.logo_path,
+            default_currency: r.parse("default_currency", &row.default_currency)?,
+            default_payment_terms: r.parse("default_payment_terms", &row.default_payment_terms)?,
+            theme_preference: row.theme_preference,
+            pdf_export_dir: row.pdf_export_dir,
+            pdf_filename_pattern: row.pdf_filename_pattern,
+            pdf_backend: row.pdf_backend,
+            pdf_archival: row.pdf_archival,
+            browser_path: row.browser_path,
+            created_at: r.timestamp("created_at", &row.created_at)?,
+            updated_at: r.timestamp("updated_at", &row.updated_at)?,
+        })
     }
 }
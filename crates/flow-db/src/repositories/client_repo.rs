@@ -1,7 +1,16 @@
 use crate::database::DbPool;
-use flow_core::models::{Address, Client};
+use crate::decode::{self, RowRef};
+use crate::query::{encode_cursor, fts_query, page_size, push_cursor, push_order, ClientQuery, Page};
+use flow_core::duplicates::normalize_tax_id;
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::{Address, Client, ClientContact, CorruptRow, TaxIdentifier};
 use chrono::Utc;
-use uuid::Uuid;
+use sqlx::{QueryBuilder, Sqlite, Transaction};
+use std::collections::HashMap;
+
+use super::{audit_repo, custom_field_repo, CustomFieldRepository};
+
+const COLUMNS: &str = "id, name, email, phone, company, address_line1, address_line2, address_city, address_state, address_postal_code, address_country, shipping_line1, shipping_line2, shipping_city, shipping_state, shipping_postal_code, shipping_country, tax_id, notes, archived_at, created_at, updated_at";
 
 pub struct ClientRepository {
     pool: DbPool,
@@ -12,79 +21,386 @@ impl ClientRepository {
         Self { pool }
     }
 
-    pub async fn create(&self, name: &str, email: Option<&str>, company: Option<&str>) -> Result<Client, sqlx::Error> {
-        let id = Uuid::new_v4();
-        let now = Utc::now();
-        let id_str = id.to_string();
-        let now_str = now.to_rfc3339();
-
-        sqlx::query(
-            r#"INSERT INTO clients (id, name, email, company, address_line1, address_city, address_postal_code, address_country, created_at, updated_at)
-               VALUES (?, ?, ?, ?, '', '', '', '', ?, ?)"#,
-        )
-        .bind(&id_str)
-        .bind(name)
-        .bind(email)
-        .bind(company)
-        .bind(&now_str)
-        .bind(&now_str)
-        .execute(&self.pool)
-        .await?;
-
-        Ok(Client {
-            id,
-            name: name.to_string(),
-            email: email.map(String::from),
-            phone: None,
-            company: company.map(String::from),
-            address: Address::default(),
-            notes: None,
-            created_at: now,
-            updated_at: now,
-        })
+    pub async fn create(&self, client: &Client) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        insert_client(&mut tx, client).await?;
+        Ok(tx.commit().await?)
     }
 
-    pub async fn list_all(&self) -> Result<Vec<Client>, sqlx::Error> {
-        let rows = sqlx::query_as::<_, ClientRow>("SELECT id, name, email, phone, company, address_line1, address_city, address_postal_code, address_country, notes, created_at, updated_at FROM clients ORDER BY name")
+    pub async fn list_all(&self) -> FlowResult<Vec<Client>> {
+        let rows = sqlx::query_as::<_, ClientRow>(&format!("SELECT {} FROM clients ORDER BY name", COLUMNS))
             .fetch_all(&self.pool)
             .await?;
+        self.with_children(rows).await
+    }
 
-        Ok(rows.into_iter().map(|r| r.into_client()).collect())
+    /// One page of clients matching `query`, in its sort order.
+    pub async fn query(&self, query: &ClientQuery) -> FlowResult<Page<Client>> {
+        let sort = query.sort.key();
+        let search = fts_query(query.search.as_deref());
+        let limit = page_size(query.limit) as usize;
+
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM clients c WHERE 1 = 1");
+        push_filters(&mut count, query, &search);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut qb = QueryBuilder::<Sqlite>::new(format!("SELECT {}, {} AS sort_key FROM clients c WHERE 1 = 1", COLUMNS, sort.key));
+        push_filters(&mut qb, query, &search);
+        if let Some(cursor) = &query.cursor {
+            push_cursor(&mut qb, &sort, "c.id", query.direction, cursor)?;
+        }
+        push_order(&mut qb, &sort, "c.id", query.direction);
+        qb.push(" LIMIT ").push_bind(limit as i64 + 1);
+        let mut rows: Vec<ClientPageRow> = qb.build_query_as().fetch_all(&self.pool).await?;
+
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|r| encode_cursor(&r.sort_key, &r.client.id))
+        } else {
+            None
+        };
+        let items = self.with_children(rows.into_iter().map(|r| r.client).collect()).await?;
+        Ok(Page { items, next_cursor, total })
     }
 
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<Client>, sqlx::Error> {
-        let row = sqlx::query_as::<_, ClientRow>("SELECT id, name, email, phone, company, address_line1, address_city, address_postal_code, address_country, notes, created_at, updated_at FROM clients WHERE id = ?")
+    /// Loads the contacts, tax IDs and custom field values of the clients.
+    async fn with_children(&self, rows: Vec<ClientRow>) -> FlowResult<Vec<Client>> {
+        let ids: Vec<String> = rows.iter().map(|r| r.id.clone()).collect();
+        let mut custom_fields = self.custom_fields().values_for_many(&ids).await?;
+        let mut contacts: HashMap<String, Vec<ClientContact>> = HashMap::new();
+        let mut tax_ids: HashMap<String, Vec<TaxIdentifier>> = HashMap::new();
+
+        if !ids.is_empty() {
+            let mut qb = QueryBuilder::<Sqlite>::new("SELECT id, client_id, name, email, phone, role, is_primary FROM client_contacts WHERE client_id IN (");
+            push_ids(&mut qb, &ids);
+            qb.push(") ORDER BY sort_order");
+            for row in qb.build_query_as::<ContactRow>().fetch_all(&self.pool).await? {
+                contacts.entry(row.client_id.clone()).or_default().push(ClientContact::try_from(row)?);
+            }
+
+            let mut qb = QueryBuilder::<Sqlite>::new("SELECT client_id, scheme, value FROM client_tax_ids WHERE client_id IN (");
+            push_ids(&mut qb, &ids);
+            qb.push(") ORDER BY sort_order");
+            for row in qb.build_query_as::<TaxIdRow>().fetch_all(&self.pool).await? {
+                tax_ids.entry(row.client_id.clone()).or_default().push(row.into_tax_id());
+            }
+        }
+
+        rows.into_iter()
+            .map(|r| {
+                let id = r.id.clone();
+                let mut client = Client::try_from(r)?;
+                client.custom_fields = custom_fields.remove(&id).unwrap_or_default();
+                client.contacts = contacts.remove(&id).unwrap_or_default();
+                client.tax_ids = tax_ids.remove(&id).unwrap_or_default();
+                Ok(client)
+            })
+            .collect()
+    }
+
+    pub async fn get_by_id(&self, id: &str) -> FlowResult<Option<Client>> {
+        let row = sqlx::query_as::<_, ClientRow>(&format!("SELECT {} FROM clients WHERE id = ?", COLUMNS))
             .bind(id)
             .fetch_optional(&self.pool)
             .await?;
 
-        Ok(row.map(|r| r.into_client()))
+        match row {
+            Some(row) => Ok(self.with_children(vec![row]).await?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    /// Deletes a client that has no invoices. A client with invoices, archived ones
+    /// included, is refused with [`FlowError::ClientHasInvoices`] so their history stays
+    /// intact; archive it instead.
+    pub async fn delete(&self, id: &str) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        delete_client(&mut tx, id).await?;
+        Ok(tx.commit().await?)
     }
 
-    pub async fn delete(&self, id: &str) -> Result<(), sqlx::Error> {
+    /// Archived clients are left out of lists and searches by default but keep their invoices.
+    pub async fn set_archived(&self, id: &str, archived: bool) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        set_client_archived(&mut tx, id, archived).await?;
+        Ok(tx.commit().await?)
+    }
+
+    /// Overwrites every field of the client; contacts, tax IDs and custom field values
+    /// are replaced by the ones given.
+    pub async fn update(&self, client: &Client) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        update_client(&mut tx, client).await?;
+        Ok(tx.commit().await?)
+    }
+
+    /// Folds `source_id` into `target_id` and deletes the source, all in one transaction.
+    ///
+    /// The source's invoices (and with them their payments) move to the target, its notes
+    /// are appended to the target's, and its email, phone, company, tax number and shipping
+    /// address fill the target's blanks. Contacts and tax IDs the target lacks are added,
+    /// as are custom field values for fields the target has not set. The merge is recorded
+    /// in the audit log with a copy of the deleted client.
+    pub async fn merge(&self, source_id: &str, target_id: &str) -> FlowResult<Client> {
+        if source_id == target_id {
+            return Err(FlowError::Validation("A client cannot be merged into itself".to_string()));
+        }
+        let source = self.get_by_id(source_id).await?.ok_or_else(|| FlowError::NotFound(format!("Client {}", source_id)))?;
+        let mut target = self.get_by_id(target_id).await?.ok_or_else(|| FlowError::NotFound(format!("Client {}", target_id)))?;
+
+        target.notes = match (target.notes.take(), source.notes.clone()) {
+            (Some(a), Some(b)) if a != b => Some(format!("{}\n\n{}", a, b)),
+            (a, b) => a.or(b),
+        };
+        target.email = target.email.take().or_else(|| source.email.clone());
+        target.phone = target.phone.take().or_else(|| source.phone.clone());
+        target.company = target.company.take().or_else(|| source.company.clone());
+        target.tax_id = target.tax_id.take().or_else(|| source.tax_id.clone());
+        target.shipping_address = target.shipping_address.take().or_else(|| source.shipping_address.clone());
+
+        let has_primary = target.contacts.iter().any(|c| c.is_primary);
+        for contact in &source.contacts {
+            let known = target.contacts.iter().any(|c| {
+                c.name.eq_ignore_ascii_case(&contact.name) && (c.email.is_none() || contact.email.is_none() || c.email == contact.email)
+            });
+            if !known {
+                target.contacts.push(ClientContact { is_primary: contact.is_primary && !has_primary, ..contact.clone() });
+            }
+        }
+        for tax_id in &source.tax_ids {
+            let value = normalize_tax_id(&tax_id.value);
+            if !target.tax_ids.iter().any(|t| normalize_tax_id(&t.value) == value) {
+                target.tax_ids.push(tax_id.clone());
+            }
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let moved = sqlx::query("UPDATE invoices SET client_id = ? WHERE client_id = ?")
+            .bind(target_id)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        // The source's contacts go first: the target takes them over under the same ids
+        for table in ["client_contacts", "client_tax_ids"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE client_id = ?", table))
+                .bind(source_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        update_row(&mut tx, &target).await?;
+        // Values for fields the target already has are kept as they are
+        sqlx::query(
+            "INSERT OR IGNORE INTO custom_field_values (field_id, entity_id, value) SELECT field_id, ?, value FROM custom_field_values WHERE entity_id = ?",
+        )
+        .bind(target_id)
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM custom_field_values WHERE entity_id = ?")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
         sqlx::query("DELETE FROM clients WHERE id = ?")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
+        let details = serde_json::json!({
+            "source_id": source_id,
+            "target_id": target_id,
+            "invoices_moved": moved,
+            "source": source,
+        });
+        audit_repo::record(&mut tx, "merge", "client", target_id, &details).await?;
+        tx.commit().await?;
+
+        self.get_by_id(target_id).await?.ok_or_else(|| FlowError::NotFound(format!("Client {}", target_id)))
+    }
+
+    fn custom_fields(&self) -> CustomFieldRepository {
+        CustomFieldRepository::new(self.pool.clone())
+    }
+}
+
+pub(crate) async fn insert_client(tx: &mut Transaction<'_, Sqlite>, client: &Client) -> FlowResult<()> {
+    let id = client.id.to_string();
+    let shipping = client.shipping_address.as_ref();
+
+    sqlx::query(
+        r#"INSERT INTO clients (id, name, email, phone, company, address_line1, address_line2, address_city, address_state, address_postal_code, address_country, shipping_line1, shipping_line2, shipping_city, shipping_state, shipping_postal_code, shipping_country, tax_id, notes, archived_at, created_at, updated_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(&id)
+    .bind(&client.name)
+    .bind(&client.email)
+    .bind(&client.phone)
+    .bind(&client.company)
+    .bind(&client.address.line1)
+    .bind(&client.address.line2)
+    .bind(&client.address.city)
+    .bind(&client.address.state)
+    .bind(&client.address.postal_code)
+    .bind(&client.address.country)
+    .bind(shipping.map(|a| &a.line1))
+    .bind(shipping.and_then(|a| a.line2.as_ref()))
+    .bind(shipping.map(|a| &a.city))
+    .bind(shipping.and_then(|a| a.state.as_ref()))
+    .bind(shipping.map(|a| &a.postal_code))
+    .bind(shipping.map(|a| &a.country))
+    .bind(&client.tax_id)
+    .bind(&client.notes)
+    .bind(client.archived_at.map(|dt| dt.to_rfc3339()))
+    .bind(client.created_at.to_rfc3339())
+    .bind(client.updated_at.to_rfc3339())
+    .execute(&mut **tx)
+    .await?;
+    write_children(tx, &id, client).await?;
+    custom_field_repo::set_values(tx, &id, &client.custom_fields).await
+}
+
+pub(crate) async fn update_client(tx: &mut Transaction<'_, Sqlite>, client: &Client) -> FlowResult<()> {
+    update_row(tx, client).await?;
+    custom_field_repo::set_values(tx, &client.id.to_string(), &client.custom_fields).await
+}
+
+pub(crate) async fn delete_client(tx: &mut Transaction<'_, Sqlite>, id: &str) -> FlowResult<()> {
+    let (name, invoice_count): (String, i64) =
+        sqlx::query_as("SELECT name, (SELECT COUNT(*) FROM invoices WHERE client_id = clients.id) FROM clients WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| FlowError::NotFound(format!("Client {}", id)))?;
+    if invoice_count > 0 {
+        return Err(FlowError::ClientHasInvoices { name, invoice_count });
+    }
+
+    for table in ["client_contacts", "client_tax_ids"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE client_id = ?", table))
+            .bind(id)
+            .execute(&mut **tx)
             .await?;
-        Ok(())
     }
+    sqlx::query("DELETE FROM custom_field_values WHERE entity_id = ?")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM clients WHERE id = ?")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
 
-    pub async fn update(&self, id: &str, name: &str, email: Option<&str>, company: Option<&str>) -> Result<(), sqlx::Error> {
-        let now = Utc::now().to_rfc3339();
-        sqlx::query(
-            "UPDATE clients SET name = ?, email = ?, company = ?, updated_at = ? WHERE id = ?",
-        )
-        .bind(name)
-        .bind(email)
-        .bind(company)
+pub(crate) async fn set_client_archived(tx: &mut Transaction<'_, Sqlite>, id: &str, archived: bool) -> FlowResult<()> {
+    let now = Utc::now().to_rfc3339();
+    sqlx::query("UPDATE clients SET archived_at = ?, updated_at = ? WHERE id = ?")
+        .bind(archived.then_some(&now))
         .bind(&now)
         .bind(id)
-        .execute(&self.pool)
+        .execute(&mut **tx)
         .await?;
-        Ok(())
+    Ok(())
+}
+
+fn push_filters(qb: &mut QueryBuilder<'_, Sqlite>, query: &ClientQuery, search: &Option<String>) {
+    qb.push(query.archived.condition("c.archived_at"));
+    if let Some(search) = search {
+        qb.push(" AND c.id IN (SELECT id FROM clients_fts WHERE clients_fts MATCH ").push_bind(search.clone()).push(")");
+    }
+}
+
+fn push_ids(qb: &mut QueryBuilder<'_, Sqlite>, ids: &[String]) {
+    let mut separated = qb.separated(", ");
+    for id in ids {
+        separated.push_bind(id.clone());
     }
 }
 
+/// Writes every column of the client and replaces its contacts and tax IDs.
+async fn update_row(tx: &mut Transaction<'_, Sqlite>, client: &Client) -> FlowResult<()> {
+    let id = client.id.to_string();
+    let shipping = client.shipping_address.as_ref();
+
+    sqlx::query(
+        r#"UPDATE clients SET name = ?, email = ?, phone = ?, company = ?,
+               address_line1 = ?, address_line2 = ?, address_city = ?, address_state = ?, address_postal_code = ?, address_country = ?,
+               shipping_line1 = ?, shipping_line2 = ?, shipping_city = ?, shipping_state = ?, shipping_postal_code = ?, shipping_country = ?,
+               tax_id = ?, notes = ?, updated_at = ?
+           WHERE id = ?"#,
+    )
+    .bind(&client.name)
+    .bind(&client.email)
+    .bind(&client.phone)
+    .bind(&client.company)
+    .bind(&client.address.line1)
+    .bind(&client.address.line2)
+    .bind(&client.address.city)
+    .bind(&client.address.state)
+    .bind(&client.address.postal_code)
+    .bind(&client.address.country)
+    .bind(shipping.map(|a| &a.line1))
+    .bind(shipping.and_then(|a| a.line2.as_ref()))
+    .bind(shipping.map(|a| &a.city))
+    .bind(shipping.and_then(|a| a.state.as_ref()))
+    .bind(shipping.map(|a| &a.postal_code))
+    .bind(shipping.map(|a| &a.country))
+    .bind(&client.tax_id)
+    .bind(&client.notes)
+    .bind(Utc::now().to_rfc3339())
+    .bind(&id)
+    .execute(&mut **tx)
+    .await?;
+    for table in ["client_contacts", "client_tax_ids"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE client_id = ?", table))
+            .bind(&id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    write_children(tx, &id, client).await
+}
+
+async fn write_children(tx: &mut Transaction<'_, Sqlite>, client_id: &str, client: &Client) -> FlowResult<()> {
+    for (sort_order, contact) in client.contacts.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO client_contacts (id, client_id, name, email, phone, role, is_primary, sort_order) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(contact.id.to_string())
+        .bind(client_id)
+        .bind(&contact.name)
+        .bind(&contact.email)
+        .bind(&contact.phone)
+        .bind(&contact.role)
+        .bind(contact.is_primary)
+        .bind(sort_order as i64)
+        .execute(&mut **tx)
+        .await?;
+    }
+    for (sort_order, tax_id) in client.tax_ids.iter().enumerate() {
+        sqlx::query("INSERT INTO client_tax_ids (client_id, scheme, value, sort_order) VALUES (?, ?, ?, ?)")
+            .bind(client_id)
+            .bind(&tax_id.scheme)
+            .bind(&tax_id.value)
+            .bind(sort_order as i64)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Client and contact rows that cannot be read back.
+pub(crate) async fn find_corrupt_rows(pool: &DbPool) -> FlowResult<Vec<CorruptRow>> {
+    let mut corrupt = decode::find_corrupt_rows::<ClientRow, Client>(pool, "clients", &format!("SELECT {} FROM clients WHERE rowid = ?", COLUMNS)).await?;
+    corrupt.extend(
+        decode::find_corrupt_rows::<ContactRow, ClientContact>(
+            pool,
+            "client_contacts",
+            "SELECT id, client_id, name, email, phone, role, is_primary FROM client_contacts WHERE rowid = ?",
+        )
+        .await?,
+    );
+    Ok(corrupt)
+}
+
 #[derive(sqlx::FromRow)]
 struct ClientRow {
     id: String,
@@ -93,37 +409,399 @@ struct ClientRow {
     phone: Option<String>,
     company: Option<String>,
     address_line1: String,
+    address_line2: Option<String>,
     address_city: String,
+    address_state: Option<String>,
     address_postal_code: String,
     address_country: String,
+    shipping_line1: Option<String>,
+    shipping_line2: Option<String>,
+    shipping_city: Option<String>,
+    shipping_state: Option<String>,
+    shipping_postal_code: Option<String>,
+    shipping_country: Option<String>,
+    tax_id: Option<String>,
     notes: Option<String>,
+    archived_at: Option<String>,
     created_at: String,
     updated_at: String,
 }
 
-impl ClientRow {
-    fn into_client(self) -> Client {
-        Client {
-            id: Uuid::parse_str(&self.id).unwrap_or_default(),
-            name: self.name,
-            email: self.email,
-            phone: self.phone,
-            company: self.company,
+#[derive(sqlx::FromRow)]
+struct ClientPageRow {
+    #[sqlx(flatten)]
+    client: ClientRow,
+    sort_key: String,
+}
+
+/// Contacts, tax IDs and custom field values are loaded separately and start out empty.
+impl TryFrom<ClientRow> for Client {
+    type Error = FlowError;
+
+    fn try_from(row: ClientRow) -> FlowResult<Self> {
+        let r = RowRef::new("clients", &row.id);
+        let id = r.uuid("id", &row.id)?;
+        let archived_at = r.optional_timestamp("archived_at", row.archived_at.as_deref())?;
+        let created_at = r.timestamp("created_at", &row.created_at)?;
+        let updated_at = r.timestamp("updated_at", &row.updated_at)?;
+
+        let shipping_address = row.shipping_line1.map(|line1| Address {
+            line1,
+            line2: row.shipping_line2,
+            city: row.shipping_city.unwrap_or_default(),
+            state: row.shipping_state,
+            postal_code: row.shipping_postal_code.unwrap_or_default(),
+            country: row.shipping_country.unwrap_or_default(),
+        });
+
+        Ok(Client {
+            id,
+            name: row.name,
+            email: row.email,
+            phone: row.phone,
+            company: row.company,
             address: Address {
-                line1: self.address_line1,
-                line2: None,
-                city: self.address_city,
-                state: None,
-                postal_code: self.address_postal_code,
-                country: self.address_country,
+                line1: row.address_line1,
+                line2: row.address_line2,
+                city: row.address_city,
+                state: row.address_state,
+                postal_code: row.address_postal_code,
+                country: row.address_country,
             },
-            notes: self.notes,
-            created_at: chrono::DateTime::parse_from_rfc3339(&self.created_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now()),
-            updated_at: chrono::DateTime::parse_from_rfc3339(&self.updated_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now()),
+            shipping_address,
+            tax_id: row.tax_id,
+            tax_ids: vec![],
+            contacts: vec![],
+            notes: row.notes,
+            custom_fields: vec![],
+            archived_at,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ContactRow {
+    id: String,
+    client_id: String,
+    name: String,
+    email: Option<String>,
+    phone: Option<String>,
+    role: Option<String>,
+    is_primary: bool,
+}
+
+impl TryFrom<ContactRow> for ClientContact {
+    type Error = FlowError;
+
+    fn try_from(row: ContactRow) -> FlowResult<Self> {
+        Ok(ClientContact {
+            id: RowRef::new("client_contacts", &row.id).uuid("id", &row.id)?,
+            name: row.name,
+            email: row.email,
+            phone: row.phone,
+            role: row.role,
+            is_primary: row.is_primary,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TaxIdRow {
+    client_id: String,
+    scheme: String,
+    value: String,
+}
+
+impl TaxIdRow {
+    fn into_tax_id(self) -> TaxIdentifier {
+        TaxIdentifier {
+            scheme: self.scheme,
+            value: self.value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::{ArchiveFilter, ClientSort, SortDirection};
+    use crate::repositories::{AuditLogRepository, BusinessProfileRepository, InvoiceRepository};
+    use crate::test_support::{new_client, new_invoice, new_pool};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    /// Every page of `query`, following the cursors.
+    async fn all_pages(repo: &ClientRepository, mut query: ClientQuery) -> Vec<Client> {
+        let mut clients = Vec::new();
+        loop {
+            let page = repo.query(&query).await.unwrap();
+            assert!(page.items.len() <= query.limit as usize);
+            clients.extend(page.items);
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return clients,
+            }
+        }
+    }
+
+    fn names(clients: &[Client]) -> Vec<&str> {
+        clients.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_pages_through_tied_names_without_gaps() {
+        let repo = ClientRepository::new(new_pool().await);
+        for name in ["beta", "Acme", "acme", "Acme", "Delta", "Acme", "Charlie"] {
+            repo.create(&new_client(name)).await.unwrap();
+        }
+
+        for direction in [SortDirection::Asc, SortDirection::Desc] {
+            let query = ClientQuery { direction, limit: 2, ..Default::default() };
+            let pages = all_pages(&repo, query.clone()).await;
+            assert_eq!(repo.query(&query).await.unwrap().total, 7);
+
+            // Name order ignoring case, then id order among equal names
+            let mut expected = repo.list_all().await.unwrap();
+            expected.sort_by_key(|c| (c.name.to_lowercase(), c.id));
+            if direction == SortDirection::Desc {
+                expected.reverse();
+            }
+            let ids: Vec<Uuid> = pages.iter().map(|c| c.id).collect();
+            assert_eq!(ids, expected.iter().map(|c| c.id).collect::<Vec<_>>(), "{:?}", direction);
+        }
+
+        // A page that ends exactly at the last row has no next page
+        let page = repo.query(&ClientQuery { limit: 7, ..Default::default() }).await.unwrap();
+        assert_eq!((page.items.len(), page.next_cursor), (7, None));
+
+        let sorted = all_pages(&repo, ClientQuery { sort: ClientSort::CreatedAt, limit: 3, ..Default::default() }).await;
+        assert_eq!(names(&sorted), ["beta", "Acme", "acme", "Acme", "Delta", "Acme", "Charlie"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_takes_words_as_prefixes_and_fts_syntax_literally() {
+        let repo = ClientRepository::new(new_pool().await);
+        let mut acme = new_client("Acme Rockets");
+        acme.email = Some("billing@acme.test".to_string());
+        repo.create(&acme).await.unwrap();
+        let mut quoted = new_client("O'Brien \"Quotes\" Ltd");
+        quoted.notes = Some("NEAR the station".to_string());
+        repo.create(&quoted).await.unwrap();
+        repo.create(&new_client("Rocket Science")).await.unwrap();
+
+        let search = |text: &str| {
+            let query = ClientQuery { search: Some(text.to_string()), limit: 10, ..Default::default() };
+            let repo = &repo;
+            async move { repo.query(&query).await.map(|page| page.items.into_iter().map(|c| c.name).collect::<Vec<_>>()) }
+        };
+        assert_eq!(search("acm").await.unwrap(), ["Acme Rockets"]);
+        assert_eq!(search("rock").await.unwrap(), ["Acme Rockets", "Rocket Science"]);
+        assert_eq!(search("rock sci").await.unwrap(), ["Rocket Science"]);
+        assert_eq!(search("billing").await.unwrap(), ["Acme Rockets"]);
+        assert_eq!(search("   ").await.unwrap().len(), 3);
+
+        // Operators and quotes are searched for, not interpreted
+        for text in ["\"quotes", "quotes\"", "NEAR(", "acme OR", "NOT acme", "-acme", "acme*", "o'brien", "*", "\"", "(", ":"] {
+            search(text).await.unwrap_or_else(|e| panic!("{:?}: {}", text, e));
+        }
+        assert_eq!(search("\"quotes\"").await.unwrap(), ["O'Brien \"Quotes\" Ltd"]);
+        assert_eq!(search("NEAR").await.unwrap(), ["O'Brien \"Quotes\" Ltd"]);
+        assert!(search("acme OR").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_cursors_it_did_not_hand_out() {
+        let repo = ClientRepository::new(new_pool().await);
+        repo.create(&new_client("Acme")).await.unwrap();
+        for cursor in ["", "no separator"] {
+            let query = ClientQuery { cursor: Some(cursor.to_string()), archived: ArchiveFilter::All, ..Default::default() };
+            assert!(matches!(repo.query(&query).await, Err(FlowError::Validation(_))), "{:?}", cursor);
+        }
+    }
+
+    fn contact(name: &str, email: Option<&str>, is_primary: bool) -> ClientContact {
+        ClientContact {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            email: email.map(String::from),
+            phone: None,
+            role: None,
+            is_primary,
+        }
+    }
+
+    fn tax_id(scheme: &str, value: &str) -> TaxIdentifier {
+        TaxIdentifier { scheme: scheme.to_string(), value: value.to_string() }
+    }
+
+    /// Creates an invoice for `client_id` and returns its id.
+    async fn invoice_for(pool: &DbPool, number: &str, client_id: Uuid) -> String {
+        let profile_id = BusinessProfileRepository::new(pool.clone()).get_profile().await.unwrap().id;
+        let invoice = new_invoice(number, client_id, profile_id, Decimal::TEN);
+        InvoiceRepository::new(pool.clone()).create(&invoice).await.unwrap();
+        invoice.id.to_string()
+    }
+
+    async fn count(pool: &DbPool, sql: &str, id: Uuid) -> i64 {
+        sqlx::query_scalar(sql).bind(id.to_string()).fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_delete_refuses_clients_with_invoices() {
+        let pool = new_pool().await;
+        let repo = ClientRepository::new(pool.clone());
+        let billed = new_client("Billed");
+        repo.create(&billed).await.unwrap();
+        let invoice_id = invoice_for(&pool, "INV-1", billed.id).await;
+        // Archived invoices count too
+        InvoiceRepository::new(pool.clone()).set_archived(&invoice_id, true).await.unwrap();
+
+        match repo.delete(&billed.id.to_string()).await {
+            Err(FlowError::ClientHasInvoices { name, invoice_count }) => assert_eq!((name.as_str(), invoice_count), ("Billed", 1)),
+            other => panic!("{:?}", other),
         }
+        assert!(repo.get_by_id(&billed.id.to_string()).await.unwrap().is_some());
+
+        let mut unbilled = new_client("Unbilled");
+        unbilled.contacts = vec![contact("Ann", None, true)];
+        unbilled.tax_ids = vec![tax_id("PAN", "AAAPA1234A")];
+        repo.create(&unbilled).await.unwrap();
+        repo.delete(&unbilled.id.to_string()).await.unwrap();
+        assert!(repo.get_by_id(&unbilled.id.to_string()).await.unwrap().is_none());
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM client_contacts WHERE client_id = ?", unbilled.id).await, 0);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM client_tax_ids WHERE client_id = ?", unbilled.id).await, 0);
+
+        assert!(matches!(repo.delete(&unbilled.id.to_string()).await, Err(FlowError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_archived_clients_are_left_out_by_default() {
+        let pool = new_pool().await;
+        let repo = ClientRepository::new(pool.clone());
+        let active = new_client("Acme");
+        let archived = new_client("Acme Old");
+        repo.create(&active).await.unwrap();
+        repo.create(&archived).await.unwrap();
+        invoice_for(&pool, "INV-1", archived.id).await;
+        repo.set_archived(&archived.id.to_string(), true).await.unwrap();
+
+        let listed = |archived: ArchiveFilter, search: Option<&str>| {
+            let query = ClientQuery { archived, search: search.map(String::from), ..Default::default() };
+            let repo = &repo;
+            async move { repo.query(&query).await.unwrap().items.into_iter().map(|c| c.name).collect::<Vec<_>>() }
+        };
+        assert_eq!(listed(ArchiveFilter::Active, None).await, ["Acme"]);
+        assert_eq!(listed(ArchiveFilter::Active, Some("acme")).await, ["Acme"]);
+        assert_eq!(listed(ArchiveFilter::Archived, Some("acme")).await, ["Acme Old"]);
+        assert_eq!(listed(ArchiveFilter::All, None).await, ["Acme", "Acme Old"]);
+
+        // Archiving keeps the client and its invoices
+        let stored = repo.get_by_id(&archived.id.to_string()).await.unwrap().unwrap();
+        assert!(stored.archived_at.is_some());
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM invoices WHERE client_id = ?", archived.id).await, 1);
+
+        repo.set_archived(&archived.id.to_string(), false).await.unwrap();
+        assert_eq!(listed(ArchiveFilter::Active, None).await, ["Acme", "Acme Old"]);
+    }
+
+    #[tokio::test]
+    async fn test_merge_moves_invoices_and_contacts_then_removes_the_source() {
+        let pool = new_pool().await;
+        let repo = ClientRepository::new(pool.clone());
+        let mut target = new_client("Acme Ltd");
+        target.contacts = vec![contact("Ann", Some("ann@acme.test"), true)];
+        target.tax_ids = vec![tax_id("VAT", "GB123456789")];
+        target.notes = Some("Pays late".to_string());
+        repo.create(&target).await.unwrap();
+        let mut source = new_client("ACME Limited");
+        source.email = Some("billing@acme.test".to_string());
+        source.contacts = vec![contact("ann", None, false), contact("Bob", Some("bob@acme.test"), true)];
+        source.tax_ids = vec![tax_id("VAT", "gb 123 456 789"), tax_id("EORI", "GB123456789000")];
+        source.notes = Some("Prefers email".to_string());
+        repo.create(&source).await.unwrap();
+        invoice_for(&pool, "INV-1", source.id).await;
+        invoice_for(&pool, "INV-2", source.id).await;
+        invoice_for(&pool, "INV-3", target.id).await;
+
+        let merged = repo.merge(&source.id.to_string(), &target.id.to_string()).await.unwrap();
+        assert_eq!(merged.name, "Acme Ltd");
+        assert_eq!(merged.email.as_deref(), Some("billing@acme.test"));
+        assert_eq!(merged.notes.as_deref(), Some("Pays late\n\nPrefers email"));
+        // Ann is already there; Bob joins but the target keeps its primary contact
+        let contacts: Vec<(&str, bool)> = merged.contacts.iter().map(|c| (c.name.as_str(), c.is_primary)).collect();
+        assert_eq!(contacts, [("Ann", true), ("Bob", false)]);
+        assert_eq!(merged.tax_ids, [tax_id("VAT", "GB123456789"), tax_id("EORI", "GB123456789000")]);
+
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM invoices WHERE client_id = ?", target.id).await, 3);
+        assert!(repo.get_by_id(&source.id.to_string()).await.unwrap().is_none());
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM client_contacts WHERE client_id = ?", source.id).await, 0);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM client_tax_ids WHERE client_id = ?", source.id).await, 0);
+
+        let audit = AuditLogRepository::new(pool.clone()).list(Some(&target.id.to_string()), 10).await.unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].action, "merge");
+        assert_eq!(audit[0].details["invoices_moved"], 2);
+
+        let target_id = target.id.to_string();
+        assert!(matches!(repo.merge(&target_id, &target_id).await, Err(FlowError::Validation(_))));
+        assert!(matches!(repo.merge(&source.id.to_string(), &target_id).await, Err(FlowError::NotFound(_))));
+    }
+
+    /// The client as the frontend sees it, timestamps aside.
+    fn fields(client: &Client) -> serde_json::Value {
+        let mut value = serde_json::to_value(client).unwrap();
+        value.as_object_mut().unwrap().retain(|key, _| !key.ends_with("_at"));
+        value
+    }
+
+    #[tokio::test]
+    async fn test_contacts_tax_ids_and_addresses_round_trip() {
+        let pool = new_pool().await;
+        let repo = ClientRepository::new(pool.clone());
+        let mut client = new_client("Acme GmbH");
+        client.company = Some("Acme Holding".to_string());
+        client.address = Address {
+            line1: "Hauptstr. 1".to_string(),
+            line2: None,
+            city: "Berlin".to_string(),
+            state: None,
+            postal_code: "10115".to_string(),
+            country: "DE".to_string(),
+        };
+        client.shipping_address = Some(Address {
+            line1: "Lagerweg 7".to_string(),
+            line2: Some("Tor 3".to_string()),
+            city: "Hamburg".to_string(),
+            state: Some("HH".to_string()),
+            postal_code: "20095".to_string(),
+            country: "DE".to_string(),
+        });
+        client.tax_id = Some("DE123456789".to_string());
+        client.tax_ids = vec![tax_id("EORI", "DE1234567890123"), tax_id("LEI", "5299000J2N45DDNE4Y28")];
+        client.contacts = vec![
+            ClientContact { role: Some("Accounts payable".to_string()), phone: Some("+49 30 1234".to_string()), ..contact("Petra", Some("ap@acme.test"), true) },
+            contact("Jonas", None, false),
+            contact("Lea", Some("lea@acme.test"), false),
+        ];
+        repo.create(&client).await.unwrap();
+        let stored = repo.get_by_id(&client.id.to_string()).await.unwrap().unwrap();
+        assert_eq!(fields(&stored), fields(&client));
+
+        // Updating replaces the contacts and tax IDs rather than adding to them
+        client.contacts.remove(1);
+        client.contacts[1].is_primary = true;
+        client.contacts[0].is_primary = false;
+        client.contacts.push(contact("Mia", None, false));
+        client.tax_ids = vec![tax_id("LEI", "5299000J2N45DDNE4Y28")];
+        client.shipping_address = None;
+        repo.update(&client).await.unwrap();
+        repo.update(&client).await.unwrap();
+        let stored = repo.get_by_id(&client.id.to_string()).await.unwrap().unwrap();
+        assert_eq!(fields(&stored), fields(&client));
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM client_contacts WHERE client_id = ?", client.id).await, 3);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM client_tax_ids WHERE client_id = ?", client.id).await, 1);
+        assert_eq!(repo.list_all().await.unwrap().len(), 1);
     }
 }
@@ -0,0 +1,226 @@
+use crate::database::DbPool;
+use chrono::Utc;
+use flow_core::error::FlowResult;
+use flow_core::models::{CustomFieldDefinition, CustomFieldValue};
+use flow_core::types::{CustomFieldEntity, CustomFieldType};
+use sqlx::{QueryBuilder, Sqlite, Transaction};
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+const COLUMNS: &str = "id, name, field_type, entity, options, required, default_value, sort_order, created_at, updated_at";
+
+pub struct CustomFieldRepository {
+    pool: DbPool,
+}
+
+impl CustomFieldRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Definitions in display order, optionally only those for one entity.
+    pub async fn list_definitions(&self, entity: Option<&CustomFieldEntity>) -> FlowResult<Vec<CustomFieldDefinition>> {
+        let rows = sqlx::query_as::<_, CustomFieldDefinitionRow>(&format!(
+            "SELECT {} FROM custom_field_definitions WHERE ? IS NULL OR entity = ? ORDER BY entity, sort_order, name",
+            COLUMNS
+        ))
+        .bind(entity.map(|e| e.to_string()))
+        .bind(entity.map(|e| e.to_string()))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into_definition()).collect())
+    }
+
+    pub async fn save_definition(&self, definition: &CustomFieldDefinition) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        save_definition(&mut tx, definition).await?;
+        Ok(tx.commit().await?)
+    }
+
+    /// Removes the definition along with every value recorded for it.
+    pub async fn delete_definition(&self, id: &str) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM custom_field_values WHERE field_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM custom_field_definitions WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(tx.commit().await?)
+    }
+
+    /// Values recorded on one client, invoice or item, in display order.
+    pub async fn values_for(&self, entity_id: &str) -> FlowResult<Vec<CustomFieldValue>> {
+        let rows = sqlx::query_as::<_, CustomFieldValueRow>(
+            r#"SELECT v.entity_id, v.field_id, d.name, d.field_type, v.value
+               FROM custom_field_values v JOIN custom_field_definitions d ON d.id = v.field_id
+               WHERE v.entity_id = ? ORDER BY d.sort_order, d.name"#,
+        )
+        .bind(entity_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into_value()).collect())
+    }
+
+    /// Values of each of the given records, keyed by record id.
+    pub async fn values_for_many(&self, entity_ids: &[String]) -> FlowResult<HashMap<String, Vec<CustomFieldValue>>> {
+        let mut values: HashMap<String, Vec<CustomFieldValue>> = HashMap::new();
+        if entity_ids.is_empty() {
+            return Ok(values);
+        }
+
+        let mut qb = QueryBuilder::<Sqlite>::new(
+            r#"SELECT v.entity_id, v.field_id, d.name, d.field_type, v.value
+               FROM custom_field_values v JOIN custom_field_definitions d ON d.id = v.field_id
+               WHERE v.entity_id IN ("#,
+        );
+        let mut ids = qb.separated(", ");
+        for id in entity_ids {
+            ids.push_bind(id.clone());
+        }
+        qb.push(") ORDER BY d.sort_order, d.name");
+        let rows: Vec<CustomFieldValueRow> = qb.build_query_as().fetch_all(&self.pool).await?;
+
+        for row in rows {
+            values.entry(row.entity_id.clone()).or_default().push(row.into_value());
+        }
+        Ok(values)
+    }
+
+    /// Replaces the values recorded on a record.
+    pub async fn set_values(&self, entity_id: &str, values: &[CustomFieldValue]) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        set_values(&mut tx, entity_id, values).await?;
+        Ok(tx.commit().await?)
+    }
+
+    pub async fn delete_values(&self, entity_id: &str) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        set_values(&mut tx, entity_id, &[]).await?;
+        Ok(tx.commit().await?)
+    }
+
+    /// Ids of the records of `entity` with a custom field value containing `query`
+    /// (case-insensitive). Item matches are reported as their invoice's id.
+    pub async fn search(&self, entity: &CustomFieldEntity, query: &str) -> FlowResult<Vec<String>> {
+        let pattern = format!("%{}%", query.trim().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"SELECT DISTINCT COALESCE(i.invoice_id, v.entity_id)
+               FROM custom_field_values v
+               JOIN custom_field_definitions d ON d.id = v.field_id
+               LEFT JOIN invoice_items i ON d.entity = 'InvoiceItem' AND i.id = v.entity_id
+               WHERE (d.entity = ? OR (? = 'Invoice' AND d.entity = 'InvoiceItem'))
+                 AND v.value LIKE ? ESCAPE '\'"#,
+        )
+        .bind(entity.to_string())
+        .bind(entity.to_string())
+        .bind(pattern)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+}
+
+pub(crate) async fn save_definition(tx: &mut Transaction<'_, Sqlite>, definition: &CustomFieldDefinition) -> FlowResult<()> {
+    let now = Utc::now().to_rfc3339();
+    let options = serde_json::to_string(&definition.options).unwrap_or_else(|_| "[]".to_string());
+
+    sqlx::query(
+        r#"INSERT INTO custom_field_definitions (id, name, field_type, entity, options, required, default_value, sort_order, created_at, updated_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(id) DO UPDATE SET
+               name = excluded.name, field_type = excluded.field_type, entity = excluded.entity,
+               options = excluded.options, required = excluded.required, default_value = excluded.default_value,
+               sort_order = excluded.sort_order, updated_at = excluded.updated_at"#,
+    )
+    .bind(definition.id.to_string())
+    .bind(&definition.name)
+    .bind(definition.field_type.to_string())
+    .bind(definition.entity.to_string())
+    .bind(options)
+    .bind(definition.required)
+    .bind(&definition.default_value)
+    .bind(definition.sort_order)
+    .bind(definition.created_at.to_rfc3339())
+    .bind(&now)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn set_values(tx: &mut Transaction<'_, Sqlite>, entity_id: &str, values: &[CustomFieldValue]) -> FlowResult<()> {
+    sqlx::query("DELETE FROM custom_field_values WHERE entity_id = ?")
+        .bind(entity_id)
+        .execute(&mut **tx)
+        .await?;
+    for value in values {
+        sqlx::query("INSERT INTO custom_field_values (field_id, entity_id, value) VALUES (?, ?, ?)")
+            .bind(value.field_id.to_string())
+            .bind(entity_id)
+            .bind(&value.value)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct CustomFieldDefinitionRow {
+    id: String,
+    name: String,
+    field_type: String,
+    entity: String,
+    options: String,
+    required: bool,
+    default_value: Option<String>,
+    sort_order: i32,
+    created_at: String,
+    updated_at: String,
+}
+
+impl CustomFieldDefinitionRow {
+    fn into_definition(self) -> CustomFieldDefinition {
+        CustomFieldDefinition {
+            id: Uuid::parse_str(&self.id).unwrap_or_default(),
+            name: self.name,
+            field_type: CustomFieldType::from_str(&self.field_type).unwrap_or_default(),
+            entity: CustomFieldEntity::from_str(&self.entity).unwrap_or(CustomFieldEntity::Invoice),
+            options: serde_json::from_str(&self.options).unwrap_or_default(),
+            required: self.required,
+            default_value: self.default_value,
+            sort_order: self.sort_order,
+            created_at: chrono::DateTime::parse_from_rfc3339(&self.created_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&self.updated_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CustomFieldValueRow {
+    entity_id: String,
+    field_id: String,
+    name: String,
+    field_type: String,
+    value: String,
+}
+
+impl CustomFieldValueRow {
+    fn into_value(self) -> CustomFieldValue {
+        CustomFieldValue {
+            field_id: Uuid::parse_str(&self.field_id).unwrap_or_default(),
+            name: self.name,
+            field_type: CustomFieldType::from_str(&self.field_type).unwrap_or_default(),
+            value: self.value,
+        }
+    }
+}
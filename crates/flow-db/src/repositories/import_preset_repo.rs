@@ -0,0 +1,89 @@
+use crate::database::DbPool;
+use crate::decode::RowRef;
+use crate::spreadsheet::ColumnMapping;
+use chrono::{DateTime, Utc};
+use flow_core::error::{FlowError, FlowResult};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A column mapping saved under a name, for spreadsheets exported the same way every time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreset {
+    pub id: Uuid,
+    pub name: String,
+    pub mapping: ColumnMapping,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct ImportPresetRepository {
+    pool: DbPool,
+}
+
+impl ImportPresetRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_all(&self) -> FlowResult<Vec<ImportPreset>> {
+        let rows = sqlx::query_as::<_, PresetRow>("SELECT id, name, mapping, created_at, updated_at FROM import_presets ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.into_iter().map(ImportPreset::try_from).collect()
+    }
+
+    /// Saves the mapping under `name`, replacing the preset of that name if there is one.
+    pub async fn save(&self, name: &str, mapping: &ColumnMapping) -> FlowResult<ImportPreset> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(FlowError::invalid_field("name", "Preset name is required"));
+        }
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO import_presets (id, name, mapping, created_at, updated_at) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(name) DO UPDATE SET mapping = excluded.mapping, updated_at = excluded.updated_at",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(name)
+        .bind(serde_json::to_string(mapping)?)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        let row = sqlx::query_as::<_, PresetRow>("SELECT id, name, mapping, created_at, updated_at FROM import_presets WHERE name = ?")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await?;
+        ImportPreset::try_from(row)
+    }
+
+    pub async fn delete(&self, id: &str) -> FlowResult<()> {
+        sqlx::query("DELETE FROM import_presets WHERE id = ?").bind(id).execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PresetRow {
+    id: String,
+    name: String,
+    mapping: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl TryFrom<PresetRow> for ImportPreset {
+    type Error = FlowError;
+
+    fn try_from(row: PresetRow) -> FlowResult<Self> {
+        let at = RowRef::new("import_presets", &row.id);
+        Ok(ImportPreset {
+            id: at.uuid("id", &row.id)?,
+            mapping: serde_json::from_str(&row.mapping).map_err(|_| at.invalid("mapping", &row.mapping))?,
+            created_at: at.timestamp("created_at", &row.created_at)?,
+            updated_at: at.timestamp("updated_at", &row.updated_at)?,
+            name: row.name,
+        })
+    }
+}
@@ -1,6 +1,19 @@
 use crate::database::DbPool;
+use crate::decode::{self, RowRef};
+use crate::query::{encode_cursor, fts_query, page_size, push_cursor, push_order, InvoiceQuery, Page};
 use chrono::Utc;
-use flow_core::models::{Invoice, InvoiceItem};
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::{CorruptRow, GstRegistration, Invoice, InvoiceItem, ProjectDetail};
+use crate::money::{self, MAX_SCALE};
+use crate::query::total_at_common_scale;
+use sqlx::{QueryBuilder, Sqlite, Transaction};
+
+use super::{custom_field_repo, BankAccountRepository, CustomFieldRepository};
+
+const COLUMNS: &str = "id, number, status, client_id, business_profile_id, issue_date, due_date, currency, currency_scale, subtotal_minor, tax_total_minor, discount_total_minor, total_minor, amount_paid_minor, amount_due_minor, payment_terms, notes, terms_and_conditions, irn, irn_ack_no, irn_ack_date, irn_signed_qr, bank_account_id, payment_note, developer, logo_path, qr_code_url, archived_at, created_at, updated_at";
+
+/// Item rows carry their invoice's currency scale, NULL when the invoice is missing.
+const ITEM_QUERY: &str = "SELECT it.id, it.invoice_id, it.description, it.quantity, it.unit_price_minor, it.unit_price_scale, it.amount_minor, it.tax_rate_name, it.hsn_code, it.sort_order, i.currency_scale FROM invoice_items it LEFT JOIN invoices i ON i.id = it.invoice_id";
 
 pub struct InvoiceRepository {
     pool: DbPool,
@@ -11,135 +24,332 @@ impl InvoiceRepository {
         Self { pool }
     }
 
-    pub async fn create(&self, invoice: &Invoice) -> Result<(), sqlx::Error> {
-        let id = invoice.id.to_string();
-        let client_id = invoice.client_id.to_string();
-        let bp_id = invoice.business_profile_id.to_string();
-        let status = format!("{:?}", invoice.status);
-        let currency = invoice.currency.to_string();
-        let issue_date = invoice.issue_date.to_string();
-        let due_date = invoice.due_date.to_string();
-        let subtotal = invoice.subtotal.to_string();
-        let tax_total = invoice.tax_total.to_string();
-        let discount_total = invoice.discount_total.to_string();
-        let total = invoice.total.to_string();
-        let amount_paid = invoice.amount_paid.to_string();
-        let amount_due = invoice.amount_due.to_string();
-        let payment_terms = format!("{:?}", invoice.payment_terms);
-        let now = Utc::now().to_rfc3339();
-
-        sqlx::query(
-            r#"INSERT INTO invoices (id, number, status, client_id, business_profile_id, issue_date, due_date, currency, subtotal, tax_total, discount_total, total, amount_paid, amount_due, payment_terms, notes, terms_and_conditions, created_at, updated_at)
-               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+    pub async fn create(&self, invoice: &Invoice) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        insert_invoice(&mut tx, invoice).await?;
+        Ok(tx.commit().await?)
+    }
+
+    pub async fn list_all(&self) -> FlowResult<Vec<InvoiceSummary>> {
+        let rows = sqlx::query_as::<_, InvoiceSummaryRow>(
+            "SELECT id, number, status, client_id, issue_date, due_date, currency, currency_scale, total_minor, amount_due_minor, archived_at FROM invoices ORDER BY created_at DESC",
         )
-        .bind(&id)
-        .bind(&invoice.number)
-        .bind(&status)
-        .bind(&client_id)
-        .bind(&bp_id)
-        .bind(&issue_date)
-        .bind(&due_date)
-        .bind(&currency)
-        .bind(&subtotal)
-        .bind(&tax_total)
-        .bind(&discount_total)
-        .bind(&total)
-        .bind(&amount_paid)
-        .bind(&amount_due)
-        .bind(&payment_terms)
-        .bind(&invoice.notes)
-        .bind(&invoice.terms_and_conditions)
-        .bind(&now)
-        .bind(&now)
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        // Insert items
-        for item in &invoice.items {
-            self.insert_item(item).await?;
+        rows.into_iter().map(InvoiceSummary::try_from).collect()
+    }
+
+    /// One page of invoice summaries matching `query`, in its sort order.
+    pub async fn query(&self, query: &InvoiceQuery) -> FlowResult<Page<InvoiceSummary>> {
+        let sort = query.sort.key();
+        let search = fts_query(query.search.as_deref());
+        let limit = page_size(query.limit) as usize;
+
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM invoices i WHERE 1 = 1");
+        push_filters(&mut count, query, &search)?;
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut qb = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT i.id, i.number, i.status, i.client_id, i.issue_date, i.due_date, i.currency, i.currency_scale, i.total_minor, i.amount_due_minor, i.archived_at, {} AS sort_key FROM invoices i WHERE 1 = 1",
+            sort.key
+        ));
+        push_filters(&mut qb, query, &search)?;
+        if let Some(cursor) = &query.cursor {
+            push_cursor(&mut qb, &sort, "i.id", query.direction, cursor)?;
         }
+        push_order(&mut qb, &sort, "i.id", query.direction);
+        qb.push(" LIMIT ").push_bind(limit as i64 + 1);
+        let mut rows: Vec<InvoicePageRow> = qb.build_query_as().fetch_all(&self.pool).await?;
 
-        Ok(())
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|r| encode_cursor(&r.sort_key, &r.summary.id))
+        } else {
+            None
+        };
+        Ok(Page {
+            items: rows.into_iter().map(|r| InvoiceSummary::try_from(r.summary)).collect::<Result<_, _>>()?,
+            next_cursor,
+            total,
+        })
     }
 
-    async fn insert_item(&self, item: &InvoiceItem) -> Result<(), sqlx::Error> {
-        let id = item.id.to_string();
-        let inv_id = item.invoice_id.to_string();
-        let qty = item.quantity.to_string();
-        let price = item.unit_price.to_string();
-        let amount = item.amount.to_string();
+    pub async fn count(&self) -> FlowResult<i64> {
+        Ok(sqlx::query_scalar("SELECT COUNT(*) FROM invoices").fetch_one(&self.pool).await?)
+    }
 
-        sqlx::query(
-            r#"INSERT INTO invoice_items (id, invoice_id, description, quantity, unit_price, amount, tax_rate_name, sort_order)
-               VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
-        )
-        .bind(&id)
-        .bind(&inv_id)
-        .bind(&item.description)
-        .bind(&qty)
-        .bind(&price)
-        .bind(&amount)
-        .bind(&item.tax_rate_name)
-        .bind(item.sort_order)
-        .execute(&self.pool)
-        .await?;
+    pub async fn number_exists(&self, number: &str) -> FlowResult<bool> {
+        Ok(sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM invoices WHERE number = ?)")
+            .bind(number)
+            .fetch_one(&self.pool)
+            .await?)
+    }
 
-        Ok(())
+    /// Archived invoices are left out of lists and searches by default.
+    pub async fn set_archived(&self, id: &str, archived: bool) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        set_invoice_archived(&mut tx, id, archived).await?;
+        Ok(tx.commit().await?)
     }
 
-    pub async fn list_all(&self) -> Result<Vec<InvoiceSummary>, sqlx::Error> {
-        let rows = sqlx::query_as::<_, InvoiceSummaryRow>(
-            "SELECT id, number, status, client_id, issue_date, due_date, currency, total, amount_due FROM invoices ORDER BY created_at DESC",
-        )
-        .fetch_all(&self.pool)
-        .await?;
+    pub async fn update_status(&self, id: &str, status: &str) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        update_invoice_status(&mut tx, id, status).await?;
+        Ok(tx.commit().await?)
+    }
 
-        Ok(rows.into_iter().map(|r| r.into_summary()).collect())
+    /// Stores (or clears) the IRN details returned by the GST invoice registration portal.
+    pub async fn set_gst_registration(&self, id: &str, registration: Option<&GstRegistration>) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        set_invoice_gst_registration(&mut tx, id, registration).await?;
+        Ok(tx.commit().await?)
     }
 
-    pub async fn update_status(&self, id: &str, status: &str) -> Result<(), sqlx::Error> {
-        let now = Utc::now().to_rfc3339();
-        sqlx::query("UPDATE invoices SET status = ?, updated_at = ? WHERE id = ?")
-            .bind(status)
-            .bind(now)
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+    pub async fn delete(&self, id: &str) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        delete_invoice(&mut tx, id).await?;
+        Ok(tx.commit().await?)
     }
 
-    pub async fn delete(&self, id: &str) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM invoices WHERE id = ?")
+    pub async fn get_by_id(&self, id: &str) -> FlowResult<Option<Invoice>> {
+        let inv_row = sqlx::query_as::<_, FullInvoiceRow>(&format!("SELECT {} FROM invoices WHERE id = ?", COLUMNS))
             .bind(id)
-            .execute(&self.pool)
+            .fetch_optional(&self.pool)
             .await?;
-        Ok(())
-    }
-
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<Invoice>, sqlx::Error> {
-        let inv_row = sqlx::query_as::<_, FullInvoiceRow>(
-            r#"SELECT id, number, status, client_id, business_profile_id, issue_date, due_date, currency, subtotal, tax_total, discount_total, total, amount_paid, amount_due, payment_terms, notes, terms_and_conditions, created_at, updated_at
-               FROM invoices WHERE id = ?"#,
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
 
         if let Some(row) = inv_row {
-            let item_rows = sqlx::query_as::<_, InvoiceItemRow>(
-                r#"SELECT id, invoice_id, description, quantity, unit_price, amount, tax_rate_name, sort_order
-                   FROM invoice_items WHERE invoice_id = ? ORDER BY sort_order ASC"#,
-            )
+            let item_rows = sqlx::query_as::<_, InvoiceItemRow>(&format!("{} WHERE it.invoice_id = ? ORDER BY it.sort_order ASC", ITEM_QUERY))
             .bind(id)
             .fetch_all(&self.pool)
             .await?;
 
-            let items = item_rows.into_iter().map(|r| r.into_item()).collect();
-            Ok(Some(row.into_invoice(items)))
+            let custom_fields = self.custom_fields();
+            let mut items = Vec::with_capacity(item_rows.len());
+            for item_row in item_rows {
+                let values = custom_fields.values_for(&item_row.id).await?;
+                let mut item = InvoiceItem::try_from(item_row)?;
+                item.custom_fields = values;
+                items.push(item);
+            }
+            let invoice_fields = custom_fields.values_for(id).await?;
+            let project_details = sqlx::query_as::<_, (String, String)>(
+                "SELECT label, value FROM invoice_project_details WHERE invoice_id = ? ORDER BY sort_order ASC",
+            )
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|(label, value)| ProjectDetail { label, value })
+            .collect();
+            let bank_account = match &row.bank_account_id {
+                Some(account_id) => BankAccountRepository::new(self.pool.clone()).get_by_id(account_id).await?,
+                None => None,
+            };
+            let mut invoice = Invoice::try_from(row)?;
+            invoice.items = items;
+            invoice.project_details = project_details;
+            invoice.custom_fields = invoice_fields;
+            invoice.bank_account = bank_account;
+            Ok(Some(invoice))
         } else {
             Ok(None)
         }
     }
+
+    fn custom_fields(&self) -> CustomFieldRepository {
+        CustomFieldRepository::new(self.pool.clone())
+    }
+}
+
+/// Inserts the invoice with its items, custom field values and project details.
+pub(crate) async fn insert_invoice(tx: &mut Transaction<'_, Sqlite>, invoice: &Invoice) -> FlowResult<()> {
+    let id = invoice.id.to_string();
+    let client_id = invoice.client_id.to_string();
+    let bp_id = invoice.business_profile_id.to_string();
+    let status = format!("{:?}", invoice.status);
+    let currency = invoice.currency.to_string();
+    let issue_date = invoice.issue_date.to_string();
+    let due_date = invoice.due_date.to_string();
+    let scale = invoice.currency.minor_units();
+    let subtotal = money::to_minor(invoice.subtotal, scale)?;
+    let tax_total = money::to_minor(invoice.tax_total, scale)?;
+    let discount_total = money::to_minor(invoice.discount_total, scale)?;
+    let total = money::to_minor(invoice.total, scale)?;
+    let amount_paid = money::to_minor(invoice.amount_paid, scale)?;
+    let amount_due = money::to_minor(invoice.amount_due, scale)?;
+    let payment_terms = format!("{:?}", invoice.payment_terms);
+
+    sqlx::query(
+        r#"INSERT INTO invoices (id, number, status, client_id, business_profile_id, issue_date, due_date, currency, currency_scale, subtotal_minor, tax_total_minor, discount_total_minor, total_minor, amount_paid_minor, amount_due_minor, payment_terms, notes, terms_and_conditions, bank_account_id, payment_note, developer, logo_path, qr_code_url, archived_at, created_at, updated_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(&id)
+    .bind(&invoice.number)
+    .bind(&status)
+    .bind(&client_id)
+    .bind(&bp_id)
+    .bind(&issue_date)
+    .bind(&due_date)
+    .bind(&currency)
+    .bind(scale as i64)
+    .bind(subtotal)
+    .bind(tax_total)
+    .bind(discount_total)
+    .bind(total)
+    .bind(amount_paid)
+    .bind(amount_due)
+    .bind(&payment_terms)
+    .bind(&invoice.notes)
+    .bind(&invoice.terms_and_conditions)
+    .bind(invoice.bank_account.as_ref().map(|a| a.id.to_string()))
+    .bind(&invoice.payment_note)
+    .bind(&invoice.developer)
+    .bind(&invoice.logo_path)
+    .bind(&invoice.qr_code_url)
+    .bind(invoice.archived_at.map(|dt| dt.to_rfc3339()))
+    .bind(invoice.created_at.to_rfc3339())
+    .bind(invoice.updated_at.to_rfc3339())
+    .execute(&mut **tx)
+    .await?;
+
+    // Insert items
+    for item in &invoice.items {
+        insert_item(tx, item, scale).await?;
+    }
+    custom_field_repo::set_values(tx, &id, &invoice.custom_fields).await?;
+
+    for (sort_order, detail) in invoice.project_details.iter().enumerate() {
+        sqlx::query("INSERT INTO invoice_project_details (invoice_id, label, value, sort_order) VALUES (?, ?, ?, ?)")
+            .bind(&id)
+            .bind(&detail.label)
+            .bind(&detail.value)
+            .bind(sort_order as i64)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Item amounts are stored at the invoice's currency `scale`; unit prices may be finer.
+async fn insert_item(tx: &mut Transaction<'_, Sqlite>, item: &InvoiceItem, scale: u32) -> FlowResult<()> {
+    let id = item.id.to_string();
+    let inv_id = item.invoice_id.to_string();
+    let qty = item.quantity.to_string();
+    let price_scale = money::price_scale(item.unit_price, scale);
+    let price = money::to_minor(item.unit_price, price_scale)?;
+    let amount = money::to_minor(item.amount, scale)?;
+
+    sqlx::query(
+        r#"INSERT INTO invoice_items (id, invoice_id, description, quantity, unit_price_minor, unit_price_scale, amount_minor, tax_rate_name, hsn_code, sort_order)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(&id)
+    .bind(&inv_id)
+    .bind(&item.description)
+    .bind(&qty)
+    .bind(price)
+    .bind(price_scale as i64)
+    .bind(amount)
+    .bind(&item.tax_rate_name)
+    .bind(&item.hsn_code)
+    .bind(item.sort_order)
+    .execute(&mut **tx)
+    .await?;
+
+    custom_field_repo::set_values(tx, &id, &item.custom_fields).await
+}
+
+pub(crate) async fn set_invoice_archived(tx: &mut Transaction<'_, Sqlite>, id: &str, archived: bool) -> FlowResult<()> {
+    let now = Utc::now().to_rfc3339();
+    sqlx::query("UPDATE invoices SET archived_at = ?, updated_at = ? WHERE id = ?")
+        .bind(archived.then_some(&now))
+        .bind(&now)
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn update_invoice_status(tx: &mut Transaction<'_, Sqlite>, id: &str, status: &str) -> FlowResult<()> {
+    let now = Utc::now().to_rfc3339();
+    sqlx::query("UPDATE invoices SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(now)
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn set_invoice_gst_registration(tx: &mut Transaction<'_, Sqlite>, id: &str, registration: Option<&GstRegistration>) -> FlowResult<()> {
+    let now = Utc::now().to_rfc3339();
+    sqlx::query("UPDATE invoices SET irn = ?, irn_ack_no = ?, irn_ack_date = ?, irn_signed_qr = ?, updated_at = ? WHERE id = ?")
+        .bind(registration.map(|r| r.irn.as_str()))
+        .bind(registration.map(|r| r.ack_no.as_str()))
+        .bind(registration.map(|r| r.ack_date.as_str()))
+        .bind(registration.map(|r| r.signed_qr.as_str()))
+        .bind(now)
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Deletes the invoice and the custom field values of it and its items; the items and
+/// project details go with it through their foreign keys.
+pub(crate) async fn delete_invoice(tx: &mut Transaction<'_, Sqlite>, id: &str) -> FlowResult<()> {
+    sqlx::query("DELETE FROM custom_field_values WHERE entity_id = ? OR entity_id IN (SELECT id FROM invoice_items WHERE invoice_id = ?)")
+        .bind(id)
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM invoices WHERE id = ?")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+fn push_filters(qb: &mut QueryBuilder<'_, Sqlite>, query: &InvoiceQuery, search: &Option<String>) -> FlowResult<()> {
+    qb.push(query.archived.condition("i.archived_at"));
+    if let Some(search) = search {
+        qb.push(" AND (i.id IN (SELECT id FROM invoices_fts WHERE invoices_fts MATCH ")
+            .push_bind(search.clone())
+            .push(") OR i.id IN (SELECT invoice_id FROM invoice_items_fts WHERE invoice_items_fts MATCH ")
+            .push_bind(search.clone())
+            .push(") OR i.client_id IN (SELECT id FROM clients_fts WHERE clients_fts MATCH ")
+            .push_bind(search.clone())
+            .push("))");
+    }
+    if !query.statuses.is_empty() {
+        qb.push(" AND i.status IN (");
+        let mut statuses = qb.separated(", ");
+        for status in &query.statuses {
+            statuses.push_bind(format!("{:?}", status));
+        }
+        qb.push(")");
+    }
+    if let Some(client_id) = &query.client_id {
+        qb.push(" AND i.client_id = ").push_bind(client_id.clone());
+    }
+    if let Some(currency) = &query.currency {
+        qb.push(" AND i.currency = ").push_bind(currency.to_string());
+    }
+    if let Some(from) = query.issued_from {
+        qb.push(" AND i.issue_date >= ").push_bind(from.to_string());
+    }
+    if let Some(to) = query.issued_to {
+        qb.push(" AND i.issue_date <= ").push_bind(to.to_string());
+    }
+    if let Some(min) = query.min_total {
+        qb.push(format!(" AND {} >= ", total_at_common_scale!())).push_bind(money::to_minor(min, MAX_SCALE)?);
+    }
+    if let Some(max) = query.max_total {
+        qb.push(format!(" AND {} <= ", total_at_common_scale!())).push_bind(money::to_minor(max, MAX_SCALE)?);
+    }
+    Ok(())
 }
 
 /// Lightweight invoice listing DTO
@@ -154,6 +364,17 @@ pub struct InvoiceSummary {
     pub currency: String,
     pub total: String,
     pub amount_due: String,
+    pub archived_at: Option<String>,
+}
+
+/// Invoice and item rows that cannot be read back.
+pub(crate) async fn find_corrupt_rows(pool: &DbPool) -> FlowResult<Vec<CorruptRow>> {
+    let mut corrupt = decode::find_corrupt_rows::<FullInvoiceRow, Invoice>(pool, "invoices", &format!("SELECT {} FROM invoices WHERE rowid = ?", COLUMNS)).await?;
+    corrupt.extend(
+        decode::find_corrupt_rows::<InvoiceItemRow, InvoiceItem>(pool, "invoice_items", &format!("{} WHERE it.rowid = ?", ITEM_QUERY))
+            .await?,
+    );
+    Ok(corrupt)
 }
 
 #[derive(sqlx::FromRow)]
@@ -165,23 +386,38 @@ struct InvoiceSummaryRow {
     issue_date: String,
     due_date: String,
     currency: String,
-    total: String,
-    amount_due: String,
+    currency_scale: i64,
+    total_minor: i64,
+    amount_due_minor: i64,
+    archived_at: Option<String>,
 }
 
-impl InvoiceSummaryRow {
-    fn into_summary(self) -> InvoiceSummary {
-        InvoiceSummary {
-            id: self.id,
-            number: self.number,
-            status: self.status,
-            client_id: self.client_id,
-            issue_date: self.issue_date,
-            due_date: self.due_date,
-            currency: self.currency,
-            total: self.total,
-            amount_due: self.amount_due,
-        }
+#[derive(sqlx::FromRow)]
+struct InvoicePageRow {
+    #[sqlx(flatten)]
+    summary: InvoiceSummaryRow,
+    sort_key: String,
+}
+
+impl TryFrom<InvoiceSummaryRow> for InvoiceSummary {
+    type Error = FlowError;
+
+    fn try_from(row: InvoiceSummaryRow) -> FlowResult<Self> {
+        let r = RowRef::new("invoices", &row.id);
+        let total = r.amount(row.total_minor, row.currency_scale)?.to_string();
+        let amount_due = r.amount(row.amount_due_minor, row.currency_scale)?.to_string();
+        Ok(InvoiceSummary {
+            id: row.id,
+            number: row.number,
+            status: row.status,
+            client_id: row.client_id,
+            issue_date: row.issue_date,
+            due_date: row.due_date,
+            currency: row.currency,
+            total,
+            amount_due,
+            archived_at: row.archived_at,
+        })
     }
 }
 
@@ -195,64 +431,77 @@ struct FullInvoiceRow {
     issue_date: String,
     due_date: String,
     currency: String,
-    subtotal: String,
-    tax_total: String,
-    discount_total: String,
-    total: String,
-    amount_paid: String,
-    amount_due: String,
+    currency_scale: i64,
+    subtotal_minor: i64,
+    tax_total_minor: i64,
+    discount_total_minor: i64,
+    total_minor: i64,
+    amount_paid_minor: i64,
+    amount_due_minor: i64,
     payment_terms: String,
     notes: Option<String>,
     terms_and_conditions: Option<String>,
+    irn: Option<String>,
+    irn_ack_no: Option<String>,
+    irn_ack_date: Option<String>,
+    irn_signed_qr: Option<String>,
+    bank_account_id: Option<String>,
+    payment_note: Option<String>,
+    developer: Option<String>,
+    logo_path: Option<String>,
+    qr_code_url: Option<String>,
+    archived_at: Option<String>,
     created_at: String,
     updated_at: String,
 }
 
-impl FullInvoiceRow {
-    fn into_invoice(self, items: Vec<InvoiceItem>) -> Invoice {
-        use std::str::FromStr;
-        use rust_decimal::Decimal;
-        use flow_core::types::{Currency, InvoiceStatus, PaymentTerms};
-
-        let status = match self.status.as_str() {
-            "Draft" => InvoiceStatus::Draft,
-            "Pending" => InvoiceStatus::Pending,
-            "Sent" => InvoiceStatus::Sent,
-            "Viewed" => InvoiceStatus::Viewed,
-            "Paid" => InvoiceStatus::Paid,
-            "Overdue" => InvoiceStatus::Overdue,
-            "Cancelled" => InvoiceStatus::Cancelled,
-            _ => InvoiceStatus::Draft,
-        };
+/// Items, project details, custom field values and the bank account are loaded
+/// separately and start out empty.
+impl TryFrom<FullInvoiceRow> for Invoice {
+    type Error = FlowError;
+
+    fn try_from(row: FullInvoiceRow) -> FlowResult<Self> {
+        let r = RowRef::new("invoices", &row.id);
+        let gst_registration = row.irn.map(|irn| GstRegistration {
+            irn,
+            ack_no: row.irn_ack_no.unwrap_or_default(),
+            ack_date: row.irn_ack_date.unwrap_or_default(),
+            signed_qr: row.irn_signed_qr.unwrap_or_default(),
+        });
 
-        Invoice {
-            id: uuid::Uuid::parse_str(&self.id).unwrap_or_default(),
-            number: self.number,
-            status,
-            client_id: uuid::Uuid::parse_str(&self.client_id).unwrap_or_default(),
-            business_profile_id: uuid::Uuid::parse_str(&self.business_profile_id).unwrap_or_default(),
-            issue_date: chrono::NaiveDate::parse_from_str(&self.issue_date, "%Y-%m-%d").unwrap_or_default(),
-            due_date: chrono::NaiveDate::parse_from_str(&self.due_date, "%Y-%m-%d").unwrap_or_default(),
-            currency: Currency::from_str(&self.currency).unwrap_or_default(),
-            items,
+        Ok(Invoice {
+            id: r.uuid("id", &row.id)?,
+            number: row.number,
+            status: r.parse("status", &row.status)?,
+            client_id: r.uuid("client_id", &row.client_id)?,
+            business_profile_id: r.uuid("business_profile_id", &row.business_profile_id)?,
+            issue_date: r.date("issue_date", &row.issue_date)?,
+            due_date: r.date("due_date", &row.due_date)?,
+            currency: r.parse("currency", &row.currency)?,
+            items: vec![],
             tax_rates: vec![],
             discount: None,
-            subtotal: Decimal::from_str(&self.subtotal).unwrap_or_default(),
-            tax_total: Decimal::from_str(&self.tax_total).unwrap_or_default(),
-            discount_total: Decimal::from_str(&self.discount_total).unwrap_or_default(),
-            total: Decimal::from_str(&self.total).unwrap_or_default(),
-            amount_paid: Decimal::from_str(&self.amount_paid).unwrap_or_default(),
-            amount_due: Decimal::from_str(&self.amount_due).unwrap_or_default(),
-            payment_terms: PaymentTerms::from_str(&self.payment_terms).unwrap_or_default(),
-            notes: self.notes,
-            terms_and_conditions: self.terms_and_conditions,
-            created_at: chrono::DateTime::parse_from_rfc3339(&self.created_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now()),
-            updated_at: chrono::DateTime::parse_from_rfc3339(&self.updated_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now()),
-        }
+            subtotal: r.amount(row.subtotal_minor, row.currency_scale)?,
+            tax_total: r.amount(row.tax_total_minor, row.currency_scale)?,
+            discount_total: r.amount(row.discount_total_minor, row.currency_scale)?,
+            total: r.amount(row.total_minor, row.currency_scale)?,
+            amount_paid: r.amount(row.amount_paid_minor, row.currency_scale)?,
+            amount_due: r.amount(row.amount_due_minor, row.currency_scale)?,
+            payment_terms: r.parse("payment_terms", &row.payment_terms)?,
+            notes: row.notes,
+            terms_and_conditions: row.terms_and_conditions,
+            payment_note: row.payment_note,
+            developer: row.developer,
+            logo_path: row.logo_path,
+            qr_code_url: row.qr_code_url,
+            project_details: vec![],
+            custom_fields: vec![],
+            gst_registration,
+            bank_account: None,
+            archived_at: r.optional_timestamp("archived_at", row.archived_at.as_deref())?,
+            created_at: r.timestamp("created_at", &row.created_at)?,
+            updated_at: r.timestamp("updated_at", &row.updated_at)?,
+        })
     }
 }
 
@@ -262,26 +511,240 @@ struct InvoiceItemRow {
     invoice_id: String,
     description: String,
     quantity: String,
-    unit_price: String,
-    amount: String,
+    unit_price_minor: i64,
+    unit_price_scale: i64,
+    amount_minor: i64,
     tax_rate_name: Option<String>,
+    hsn_code: Option<String>,
     sort_order: i32,
+    currency_scale: i64,
+}
+
+/// Custom field values are loaded separately and start out empty.
+impl TryFrom<InvoiceItemRow> for InvoiceItem {
+    type Error = FlowError;
+
+    fn try_from(row: InvoiceItemRow) -> FlowResult<Self> {
+        let r = RowRef::new("invoice_items", &row.id);
+        Ok(InvoiceItem {
+            id: r.uuid("id", &row.id)?,
+            invoice_id: r.uuid("invoice_id", &row.invoice_id)?,
+            description: row.description,
+            quantity: r.decimal("quantity", &row.quantity)?,
+            unit_price: r.amount(row.unit_price_minor, row.unit_price_scale)?,
+            amount: r.amount(row.amount_minor, row.currency_scale)?,
+            tax_rate_name: row.tax_rate_name,
+            hsn_code: row.hsn_code,
+            sort_order: row.sort_order,
+            custom_fields: vec![],
+        })
+    }
 }
 
-impl InvoiceItemRow {
-    fn into_item(self) -> InvoiceItem {
-        use std::str::FromStr;
-        use rust_decimal::Decimal;
-
-        InvoiceItem {
-            id: uuid::Uuid::parse_str(&self.id).unwrap_or_default(),
-            invoice_id: uuid::Uuid::parse_str(&self.invoice_id).unwrap_or_default(),
-            description: self.description,
-            quantity: Decimal::from_str(&self.quantity).unwrap_or_default(),
-            unit_price: Decimal::from_str(&self.unit_price).unwrap_or_default(),
-            amount: Decimal::from_str(&self.amount).unwrap_or_default(),
-            tax_rate_name: self.tax_rate_name,
-            sort_order: self.sort_order,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::{InvoiceSort, SortDirection};
+    use crate::repositories::{BusinessProfileRepository, ClientRepository};
+    use crate::test_support::{new_client, new_invoice, new_pool};
+    use flow_core::models::BankAccount;
+    use flow_core::types::{Currency, InvoiceStatus};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    /// Every page of `query`, following the cursors.
+    async fn all_pages(repo: &InvoiceRepository, mut query: InvoiceQuery) -> Vec<InvoiceSummary> {
+        let mut invoices = Vec::new();
+        loop {
+            let page = repo.query(&query).await.unwrap();
+            assert!(page.items.len() <= query.limit as usize);
+            invoices.extend(page.items);
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return invoices,
+            }
         }
     }
+
+    fn numbers(invoices: &[InvoiceSummary]) -> Vec<&str> {
+        invoices.iter().map(|i| i.number.as_str()).collect()
+    }
+
+    /// Acme's INV-1 (100.00 USD, paid), INV-2 (100 JPY), INV-3 (100.00 USD) and INV-4
+    /// (99.99 USD), and Globex's INV-5 (250.50 EUR) for "Hosting".
+    async fn seeded() -> (InvoiceRepository, Uuid) {
+        let pool = new_pool().await;
+        let profile_id = BusinessProfileRepository::new(pool.clone()).get_profile().await.unwrap().id;
+        let clients = ClientRepository::new(pool.clone());
+        let acme = new_client("Acme Rockets");
+        let globex = new_client("Globex");
+        clients.create(&acme).await.unwrap();
+        clients.create(&globex).await.unwrap();
+
+        let repo = InvoiceRepository::new(pool);
+        for (number, client, currency, total, status) in [
+            ("INV-1", acme.id, Currency::USD, Decimal::new(10000, 2), InvoiceStatus::Paid),
+            ("INV-2", acme.id, Currency::Custom("JPY".to_string()), Decimal::from(100), InvoiceStatus::Sent),
+            ("INV-3", acme.id, Currency::USD, Decimal::new(10000, 2), InvoiceStatus::Sent),
+            ("INV-4", acme.id, Currency::USD, Decimal::new(9999, 2), InvoiceStatus::Draft),
+            ("INV-5", globex.id, Currency::EUR, Decimal::new(25050, 2), InvoiceStatus::Sent),
+        ] {
+            let mut invoice = new_invoice(number, client, profile_id, total);
+            invoice.currency = currency;
+            invoice.status = status;
+            if number == "INV-5" {
+                invoice.items[0].description = "Hosting".to_string();
+            }
+            repo.create(&invoice).await.unwrap();
+        }
+        (repo, acme.id)
+    }
+
+    #[tokio::test]
+    async fn test_amounts_are_stored_exactly_or_refused() {
+        let pool = new_pool().await;
+        let profile_id = BusinessProfileRepository::new(pool.clone()).get_profile().await.unwrap().id;
+        let client = new_client("Acme");
+        ClientRepository::new(pool.clone()).create(&client).await.unwrap();
+        let repo = InvoiceRepository::new(pool.clone());
+
+        // A unit price may be finer than a cent; the amounts may not
+        let mut invoice = new_invoice("INV-1", client.id, profile_id, Decimal::new(100, 2));
+        invoice.items[0].quantity = Decimal::from(8);
+        invoice.items[0].unit_price = Decimal::new(125, 3);
+        repo.create(&invoice).await.unwrap();
+        let stored = repo.get_by_id(&invoice.id.to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.items[0].unit_price.to_string(), "0.125");
+        assert_eq!(stored.total.to_string(), "1.00");
+
+        // Whole prices still read back at the currency's scale
+        let invoice = new_invoice("INV-2", client.id, profile_id, Decimal::TEN);
+        repo.create(&invoice).await.unwrap();
+        let stored = repo.get_by_id(&invoice.id.to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.items[0].unit_price.to_string(), "10.00");
+
+        for (number, total, unit_price) in [("INV-3", Decimal::new(125, 3), Decimal::ONE), ("INV-4", Decimal::ONE, Decimal::new(1, 5))] {
+            let mut invoice = new_invoice(number, client.id, profile_id, total);
+            invoice.items[0].unit_price = unit_price;
+            let err = repo.create(&invoice).await.unwrap_err();
+            assert!(matches!(err, FlowError::Validation(ref message) if message.contains("decimal places")), "{}", err);
+            assert!(!repo.number_exists(number).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pages_through_tied_totals_without_gaps() {
+        let (repo, _) = seeded().await;
+
+        // 100 JPY, 100.00 USD and 100.00 USD tie, and come back in id order
+        let mut tied: Vec<InvoiceSummary> = repo.list_all().await.unwrap().into_iter().filter(|i| ["INV-1", "INV-2", "INV-3"].contains(&i.number.as_str())).collect();
+        tied.sort_by(|a, b| a.id.cmp(&b.id));
+        let tied = numbers(&tied);
+
+        for limit in [1, 2, 3] {
+            let asc = all_pages(&repo, InvoiceQuery { sort: InvoiceSort::Total, direction: SortDirection::Asc, limit, ..Default::default() }).await;
+            assert_eq!(numbers(&asc), [&["INV-4"][..], &tied, &["INV-5"]].concat(), "limit {}", limit);
+
+            let desc = all_pages(&repo, InvoiceQuery { sort: InvoiceSort::Total, limit, ..Default::default() }).await;
+            let mut reversed = numbers(&asc);
+            reversed.reverse();
+            assert_eq!(numbers(&desc), reversed, "limit {}", limit);
+        }
+
+        let by_number = all_pages(&repo, InvoiceQuery { sort: InvoiceSort::Number, direction: SortDirection::Asc, limit: 2, ..Default::default() }).await;
+        assert_eq!(numbers(&by_number), ["INV-1", "INV-2", "INV-3", "INV-4", "INV-5"]);
+
+        let cursor = Some("not a number|x".to_string());
+        assert!(matches!(repo.query(&InvoiceQuery { sort: InvoiceSort::Total, cursor, ..Default::default() }).await, Err(FlowError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_filters() {
+        let (repo, acme) = seeded().await;
+        let find = |query: InvoiceQuery| {
+            let repo = &repo;
+            async move {
+                let page = repo.query(&InvoiceQuery { sort: InvoiceSort::Number, direction: SortDirection::Asc, ..query }).await.unwrap();
+                assert_eq!(page.total, page.items.len() as i64);
+                page.items.into_iter().map(|i| i.number).collect::<Vec<_>>()
+            }
+        };
+
+        // Both ends of the total range are inclusive, and compare across currencies
+        let range = |min: i64, max: i64| InvoiceQuery { min_total: Some(Decimal::new(min, 2)), max_total: Some(Decimal::new(max, 2)), ..Default::default() };
+        assert_eq!(find(range(10000, 10000)).await, ["INV-1", "INV-2", "INV-3"]);
+        assert_eq!(find(range(9999, 10000)).await, ["INV-1", "INV-2", "INV-3", "INV-4"]);
+        assert_eq!(find(range(10001, 25050)).await, ["INV-5"]);
+        assert_eq!(find(InvoiceQuery { min_total: Some(Decimal::new(25051, 2)), ..Default::default() }).await, Vec::<String>::new());
+        assert_eq!(find(InvoiceQuery { max_total: Some(Decimal::new(9999, 2)), ..Default::default() }).await, ["INV-4"]);
+
+        assert_eq!(find(InvoiceQuery { statuses: vec![InvoiceStatus::Paid, InvoiceStatus::Draft], ..Default::default() }).await, ["INV-1", "INV-4"]);
+        assert_eq!(find(InvoiceQuery { currency: Some(Currency::USD), ..Default::default() }).await, ["INV-1", "INV-3", "INV-4"]);
+        assert_eq!(find(InvoiceQuery { client_id: Some(acme.to_string()), statuses: vec![InvoiceStatus::Sent], ..Default::default() }).await, ["INV-2", "INV-3"]);
+
+        // Search covers the number, item descriptions and the client
+        let search = |text: &str| InvoiceQuery { search: Some(text.to_string()), ..Default::default() };
+        assert_eq!(find(search("host")).await, ["INV-5"]);
+        assert_eq!(find(search("glob")).await, ["INV-5"]);
+        assert_eq!(find(search("rockets")).await, ["INV-1", "INV-2", "INV-3", "INV-4"]);
+        assert_eq!(find(search("\"inv-3")).await, ["INV-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_presentation_details_round_trip() {
+        let pool = new_pool().await;
+        let profile_id = BusinessProfileRepository::new(pool.clone()).get_profile().await.unwrap().id;
+        let client = new_client("Acme");
+        ClientRepository::new(pool.clone()).create(&client).await.unwrap();
+        let account = BankAccount {
+            id: Uuid::new_v4(),
+            label: "EUR business account".to_string(),
+            account_holder: "Me".to_string(),
+            bank_name: "Bank".to_string(),
+            branch: None,
+            account_number: None,
+            iban: Some("DE89370400440532013000".to_string()),
+            swift_bic: Some("COBADEFFXXX".to_string()),
+            ifsc_code: None,
+            routing_number: None,
+            upi_id: None,
+            currency: Some(Currency::EUR),
+            is_default: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        BankAccountRepository::new(pool.clone()).save(&account).await.unwrap();
+
+        let mut invoice = new_invoice("INV-1", client.id, profile_id, Decimal::TEN);
+        invoice.notes = Some("Thanks for your business".to_string());
+        invoice.payment_note = Some("Pay within 14 days".to_string());
+        invoice.developer = Some("Dev Co".to_string());
+        invoice.logo_path = Some("asset:logo".to_string());
+        invoice.qr_code_url = Some("upi://pay?pa=me@bank".to_string());
+        invoice.project_details = vec![
+            ProjectDetail { label: "Project".to_string(), value: "Website".to_string() },
+            ProjectDetail { label: "PO".to_string(), value: "4500012345".to_string() },
+        ];
+        invoice.bank_account = Some(account.clone());
+        let repo = InvoiceRepository::new(pool.clone());
+        repo.create(&invoice).await.unwrap();
+
+        let stored = repo.get_by_id(&invoice.id.to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.notes, invoice.notes);
+        assert_eq!(stored.payment_note, invoice.payment_note);
+        assert_eq!(stored.developer, invoice.developer);
+        assert_eq!(stored.logo_path, invoice.logo_path);
+        assert_eq!(stored.qr_code_url, invoice.qr_code_url);
+        let details: Vec<(&str, &str)> = stored.project_details.iter().map(|d| (d.label.as_str(), d.value.as_str())).collect();
+        assert_eq!(details, [("Project", "Website"), ("PO", "4500012345")]);
+        let bank = stored.bank_account.unwrap();
+        assert_eq!((bank.id, bank.iban), (account.id, account.iban));
+
+        // Unset details stay unset rather than coming back empty
+        let plain = new_invoice("INV-2", client.id, profile_id, Decimal::TEN);
+        repo.create(&plain).await.unwrap();
+        let stored = repo.get_by_id(&plain.id.to_string()).await.unwrap().unwrap();
+        assert_eq!((stored.notes, stored.payment_note, stored.developer, stored.logo_path, stored.qr_code_url), (None, None, None, None, None));
+        assert!(stored.project_details.is_empty() && stored.bank_account.is_none());
+    }
 }
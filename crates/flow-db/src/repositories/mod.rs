@@ -1,7 +1,21 @@
 pub mod client_repo;
 pub mod invoice_repo;
 pub mod business_profile_repo;
+pub mod bank_account_repo;
+pub mod custom_field_repo;
+pub mod audit_repo;
+pub mod asset_repo;
+pub mod app_lock_repo;
+pub mod template_repo;
+pub mod import_preset_repo;
 
 pub use client_repo::ClientRepository;
 pub use invoice_repo::InvoiceRepository;
 pub use business_profile_repo::BusinessProfileRepository;
+pub use bank_account_repo::BankAccountRepository;
+pub use custom_field_repo::CustomFieldRepository;
+pub use audit_repo::AuditLogRepository;
+pub use asset_repo::AssetRepository;
+pub use app_lock_repo::{AppLockRepository, AppLockSettings};
+pub use template_repo::TemplateRepository;
+pub use import_preset_repo::{ImportPreset, ImportPresetRepository};
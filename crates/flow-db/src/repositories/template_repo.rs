@@ -0,0 +1,87 @@
+use crate::database::DbPool;
+use crate::decode::RowRef;
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::InvoiceTemplate;
+use sqlx::{Sqlite, Transaction};
+
+const COLUMNS: &str = "id, name, description, layout_json, is_default, created_at, updated_at";
+
+/// Saved invoice layouts.
+pub struct TemplateRepository {
+    pool: DbPool,
+}
+
+impl TemplateRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_all(&self) -> FlowResult<Vec<InvoiceTemplate>> {
+        let rows = sqlx::query_as::<_, TemplateRow>(&format!("SELECT {} FROM invoice_templates ORDER BY is_default DESC, name", COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        rows.into_iter().map(InvoiceTemplate::try_from).collect()
+    }
+
+    /// Inserts or updates the template. Marking it default clears the flag on the others.
+    pub async fn save(&self, template: &InvoiceTemplate) -> FlowResult<()> {
+        let mut tx = self.pool.begin().await?;
+        save_template(&mut tx, template).await?;
+        Ok(tx.commit().await?)
+    }
+}
+
+pub(crate) async fn save_template(tx: &mut Transaction<'_, Sqlite>, template: &InvoiceTemplate) -> FlowResult<()> {
+    let id = template.id.to_string();
+    if template.is_default {
+        sqlx::query("UPDATE invoice_templates SET is_default = 0 WHERE id != ?")
+            .bind(&id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    sqlx::query(
+        r#"INSERT INTO invoice_templates (id, name, description, layout_json, is_default, created_at, updated_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(id) DO UPDATE SET
+               name = excluded.name, description = excluded.description, layout_json = excluded.layout_json,
+               is_default = excluded.is_default, updated_at = excluded.updated_at"#,
+    )
+    .bind(&id)
+    .bind(&template.name)
+    .bind(&template.description)
+    .bind(&template.layout_json)
+    .bind(template.is_default)
+    .bind(template.created_at.to_rfc3339())
+    .bind(template.updated_at.to_rfc3339())
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct TemplateRow {
+    id: String,
+    name: String,
+    description: Option<String>,
+    layout_json: String,
+    is_default: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+impl TryFrom<TemplateRow> for InvoiceTemplate {
+    type Error = FlowError;
+
+    fn try_from(row: TemplateRow) -> FlowResult<Self> {
+        let at = RowRef::new("invoice_templates", &row.id);
+        Ok(InvoiceTemplate {
+            id: at.uuid("id", &row.id)?,
+            created_at: at.timestamp("created_at", &row.created_at)?,
+            updated_at: at.timestamp("updated_at", &row.updated_at)?,
+            name: row.name,
+            description: row.description,
+            layout_json: row.layout_json,
+            is_default: row.is_default,
+        })
+    }
+}
@@ -0,0 +1,763 @@
+//! Importing clients and invoices from CSV and Excel spreadsheets.
+//!
+//! [`read_sheet`] reads a header row and the rows below it as text. A [`ColumnMapping`] names
+//! the column holding each field; [`plan_import`] checks every row against it and builds the
+//! records without writing anything, reporting each problem with its spreadsheet line.
+//! [`commit_import`] writes a plan without errors in one unit of work. Mappings are saved as
+//! presets by [`crate::repositories::ImportPresetRepository`].
+
+use crate::database::DbPool;
+use crate::money;
+use crate::repositories::{BankAccountRepository, BusinessProfileRepository, ClientRepository, InvoiceRepository};
+use crate::unit_of_work::UnitOfWork;
+use calamine::{open_workbook_auto, Data, Reader};
+use chrono::{Duration, NaiveDate, Utc};
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::{Address, Client, Invoice, InvoiceItem};
+use flow_core::types::{Currency, InvoiceStatus, PaymentTerms};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Statuses an imported invoice can have, matched without regard to case.
+const STATUSES: [&str; 7] = ["Draft", "Pending", "Sent", "Viewed", "Paid", "Overdue", "Cancelled"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportTarget {
+    /// One client per row.
+    #[default]
+    Clients,
+    /// One line item per row. Rows with the same invoice number make up one invoice, and
+    /// clients that are not found by name are created.
+    Invoices,
+}
+
+/// A field a spreadsheet column can be mapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportField {
+    ClientName,
+    ClientEmail,
+    ClientPhone,
+    ClientCompany,
+    ClientAddressLine1,
+    ClientAddressLine2,
+    ClientCity,
+    ClientState,
+    ClientPostalCode,
+    ClientCountry,
+    ClientTaxId,
+    ClientNotes,
+    InvoiceNumber,
+    InvoiceStatus,
+    IssueDate,
+    DueDate,
+    Currency,
+    AmountPaid,
+    InvoiceNotes,
+    ItemDescription,
+    ItemQuantity,
+    ItemUnitPrice,
+    ItemTaxRateName,
+    ItemHsnCode,
+}
+
+impl ImportField {
+    pub const ALL: [ImportField; 24] = [
+        ImportField::ClientName,
+        ImportField::ClientEmail,
+        ImportField::ClientPhone,
+        ImportField::ClientCompany,
+        ImportField::ClientAddressLine1,
+        ImportField::ClientAddressLine2,
+        ImportField::ClientCity,
+        ImportField::ClientState,
+        ImportField::ClientPostalCode,
+        ImportField::ClientCountry,
+        ImportField::ClientTaxId,
+        ImportField::ClientNotes,
+        ImportField::InvoiceNumber,
+        ImportField::InvoiceStatus,
+        ImportField::IssueDate,
+        ImportField::DueDate,
+        ImportField::Currency,
+        ImportField::AmountPaid,
+        ImportField::InvoiceNotes,
+        ImportField::ItemDescription,
+        ImportField::ItemQuantity,
+        ImportField::ItemUnitPrice,
+        ImportField::ItemTaxRateName,
+        ImportField::ItemHsnCode,
+    ];
+
+    /// Name shown in the mapping and in error messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportField::ClientName => "Client name",
+            ImportField::ClientEmail => "Client email",
+            ImportField::ClientPhone => "Client phone",
+            ImportField::ClientCompany => "Client company",
+            ImportField::ClientAddressLine1 => "Client address line 1",
+            ImportField::ClientAddressLine2 => "Client address line 2",
+            ImportField::ClientCity => "Client city",
+            ImportField::ClientState => "Client state",
+            ImportField::ClientPostalCode => "Client postal code",
+            ImportField::ClientCountry => "Client country",
+            ImportField::ClientTaxId => "Client tax ID",
+            ImportField::ClientNotes => "Client notes",
+            ImportField::InvoiceNumber => "Invoice number",
+            ImportField::InvoiceStatus => "Invoice status",
+            ImportField::IssueDate => "Issue date",
+            ImportField::DueDate => "Due date",
+            ImportField::Currency => "Currency",
+            ImportField::AmountPaid => "Amount paid",
+            ImportField::InvoiceNotes => "Invoice notes",
+            ImportField::ItemDescription => "Item description",
+            ImportField::ItemQuantity => "Item quantity",
+            ImportField::ItemUnitPrice => "Item unit price",
+            ImportField::ItemTaxRateName => "Item tax rate",
+            ImportField::ItemHsnCode => "Item HSN/SAC code",
+        }
+    }
+
+    /// Fields that can be mapped when importing `target`.
+    pub fn fields_for(target: ImportTarget) -> impl Iterator<Item = ImportField> {
+        ImportField::ALL.into_iter().filter(move |field| target == ImportTarget::Invoices || field.label().starts_with("Client "))
+    }
+
+    /// Fields a mapping for `target` cannot leave out.
+    pub fn required(target: ImportTarget) -> &'static [ImportField] {
+        match target {
+            ImportTarget::Clients => &[ImportField::ClientName],
+            ImportTarget::Invoices => &[
+                ImportField::InvoiceNumber,
+                ImportField::ClientName,
+                ImportField::IssueDate,
+                ImportField::ItemDescription,
+                ImportField::ItemUnitPrice,
+            ],
+        }
+    }
+}
+
+/// How dates that are text are written. Dates Excel stores as dates, and ISO dates, are read
+/// under every format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateFormat {
+    /// 2024-03-31
+    #[default]
+    Iso,
+    /// 31/03/2024, also with `.` or `-`
+    DayMonthYear,
+    /// 03/31/2024, also with `.` or `-`
+    MonthDayYear,
+}
+
+impl DateFormat {
+    fn parse(self, value: &str) -> Option<NaiveDate> {
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(date);
+        }
+        let pattern = match self {
+            DateFormat::Iso => return None,
+            DateFormat::DayMonthYear => "%d/%m/%Y",
+            DateFormat::MonthDayYear => "%m/%d/%Y",
+        };
+        NaiveDate::parse_from_str(&value.replace(['.', '-'], "/"), pattern).ok()
+    }
+
+    fn example(self) -> &'static str {
+        match self {
+            DateFormat::Iso => "2024-03-31",
+            DateFormat::DayMonthYear => "31/03/2024",
+            DateFormat::MonthDayYear => "03/31/2024",
+        }
+    }
+}
+
+/// Which spreadsheet column holds which field.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub target: ImportTarget,
+    /// Header of the column holding each mapped field.
+    pub columns: BTreeMap<ImportField, String>,
+    #[serde(default)]
+    pub date_format: DateFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SheetRow {
+    /// Line in the spreadsheet, counting from 1.
+    pub line: usize,
+    pub cells: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sheet {
+    /// Line of the header row; blank lines above it are skipped.
+    pub header_line: usize,
+    pub headers: Vec<String>,
+    /// Rows below the header, without blank ones.
+    pub rows: Vec<SheetRow>,
+}
+
+/// A problem with one spreadsheet line, or with the mapping when it is the header line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowError {
+    pub line: usize,
+    pub field: Option<ImportField>,
+    pub message: String,
+}
+
+/// The records an import would write. Rows with errors are left out of it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportPlan {
+    /// Clients the import creates.
+    pub clients: Vec<Client>,
+    pub invoices: Vec<Invoice>,
+    pub errors: Vec<RowError>,
+}
+
+/// Reads a `.csv` file, or the first worksheet of an Excel or OpenDocument spreadsheet.
+pub fn read_sheet(path: &Path) -> FlowResult<Sheet> {
+    let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let lines = if is_csv { read_csv(path)? } else { read_workbook(path)? };
+    let mut lines = lines.into_iter().filter(|(_, cells)| cells.iter().any(|cell| !cell.is_empty()));
+    let (header_line, headers) = lines.next().ok_or_else(|| FlowError::Validation("The spreadsheet is empty".to_string()))?;
+    Ok(Sheet {
+        header_line,
+        headers: headers.into_iter().map(|header| header.trim_start_matches('\u{feff}').to_string()).collect(),
+        rows: lines.map(|(line, cells)| SheetRow { line, cells }).collect(),
+    })
+}
+
+fn read_csv(path: &Path) -> FlowResult<Vec<(usize, Vec<String>)>> {
+    let unreadable = |e: csv::Error| FlowError::Validation(format!("Could not read the CSV file: {}", e));
+    let content = std::fs::read(path)?;
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(content.as_slice());
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(unreadable)?;
+            // Counted here because the reader does not count the blank lines it skips, and
+            // a record's position is where such lines before it start
+            let mut start = record.position().map_or(0, |position| position.byte() as usize);
+            while matches!(content.get(start), Some(b'\r' | b'\n')) {
+                start += 1;
+            }
+            let line = 1 + content[..start].iter().filter(|&&byte| byte == b'\n').count();
+            Ok((line, record.iter().map(|cell| cell.trim().to_string()).collect()))
+        })
+        .collect()
+}
+
+fn read_workbook(path: &Path) -> FlowResult<Vec<(usize, Vec<String>)>> {
+    let unreadable = |e: calamine::Error| FlowError::Validation(format!("Could not read the spreadsheet: {}", e));
+    let mut workbook = open_workbook_auto(path).map_err(unreadable)?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| FlowError::Validation("The spreadsheet has no worksheets".to_string()))?
+        .map_err(unreadable)?;
+    let first_line = range.start().map_or(1, |(row, _)| row as usize + 1);
+    Ok(range.rows().enumerate().map(|(i, row)| (first_line + i, row.iter().map(cell_text).collect())).collect())
+}
+
+/// Dates come out as ISO dates, whatever their display format.
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(value) => value.as_datetime().map_or_else(|| value.to_string(), |dt| dt.date().to_string()),
+        Data::DateTimeIso(value) => value.split('T').next().unwrap_or_default().to_string(),
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+/// Maps every column whose header names a field, ignoring case, spaces and punctuation.
+/// Client fields also match without the "Client" prefix when importing clients.
+pub fn guess_mapping(headers: &[String], target: ImportTarget) -> ColumnMapping {
+    let normalize = |s: &str| s.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_ascii_lowercase();
+    let mut columns = BTreeMap::new();
+    for field in ImportField::fields_for(target) {
+        let mut names = vec![normalize(field.label())];
+        if target == ImportTarget::Clients {
+            names.push(normalize(field.label().trim_start_matches("Client ")));
+        }
+        if let Some(header) = headers.iter().find(|header| names.contains(&normalize(header))) {
+            columns.insert(field, header.clone());
+        }
+    }
+    ColumnMapping { target, columns, date_format: DateFormat::default() }
+}
+
+/// Checks every row and builds the records the import would write. Nothing is written.
+pub async fn plan_import(pool: &DbPool, sheet: &Sheet, mapping: &ColumnMapping) -> FlowResult<ImportPlan> {
+    let mut plan = ImportPlan::default();
+    let columns = match resolve_columns(sheet, mapping) {
+        Ok(columns) => columns,
+        Err(errors) => {
+            plan.errors = errors;
+            return Ok(plan);
+        }
+    };
+    let clients: HashMap<String, Uuid> = ClientRepository::new(pool.clone())
+        .list_all()
+        .await?
+        .into_iter()
+        .map(|client| (client.name.to_lowercase(), client.id))
+        .collect();
+    match mapping.target {
+        ImportTarget::Clients => plan_clients(sheet, &columns, &clients, &mut plan),
+        ImportTarget::Invoices => plan_invoices(pool, sheet, mapping, &columns, &clients, &mut plan).await?,
+    }
+    Ok(plan)
+}
+
+/// Writes the plan's clients and invoices together or not at all. A plan with errors is
+/// refused.
+pub async fn commit_import(pool: &DbPool, plan: &ImportPlan) -> FlowResult<()> {
+    if let Some(error) = plan.errors.first() {
+        return Err(FlowError::Validation(format!("Line {}: {}", error.line, error.message)));
+    }
+    let mut uow = UnitOfWork::begin(pool).await?;
+    for client in &plan.clients {
+        uow.create_client(client).await?;
+    }
+    for invoice in &plan.invoices {
+        uow.create_invoice(invoice).await?;
+    }
+    uow.commit().await
+}
+
+/// The column index of each mapped field, or the mapping's problems.
+fn resolve_columns(sheet: &Sheet, mapping: &ColumnMapping) -> Result<HashMap<ImportField, usize>, Vec<RowError>> {
+    let header_error = |field: ImportField, message: String| RowError { line: sheet.header_line, field: Some(field), message };
+    let mut errors: Vec<RowError> = ImportField::required(mapping.target)
+        .iter()
+        .filter(|field| !mapping.columns.contains_key(field))
+        .map(|field| header_error(*field, format!("{} is not mapped to a column", field.label())))
+        .collect();
+
+    let mut columns = HashMap::new();
+    for field in ImportField::fields_for(mapping.target) {
+        let Some(header) = mapping.columns.get(&field) else { continue };
+        match sheet.headers.iter().position(|h| h == header) {
+            Some(index) => {
+                columns.insert(field, index);
+            }
+            None => errors.push(header_error(field, format!("There is no column headed {:?}", header))),
+        }
+    }
+    if errors.is_empty() {
+        Ok(columns)
+    } else {
+        Err(errors)
+    }
+}
+
+fn plan_clients(sheet: &Sheet, columns: &HashMap<ImportField, usize>, existing: &HashMap<String, Uuid>, plan: &mut ImportPlan) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for sheet_row in &sheet.rows {
+        let mut row = Row::new(sheet_row, columns);
+        let client = row.client();
+        let key = client.name.to_lowercase();
+        if existing.contains_key(&key) {
+            row.fail(ImportField::ClientName, format!("A client named {:?} already exists", client.name));
+        } else if let Some(line) = seen.get(&key) {
+            row.fail(ImportField::ClientName, format!("{:?} is already on line {}", client.name, line));
+        }
+        if row.errors.is_empty() {
+            seen.insert(key, row.line);
+            plan.clients.push(client);
+        }
+        plan.errors.append(&mut row.errors);
+    }
+}
+
+async fn plan_invoices(
+    pool: &DbPool,
+    sheet: &Sheet,
+    mapping: &ColumnMapping,
+    columns: &HashMap<ImportField, usize>,
+    existing_clients: &HashMap<String, Uuid>,
+    plan: &mut ImportPlan,
+) -> FlowResult<()> {
+    let profile = BusinessProfileRepository::new(pool.clone()).get_profile().await?;
+    let invoice_repo = InvoiceRepository::new(pool.clone());
+    let bank_accounts = BankAccountRepository::new(pool.clone());
+    let terms_days = payment_terms_days(&profile.default_payment_terms);
+
+    let mut new_clients: HashMap<String, Uuid> = HashMap::new();
+    let mut invoices: Vec<Invoice> = Vec::new();
+    // Index in `invoices` and first line of each invoice number
+    let mut by_number: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut failed: HashSet<String> = HashSet::new();
+
+    for sheet_row in &sheet.rows {
+        let mut row = Row::new(sheet_row, columns);
+        let number = row.required(ImportField::InvoiceNumber);
+        let index = match by_number.get(&number) {
+            Some(&(index, _)) => index,
+            None => {
+                if !number.is_empty() && invoice_repo.number_exists(&number).await? {
+                    row.fail(ImportField::InvoiceNumber, format!("Invoice {} already exists", number));
+                }
+                let client_name = row.required(ImportField::ClientName);
+                let key = client_name.to_lowercase();
+                let client_id = match existing_clients.get(&key).or_else(|| new_clients.get(&key)) {
+                    Some(id) => *id,
+                    None if client_name.is_empty() => Uuid::nil(),
+                    None => {
+                        let client = row.client();
+                        new_clients.insert(key, client.id);
+                        let id = client.id;
+                        plan.clients.push(client);
+                        id
+                    }
+                };
+                let issue_date = row.required_date(ImportField::IssueDate, mapping.date_format);
+                let due_date = row
+                    .date(ImportField::DueDate, mapping.date_format)
+                    .unwrap_or_else(|| issue_date + Duration::days(terms_days));
+                let currency = row.currency().unwrap_or_else(|| profile.default_currency.clone());
+                let status = row.status();
+                let amount_paid = row.decimal(ImportField::AmountPaid).unwrap_or_default();
+                if amount_paid < Decimal::ZERO {
+                    row.fail(ImportField::AmountPaid, "Amount paid cannot be negative");
+                }
+                if amount_paid.normalize().scale() > currency.minor_units() {
+                    row.fail(ImportField::AmountPaid, format!("{} amounts have at most {} decimal places", currency, currency.minor_units()));
+                }
+                let bank_account = bank_accounts.default_for_currency(&currency).await?;
+
+                invoices.push(Invoice {
+                    id: Uuid::new_v4(),
+                    number: number.clone(),
+                    status,
+                    client_id,
+                    business_profile_id: profile.id,
+                    issue_date,
+                    due_date,
+                    currency,
+                    items: vec![],
+                    tax_rates: vec![],
+                    discount: None,
+                    subtotal: Decimal::ZERO,
+                    tax_total: Decimal::ZERO,
+                    discount_total: Decimal::ZERO,
+                    total: Decimal::ZERO,
+                    amount_paid,
+                    amount_due: Decimal::ZERO,
+                    payment_terms: profile.default_payment_terms.clone(),
+                    notes: row.text(ImportField::InvoiceNotes),
+                    terms_and_conditions: None,
+                    payment_note: None,
+                    developer: None,
+                    logo_path: None,
+                    qr_code_url: None,
+                    project_details: vec![],
+                    custom_fields: vec![],
+                    gst_registration: None,
+                    bank_account,
+                    archived_at: None,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                });
+                by_number.insert(number.clone(), (invoices.len() - 1, row.line));
+                invoices.len() - 1
+            }
+        };
+
+        let invoice = &mut invoices[index];
+        let description = row.required(ImportField::ItemDescription);
+        let quantity = row.decimal(ImportField::ItemQuantity).unwrap_or(Decimal::ONE);
+        let unit_price = row.required_decimal(ImportField::ItemUnitPrice);
+        if unit_price.normalize().scale() > money::MAX_SCALE {
+            row.fail(ImportField::ItemUnitPrice, format!("Unit prices have at most {} decimal places", money::MAX_SCALE));
+        }
+        let amount = quantity.checked_mul(unit_price).unwrap_or_else(|| {
+            row.fail(ImportField::ItemUnitPrice, "The item amount is too large");
+            Decimal::ZERO
+        });
+        invoice.items.push(InvoiceItem {
+            id: Uuid::new_v4(),
+            invoice_id: invoice.id,
+            description,
+            quantity,
+            unit_price,
+            amount: amount.round_dp(invoice.currency.minor_units()),
+            tax_rate_name: row.text(ImportField::ItemTaxRateName),
+            hsn_code: row.text(ImportField::ItemHsnCode),
+            sort_order: invoice.items.len() as i32,
+            custom_fields: vec![],
+        });
+
+        if !row.errors.is_empty() {
+            failed.insert(number);
+            plan.errors.append(&mut row.errors);
+        }
+    }
+
+    for invoice in &mut invoices {
+        invoice.subtotal = invoice.items.iter().map(|item| item.amount).sum();
+        invoice.total = invoice.subtotal;
+        invoice.amount_due = invoice.total - invoice.amount_paid;
+        if invoice.amount_paid > invoice.total {
+            let line = by_number[&invoice.number].1;
+            plan.errors.push(RowError {
+                line,
+                field: Some(ImportField::AmountPaid),
+                message: format!("Amount paid {} is more than the invoice total {}", invoice.amount_paid, invoice.total),
+            });
+            failed.insert(invoice.number.clone());
+        }
+    }
+    plan.errors.sort_by_key(|error| error.line);
+    plan.invoices = invoices.into_iter().filter(|invoice| !failed.contains(&invoice.number)).collect();
+    // Clients only the failed invoices would have created are left out
+    let invoices = &plan.invoices;
+    plan.clients.retain(|client| invoices.iter().any(|invoice| invoice.client_id == client.id));
+    Ok(())
+}
+
+fn payment_terms_days(terms: &PaymentTerms) -> i64 {
+    match terms {
+        PaymentTerms::DueOnReceipt => 0,
+        PaymentTerms::Net15 => 15,
+        PaymentTerms::Net30 => 30,
+        PaymentTerms::Net60 => 60,
+        PaymentTerms::Net90 => 90,
+        PaymentTerms::Custom { days, .. } => i64::from(*days),
+    }
+}
+
+/// One spreadsheet row read through the mapping, collecting its problems.
+struct Row<'a> {
+    line: usize,
+    cells: &'a [String],
+    columns: &'a HashMap<ImportField, usize>,
+    errors: Vec<RowError>,
+}
+
+impl<'a> Row<'a> {
+    fn new(row: &'a SheetRow, columns: &'a HashMap<ImportField, usize>) -> Self {
+        Row { line: row.line, cells: &row.cells, columns, errors: Vec::new() }
+    }
+
+    fn fail(&mut self, field: ImportField, message: impl Into<String>) {
+        self.errors.push(RowError { line: self.line, field: Some(field), message: message.into() });
+    }
+
+    /// The trimmed value of `field`; `None` when it is not mapped or the cell is blank.
+    fn text(&self, field: ImportField) -> Option<String> {
+        let index = self.columns.get(&field)?;
+        let value = self.cells.get(*index)?.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    /// Records that the required `field` is empty, if it is.
+    fn missing(&mut self, field: ImportField) -> bool {
+        let missing = self.text(field).is_none();
+        if missing {
+            self.fail(field, format!("{} is empty", field.label()));
+        }
+        missing
+    }
+
+    fn required(&mut self, field: ImportField) -> String {
+        if self.missing(field) {
+            return String::new();
+        }
+        self.text(field).unwrap_or_default()
+    }
+
+    fn decimal(&mut self, field: ImportField) -> Option<Decimal> {
+        let value = self.text(field)?;
+        let number = Decimal::from_str(&value).ok();
+        if number.is_none() {
+            self.fail(field, format!("{:?} is not a number; write it like 1200.50", value));
+        }
+        number
+    }
+
+    fn required_decimal(&mut self, field: ImportField) -> Decimal {
+        if self.missing(field) {
+            return Decimal::ZERO;
+        }
+        self.decimal(field).unwrap_or_default()
+    }
+
+    fn date(&mut self, field: ImportField, format: DateFormat) -> Option<NaiveDate> {
+        let value = self.text(field)?;
+        let date = format.parse(&value);
+        if date.is_none() {
+            self.fail(field, format!("{:?} is not a date like {}", value, format.example()));
+        }
+        date
+    }
+
+    fn required_date(&mut self, field: ImportField, format: DateFormat) -> NaiveDate {
+        if self.missing(field) {
+            return NaiveDate::default();
+        }
+        self.date(field, format).unwrap_or_default()
+    }
+
+    fn currency(&mut self) -> Option<Currency> {
+        let code = self.text(ImportField::Currency)?.to_uppercase();
+        if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            self.fail(ImportField::Currency, format!("{:?} is not a three-letter currency code", code));
+            return None;
+        }
+        Currency::from_str(&code).ok()
+    }
+
+    fn status(&mut self) -> InvoiceStatus {
+        let Some(value) = self.text(ImportField::InvoiceStatus) else {
+            return InvoiceStatus::Draft;
+        };
+        match STATUSES.iter().find(|status| status.eq_ignore_ascii_case(&value)) {
+            Some(status) => InvoiceStatus::from_str(status).unwrap_or_default(),
+            None => {
+                self.fail(ImportField::InvoiceStatus, format!("{:?} is not one of {}", value, STATUSES.join(", ")));
+                InvoiceStatus::Draft
+            }
+        }
+    }
+
+    /// A new client from the row's client columns.
+    fn client(&mut self) -> Client {
+        let name = self.required(ImportField::ClientName);
+        let email = self.text(ImportField::ClientEmail);
+        if let Some(email) = email.as_ref().filter(|email| !email.contains('@') || email.contains(char::is_whitespace)) {
+            self.fail(ImportField::ClientEmail, format!("{:?} is not an email address", email));
+        }
+        let now = Utc::now();
+        Client {
+            id: Uuid::new_v4(),
+            name,
+            email,
+            phone: self.text(ImportField::ClientPhone),
+            company: self.text(ImportField::ClientCompany),
+            address: Address {
+                line1: self.text(ImportField::ClientAddressLine1).unwrap_or_default(),
+                line2: self.text(ImportField::ClientAddressLine2),
+                city: self.text(ImportField::ClientCity).unwrap_or_default(),
+                state: self.text(ImportField::ClientState),
+                postal_code: self.text(ImportField::ClientPostalCode).unwrap_or_default(),
+                country: self.text(ImportField::ClientCountry).unwrap_or_default(),
+            },
+            shipping_address: None,
+            tax_id: self.text(ImportField::ClientTaxId),
+            tax_ids: vec![],
+            contacts: vec![],
+            notes: self.text(ImportField::ClientNotes),
+            custom_fields: vec![],
+            archived_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::new_pool;
+
+    fn read_csv_text(content: &str) -> Sheet {
+        let path = std::env::temp_dir().join(format!("invoiceflow-import-{}.csv", Uuid::new_v4()));
+        std::fs::write(&path, content).unwrap();
+        let sheet = read_sheet(&path);
+        std::fs::remove_file(&path).unwrap();
+        sheet.unwrap()
+    }
+
+    fn invoice_mapping(sheet: &Sheet) -> ColumnMapping {
+        let mut mapping = guess_mapping(&sheet.headers, ImportTarget::Invoices);
+        mapping.columns.insert(ImportField::ItemUnitPrice, "Rate".to_string());
+        mapping.date_format = DateFormat::DayMonthYear;
+        mapping
+    }
+
+    #[tokio::test]
+    async fn test_invoice_rows_are_grouped_by_number() {
+        let pool = new_pool().await;
+        let sheet = read_csv_text(
+            "Invoice number,Client name,Client email,Issue date,Item description,Item quantity,Rate,Amount paid\n\
+             INV-10,Acme,billing@acme.test,31/01/2024,Design,2,100.00,50\n\
+             \n\
+             INV-10,Acme,,31/01/2024,Hosting,1,25.50,\n\
+             INV-11,Acme,,2024-02-15,Support,3,10,\n",
+        );
+        assert_eq!(sheet.rows.iter().map(|r| r.line).collect::<Vec<_>>(), vec![2, 4, 5]);
+
+        let plan = plan_import(&pool, &sheet, &invoice_mapping(&sheet)).await.unwrap();
+        assert!(plan.errors.is_empty(), "{:?}", plan.errors);
+        assert_eq!(plan.clients.len(), 1);
+        assert_eq!(plan.clients[0].email.as_deref(), Some("billing@acme.test"));
+        let first = &plan.invoices[0];
+        assert_eq!((first.items.len(), first.total, first.amount_due), (2, Decimal::new(22550, 2), Decimal::new(17550, 2)));
+        assert_eq!(first.issue_date, NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+        assert_eq!(first.due_date, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(plan.invoices[1].client_id, plan.clients[0].id);
+
+        commit_import(&pool, &plan).await.unwrap();
+        let invoices = InvoiceRepository::new(pool.clone());
+        assert!(invoices.number_exists("INV-11").await.unwrap());
+        assert_eq!(invoices.get_by_id(&first.id.to_string()).await.unwrap().unwrap().items[1].description, "Hosting");
+
+        // The same rows again clash with what was imported
+        let plan = plan_import(&pool, &sheet, &invoice_mapping(&sheet)).await.unwrap();
+        assert_eq!(plan.errors.iter().map(|e| e.line).collect::<Vec<_>>(), vec![2, 5]);
+        assert!(plan.invoices.is_empty() && plan.clients.is_empty());
+        assert!(commit_import(&pool, &plan).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_row_errors_name_their_line_and_field() {
+        let pool = new_pool().await;
+        let sheet = read_csv_text(
+            "Invoice number,Client name,Issue date,Item description,Rate,Invoice status\n\
+             INV-1,Acme,2024-13-01,Design,abc,Sent\n\
+             INV-2,,2024-01-01,Design,10,Unknown\n\
+             INV-3,Globex,2024-01-01,Design,10,paid\n",
+        );
+        let plan = plan_import(&pool, &sheet, &invoice_mapping(&sheet)).await.unwrap();
+        let errors: Vec<(usize, Option<ImportField>)> = plan.errors.iter().map(|e| (e.line, e.field)).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (2, Some(ImportField::IssueDate)),
+                (2, Some(ImportField::ItemUnitPrice)),
+                (3, Some(ImportField::ClientName)),
+                (3, Some(ImportField::InvoiceStatus)),
+            ]
+        );
+        assert_eq!(plan.invoices.len(), 1);
+        assert_eq!(plan.invoices[0].status, InvoiceStatus::Paid);
+        assert_eq!(plan.clients.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["Globex"]);
+
+        // An unmapped required field is reported against the header
+        let mut mapping = invoice_mapping(&sheet);
+        mapping.columns.remove(&ImportField::IssueDate);
+        mapping.columns.insert(ImportField::DueDate, "Due".to_string());
+        let plan = plan_import(&pool, &sheet, &mapping).await.unwrap();
+        assert_eq!(plan.errors.iter().map(|e| (e.line, e.field)).collect::<Vec<_>>(), vec![(1, Some(ImportField::IssueDate)), (1, Some(ImportField::DueDate))]);
+    }
+
+    #[tokio::test]
+    async fn test_clients_are_not_duplicated() {
+        let pool = new_pool().await;
+        let sheet = read_csv_text("Name,Email,City\nAcme,a@acme.test,Pune\nGlobex,not-an-email,\nacme,,\n");
+        let mapping = guess_mapping(&sheet.headers, ImportTarget::Clients);
+        assert_eq!(mapping.columns.len(), 3);
+
+        let plan = plan_import(&pool, &sheet, &mapping).await.unwrap();
+        assert_eq!(plan.errors.iter().map(|e| e.line).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(plan.clients[0].address.city, "Pune");
+    }
+}
@@ -0,0 +1,87 @@
+//! Builders shared by the repository tests.
+
+use crate::database::DbPool;
+use crate::migrations::run_migrations;
+use chrono::{NaiveDate, Utc};
+use flow_core::models::{Address, Client, Invoice, InvoiceItem};
+use flow_core::types::{Currency, InvoiceStatus, PaymentTerms};
+use rust_decimal::Decimal;
+use sqlx::sqlite::SqlitePoolOptions;
+use uuid::Uuid;
+
+/// A migrated in-memory database.
+pub(crate) async fn new_pool() -> DbPool {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+    run_migrations(&pool).await.unwrap();
+    pool
+}
+
+pub(crate) fn new_client(name: &str) -> Client {
+    Client {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        email: None,
+        phone: None,
+        company: None,
+        address: Address::default(),
+        shipping_address: None,
+        tax_id: None,
+        tax_ids: vec![],
+        contacts: vec![],
+        notes: None,
+        custom_fields: vec![],
+        archived_at: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+/// An unpaid USD invoice with a single item worth `total`.
+pub(crate) fn new_invoice(number: &str, client_id: Uuid, business_profile_id: Uuid, total: Decimal) -> Invoice {
+    let id = Uuid::new_v4();
+    let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    Invoice {
+        id,
+        number: number.to_string(),
+        status: InvoiceStatus::Sent,
+        client_id,
+        business_profile_id,
+        issue_date: date,
+        due_date: date,
+        currency: Currency::USD,
+        items: vec![InvoiceItem {
+            id: Uuid::new_v4(),
+            invoice_id: id,
+            description: "Work".to_string(),
+            quantity: Decimal::ONE,
+            unit_price: total,
+            amount: total,
+            tax_rate_name: None,
+            hsn_code: None,
+            sort_order: 0,
+            custom_fields: vec![],
+        }],
+        tax_rates: vec![],
+        discount: None,
+        subtotal: total,
+        tax_total: Decimal::ZERO,
+        discount_total: Decimal::ZERO,
+        total,
+        amount_paid: Decimal::ZERO,
+        amount_due: total,
+        payment_terms: PaymentTerms::Net30,
+        notes: None,
+        terms_and_conditions: None,
+        payment_note: None,
+        developer: None,
+        logo_path: None,
+        qr_code_url: None,
+        project_details: vec![],
+        custom_fields: vec![],
+        gst_registration: None,
+        bank_account: None,
+        archived_at: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
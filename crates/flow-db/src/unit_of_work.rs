@@ -0,0 +1,148 @@
+//! Several repository writes committed together or not at all.
+//!
+//! Each repository write runs in a transaction of its own. A command that has to make
+//! more than one, such as resetting the data and then the business profile, begins a
+//! [`UnitOfWork`], makes the writes through it and commits. Dropping it without
+//! committing rolls every write back.
+
+use crate::database::DbPool;
+use crate::repositories::{bank_account_repo, business_profile_repo, client_repo, custom_field_repo, invoice_repo};
+use flow_core::error::FlowResult;
+use flow_core::models::{BankAccount, BusinessProfile, Client, CustomFieldValue, GstRegistration, Invoice};
+use sqlx::{Sqlite, Transaction};
+
+/// Tables emptied by [`UnitOfWork::delete_all_data`], children before their parents.
+const DATA_TABLES: &[&str] = &[
+    "invoice_items",
+    "invoice_project_details",
+    "custom_field_values",
+    "custom_field_definitions",
+    "invoices",
+    "client_contacts",
+    "client_tax_ids",
+    "clients",
+    "audit_log",
+    "bank_accounts",
+    "app_assets",
+];
+
+pub struct UnitOfWork {
+    tx: Transaction<'static, Sqlite>,
+}
+
+impl UnitOfWork {
+    pub async fn begin(pool: &DbPool) -> FlowResult<Self> {
+        Ok(Self { tx: pool.begin().await? })
+    }
+
+    pub async fn commit(self) -> FlowResult<()> {
+        Ok(self.tx.commit().await?)
+    }
+
+    pub async fn rollback(self) -> FlowResult<()> {
+        Ok(self.tx.rollback().await?)
+    }
+
+    pub async fn create_client(&mut self, client: &Client) -> FlowResult<()> {
+        client_repo::insert_client(&mut self.tx, client).await
+    }
+
+    pub async fn update_client(&mut self, client: &Client) -> FlowResult<()> {
+        client_repo::update_client(&mut self.tx, client).await
+    }
+
+    /// Fails with `FlowError::ClientHasInvoices` for clients with invoices.
+    pub async fn delete_client(&mut self, id: &str) -> FlowResult<()> {
+        client_repo::delete_client(&mut self.tx, id).await
+    }
+
+    pub async fn set_client_archived(&mut self, id: &str, archived: bool) -> FlowResult<()> {
+        client_repo::set_client_archived(&mut self.tx, id, archived).await
+    }
+
+    pub async fn create_invoice(&mut self, invoice: &Invoice) -> FlowResult<()> {
+        invoice_repo::insert_invoice(&mut self.tx, invoice).await
+    }
+
+    pub async fn delete_invoice(&mut self, id: &str) -> FlowResult<()> {
+        invoice_repo::delete_invoice(&mut self.tx, id).await
+    }
+
+    pub async fn set_invoice_archived(&mut self, id: &str, archived: bool) -> FlowResult<()> {
+        invoice_repo::set_invoice_archived(&mut self.tx, id, archived).await
+    }
+
+    pub async fn update_invoice_status(&mut self, id: &str, status: &str) -> FlowResult<()> {
+        invoice_repo::update_invoice_status(&mut self.tx, id, status).await
+    }
+
+    pub async fn set_gst_registration(&mut self, id: &str, registration: Option<&GstRegistration>) -> FlowResult<()> {
+        invoice_repo::set_invoice_gst_registration(&mut self.tx, id, registration).await
+    }
+
+    pub async fn set_custom_values(&mut self, entity_id: &str, values: &[CustomFieldValue]) -> FlowResult<()> {
+        custom_field_repo::set_values(&mut self.tx, entity_id, values).await
+    }
+
+    pub async fn save_bank_account(&mut self, account: &BankAccount) -> FlowResult<()> {
+        bank_account_repo::save_bank_account(&mut self.tx, account).await
+    }
+
+    pub async fn delete_bank_account(&mut self, id: &str) -> FlowResult<()> {
+        bank_account_repo::delete_bank_account(&mut self.tx, id).await
+    }
+
+    pub async fn update_profile(&mut self, profile: &BusinessProfile) -> FlowResult<()> {
+        business_profile_repo::update_profile(&mut self.tx, profile).await
+    }
+
+    /// Deletes every client, invoice, bank account, custom field and audit entry. The
+    /// business profile and the migration history are kept.
+    pub async fn delete_all_data(&mut self) -> FlowResult<()> {
+        for table in DATA_TABLES {
+            sqlx::query(&format!("DELETE FROM {}", table)).execute(&mut *self.tx).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::{BusinessProfileRepository, ClientRepository, InvoiceRepository};
+    use crate::test_support::{new_client, new_invoice, new_pool};
+    use rust_decimal::Decimal;
+
+    async fn count(pool: &DbPool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table)).fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_all_or_nothing() {
+        let pool = new_pool().await;
+        let profile_id = BusinessProfileRepository::new(pool.clone()).get_profile().await.unwrap().id;
+        let first = new_client("Acme");
+        ClientRepository::new(pool.clone()).create(&first).await.unwrap();
+
+        // The second item reuses the first one's id, so the invoice fails half-way
+        let mut broken = new_invoice("INV-001", first.id, profile_id, Decimal::TEN);
+        broken.items.push(broken.items[0].clone());
+        assert!(InvoiceRepository::new(pool.clone()).create(&broken).await.is_err());
+        assert_eq!((count(&pool, "invoices").await, count(&pool, "invoice_items").await), (0, 0));
+
+        // A unit of work that is dropped leaves nothing behind
+        let other = new_client("Acme");
+        let mut uow = UnitOfWork::begin(&pool).await.unwrap();
+        uow.create_client(&other).await.unwrap();
+        uow.create_invoice(&new_invoice("INV-002", other.id, profile_id, Decimal::TEN)).await.unwrap();
+        drop(uow);
+        assert_eq!((count(&pool, "clients").await, count(&pool, "invoices").await), (1, 0));
+
+        // ...and one that is committed keeps every write
+        let mut uow = UnitOfWork::begin(&pool).await.unwrap();
+        uow.create_client(&other).await.unwrap();
+        uow.create_invoice(&new_invoice("INV-002", other.id, profile_id, Decimal::TEN)).await.unwrap();
+        uow.commit().await.unwrap();
+        assert_eq!((count(&pool, "clients").await, count(&pool, "invoice_items").await), (2, 1));
+    }
+}
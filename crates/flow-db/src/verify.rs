@@ -0,0 +1,83 @@
+//! Read-only check of the whole database for rows the app cannot load.
+
+use crate::database::DbPool;
+use crate::migrations::has_column;
+use crate::repositories::{business_profile_repo, client_repo, invoice_repo};
+use flow_core::error::FlowResult;
+use flow_core::models::CorruptRow;
+
+/// Every problem found: SQLite's own integrity and foreign key checks, then each client,
+/// contact, invoice, item and business profile row that does not convert to its model.
+pub async fn verify_database(pool: &DbPool) -> FlowResult<Vec<CorruptRow>> {
+    let mut corrupt = Vec::new();
+
+    let integrity: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check").fetch_all(pool).await?;
+    corrupt.extend(integrity.into_iter().filter(|m| m != "ok").map(|problem| CorruptRow {
+        table: "database".to_string(),
+        id: String::new(),
+        problem,
+    }));
+
+    let orphans: Vec<(String, Option<i64>, String, i64)> = sqlx::query_as("PRAGMA foreign_key_check").fetch_all(pool).await?;
+    for (table, rowid, parent, _) in orphans {
+        let id: Option<String> = if has_column(pool, &table, "id").await? {
+            sqlx::query_scalar(&format!("SELECT CAST(id AS TEXT) FROM {} WHERE rowid = ?", table))
+                .bind(rowid)
+                .fetch_optional(pool)
+                .await?
+                .flatten()
+        } else {
+            None
+        };
+        let id = id.unwrap_or_else(|| format!("rowid {}", rowid.unwrap_or_default()));
+        corrupt.push(CorruptRow {
+            problem: format!("{} row {}: refers to a missing {} row", table, id, parent),
+            table,
+            id,
+        });
+    }
+
+    corrupt.extend(business_profile_repo::find_corrupt_rows(pool).await?);
+    corrupt.extend(client_repo::find_corrupt_rows(pool).await?);
+    corrupt.extend(invoice_repo::find_corrupt_rows(pool).await?);
+    Ok(corrupt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::{ClientRepository, InvoiceRepository};
+    use crate::test_support::new_pool;
+
+    #[tokio::test]
+    async fn test_reports_rows_that_do_not_decode() {
+        let pool = new_pool().await;
+        assert!(verify_database(&pool).await.unwrap().is_empty());
+
+        sqlx::raw_sql(
+            r#"INSERT INTO business_profiles (id, name, address_line1, address_city, address_postal_code, address_country, created_at, updated_at)
+               VALUES ('00000000-0000-0000-0000-000000000001', 'Me', '', '', '', '', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');
+               INSERT INTO clients (id, name, address_line1, address_city, address_postal_code, address_country, created_at, updated_at)
+               VALUES ('00000000-0000-0000-0000-000000000002', 'Acme', '', '', '', '', 'yesterday', '2024-01-01T00:00:00Z');
+               INSERT INTO invoices (id, number, status, client_id, business_profile_id, issue_date, due_date, currency, payment_terms, created_at, updated_at)
+               VALUES ('00000000-0000-0000-0000-000000000003', 'INV-1', 'Lost', '00000000-0000-0000-0000-000000000002',
+                       '00000000-0000-0000-0000-000000000001', '2024-01-01', '2024-02-01', 'USD', 'Net30', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let problems: Vec<String> = verify_database(&pool).await.unwrap().into_iter().map(|r| r.problem).collect();
+        assert_eq!(
+            problems,
+            [
+                r#"clients row 00000000-0000-0000-0000-000000000002: invalid created_at "yesterday""#,
+                r#"invoices row 00000000-0000-0000-0000-000000000003: invalid status "Lost""#,
+            ]
+        );
+
+        let err = InvoiceRepository::new(pool.clone()).get_by_id("00000000-0000-0000-0000-000000000003").await.unwrap_err();
+        assert!(err.to_string().contains(r#"invalid status "Lost""#));
+        assert!(ClientRepository::new(pool).list_all().await.is_err());
+    }
+}
@@ -0,0 +1,80 @@
+//! How the buyer is named and how they refer to an invoice.
+//!
+//! InvoiceFlow has no dedicated reference fields; buyers' references are kept in custom
+//! fields or project details, so they are recognised by their label.
+
+use flow_core::models::{Client, Invoice};
+
+/// Labels under which a buyer's purchase order number is recorded.
+const ORDER_LABELS: &[&str] = &["po", "pono", "ponumber", "purchaseorder", "purchaseorderno", "purchaseordernumber", "orderreference"];
+
+/// Labels under which a buyer's own reference (e.g. a German Leitweg-ID) is recorded.
+const BUYER_LABELS: &[&str] = &["buyerreference", "yourreference", "customerreference", "leitwegid"];
+
+/// The company name, or the contact's name when no company is set.
+pub fn buyer_name(client: &Client) -> &str {
+    client.company.as_deref().filter(|c| !c.trim().is_empty()).unwrap_or(&client.name)
+}
+
+/// The purchase order number the buyer issued for this invoice, if one was recorded.
+pub fn order_reference(invoice: &Invoice) -> Option<&str> {
+    let custom = invoice.custom_fields.iter().map(|f| (f.name.as_str(), f.value.as_str()));
+    let details = invoice.project_details.iter().map(|d| (d.label.as_str(), d.value.as_str()));
+    find(custom.chain(details), ORDER_LABELS)
+}
+
+/// The reference the buyer asked to be quoted, from the invoice or else the client.
+pub fn buyer_reference<'a>(invoice: &'a Invoice, client: &'a Client) -> Option<&'a str> {
+    let invoice_fields = invoice.custom_fields.iter().map(|f| (f.name.as_str(), f.value.as_str()));
+    let details = invoice.project_details.iter().map(|d| (d.label.as_str(), d.value.as_str()));
+    let client_fields = client.custom_fields.iter().map(|f| (f.name.as_str(), f.value.as_str()));
+    find(invoice_fields.chain(details).chain(client_fields), BUYER_LABELS)
+}
+
+fn find<'a>(fields: impl Iterator<Item = (&'a str, &'a str)>, labels: &[&str]) -> Option<&'a str> {
+    fields
+        .filter(|(label, _)| {
+            let label: String = label.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_ascii_lowercase();
+            labels.contains(&label.as_str())
+        })
+        .map(|(_, value)| value.trim())
+        .find(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+    use flow_core::models::{CustomFieldValue, ProjectDetail};
+    use flow_core::types::CustomFieldType;
+    use uuid::Uuid;
+
+    fn field(name: &str, value: &str) -> CustomFieldValue {
+        CustomFieldValue { field_id: Uuid::nil(), name: name.to_string(), field_type: CustomFieldType::Text, value: value.to_string() }
+    }
+
+    #[test]
+    fn test_blank_company_falls_back_to_the_contact() {
+        let (_, mut client, _) = fixture();
+        assert_eq!(buyer_name(&client), "Kunde GmbH");
+        client.company = Some("  ".to_string());
+        assert_eq!(buyer_name(&client), "Erika Mustermann");
+    }
+
+    #[test]
+    fn test_references_are_found_by_label() {
+        let (mut invoice, mut client, _) = fixture();
+        invoice.project_details.clear();
+        assert_eq!(order_reference(&invoice), None);
+        assert_eq!(buyer_reference(&invoice, &client), None);
+
+        invoice.project_details = vec![ProjectDetail { label: "Project".to_string(), value: "Website".to_string() }];
+        invoice.custom_fields = vec![field("P.O. Number", " "), field("PO Number", "4500012345")];
+        client.custom_fields = vec![field("Leitweg-ID", "991-01234-44")];
+        assert_eq!(order_reference(&invoice), Some("4500012345"));
+        assert_eq!(buyer_reference(&invoice, &client), Some("991-01234-44"));
+
+        invoice.project_details.push(ProjectDetail { label: "Your reference".to_string(), value: "Q2 rollout".to_string() });
+        assert_eq!(buyer_reference(&invoice, &client), Some("Q2 rollout"));
+    }
+}
@@ -0,0 +1,75 @@
+/// ISO 3166-1 alpha-2 codes with the English names users typically type.
+const COUNTRIES: &[(&str, &str)] = &[
+    ("AE", "United Arab Emirates"),
+    ("AT", "Austria"),
+    ("AU", "Australia"),
+    ("BE", "Belgium"),
+    ("BG", "Bulgaria"),
+    ("CA", "Canada"),
+    ("CH", "Switzerland"),
+    ("CY", "Cyprus"),
+    ("CZ", "Czechia"),
+    ("DE", "Germany"),
+    ("DK", "Denmark"),
+    ("EE", "Estonia"),
+    ("ES", "Spain"),
+    ("FI", "Finland"),
+    ("FR", "France"),
+    ("GB", "United Kingdom"),
+    ("GR", "Greece"),
+    ("HR", "Croatia"),
+    ("HU", "Hungary"),
+    ("IE", "Ireland"),
+    ("IN", "India"),
+    ("IS", "Iceland"),
+    ("IT", "Italy"),
+    ("LI", "Liechtenstein"),
+    ("LT", "Lithuania"),
+    ("LU", "Luxembourg"),
+    ("LV", "Latvia"),
+    ("MT", "Malta"),
+    ("NL", "Netherlands"),
+    ("NO", "Norway"),
+    ("NZ", "New Zealand"),
+    ("PL", "Poland"),
+    ("PT", "Portugal"),
+    ("RO", "Romania"),
+    ("SA", "Saudi Arabia"),
+    ("SE", "Sweden"),
+    ("SG", "Singapore"),
+    ("SI", "Slovenia"),
+    ("SK", "Slovakia"),
+    ("US", "United States"),
+];
+
+/// Common alternative spellings.
+const ALIASES: &[(&str, &str)] = &[
+    ("UAE", "AE"),
+    ("UK", "GB"),
+    ("GREAT BRITAIN", "GB"),
+    ("ENGLAND", "GB"),
+    ("USA", "US"),
+    ("UNITED STATES OF AMERICA", "US"),
+    ("DEUTSCHLAND", "DE"),
+    ("THE NETHERLANDS", "NL"),
+    ("HOLLAND", "NL"),
+    ("CZECH REPUBLIC", "CZ"),
+    ("ÖSTERREICH", "AT"),
+    ("SCHWEIZ", "CH"),
+    ("ESPAÑA", "ES"),
+    ("ITALIA", "IT"),
+    ("BHARAT", "IN"),
+];
+
+/// Resolves a free-text country (as stored on addresses) to its ISO 3166-1 alpha-2 code.
+pub fn country_code(country: &str) -> Option<&'static str> {
+    let upper = country.trim().to_uppercase();
+    if upper.is_empty() {
+        return None;
+    }
+    COUNTRIES
+        .iter()
+        .find(|(code, name)| upper == *code || upper == name.to_uppercase())
+        .map(|(code, _)| *code)
+        .or_else(|| ALIASES.iter().find(|(alias, _)| upper == *alias).map(|(_, code)| *code))
+}
@@ -0,0 +1,29 @@
+use flow_core::error::FlowError;
+use thiserror::Error;
+
+use crate::validation::ValidationIssue;
+
+#[derive(Error, Debug)]
+pub enum EInvoiceError {
+    #[error("Invoice is not a valid e-invoice: {}", summarize(.0))]
+    Invalid(Vec<ValidationIssue>),
+
+    #[error("Unrecognised IRN response: {0}")]
+    Response(String),
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+fn summarize(issues: &[ValidationIssue]) -> String {
+    issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+impl From<EInvoiceError> for FlowError {
+    fn from(err: EInvoiceError) -> Self {
+        match err {
+            EInvoiceError::Json(e) => FlowError::Serialization(e),
+            other => FlowError::Validation(other.to_string()),
+        }
+    }
+}
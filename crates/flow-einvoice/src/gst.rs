@@ -0,0 +1,490 @@
+//! GSTN e-invoice JSON (schema v1.1) for registration on the Invoice Registration Portal.
+//!
+//! The portal returns an IRN, acknowledgement and signed QR payload for each registered
+//! invoice; [`parse_irn_response`] turns that response into a [`GstRegistration`].
+
+use flow_core::models::{Address, BusinessProfile, Client, GstRegistration, Invoice};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+use crate::buyer::buyer_name;
+use crate::error::EInvoiceError;
+use crate::validation::ValidationIssue;
+
+pub const SCHEMA_VERSION: &str = "1.1";
+
+/// Rates accepted by `ItemList.GstRt`.
+const GST_RATES: &[&str] = &["0", "0.1", "0.25", "1", "1.5", "3", "5", "6", "7.5", "12", "18", "28", "40"];
+
+const GSTIN_CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct GstInvoice {
+    version: &'static str,
+    tran_dtls: TranDtls,
+    doc_dtls: DocDtls,
+    seller_dtls: Party,
+    buyer_dtls: Party,
+    item_list: Vec<Item>,
+    val_dtls: ValDtls,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct TranDtls {
+    tax_sch: &'static str,
+    sup_typ: &'static str,
+    reg_rev: &'static str,
+    igst_on_intra: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DocDtls {
+    typ: &'static str,
+    no: String,
+    dt: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Party {
+    gstin: String,
+    lgl_nm: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pos: Option<String>,
+    addr1: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    addr2: Option<String>,
+    loc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pin: Option<u32>,
+    stcd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ph: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    em: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Item {
+    sl_no: String,
+    prd_desc: String,
+    is_servc: &'static str,
+    hsn_cd: String,
+    qty: Number,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<&'static str>,
+    unit_price: Number,
+    tot_amt: Number,
+    discount: Number,
+    ass_amt: Number,
+    gst_rt: Number,
+    igst_amt: Number,
+    cgst_amt: Number,
+    sgst_amt: Number,
+    tot_item_val: Number,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ValDtls {
+    ass_val: Number,
+    cgst_val: Number,
+    sgst_val: Number,
+    igst_val: Number,
+    discount: Number,
+    rnd_off_amt: Number,
+    tot_inv_val: Number,
+}
+
+/// Validates the invoice against the schema constraints and renders the GSTN JSON.
+pub fn to_gst_json(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> Result<String, EInvoiceError> {
+    let issues = validate(invoice, client, profile);
+    if !issues.is_empty() {
+        return Err(EInvoiceError::Invalid(issues));
+    }
+    Ok(serde_json::to_string_pretty(&build(invoice, client, profile))?)
+}
+
+/// Returns every schema constraint the invoice violates; an empty list means it can be exported.
+pub fn validate(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut fail = |rule: &'static str, field: &'static str, message: String| {
+        issues.push(ValidationIssue { rule, field, message });
+    };
+
+    if invoice.currency.to_string() != "INR" {
+        fail("ValDtls.TotInvVal", "invoice.currency", "GST e-invoices must be issued in INR".to_string());
+    }
+    if !is_document_number(&invoice.number) {
+        fail(
+            "DocDtls.No",
+            "invoice.number",
+            "Invoice number must be 1-16 uppercase letters, digits, '/' or '-' and not start with 0, '/' or '-'".to_string(),
+        );
+    }
+    if invoice.items.is_empty() {
+        fail("ItemList", "invoice.items", "Invoice must have at least one line item".to_string());
+    }
+
+    match profile.tax_id.as_deref().map(str::trim) {
+        Some(gstin) if is_gstin(gstin) => {}
+        _ => fail("SellerDtls.Gstin", "profile.tax_id", "Seller tax ID must be a valid 15-character GSTIN".to_string()),
+    }
+    check_name(&profile.name, "SellerDtls.LglNm", "profile.name", "Seller", &mut fail);
+    check_address(&profile.address, "SellerDtls", "profile.address", "Seller", &mut fail);
+
+    match client.tax_id.as_deref().map(str::trim) {
+        Some(gstin) if is_gstin(gstin) => {}
+        _ => fail("BuyerDtls.Gstin", "client.tax_id", "Client tax ID must be a valid 15-character GSTIN".to_string()),
+    }
+    check_name(buyer_name(client), "BuyerDtls.LglNm", "client.name", "Buyer", &mut fail);
+    check_address(&client.address, "BuyerDtls", "client.address", "Buyer", &mut fail);
+
+    for item in &invoice.items {
+        if item.description.trim().is_empty() {
+            fail("ItemList.PrdDesc", "invoice.items.description", "Every line item needs a description".to_string());
+        }
+        if !item.hsn_code.as_deref().is_some_and(is_hsn_code) {
+            fail(
+                "ItemList.HsnCd",
+                "invoice.items.hsn_code",
+                format!("\"{}\" needs a 4, 6 or 8 digit HSN/SAC code", item.description.trim()),
+            );
+        }
+    }
+
+    let rate = gst_rate(invoice);
+    if !GST_RATES.contains(&rate.normalize().to_string().as_str()) {
+        fail(
+            "ItemList.GstRt",
+            "invoice.tax_rates",
+            format!("Effective tax rate {}% is not a GST rate", rate.normalize()),
+        );
+    }
+
+    issues
+}
+
+/// Extracts the IRN details from a portal (or GSP) registration response.
+pub fn parse_irn_response(response: &str) -> Result<GstRegistration, EInvoiceError> {
+    let mut value: Value = serde_json::from_str(response)?;
+    // GSPs wrap the portal payload in `Data`, sometimes as an encoded JSON string.
+    if let Some(data) = value.get("Data").cloned() {
+        value = match data {
+            Value::String(inner) => serde_json::from_str(&inner)?,
+            other => other,
+        };
+    }
+
+    let text = |key: &str| match value.get(key) {
+        Some(Value::String(s)) => Some(s.trim().to_string()),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+    let irn = text("Irn")
+        .filter(|irn| irn.len() == 64 && irn.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| EInvoiceError::Response("missing or malformed Irn".to_string()))?;
+    let ack_no = text("AckNo").ok_or_else(|| EInvoiceError::Response("missing AckNo".to_string()))?;
+    let ack_date = text("AckDt").ok_or_else(|| EInvoiceError::Response("missing AckDt".to_string()))?;
+    let signed_qr = text("SignedQRCode")
+        .filter(|qr| !qr.is_empty())
+        .ok_or_else(|| EInvoiceError::Response("missing SignedQRCode".to_string()))?;
+
+    Ok(GstRegistration { irn, ack_no, ack_date, signed_qr })
+}
+
+fn build(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> GstInvoice {
+    let seller_gstin = profile.tax_id.as_deref().unwrap_or_default().trim().to_uppercase();
+    let buyer_gstin = client.tax_id.as_deref().unwrap_or_default().trim().to_uppercase();
+    let buyer_state = state_code(&buyer_gstin).to_string();
+    // Supplies within one state split the tax into CGST and SGST; others are IGST.
+    let intra_state = state_code(&seller_gstin) == buyer_state;
+    let rate = gst_rate(invoice);
+
+    let mut remaining_tax = invoice.tax_total;
+    let mut totals = (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+    let last = invoice.items.len().saturating_sub(1);
+    let items = invoice
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            // Spread the stored tax over the lines so the line taxes add up to it exactly.
+            let tax = if i == last || invoice.subtotal.is_zero() {
+                remaining_tax
+            } else {
+                (item.amount * invoice.tax_total / invoice.subtotal).round_dp(2)
+            };
+            remaining_tax -= tax;
+            let (igst, cgst, sgst) = if intra_state {
+                let cgst = (tax / Decimal::TWO).round_dp(2);
+                (Decimal::ZERO, cgst, tax - cgst)
+            } else {
+                (tax, Decimal::ZERO, Decimal::ZERO)
+            };
+            totals = (totals.0 + igst, totals.1 + cgst, totals.2 + sgst);
+
+            let hsn = item.hsn_code.as_deref().unwrap_or_default().trim().to_string();
+            let is_service = hsn.starts_with("99");
+            Item {
+                sl_no: (i + 1).to_string(),
+                prd_desc: item.description.trim().to_string(),
+                is_servc: if is_service { "Y" } else { "N" },
+                hsn_cd: hsn,
+                qty: number(item.quantity, 3),
+                unit: (!is_service).then_some("NOS"),
+                unit_price: number(item.unit_price, 3),
+                tot_amt: number(item.amount, 2),
+                discount: number(Decimal::ZERO, 2),
+                ass_amt: number(item.amount, 2),
+                gst_rt: number(rate, 3),
+                igst_amt: number(igst, 2),
+                cgst_amt: number(cgst, 2),
+                sgst_amt: number(sgst, 2),
+                tot_item_val: number(item.amount + tax, 2),
+            }
+        })
+        .collect();
+
+    // InvoiceFlow deducts discounts after tax, which GSTN models as an invoice-level discount.
+    let assessable: Decimal = invoice.items.iter().map(|item| item.amount).sum();
+    let computed = assessable + invoice.tax_total - invoice.discount_total;
+
+    GstInvoice {
+        version: SCHEMA_VERSION,
+        tran_dtls: TranDtls { tax_sch: "GST", sup_typ: "B2B", reg_rev: "N", igst_on_intra: "N" },
+        doc_dtls: DocDtls {
+            typ: "INV",
+            no: invoice.number.trim().to_string(),
+            dt: invoice.issue_date.format("%d/%m/%Y").to_string(),
+        },
+        seller_dtls: party(&profile.name, seller_gstin, &profile.address, None, profile.phone.as_deref(), profile.email.as_deref()),
+        buyer_dtls: party(
+            buyer_name(client),
+            buyer_gstin,
+            &client.address,
+            Some(buyer_state),
+            client.phone.as_deref(),
+            client.email.as_deref(),
+        ),
+        item_list: items,
+        val_dtls: ValDtls {
+            ass_val: number(assessable, 2),
+            cgst_val: number(totals.1, 2),
+            sgst_val: number(totals.2, 2),
+            igst_val: number(totals.0, 2),
+            discount: number(invoice.discount_total, 2),
+            rnd_off_amt: number(invoice.total - computed, 2),
+            tot_inv_val: number(invoice.total, 2),
+        },
+    }
+}
+
+fn party(name: &str, gstin: String, address: &Address, pos: Option<String>, phone: Option<&str>, email: Option<&str>) -> Party {
+    let stcd = state_code(&gstin).to_string();
+    let phone = phone.map(|p| p.chars().filter(char::is_ascii_digit).collect::<String>());
+    Party {
+        gstin,
+        lgl_nm: name.trim().to_string(),
+        pos,
+        addr1: address.line1.trim().to_string(),
+        addr2: address.line2.as_deref().map(str::trim).filter(|l| l.len() >= 3).map(String::from),
+        loc: address.city.trim().to_string(),
+        pin: address.postal_code.trim().parse().ok(),
+        stcd,
+        ph: phone.filter(|p| (6..=12).contains(&p.len())),
+        em: email.map(str::trim).filter(|e| (6..=100).contains(&e.len())).map(String::from),
+    }
+}
+
+fn check_name(
+    name: &str,
+    rule: &'static str,
+    field: &'static str,
+    party: &str,
+    fail: &mut impl FnMut(&'static str, &'static str, String),
+) {
+    if !(3..=100).contains(&name.trim().chars().count()) {
+        fail(rule, field, format!("{} legal name must be 3-100 characters", party));
+    }
+}
+
+fn check_address(
+    address: &Address,
+    group: &'static str,
+    field: &'static str,
+    party: &str,
+    fail: &mut impl FnMut(&'static str, &'static str, String),
+) {
+    let (addr_rule, loc_rule, pin_rule) = if group == "SellerDtls" {
+        ("SellerDtls.Addr1", "SellerDtls.Loc", "SellerDtls.Pin")
+    } else {
+        ("BuyerDtls.Addr1", "BuyerDtls.Loc", "BuyerDtls.Pin")
+    };
+    if !(1..=100).contains(&address.line1.trim().chars().count()) {
+        fail(addr_rule, field, format!("{} address line is required", party));
+    }
+    if !(3..=50).contains(&address.city.trim().chars().count()) {
+        fail(loc_rule, field, format!("{} city must be 3-50 characters", party));
+    }
+    let pin = address.postal_code.trim();
+    if pin.len() != 6 || !pin.chars().all(|c| c.is_ascii_digit()) || pin.starts_with('0') {
+        fail(pin_rule, field, format!("{} PIN code must be 6 digits", party));
+    }
+}
+
+/// Tax rate applied to the subtotal; InvoiceFlow stores one tax total per invoice.
+fn gst_rate(invoice: &Invoice) -> Decimal {
+    if invoice.subtotal <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    (invoice.tax_total * Decimal::ONE_HUNDRED / invoice.subtotal).round_dp(2)
+}
+
+/// The state code is the first two digits of a GSTIN.
+fn state_code(gstin: &str) -> &str {
+    gstin.get(..2).unwrap_or_default()
+}
+
+/// GSTIN format (2 digit state, PAN, entity, `Z`, check character) and mod-36 checksum.
+pub fn is_gstin(gstin: &str) -> bool {
+    let bytes = gstin.as_bytes();
+    if bytes.len() != 15 || !bytes.iter().all(|b| GSTIN_CHARS.contains(b)) {
+        return false;
+    }
+    let pan = &bytes[2..12];
+    let shape_ok = bytes[..2].iter().all(u8::is_ascii_digit)
+        && pan[..5].iter().all(u8::is_ascii_uppercase)
+        && pan[5..9].iter().all(u8::is_ascii_digit)
+        && pan[9].is_ascii_uppercase()
+        && bytes[13] == b'Z';
+    if !shape_ok {
+        return false;
+    }
+
+    let sum: usize = bytes[..14]
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            let value = GSTIN_CHARS.iter().position(|c| c == b).unwrap_or_default();
+            let product = value * if i % 2 == 0 { 1 } else { 2 };
+            product / 36 + product % 36
+        })
+        .sum();
+    bytes[14] == GSTIN_CHARS[(36 - sum % 36) % 36]
+}
+
+fn is_document_number(number: &str) -> bool {
+    let bytes = number.as_bytes();
+    (1..=16).contains(&bytes.len())
+        && matches!(bytes[0], b'A'..=b'Z' | b'1'..=b'9')
+        && bytes.iter().all(|b| matches!(b, b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-'))
+}
+
+fn is_hsn_code(code: &str) -> bool {
+    let code = code.trim();
+    matches!(code.len(), 4 | 6 | 8) && code.chars().all(|c| c.is_ascii_digit()) && code.chars().any(|c| c != '0')
+}
+
+fn number(value: Decimal, places: u32) -> Number {
+    value.round_dp(places).normalize().to_string().parse().unwrap_or_else(|_| Number::from(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{address, item};
+    use chrono::NaiveDate;
+    use flow_core::models::InvoiceItem;
+    use flow_core::types::Currency;
+
+    /// The shared fixture moved to India: an intra-state INR invoice between two
+    /// Maharashtra GSTINs.
+    fn fixture() -> (Invoice, Client, BusinessProfile) {
+        let (mut invoice, mut client, mut profile) = crate::test_support::fixture();
+        let line = |n: u128, description: &str, price: i64, hsn: &str| InvoiceItem {
+            hsn_code: Some(hsn.to_string()),
+            ..item(n, description, 1, price)
+        };
+        invoice.number = "INV-2025-0007".to_string();
+        invoice.issue_date = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        invoice.due_date = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+        invoice.currency = Currency::INR;
+        invoice.items = vec![line(10, "Website development", 50000, "998314"), line(11, "Hosting setup", 10001, "998315")];
+        invoice.subtotal = Decimal::from(60001);
+        invoice.tax_total = Decimal::new(1080018, 2);
+        invoice.total = Decimal::new(7080118, 2);
+        invoice.amount_due = invoice.total;
+
+        client.name = "Priya Sharma".to_string();
+        client.email = Some("accounts@client.example".to_string());
+        client.phone = Some("+91 98200 12345".to_string());
+        client.company = Some("Client Pvt Ltd".to_string());
+        client.address = address("12 MG Road", "Mumbai", "400001", "India");
+        client.tax_id = Some("27AAACR5055K1Z7".to_string());
+
+        profile.name = "Studio LLP".to_string();
+        profile.address = address("12 MG Road", "Pune", "411001", "India");
+        profile.tax_id = Some("27AAPFU0939F1ZV".to_string());
+        profile.default_currency = Currency::INR;
+        (invoice, client, profile)
+    }
+
+    #[test]
+    fn test_intra_state_invoice_splits_cgst_and_sgst() {
+        let (invoice, client, profile) = fixture();
+        let json: Value = serde_json::from_str(&to_gst_json(&invoice, &client, &profile).unwrap()).unwrap();
+
+        assert_eq!(json["Version"], "1.1");
+        assert_eq!(json["DocDtls"]["Dt"], "01/04/2025");
+        assert_eq!(json["SellerDtls"]["Stcd"], "27");
+        assert_eq!(json["SellerDtls"]["Pin"], 411001);
+        assert_eq!(json["BuyerDtls"]["Pos"], "27");
+        assert_eq!(json["BuyerDtls"]["Ph"], "919820012345");
+
+        let items = json["ItemList"].as_array().unwrap();
+        assert_eq!(items[0]["IsServc"], "Y");
+        assert_eq!(items[0]["GstRt"], 18);
+        assert_eq!(items[0]["CgstAmt"], 4500);
+        assert_eq!(items[0]["IgstAmt"], 0);
+        // The last line absorbs rounding so line taxes add up to the stored tax total.
+        assert_eq!(items[1]["CgstAmt"], 900.09);
+        assert_eq!(items[1]["SgstAmt"], 900.09);
+        assert_eq!(json["ValDtls"]["CgstVal"], 5400.09);
+        assert_eq!(json["ValDtls"]["TotInvVal"], 70801.18);
+        assert_eq!(json["ValDtls"]["RndOffAmt"], 0);
+    }
+
+    #[test]
+    fn test_schema_constraints_are_reported() {
+        let (mut invoice, mut client, mut profile) = fixture();
+        invoice.number = "inv 7".to_string();
+        invoice.items[1].hsn_code = None;
+        client.tax_id = Some("29AABCT1332L1ZB".to_string());
+        profile.address.postal_code = "4110".to_string();
+
+        let rules: Vec<_> = validate(&invoice, &client, &profile).into_iter().map(|i| i.rule).collect();
+        assert_eq!(rules, ["DocDtls.No", "SellerDtls.Pin", "BuyerDtls.Gstin", "ItemList.HsnCd"]);
+        assert!(matches!(to_gst_json(&invoice, &client, &profile), Err(EInvoiceError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_parse_irn_response() {
+        let irn = "a".repeat(64);
+        let response = format!(
+            r#"{{"Status":1,"Data":"{{\"AckNo\":112010036563310,\"AckDt\":\"2025-04-01 12:46:00\",\"Irn\":\"{}\",\"SignedQRCode\":\"eyJhbGciOi.payload.sig\"}}"}}"#,
+            irn
+        );
+        let registration = parse_irn_response(&response).unwrap();
+        assert_eq!(registration.irn, irn);
+        assert_eq!(registration.ack_no, "112010036563310");
+        assert_eq!(registration.signed_qr, "eyJhbGciOi.payload.sig");
+
+        assert!(parse_irn_response(r#"{"Irn":"short","AckNo":1,"AckDt":"x","SignedQRCode":"y"}"#).is_err());
+    }
+}
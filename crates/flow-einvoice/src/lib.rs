@@ -0,0 +1,16 @@
+pub mod buyer;
+pub mod country;
+pub mod error;
+pub mod gst;
+pub mod tax;
+pub mod ubl;
+pub mod validation;
+#[cfg(test)]
+mod test_support;
+pub mod xml;
+
+pub use buyer::{buyer_name, buyer_reference, order_reference};
+pub use country::country_code;
+pub use error::EInvoiceError;
+pub use tax::VatBreakdown;
+pub use validation::{validate, ValidationIssue};
@@ -0,0 +1,40 @@
+use flow_core::models::Invoice;
+use rust_decimal::Decimal;
+
+/// The single VAT breakdown reported for an invoice in EN 16931 based formats.
+///
+/// InvoiceFlow applies its tax rates to the whole subtotal (optionally compounding) and
+/// deducts discounts after tax. EN 16931 expects the opposite order and one rate per
+/// category, so the stored taxes are reported as one category with the effective rate
+/// that reproduces the stored totals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VatBreakdown {
+    /// UNCL5305 category: `S` (standard rated) or `E` (exempt).
+    pub category: &'static str,
+    pub rate: Decimal,
+    pub taxable_amount: Decimal,
+    pub tax_amount: Decimal,
+    pub exemption_reason: Option<&'static str>,
+}
+
+impl VatBreakdown {
+    pub fn for_invoice(invoice: &Invoice) -> Self {
+        let taxable_amount = invoice.subtotal - invoice.discount_total;
+        if invoice.tax_total.is_zero() || taxable_amount <= Decimal::ZERO {
+            return Self {
+                category: "E",
+                rate: Decimal::ZERO,
+                taxable_amount,
+                tax_amount: invoice.tax_total,
+                exemption_reason: Some("Not subject to VAT"),
+            };
+        }
+        Self {
+            category: "S",
+            rate: (invoice.tax_total * Decimal::from(100) / taxable_amount).round_dp(2),
+            taxable_amount,
+            tax_amount: invoice.tax_total,
+            exemption_reason: None,
+        }
+    }
+}
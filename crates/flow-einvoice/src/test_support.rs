@@ -0,0 +1,109 @@
+//! Builders shared by the exporter tests.
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use flow_core::models::{Address, BusinessProfile, Client, Invoice, InvoiceItem, ProjectDetail};
+use flow_core::types::{Currency, InvoiceStatus, PaymentTerms};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+pub(crate) fn address(line1: &str, city: &str, postal_code: &str, country: &str) -> Address {
+    Address {
+        line1: line1.to_string(),
+        line2: None,
+        city: city.to_string(),
+        state: None,
+        postal_code: postal_code.to_string(),
+        country: country.to_string(),
+    }
+}
+
+/// Line `n` of the fixture invoice, priced at `quantity × price`.
+pub(crate) fn item(n: u128, description: &str, quantity: i64, price: i64) -> InvoiceItem {
+    InvoiceItem {
+        id: Uuid::from_u128(n),
+        invoice_id: Uuid::from_u128(1),
+        description: description.to_string(),
+        quantity: Decimal::from(quantity),
+        unit_price: Decimal::from(price),
+        amount: Decimal::from(quantity * price),
+        tax_rate_name: None,
+        hsn_code: None,
+        sort_order: n as i32,
+        custom_fields: vec![],
+    }
+}
+
+/// A valid cross-border EUR invoice from a Dutch studio to a German company, taxed at 19%.
+pub(crate) fn fixture() -> (Invoice, Client, BusinessProfile) {
+    let now = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+    let invoice = Invoice {
+        id: Uuid::from_u128(1),
+        number: "INV-2025-0042".to_string(),
+        status: InvoiceStatus::Sent,
+        client_id: Uuid::from_u128(2),
+        business_profile_id: Uuid::from_u128(3),
+        issue_date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+        due_date: NaiveDate::from_ymd_opt(2025, 3, 31).unwrap(),
+        currency: Currency::EUR,
+        items: vec![item(10, "Consulting & workshops", 4, 250), item(11, "Travel", 1, 200)],
+        tax_rates: vec![],
+        discount: None,
+        subtotal: Decimal::from(1200),
+        tax_total: Decimal::from(228),
+        discount_total: Decimal::ZERO,
+        total: Decimal::from(1428),
+        amount_paid: Decimal::ZERO,
+        amount_due: Decimal::from(1428),
+        payment_terms: PaymentTerms::Net30,
+        notes: None,
+        terms_and_conditions: None,
+        payment_note: None,
+        developer: None,
+        logo_path: None,
+        qr_code_url: None,
+        project_details: vec![ProjectDetail { label: "PO".to_string(), value: "4500012345".to_string() }],
+        custom_fields: vec![],
+        gst_registration: None,
+        bank_account: None,
+        archived_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let client = Client {
+        id: Uuid::from_u128(2),
+        name: "Erika Mustermann".to_string(),
+        email: Some("ap@kunde.example".to_string()),
+        phone: None,
+        company: Some("Kunde GmbH".to_string()),
+        address: address("Hauptstraße 1", "Berlin", "10115", "Germany"),
+        shipping_address: None,
+        tax_id: None,
+        tax_ids: vec![],
+        contacts: vec![],
+        notes: None,
+        custom_fields: vec![],
+        archived_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let profile = BusinessProfile {
+        id: Uuid::from_u128(3),
+        name: "Studio B.V.".to_string(),
+        email: Some("billing@studio.example".to_string()),
+        phone: None,
+        address: address("Hauptstraße 1", "Amsterdam", "10115", "NL"),
+        tax_id: Some("NL123456789B01".to_string()),
+        logo_path: None,
+        default_currency: Currency::EUR,
+        default_payment_terms: PaymentTerms::Net30,
+        theme_preference: "system".to_string(),
+        pdf_export_dir: None,
+        pdf_filename_pattern: "Invoice_{number}.pdf".to_string(),
+        pdf_backend: "native".to_string(),
+        pdf_archival: false,
+        browser_path: None,
+        created_at: now,
+        updated_at: now,
+    };
+    (invoice, client, profile)
+}
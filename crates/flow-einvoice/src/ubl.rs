@@ -0,0 +1,227 @@
+//! UBL 2.1 invoices following Peppol BIS Billing 3.0.
+
+use flow_core::models::{Address, BusinessProfile, Client, Invoice};
+use rust_decimal::Decimal;
+
+use crate::buyer::{buyer_name, buyer_reference, order_reference};
+use crate::country::country_code;
+use crate::error::EInvoiceError;
+use crate::tax::VatBreakdown;
+use crate::validation::validate;
+use crate::xml::{amount, decimal, XmlWriter};
+
+pub const CUSTOMIZATION_ID: &str = "urn:cen.eu:en16931:2017#compliant#urn:fdc:peppol.eu:2017:poacc:billing:3.0";
+pub const PROFILE_ID: &str = "urn:fdc:peppol.eu:2017:poacc:billing:01:1.0";
+
+const INVOICE_NS: &str = "urn:oasis:names:specification:ubl:schema:xsd:Invoice-2";
+const CAC_NS: &str = "urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2";
+const CBC_NS: &str = "urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2";
+
+/// Validates the invoice and renders it as UBL 2.1 XML.
+pub fn to_ubl(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> Result<String, EInvoiceError> {
+    let issues = validate(invoice, client, profile);
+    if !issues.is_empty() {
+        return Err(EInvoiceError::Invalid(issues));
+    }
+    Ok(render(invoice, client, profile))
+}
+
+/// Renders UBL without validating; missing mandatory data produces an invalid document.
+pub fn render(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> String {
+    let currency = invoice.currency.to_string();
+    let cur = [("currencyID", currency.as_str())];
+    let vat = VatBreakdown::for_invoice(invoice);
+    let rate = decimal(vat.rate, 2);
+
+    let mut xml = XmlWriter::new();
+    xml.open("Invoice", &[("xmlns", INVOICE_NS), ("xmlns:cac", CAC_NS), ("xmlns:cbc", CBC_NS)]);
+    xml.leaf("cbc:CustomizationID", &[], CUSTOMIZATION_ID);
+    xml.leaf("cbc:ProfileID", &[], PROFILE_ID);
+    xml.leaf("cbc:ID", &[], &invoice.number);
+    xml.leaf("cbc:IssueDate", &[], &invoice.issue_date.format("%Y-%m-%d").to_string());
+    xml.leaf("cbc:DueDate", &[], &invoice.due_date.format("%Y-%m-%d").to_string());
+    xml.leaf("cbc:InvoiceTypeCode", &[], "380");
+    xml.leaf_opt("cbc:Note", invoice.terms_and_conditions.as_deref());
+    xml.leaf("cbc:DocumentCurrencyCode", &[], &currency);
+    xml.leaf_opt("cbc:BuyerReference", buyer_reference(invoice, client));
+    if let Some(order) = order_reference(invoice) {
+        xml.open("cac:OrderReference", &[]);
+        xml.leaf("cbc:ID", &[], order);
+        xml.close("cac:OrderReference");
+    }
+
+    xml.open("cac:AccountingSupplierParty", &[]);
+    party(&mut xml, &profile.name, &profile.address, profile.email.as_deref(), profile.phone.as_deref(), profile.tax_id.as_deref());
+    xml.close("cac:AccountingSupplierParty");
+
+    xml.open("cac:AccountingCustomerParty", &[]);
+    party(&mut xml, buyer_name(client), &client.address, client.email.as_deref(), client.phone.as_deref(), client.tax_id.as_deref());
+    xml.close("cac:AccountingCustomerParty");
+
+    if invoice.discount_total > Decimal::ZERO {
+        xml.open("cac:AllowanceCharge", &[]);
+        xml.leaf("cbc:ChargeIndicator", &[], "false");
+        xml.leaf("cbc:AllowanceChargeReason", &[], "Discount");
+        xml.leaf("cbc:Amount", &cur, &amount(invoice.discount_total));
+        tax_category(&mut xml, "cac:TaxCategory", &vat, &rate, false);
+        xml.close("cac:AllowanceCharge");
+    }
+
+    xml.open("cac:TaxTotal", &[]);
+    xml.leaf("cbc:TaxAmount", &cur, &amount(vat.tax_amount));
+    xml.open("cac:TaxSubtotal", &[]);
+    xml.leaf("cbc:TaxableAmount", &cur, &amount(vat.taxable_amount));
+    xml.leaf("cbc:TaxAmount", &cur, &amount(vat.tax_amount));
+    tax_category(&mut xml, "cac:TaxCategory", &vat, &rate, true);
+    xml.close("cac:TaxSubtotal");
+    xml.close("cac:TaxTotal");
+
+    xml.open("cac:LegalMonetaryTotal", &[]);
+    xml.leaf("cbc:LineExtensionAmount", &cur, &amount(invoice.subtotal));
+    xml.leaf("cbc:TaxExclusiveAmount", &cur, &amount(vat.taxable_amount));
+    xml.leaf("cbc:TaxInclusiveAmount", &cur, &amount(invoice.total));
+    xml.leaf("cbc:AllowanceTotalAmount", &cur, &amount(invoice.discount_total));
+    xml.leaf("cbc:PrepaidAmount", &cur, &amount(invoice.amount_paid));
+    xml.leaf("cbc:PayableAmount", &cur, &amount(invoice.amount_due));
+    xml.close("cac:LegalMonetaryTotal");
+
+    for (index, item) in invoice.items.iter().enumerate() {
+        let description = item.description.trim();
+        let (name, rest) = description.split_once('\n').unwrap_or((description, ""));
+
+        xml.open("cac:InvoiceLine", &[]);
+        xml.leaf("cbc:ID", &[], &(index + 1).to_string());
+        xml.leaf("cbc:InvoicedQuantity", &[("unitCode", "C62")], &decimal(item.quantity, 4));
+        xml.leaf("cbc:LineExtensionAmount", &cur, &amount(item.amount));
+        xml.open("cac:Item", &[]);
+        if !rest.trim().is_empty() {
+            xml.leaf("cbc:Description", &[], description);
+        }
+        xml.leaf("cbc:Name", &[], name.trim());
+        tax_category(&mut xml, "cac:ClassifiedTaxCategory", &vat, &rate, false);
+        xml.close("cac:Item");
+        xml.open("cac:Price", &[]);
+        xml.leaf("cbc:PriceAmount", &cur, &decimal(item.unit_price, 4));
+        xml.close("cac:Price");
+        xml.close("cac:InvoiceLine");
+    }
+
+    xml.close("Invoice");
+    xml.finish()
+}
+
+fn party(xml: &mut XmlWriter, name: &str, address: &Address, email: Option<&str>, phone: Option<&str>, vat_id: Option<&str>) {
+    let email = email.map(str::trim).filter(|e| !e.is_empty());
+    xml.open("cac:Party", &[]);
+    if let Some(email) = email {
+        xml.leaf("cbc:EndpointID", &[("schemeID", "EM")], email);
+    }
+    xml.open("cac:PartyName", &[]);
+    xml.leaf("cbc:Name", &[], name);
+    xml.close("cac:PartyName");
+
+    xml.open("cac:PostalAddress", &[]);
+    xml.leaf_opt("cbc:StreetName", Some(&address.line1));
+    xml.leaf_opt("cbc:AdditionalStreetName", address.line2.as_deref());
+    xml.leaf_opt("cbc:CityName", Some(&address.city));
+    xml.leaf_opt("cbc:PostalZone", Some(&address.postal_code));
+    xml.leaf_opt("cbc:CountrySubentity", address.state.as_deref());
+    if let Some(code) = country_code(&address.country) {
+        xml.open("cac:Country", &[]);
+        xml.leaf("cbc:IdentificationCode", &[], code);
+        xml.close("cac:Country");
+    }
+    xml.close("cac:PostalAddress");
+
+    if let Some(vat_id) = vat_id.map(str::trim).filter(|v| !v.is_empty()) {
+        xml.open("cac:PartyTaxScheme", &[]);
+        xml.leaf("cbc:CompanyID", &[], vat_id);
+        tax_scheme(xml);
+        xml.close("cac:PartyTaxScheme");
+    }
+
+    xml.open("cac:PartyLegalEntity", &[]);
+    xml.leaf("cbc:RegistrationName", &[], name);
+    xml.close("cac:PartyLegalEntity");
+
+    if email.is_some() || phone.is_some_and(|p| !p.trim().is_empty()) {
+        xml.open("cac:Contact", &[]);
+        xml.leaf_opt("cbc:Telephone", phone);
+        xml.leaf_opt("cbc:ElectronicMail", email);
+        xml.close("cac:Contact");
+    }
+    xml.close("cac:Party");
+}
+
+fn tax_category(xml: &mut XmlWriter, tag: &str, vat: &VatBreakdown, rate: &str, with_reason: bool) {
+    xml.open(tag, &[]);
+    xml.leaf("cbc:ID", &[], vat.category);
+    xml.leaf("cbc:Percent", &[], rate);
+    if with_reason {
+        xml.leaf_opt("cbc:TaxExemptionReason", vat.exemption_reason);
+    }
+    tax_scheme(xml);
+    xml.close(tag);
+}
+
+fn tax_scheme(xml: &mut XmlWriter) {
+    xml.open("cac:TaxScheme", &[]);
+    xml.leaf("cbc:ID", &[], "VAT");
+    xml.close("cac:TaxScheme");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+    use flow_core::models::ProjectDetail;
+
+    #[test]
+    fn test_valid_invoice_renders_peppol_ubl() {
+        let (invoice, client, profile) = fixture();
+        let xml = to_ubl(&invoice, &client, &profile).unwrap();
+        assert!(xml.contains(&format!("<cbc:CustomizationID>{}</cbc:CustomizationID>", CUSTOMIZATION_ID)));
+        assert!(xml.contains("<cbc:EndpointID schemeID=\"EM\">billing@studio.example</cbc:EndpointID>"));
+        assert!(xml.contains("<cbc:IdentificationCode>DE</cbc:IdentificationCode>"));
+        assert!(xml.contains("<cbc:Percent>19.00</cbc:Percent>"));
+        assert!(xml.contains("<cbc:PayableAmount currencyID=\"EUR\">1428.00</cbc:PayableAmount>"));
+        assert!(xml.contains("<cbc:Name>Consulting &amp; workshops</cbc:Name>"));
+        assert!(xml.contains("<cac:OrderReference>") && xml.contains("<cbc:ID>4500012345</cbc:ID>"));
+        assert!(!xml.contains("<cbc:BuyerReference>"));
+        assert_eq!(xml.matches("<cac:InvoiceLine>").count(), 2);
+    }
+
+    #[test]
+    fn test_buyer_or_order_reference_is_required() {
+        let (mut invoice, mut client, profile) = fixture();
+        invoice.project_details.clear();
+        client.company = Some(" ".to_string());
+
+        let Err(EInvoiceError::Invalid(issues)) = to_ubl(&invoice, &client, &profile) else {
+            panic!("expected validation to fail");
+        };
+        let rules: Vec<&str> = issues.iter().map(|issue| issue.rule).collect();
+        assert_eq!(rules, ["PEPPOL-EN16931-R003"]);
+
+        invoice.project_details.push(ProjectDetail { label: "Your Reference".to_string(), value: "Q2 rollout".to_string() });
+        let xml = to_ubl(&invoice, &client, &profile).unwrap();
+        assert!(xml.contains("<cbc:BuyerReference>Q2 rollout</cbc:BuyerReference>"));
+        assert!(!xml.contains("<cac:OrderReference>"));
+        assert!(xml.contains("<cbc:RegistrationName>Erika Mustermann</cbc:RegistrationName>"));
+    }
+
+    #[test]
+    fn test_missing_mandatory_fields_are_reported() {
+        let (mut invoice, mut client, mut profile) = fixture();
+        profile.tax_id = None;
+        client.email = None;
+        client.address.country = "Atlantis".to_string();
+        invoice.total = Decimal::from(1500);
+
+        let Err(EInvoiceError::Invalid(issues)) = to_ubl(&invoice, &client, &profile) else {
+            panic!("expected validation to fail");
+        };
+        let rules: Vec<&str> = issues.iter().map(|issue| issue.rule).collect();
+        assert_eq!(rules, ["PEPPOL-EN16931-R010", "BR-11", "BR-CO-15", "BR-CO-16", "BR-S-02"]);
+    }
+}
@@ -0,0 +1,128 @@
+//! Local checks for the Peppol BIS Billing 3.0 / EN 16931 rules that depend on data
+//! InvoiceFlow stores. Rule identifiers match the official schematron so messages can be
+//! looked up in the Peppol documentation.
+
+use flow_core::models::{Address, BusinessProfile, Client, Invoice};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fmt;
+
+use crate::buyer::{buyer_name, buyer_reference, order_reference};
+use crate::country::country_code;
+use crate::tax::VatBreakdown;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    /// Schematron rule identifier, e.g. `BR-06` or `PEPPOL-EN16931-R020`.
+    pub rule: &'static str,
+    /// The InvoiceFlow field that needs to be filled in or corrected.
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.rule, self.message)
+    }
+}
+
+/// Returns every rule the invoice violates; an empty list means it can be exported.
+pub fn validate(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut fail = |rule: &'static str, field: &'static str, message: &str| {
+        issues.push(ValidationIssue { rule, field, message: message.to_string() });
+    };
+
+    if invoice.number.trim().is_empty() {
+        fail("BR-02", "invoice.number", "Invoice number is required");
+    }
+    let currency = invoice.currency.to_string();
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+        fail("BR-05", "invoice.currency", "Currency must be an ISO 4217 code such as EUR");
+    }
+    if invoice.items.is_empty() {
+        fail("BR-16", "invoice.items", "Invoice must have at least one line item");
+    }
+
+    if profile.name.trim().is_empty() {
+        fail("BR-06", "profile.name", "Seller name is required");
+    }
+    if is_blank(profile.email.as_deref()) {
+        fail("PEPPOL-EN16931-R020", "profile.email", "Seller electronic address (email) is required");
+    }
+    check_address(&profile.address, "BR-08", "profile.address", "Seller", &mut fail);
+
+    if buyer_name(client).trim().is_empty() {
+        fail("BR-07", "client.name", "Buyer name is required");
+    }
+    if is_blank(client.email.as_deref()) {
+        fail("PEPPOL-EN16931-R010", "client.email", "Buyer electronic address (email) is required");
+    }
+    check_address(&client.address, "BR-10", "client.address", "Buyer", &mut fail);
+    if buyer_reference(invoice, client).is_none() && order_reference(invoice).is_none() {
+        fail(
+            "PEPPOL-EN16931-R003",
+            "invoice.custom_fields",
+            "A buyer reference or purchase order number is required; add a \"PO Number\" or \"Buyer Reference\" field",
+        );
+    }
+
+    for item in &invoice.items {
+        if item.description.trim().is_empty() {
+            fail("BR-25", "invoice.items.description", "Every line item needs a description");
+        }
+        if item.unit_price < Decimal::ZERO {
+            fail("BR-27", "invoice.items.unit_price", "Item net price must not be negative");
+        }
+        if (item.quantity * item.unit_price).round_dp(2) != item.amount.round_dp(2) {
+            fail(
+                "PEPPOL-EN16931-R120",
+                "invoice.items.amount",
+                &format!("Line amount for \"{}\" must equal quantity × unit price", item.description.trim()),
+            );
+        }
+    }
+
+    let line_total: Decimal = invoice.items.iter().map(|item| item.amount).sum();
+    if line_total.round_dp(2) != invoice.subtotal.round_dp(2) {
+        fail("BR-CO-10", "invoice.subtotal", "Subtotal must equal the sum of the line amounts");
+    }
+    let vat = VatBreakdown::for_invoice(invoice);
+    if (vat.taxable_amount + vat.tax_amount).round_dp(2) != invoice.total.round_dp(2) {
+        fail("BR-CO-15", "invoice.total", "Total must equal subtotal minus discount plus tax");
+    }
+    if (invoice.total - invoice.amount_paid).round_dp(2) != invoice.amount_due.round_dp(2) {
+        fail("BR-CO-16", "invoice.amount_due", "Amount due must equal total minus amount paid");
+    }
+    if vat.category == "S" && is_blank(profile.tax_id.as_deref()) {
+        fail("BR-S-02", "profile.tax_id", "Seller VAT identifier is required when VAT is charged");
+    }
+
+    issues
+}
+
+fn check_address(
+    address: &Address,
+    rule: &'static str,
+    field: &'static str,
+    party: &str,
+    fail: &mut impl FnMut(&'static str, &'static str, &str),
+) {
+    // BR-08/BR-10 require the postal address group, BR-09/BR-11 its country code.
+    let country_rule = if rule == "BR-08" { "BR-09" } else { "BR-11" };
+    if address.city.trim().is_empty() && address.line1.trim().is_empty() {
+        fail(rule, field, &format!("{} postal address is required", party));
+    }
+    if country_code(&address.country).is_none() {
+        let message = if address.country.trim().is_empty() {
+            format!("{} country is required", party)
+        } else {
+            format!("{} country \"{}\" is not a recognised country", party, address.country.trim())
+        };
+        fail(country_rule, field, &message);
+    }
+}
+
+fn is_blank(value: Option<&str>) -> bool {
+    value.map(str::trim).is_none_or(str::is_empty)
+}
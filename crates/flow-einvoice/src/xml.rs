@@ -0,0 +1,117 @@
+//! XML text helpers shared by the exporters here and the Factur-X data in flow-pdf.
+
+use rust_decimal::Decimal;
+
+/// Minimal indented XML writer; callers are responsible for well-formed nesting.
+pub(crate) struct XmlWriter {
+    buf: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    pub(crate) fn new() -> Self {
+        Self { buf: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"), depth: 0 }
+    }
+
+    pub(crate) fn open(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        self.indent();
+        self.buf.push('<');
+        self.buf.push_str(tag);
+        self.attrs(attrs);
+        self.buf.push_str(">\n");
+        self.depth += 1;
+    }
+
+    pub(crate) fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.indent();
+        self.buf.push_str("</");
+        self.buf.push_str(tag);
+        self.buf.push_str(">\n");
+    }
+
+    pub(crate) fn leaf(&mut self, tag: &str, attrs: &[(&str, &str)], text: &str) {
+        self.indent();
+        self.buf.push('<');
+        self.buf.push_str(tag);
+        self.attrs(attrs);
+        self.buf.push('>');
+        self.buf.push_str(&escape(text));
+        self.buf.push_str("</");
+        self.buf.push_str(tag);
+        self.buf.push_str(">\n");
+    }
+
+    /// Writes the element only when `text` has non-whitespace content.
+    pub(crate) fn leaf_opt(&mut self, tag: &str, text: Option<&str>) {
+        if let Some(text) = text.map(str::trim).filter(|t| !t.is_empty()) {
+            self.leaf(tag, &[], text);
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.buf
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.buf.push_str("  ");
+        }
+    }
+
+    fn attrs(&mut self, attrs: &[(&str, &str)]) {
+        for (name, value) in attrs {
+            self.buf.push(' ');
+            self.buf.push_str(name);
+            self.buf.push_str("=\"");
+            self.buf.push_str(&escape(value));
+            self.buf.push('"');
+        }
+    }
+}
+
+/// Escapes text for element content or attribute values, dropping control characters XML forbids.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if c.is_control() && !matches!(c, '\n' | '\t' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Monetary amount with exactly two decimals, as required for EN 16931 amounts.
+pub fn amount(value: Decimal) -> String {
+    format!("{:.2}", value.round_dp(2))
+}
+
+/// Quantity or unit price with at most `places` decimals and at least two.
+pub fn decimal(value: Decimal, places: u32) -> String {
+    let rounded = value.round_dp(places).normalize();
+    if rounded.scale() < 2 {
+        format!("{:.2}", rounded)
+    } else {
+        rounded.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_and_number_formatting() {
+        assert_eq!(escape("Tom & \"Jerry\" <x>\u{1}"), "Tom &amp; &quot;Jerry&quot; &lt;x&gt;");
+        assert_eq!(amount(Decimal::new(12346, 3)), "12.35");
+        assert_eq!(decimal(Decimal::new(15, 0), 2), "15.00");
+        assert_eq!(decimal(Decimal::new(12345, 4), 4), "1.2345");
+        assert_eq!(decimal(Decimal::new(1200, 3), 4), "1.20");
+    }
+}
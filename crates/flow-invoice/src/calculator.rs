@@ -67,7 +67,9 @@ mod tests {
             unit_price: Decimal::from_str_exact(price).unwrap(),
             amount: Decimal::zero(),
             tax_rate_name: None,
+            hsn_code: None,
             sort_order: 0,
+            custom_fields: vec![],
         }
     }
 
@@ -0,0 +1,179 @@
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::{BusinessProfile, Client, Invoice};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use crate::browser::discover_browser;
+use crate::error::{Context, PdfError};
+use crate::native::NativeBackend;
+
+/// A renderer that turns an invoice into PDF bytes.
+pub trait PdfBackend: Send + Sync {
+    /// Identifier stored in the `pdf_backend` preference.
+    fn name(&self) -> &'static str;
+
+    fn render(&self, invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> FlowResult<Vec<u8>>;
+}
+
+/// Pick a backend from the profile's `pdf_backend` preference, defaulting to the native renderer.
+pub fn backend_for_profile(profile: &BusinessProfile) -> Box<dyn PdfBackend> {
+    match profile.pdf_backend.as_str() {
+        BrowserBackend::NAME => Box::new(BrowserBackend::for_profile(profile)),
+        _ => Box::new(NativeBackend::new()),
+    }
+}
+
+/// Prints the HTML template through a system-installed Chromium-based browser in headless mode.
+#[derive(Debug, Clone, Default)]
+pub struct BrowserBackend {
+    executable: Option<PathBuf>,
+}
+
+impl BrowserBackend {
+    pub const NAME: &'static str = "browser";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `path` instead of searching for an installed browser.
+    pub fn with_executable(path: impl Into<PathBuf>) -> Self {
+        Self { executable: Some(path.into()) }
+    }
+
+    /// Uses the profile's `browser_path` when set, otherwise searches for an installed browser.
+    pub fn for_profile(profile: &BusinessProfile) -> Self {
+        match profile.browser_path.as_deref().map(str::trim) {
+            Some(path) if !path.is_empty() => Self::with_executable(path),
+            _ => Self::new(),
+        }
+    }
+
+    /// Resolves the browser to launch: the configured executable if set, otherwise the first one discovered.
+    pub fn locate(&self) -> Result<PathBuf, PdfError> {
+        match &self.executable {
+            Some(path) if path.is_file() => Ok(path.clone()),
+            Some(path) => Err(PdfError::ConfiguredBrowserMissing(path.clone())),
+            None => discover_browser(),
+        }
+    }
+
+    /// Prints `url` to `output_path`.
+    /// Uses a persistent user-data-dir to avoid slow cold-start profile creation.
+    pub fn print_to_pdf(&self, url: &str, output_path: &Path) -> FlowResult<()> {
+        let exe_path = self.locate()?;
+
+        // Use a persistent user-data-dir so the browser reuses its cached profile
+        // instead of creating a fresh temporary one every time (~2-3s saved)
+        let pdf_profile_dir = std::env::temp_dir().join("invoiceflow_pdf_profile");
+        fs::create_dir_all(&pdf_profile_dir).ok();
+
+        let mut cmd = Command::new(&exe_path);
+
+        #[cfg(windows)]
+        {
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let status = cmd
+            .args([
+                "--headless=new",
+                "--disable-gpu",
+                "--no-sandbox",
+                "--no-pdf-header-footer",
+                "--disable-software-rasterizer",
+                "--run-all-compositor-stages-before-draw",
+                // Performance flags to minimize startup time
+                "--disable-extensions",
+                "--disable-background-networking",
+                "--disable-sync",
+                "--disable-default-apps",
+                "--no-first-run",
+                "--disable-features=TranslateUI",
+                "--disable-component-update",
+                "--disable-hang-monitor",
+                "--disable-prompt-on-repost",
+                "--disable-domain-reliability",
+                "--disable-client-side-phishing-detection",
+                "--disable-breakpad",
+                // Reuse a persistent profile directory for faster startup
+                &format!("--user-data-dir={}", pdf_profile_dir.display()),
+                // Virtual time budget so the page doesn't wait for real timers
+                "--virtual-time-budget=5000",
+                &format!("--print-to-pdf={}", output_path.display()),
+                url,
+            ])
+            .status()
+            .context("Failed to execute browser process for PDF generation")?;
+
+        if !status.success() {
+            return Err(FlowError::PdfGeneration(format!("Browser process failed with status: {}", status)));
+        }
+
+        if !output_path.exists() {
+            return Err(FlowError::PdfGeneration("PDF file was not created at expected path".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Writes `html` to a temporary file and prints it to PDF bytes.
+    pub fn print_html(&self, html: &str) -> FlowResult<Vec<u8>> {
+        let temp_dir = std::env::temp_dir();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros();
+        let temp_html_path = temp_dir.join(format!("invoice_flow_{}.html", timestamp));
+        let temp_pdf_path = temp_dir.join(format!("invoice_flow_{}.pdf", timestamp));
+
+        fs::write(&temp_html_path, html).context("Failed to write temporary HTML file")?;
+
+        let url = format!("file:///{}", temp_html_path.to_string_lossy().replace("\\", "/"));
+
+        let result = self
+            .print_to_pdf(&url, &temp_pdf_path)
+            .and_then(|_| fs::read(&temp_pdf_path).context("Failed to read generated PDF"));
+
+        let _ = fs::remove_file(temp_html_path);
+        let _ = fs::remove_file(temp_pdf_path);
+
+        result
+    }
+}
+
+impl PdfBackend for BrowserBackend {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn render(&self, invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> FlowResult<Vec<u8>> {
+        if profile.pdf_archival {
+            return Err(PdfError::ArchivalUnsupported { backend: Self::NAME }.into());
+        }
+        let html = crate::template::render_invoice_html(invoice, client, profile);
+        self.print_html(&html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::tests::fixture;
+
+    #[test]
+    fn test_browser_comes_from_the_profile_when_configured() {
+        let (_, _, mut profile) = fixture();
+        profile.browser_path = Some("  ".to_string());
+        assert_eq!(BrowserBackend::for_profile(&profile).executable, None);
+
+        profile.browser_path = Some(" /nonexistent/chrome ".to_string());
+        let err = BrowserBackend::for_profile(&profile).locate().unwrap_err();
+        assert!(matches!(err, PdfError::ConfiguredBrowserMissing(path) if path == Path::new("/nonexistent/chrome")));
+    }
+}
@@ -0,0 +1,168 @@
+//! Locates a Chromium-based browser for the headless print backend.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use crate::error::PdfError;
+
+/// Executable names looked up on Unix-like systems, in order of preference.
+const UNIX_NAMES: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "microsoft-edge",
+    "microsoft-edge-stable",
+    "brave-browser",
+    "brave",
+];
+
+/// Flatpak application ids exported as launchers under `exports/bin`.
+const FLATPAK_IDS: &[&str] = &[
+    "org.chromium.Chromium",
+    "com.google.Chrome",
+    "com.microsoft.Edge",
+    "com.brave.Browser",
+];
+
+/// Executables inside macOS application bundles.
+const MACOS_BUNDLES: &[&str] = &[
+    "Google Chrome.app/Contents/MacOS/Google Chrome",
+    "Chromium.app/Contents/MacOS/Chromium",
+    "Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    "Brave Browser.app/Contents/MacOS/Brave Browser",
+];
+
+/// Install locations relative to `Program Files` or `%LOCALAPPDATA%` on Windows.
+const WINDOWS_INSTALLS: &[&str] = &[
+    r"Microsoft\Edge\Application\msedge.exe",
+    r"Google\Chrome\Application\chrome.exe",
+    r"BraveSoftware\Brave-Browser\Application\brave.exe",
+    r"Chromium\Application\chrome.exe",
+];
+
+/// Returns the first installed browser, or every path that was probed.
+pub fn discover_browser() -> Result<PathBuf, PdfError> {
+    let searched = candidate_paths(std::env::consts::OS, |key| std::env::var_os(key));
+    match searched.iter().find(|path| path.is_file()) {
+        Some(path) => Ok(path.clone()),
+        None => Err(PdfError::BrowserNotFound { searched }),
+    }
+}
+
+/// Every location worth probing on `os`, most preferred first and without duplicates.
+pub(crate) fn candidate_paths(os: &str, env: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let home = env("HOME").map(PathBuf::from);
+
+    match os {
+        "windows" => {
+            let roots = [
+                env("ProgramFiles(x86)").unwrap_or_else(|| r"C:\Program Files (x86)".into()),
+                env("ProgramFiles").unwrap_or_else(|| r"C:\Program Files".into()),
+            ];
+            let local = env("LOCALAPPDATA");
+            for install in WINDOWS_INSTALLS {
+                for root in roots.iter().chain(local.iter()) {
+                    paths.push(PathBuf::from(root).join(install));
+                }
+            }
+        }
+        "macos" => {
+            let roots = std::iter::once(PathBuf::from("/Applications"))
+                .chain(home.iter().map(|h| h.join("Applications")));
+            for root in roots {
+                for bundle in MACOS_BUNDLES {
+                    paths.push(root.join(bundle));
+                }
+            }
+            paths.extend(unix_search_path(&env));
+        }
+        _ => {
+            paths.extend(unix_search_path(&env));
+            for dir in ["/usr/bin", "/usr/local/bin", "/snap/bin"] {
+                for name in UNIX_NAMES {
+                    paths.push(PathBuf::from(dir).join(name));
+                }
+            }
+            let flatpak_dirs = std::iter::once(PathBuf::from("/var/lib/flatpak/exports/bin"))
+                .chain(home.iter().map(|h| h.join(".local/share/flatpak/exports/bin")));
+            for dir in flatpak_dirs {
+                for id in FLATPAK_IDS {
+                    paths.push(dir.join(id));
+                }
+            }
+        }
+    }
+
+    let mut unique = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !unique.contains(&path) {
+            unique.push(path);
+        }
+    }
+    unique
+}
+
+fn unix_search_path(env: &impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let Some(path) = env("PATH") else {
+        return Vec::new();
+    };
+    let dirs: Vec<PathBuf> = path
+        .to_string_lossy()
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    UNIX_NAMES
+        .iter()
+        .flat_map(|name| dirs.iter().map(move |dir| dir.join(name)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let map: HashMap<String, OsString> = vars.iter().map(|(k, v)| (k.to_string(), OsString::from(v))).collect();
+        move |key| map.get(key).cloned()
+    }
+
+    #[test]
+    fn test_linux_candidates_cover_path_snap_and_flatpak() {
+        let paths = candidate_paths("linux", env(&[("PATH", "/opt/bin:/usr/bin"), ("HOME", "/home/jabir")]));
+        assert_eq!(paths[0], PathBuf::from("/opt/bin/chromium"));
+        assert!(paths.contains(&PathBuf::from("/snap/bin/chromium")));
+        assert!(paths.contains(&PathBuf::from("/var/lib/flatpak/exports/bin/org.chromium.Chromium")));
+        assert!(paths.contains(&PathBuf::from("/home/jabir/.local/share/flatpak/exports/bin/com.brave.Browser")));
+        let usr_bin = paths.iter().filter(|p| p.as_path() == Path::new("/usr/bin/chromium")).count();
+        assert_eq!(usr_bin, 1);
+    }
+
+    #[test]
+    fn test_macos_candidates_prefer_app_bundles() {
+        let paths = candidate_paths("macos", env(&[("HOME", "/Users/jabir")]));
+        assert_eq!(paths[0], PathBuf::from("/Applications/Google Chrome.app/Contents/MacOS/Google Chrome"));
+        assert!(paths.contains(&PathBuf::from("/Users/jabir/Applications/Brave Browser.app/Contents/MacOS/Brave Browser")));
+    }
+
+    #[test]
+    fn test_windows_candidates_use_program_files_env() {
+        let paths = candidate_paths("windows", env(&[("ProgramFiles", r"D:\Apps")]));
+        assert!(paths.contains(&PathBuf::from(r"D:\Apps").join(r"Microsoft\Edge\Application\msedge.exe")));
+        assert!(paths.contains(&PathBuf::from(r"C:\Program Files (x86)").join(r"Google\Chrome\Application\chrome.exe")));
+    }
+
+    #[test]
+    fn test_not_found_error_lists_searched_paths() {
+        let err = PdfError::BrowserNotFound {
+            searched: vec![PathBuf::from("/usr/bin/chromium"), PathBuf::from("/snap/bin/chromium")],
+        };
+        let message = err.to_string();
+        assert!(message.contains("/usr/bin/chromium, /snap/bin/chromium"));
+        assert_eq!(err.searched_paths().len(), 2);
+    }
+}
@@ -0,0 +1,541 @@
+//! Minimal drawing surface over `pdf-writer` used by the native backend.
+//! Coordinates are in points with the origin at the top-left corner of the page.
+
+use base64::Engine;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flow_core::error::{FlowError, FlowResult};
+use pdf_writer::types::{CidFontType, FontFlags, SystemInfo, UnicodeCmap};
+use pdf_writer::{Content, Date, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use std::collections::BTreeMap;
+
+use crate::error::Context;
+use crate::pdfa::{self, Archival, ArchivalRefs};
+
+pub(crate) const PAGE_WIDTH: f32 = 595.28;
+pub(crate) const PAGE_HEIGHT: f32 = 841.89;
+
+static REGULAR_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+static BOLD_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf");
+
+const SYSTEM_INFO: SystemInfo = SystemInfo {
+    registry: Str(b"Adobe"),
+    ordering: Str(b"Identity"),
+    supplement: 0,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Weight {
+    Regular,
+    Bold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    pub(crate) const fn hex(value: u32) -> Self {
+        Self((value >> 16) as u8, (value >> 8) as u8, value as u8)
+    }
+
+    fn components(self) -> (f32, f32, f32) {
+        (self.0 as f32 / 255.0, self.1 as f32 / 255.0, self.2 as f32 / 255.0)
+    }
+}
+
+/// Document-level metadata written into the info dictionary and trailer.
+pub(crate) struct DocumentInfo {
+    pub title: String,
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+    pub file_id: [u8; 16],
+    /// Write PDF/A-3b instead of a plain PDF.
+    pub archival: Option<Archival>,
+}
+
+struct Font {
+    data: &'static [u8],
+    face: ttf_parser::Face<'static>,
+    postscript_name: &'static str,
+    resource: &'static [u8],
+    /// Glyphs drawn so far, mapped back to the character they represent.
+    glyphs: BTreeMap<u16, char>,
+}
+
+impl Font {
+    fn load(data: &'static [u8], postscript_name: &'static str, resource: &'static [u8]) -> Self {
+        let face = ttf_parser::Face::parse(data, 0).expect("bundled font is a valid TrueType file");
+        Self { data, face, postscript_name, resource, glyphs: BTreeMap::new() }
+    }
+
+    /// Converts font units into the 1/1000 text space PDF widths use.
+    fn to_pdf_units(&self, value: f32) -> f32 {
+        value * 1000.0 / self.face.units_per_em() as f32
+    }
+
+    fn glyph(&self, c: char) -> u16 {
+        self.face.glyph_index(c).map(|g| g.0).unwrap_or(0)
+    }
+
+    fn advance(&self, glyph: u16) -> f32 {
+        let advance = self.face.glyph_hor_advance(ttf_parser::GlyphId(glyph)).unwrap_or(0);
+        self.to_pdf_units(advance as f32)
+    }
+
+    fn width(&self, text: &str, size: f32) -> f32 {
+        text.chars().map(|c| self.advance(self.glyph(c))).sum::<f32>() * size / 1000.0
+    }
+
+    /// Encodes `text` as big-endian glyph ids for the Identity-H encoding.
+    fn encode(&mut self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(text.len() * 2);
+        for c in text.chars() {
+            let glyph = self.glyph(c);
+            self.glyphs.entry(glyph).or_insert(c);
+            bytes.extend_from_slice(&glyph.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Six-letter subset prefix derived from the glyph set so identical input yields identical output.
+    fn subset_tag(glyphs: &[u16]) -> String {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for glyph in glyphs {
+            for byte in glyph.to_be_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        (0..6)
+            .map(|i| (b'A' + ((hash >> (i * 8)) % 26) as u8) as char)
+            .collect()
+    }
+
+    fn write(&self, pdf: &mut Pdf, alloc: &mut Ref, type0_id: Ref) -> FlowResult<()> {
+        let cid_id = alloc.bump();
+        let descriptor_id = alloc.bump();
+        let file_id = alloc.bump();
+        let cmap_id = alloc.bump();
+
+        let mut glyph_ids: Vec<u16> = vec![0];
+        glyph_ids.extend(self.glyphs.keys().copied().filter(|&g| g != 0));
+        let base_font = format!("{}+{}", Self::subset_tag(&glyph_ids), self.postscript_name);
+        let base_font = Name(base_font.as_bytes());
+
+        pdf.type0_font(type0_id)
+            .base_font(base_font)
+            .encoding_predefined(Name(b"Identity-H"))
+            .descendant_font(cid_id)
+            .to_unicode(cmap_id);
+
+        let mut cid = pdf.cid_font(cid_id);
+        cid.subtype(CidFontType::Type2)
+            .base_font(base_font)
+            .system_info(SYSTEM_INFO)
+            .font_descriptor(descriptor_id)
+            .default_width(0.0)
+            .cid_to_gid_map_predefined(Name(b"Identity"));
+        let mut widths = cid.widths();
+        for &glyph in &glyph_ids {
+            widths.consecutive(glyph, [self.advance(glyph)]);
+        }
+        widths.finish();
+        cid.finish();
+
+        let bbox = self.face.global_bounding_box();
+        pdf.font_descriptor(descriptor_id)
+            .name(base_font)
+            .flags(FontFlags::NON_SYMBOLIC)
+            .bbox(Rect::new(
+                self.to_pdf_units(bbox.x_min as f32),
+                self.to_pdf_units(bbox.y_min as f32),
+                self.to_pdf_units(bbox.x_max as f32),
+                self.to_pdf_units(bbox.y_max as f32),
+            ))
+            .italic_angle(0.0)
+            .ascent(self.to_pdf_units(self.face.ascender() as f32))
+            .descent(self.to_pdf_units(self.face.descender() as f32))
+            .cap_height(self.to_pdf_units(self.face.capital_height().unwrap_or(self.face.ascender()) as f32))
+            .stem_v(80.0)
+            .font_file2(file_id);
+
+        let subset = subsetter::subset(self.data, 0, subsetter::Profile::pdf(&glyph_ids))
+            .map_err(|e| FlowError::PdfGeneration(format!("Failed to subset font {}: {}", self.postscript_name, e)))?;
+        let compressed = deflate(&subset);
+        pdf.stream(file_id, &compressed)
+            .filter(Filter::FlateDecode)
+            .pair(Name(b"Length1"), subset.len() as i32);
+
+        let mut cmap = UnicodeCmap::new(Name(b"Custom"), SYSTEM_INFO);
+        for (&glyph, &c) in &self.glyphs {
+            cmap.pair(glyph, c);
+        }
+        pdf.cmap(cmap_id, &cmap.finish());
+
+        Ok(())
+    }
+}
+
+enum ImageData {
+    Jpeg { data: Vec<u8>, components: u8 },
+    Pixels { rgb: Vec<u8>, alpha: Option<Vec<u8>> },
+}
+
+/// A raster image decoded far enough to embed it in a PDF.
+pub(crate) struct Image {
+    pub width: u32,
+    pub height: u32,
+    data: ImageData,
+}
+
+impl Image {
+    /// Loads a `data:image/...;base64,` URI or a path on disk.
+    pub(crate) fn from_source(source: &str) -> FlowResult<Self> {
+        let bytes = if let Some(rest) = source.strip_prefix("data:") {
+            let (_, payload) = rest.split_once(',').context("Malformed data URI")?;
+            base64::engine::general_purpose::STANDARD
+                .decode(payload.trim())
+                .context("Invalid base64 image data")?
+        } else {
+            std::fs::read(source).with_context(|| format!("Failed to read image {}", source))?
+        };
+        Self::decode(&bytes)
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> FlowResult<Self> {
+        if bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+            Self::decode_png(bytes)
+        } else if bytes.starts_with(&[0xFF, 0xD8]) {
+            Self::decode_jpeg(bytes)
+        } else {
+            Err(FlowError::PdfGeneration("Unsupported image format (expected PNG or JPEG)".to_string()))
+        }
+    }
+
+    fn decode_png(bytes: &[u8]) -> FlowResult<Self> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().context("Invalid PNG image")?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).context("Invalid PNG image")?;
+        let buf = &buf[..info.buffer_size()];
+
+        let pixels = (info.width * info.height) as usize;
+        let mut rgb = Vec::with_capacity(pixels * 3);
+        let mut alpha = Vec::with_capacity(pixels);
+        match info.color_type {
+            png::ColorType::Rgb => rgb.extend_from_slice(buf),
+            png::ColorType::Rgba => {
+                for px in buf.chunks_exact(4) {
+                    rgb.extend_from_slice(&px[..3]);
+                    alpha.push(px[3]);
+                }
+            }
+            png::ColorType::Grayscale => {
+                for &g in buf {
+                    rgb.extend_from_slice(&[g, g, g]);
+                }
+            }
+            png::ColorType::GrayscaleAlpha => {
+                for px in buf.chunks_exact(2) {
+                    rgb.extend_from_slice(&[px[0], px[0], px[0]]);
+                    alpha.push(px[1]);
+                }
+            }
+            png::ColorType::Indexed => return Err(FlowError::PdfGeneration("Unexpanded indexed PNG".to_string())),
+        }
+
+        let alpha = if alpha.iter().any(|&a| a != 255) { Some(alpha) } else { None };
+        Ok(Self { width: info.width, height: info.height, data: ImageData::Pixels { rgb, alpha } })
+    }
+
+    /// JPEGs are embedded as-is; only the frame header is read for dimensions.
+    fn decode_jpeg(bytes: &[u8]) -> FlowResult<Self> {
+        let mut pos = 2;
+        while pos + 4 <= bytes.len() {
+            if bytes[pos] != 0xFF {
+                pos += 1;
+                continue;
+            }
+            let marker = bytes[pos + 1];
+            let len = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+            let is_frame = matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+            if is_frame && pos + 10 <= bytes.len() {
+                let height = u16::from_be_bytes([bytes[pos + 5], bytes[pos + 6]]) as u32;
+                let width = u16::from_be_bytes([bytes[pos + 7], bytes[pos + 8]]) as u32;
+                let components = bytes[pos + 9];
+                return Ok(Self {
+                    width,
+                    height,
+                    data: ImageData::Jpeg { data: bytes.to_vec(), components },
+                });
+            }
+            pos += 2 + len;
+        }
+        Err(FlowError::PdfGeneration("Invalid JPEG image: no frame header".to_string()))
+    }
+
+    fn write(&self, pdf: &mut Pdf, alloc: &mut Ref, id: Ref) {
+        match &self.data {
+            ImageData::Jpeg { data, components } => {
+                let color_space: &[u8] = match components {
+                    1 => b"DeviceGray",
+                    4 => b"DeviceCMYK",
+                    _ => b"DeviceRGB",
+                };
+                let mut image = pdf.image_xobject(id, data);
+                image.filter(Filter::DctDecode);
+                image.width(self.width as i32);
+                image.height(self.height as i32);
+                image.color_space_name(Name(color_space));
+                image.bits_per_component(8);
+            }
+            ImageData::Pixels { rgb, alpha } => {
+                let mask_id = alpha.as_ref().map(|_| alloc.bump());
+                let compressed = deflate(rgb);
+                let mut image = pdf.image_xobject(id, &compressed);
+                image.filter(Filter::FlateDecode);
+                image.width(self.width as i32);
+                image.height(self.height as i32);
+                image.color_space_name(Name(b"DeviceRGB"));
+                image.bits_per_component(8);
+                if let Some(mask_id) = mask_id {
+                    image.s_mask(mask_id);
+                }
+                image.finish();
+
+                if let (Some(alpha), Some(mask_id)) = (alpha, mask_id) {
+                    let compressed = deflate(alpha);
+                    let mut mask = pdf.image_xobject(mask_id, &compressed);
+                    mask.filter(Filter::FlateDecode);
+                    mask.width(self.width as i32);
+                    mask.height(self.height as i32);
+                    mask.color_space_name(Name(b"DeviceGray"));
+                    mask.bits_per_component(8);
+                }
+            }
+        }
+    }
+}
+
+/// An in-memory, multi-page document built from simple drawing primitives.
+pub(crate) struct Canvas {
+    fonts: [Font; 2],
+    images: Vec<Image>,
+    pages: Vec<Content>,
+}
+
+impl Canvas {
+    pub(crate) fn new() -> Self {
+        Self {
+            fonts: [
+                Font::load(REGULAR_FONT, "DejaVuSans", b"F1"),
+                Font::load(BOLD_FONT, "DejaVuSans-Bold", b"F2"),
+            ],
+            images: Vec::new(),
+            pages: vec![Content::new()],
+        }
+    }
+
+    pub(crate) fn add_page(&mut self) {
+        self.pages.push(Content::new());
+    }
+
+    fn font(&self, weight: Weight) -> &Font {
+        match weight {
+            Weight::Regular => &self.fonts[0],
+            Weight::Bold => &self.fonts[1],
+        }
+    }
+
+    fn page(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("canvas always has a page")
+    }
+
+    pub(crate) fn text_width(&self, text: &str, size: f32, weight: Weight) -> f32 {
+        self.font(weight).width(text, size)
+    }
+
+    pub(crate) fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        let (r, g, b) = color.components();
+        self.page()
+            .set_fill_rgb(r, g, b)
+            .rect(x, PAGE_HEIGHT - y - height, width, height)
+            .fill_nonzero();
+    }
+
+    pub(crate) fn stroke_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color, line_width: f32) {
+        let (r, g, b) = color.components();
+        self.page()
+            .set_stroke_rgb(r, g, b)
+            .set_line_width(line_width)
+            .rect(x, PAGE_HEIGHT - y - height, width, height)
+            .stroke();
+    }
+
+    pub(crate) fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, color: Color, line_width: f32) {
+        let (r, g, b) = color.components();
+        self.page()
+            .set_stroke_rgb(r, g, b)
+            .set_line_width(line_width)
+            .move_to(x1, PAGE_HEIGHT - y1)
+            .line_to(x2, PAGE_HEIGHT - y2)
+            .stroke();
+    }
+
+    /// Draws a single line of text whose em box starts at `y`.
+    pub(crate) fn text(&mut self, x: f32, y: f32, size: f32, weight: Weight, color: Color, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let index = match weight {
+            Weight::Regular => 0,
+            Weight::Bold => 1,
+        };
+        let font = &mut self.fonts[index];
+        let encoded = font.encode(text);
+        let resource = font.resource;
+        let (r, g, b) = color.components();
+        self.page()
+            .begin_text()
+            .set_font(Name(resource), size)
+            .set_fill_rgb(r, g, b)
+            .next_line(x, PAGE_HEIGHT - y - size * 0.8)
+            .show(Str(&encoded))
+            .end_text();
+    }
+
+    pub(crate) fn text_right(&mut self, right: f32, y: f32, size: f32, weight: Weight, color: Color, text: &str) {
+        let width = self.text_width(text, size, weight);
+        self.text(right - width, y, size, weight, color, text);
+    }
+
+    pub(crate) fn text_centered(&mut self, center: f32, y: f32, size: f32, weight: Weight, color: Color, text: &str) {
+        let width = self.text_width(text, size, weight);
+        self.text(center - width / 2.0, y, size, weight, color, text);
+    }
+
+    /// Breaks `text` into lines no wider than `max_width`, honouring explicit newlines.
+    pub(crate) fn wrap(&self, text: &str, size: f32, weight: Weight, max_width: f32) -> Vec<String> {
+        let font = self.font(weight);
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut current = String::new();
+            for word in paragraph.split_whitespace() {
+                let candidate = if current.is_empty() { word.to_string() } else { format!("{} {}", current, word) };
+                if font.width(&candidate, size) <= max_width || current.is_empty() {
+                    current = candidate;
+                } else {
+                    lines.push(std::mem::replace(&mut current, word.to_string()));
+                }
+            }
+            lines.push(current);
+        }
+        lines
+    }
+
+    pub(crate) fn add_image(&mut self, image: Image) -> usize {
+        self.images.push(image);
+        self.images.len() - 1
+    }
+
+    pub(crate) fn image_size(&self, index: usize) -> (u32, u32) {
+        let image = &self.images[index];
+        (image.width, image.height)
+    }
+
+    pub(crate) fn draw_image(&mut self, index: usize, x: f32, y: f32, width: f32, height: f32) {
+        let name = format!("Im{}", index);
+        self.page()
+            .save_state()
+            .transform([width, 0.0, 0.0, height, x, PAGE_HEIGHT - y - height])
+            .x_object(Name(name.as_bytes()))
+            .restore_state();
+    }
+
+    /// Serializes the document. Output depends only on what was drawn and on `info`.
+    pub(crate) fn finish(self, info: &DocumentInfo) -> FlowResult<Vec<u8>> {
+        let mut pdf = Pdf::new();
+        let mut alloc = Ref::new(1);
+
+        let catalog_id = alloc.bump();
+        let page_tree_id = alloc.bump();
+        let info_id = alloc.bump();
+        let font_ids: Vec<Ref> = self.fonts.iter().map(|_| alloc.bump()).collect();
+        let image_ids: Vec<Ref> = self.images.iter().map(|_| alloc.bump()).collect();
+        let page_ids: Vec<Ref> = self.pages.iter().map(|_| alloc.bump()).collect();
+        let content_ids: Vec<Ref> = self.pages.iter().map(|_| alloc.bump()).collect();
+        let archival_refs = info.archival.as_ref().map(|archival| ArchivalRefs::allocate(archival, &mut alloc));
+
+        let mut catalog = pdf.catalog(catalog_id);
+        catalog.pages(page_tree_id);
+        if let (Some(archival), Some(refs)) = (&info.archival, &archival_refs) {
+            pdfa::write_catalog_entries(&mut catalog, archival, refs);
+        }
+        catalog.finish();
+        pdf.pages(page_tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+
+        for ((content, &page_id), &content_id) in self.pages.into_iter().zip(&page_ids).zip(&content_ids) {
+            let mut page = pdf.page(page_id);
+            page.parent(page_tree_id)
+                .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+                .contents(content_id);
+            let mut resources = page.resources();
+            let mut fonts = resources.fonts();
+            for (font, &id) in self.fonts.iter().zip(&font_ids) {
+                fonts.pair(Name(font.resource), id);
+            }
+            fonts.finish();
+            let mut x_objects = resources.x_objects();
+            for (index, &id) in image_ids.iter().enumerate() {
+                let name = format!("Im{}", index);
+                x_objects.pair(Name(name.as_bytes()), id);
+            }
+            x_objects.finish();
+            resources.finish();
+            page.finish();
+
+            let compressed = deflate(&content.finish());
+            pdf.stream(content_id, &compressed).filter(Filter::FlateDecode);
+        }
+
+        for (font, &id) in self.fonts.iter().zip(&font_ids) {
+            font.write(&mut pdf, &mut alloc, id)?;
+        }
+        for (image, &id) in self.images.iter().zip(&image_ids) {
+            image.write(&mut pdf, &mut alloc, id);
+        }
+
+        let created = pdf_date(&info.created_at);
+        pdf.document_info(info_id)
+            .title(TextStr(&info.title))
+            .author(TextStr(&info.author))
+            .creator(TextStr("InvoiceFlow"))
+            .producer(TextStr("InvoiceFlow"))
+            .creation_date(created)
+            .modified_date(created);
+        pdf.set_file_id((info.file_id.to_vec(), info.file_id.to_vec()));
+
+        if let (Some(archival), Some(refs)) = (&info.archival, &archival_refs) {
+            pdfa::write_objects(&mut pdf, info, archival, refs);
+        }
+
+        Ok(pdf.finish())
+    }
+}
+
+pub(crate) fn pdf_date(dt: &DateTime<Utc>) -> Date {
+    Date::new(dt.year() as u16)
+        .month(dt.month() as u8)
+        .day(dt.day() as u8)
+        .hour(dt.hour() as u8)
+        .minute(dt.minute() as u8)
+        .second(dt.second() as u8)
+        .utc_offset_hour(0)
+        .utc_offset_minute(0)
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
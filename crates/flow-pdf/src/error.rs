@@ -0,0 +1,65 @@
+use flow_core::error::{FlowError, FlowResult};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PdfError {
+    #[error("Configured browser executable not found: {}", .0.display())]
+    ConfiguredBrowserMissing(PathBuf),
+
+    #[error("No supported browser (Chromium/Chrome/Edge/Brave) found for PDF generation. Searched: {}", display_paths(.searched))]
+    BrowserNotFound { searched: Vec<PathBuf> },
+
+    #[error("PDF/A-3 output is not supported by the {backend} backend; switch to the native backend")]
+    ArchivalUnsupported { backend: &'static str },
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+}
+
+impl PdfError {
+    /// Paths that were probed before giving up, reported by the desktop app's browser check.
+    pub fn searched_paths(&self) -> Vec<&Path> {
+        match self {
+            PdfError::ConfiguredBrowserMissing(path) => vec![path.as_path()],
+            PdfError::BrowserNotFound { searched } => searched.iter().map(PathBuf::as_path).collect(),
+            PdfError::ArchivalUnsupported { .. } => Vec::new(),
+        }
+    }
+}
+
+impl From<PdfError> for FlowError {
+    fn from(err: PdfError) -> Self {
+        FlowError::PdfGeneration(err.to_string())
+    }
+}
+
+/// Attaches a description of the step that failed, turning the failure into a
+/// [`FlowError::PdfGeneration`].
+pub(crate) trait Context<T> {
+    fn context(self, message: &str) -> FlowResult<T>;
+
+    fn with_context(self, message: impl FnOnce() -> String) -> FlowResult<T>;
+}
+
+impl<T, E: Display> Context<T> for Result<T, E> {
+    fn context(self, message: &str) -> FlowResult<T> {
+        self.with_context(|| message.to_string())
+    }
+
+    fn with_context(self, message: impl FnOnce() -> String) -> FlowResult<T> {
+        self.map_err(|e| FlowError::PdfGeneration(format!("{}: {}", message(), e)))
+    }
+}
+
+impl<T> Context<T> for Option<T> {
+    fn context(self, message: &str) -> FlowResult<T> {
+        self.with_context(|| message.to_string())
+    }
+
+    fn with_context(self, message: impl FnOnce() -> String) -> FlowResult<T> {
+        self.ok_or_else(|| FlowError::PdfGeneration(message()))
+    }
+}
@@ -0,0 +1,110 @@
+//! Output path resolution for exported invoices.
+
+use flow_core::models::{Client, Invoice};
+use std::path::{Path, PathBuf};
+
+/// Pattern used when the profile does not configure one.
+pub const DEFAULT_FILENAME_PATTERN: &str = "Invoice_{number}.pdf";
+
+/// Expands a filename pattern such as `{client}/{year}/{number}.pdf` into a relative path.
+///
+/// Supported placeholders: `{number}`, `{client}`, `{company}`, `{year}`, `{month}`, `{day}`
+/// and `{date}` (issue date, `YYYY-MM-DD`). `/` and `\` separate sub-directories; every
+/// component is sanitized so the result can never escape the export directory.
+pub fn render_filename(pattern: &str, invoice: &Invoice, client: &Client) -> PathBuf {
+    let pattern = if pattern.trim().is_empty() { DEFAULT_FILENAME_PATTERN } else { pattern };
+    let date = invoice.issue_date;
+    let company = client.company.as_deref().unwrap_or(&client.name);
+    let values = [
+        ("{number}", invoice.number.clone()),
+        ("{client}", client.name.clone()),
+        ("{company}", company.to_string()),
+        ("{year}", date.format("%Y").to_string()),
+        ("{month}", date.format("%m").to_string()),
+        ("{day}", date.format("%d").to_string()),
+        ("{date}", date.format("%Y-%m-%d").to_string()),
+    ];
+
+    // Split before substituting so separators inside a client name cannot create directories.
+    let mut path: PathBuf = pattern
+        .split(['/', '\\'])
+        .map(|part| {
+            let expanded = values.iter().fold(part.to_string(), |acc, (key, value)| {
+                acc.replace(key, &value.replace(['/', '\\'], "_"))
+            });
+            sanitize_component(&expanded)
+        })
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    if path.as_os_str().is_empty() {
+        path = PathBuf::from(sanitize_component(&invoice.number));
+    }
+    if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf")) {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".pdf");
+        path.set_file_name(name);
+    }
+    path
+}
+
+/// Replaces characters that are invalid in file names on any supported OS.
+pub fn sanitize_component(part: &str) -> String {
+    let cleaned: String = part
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Windows rejects trailing dots and spaces; leading dots would hide the file or walk upwards.
+    cleaned.trim().trim_matches('.').trim().to_string()
+}
+
+/// Returns `path` if it is free, otherwise the first of `name_1.pdf`, `name_2.pdf`, ... that is.
+pub fn unique_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    let ext = path.extension().map(|e| e.to_string_lossy().into_owned());
+    (1..)
+        .map(|n| {
+            let name = match &ext {
+                Some(ext) => format!("{}_{}.{}", stem, n, ext),
+                None => format!("{}_{}", stem, n),
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded counter always finds a free name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::tests::fixture;
+
+    #[test]
+    fn test_pattern_expands_into_sanitized_subdirectories() {
+        let (invoice, mut client, _) = fixture();
+        client.name = "Acme: West/East".to_string();
+        let path = render_filename("{client}/{year}/{number}", &invoice, &client);
+        assert_eq!(path, PathBuf::from("Acme_ West_East").join("2025").join("INV-2025-00001.pdf"));
+        assert_eq!(render_filename("", &invoice, &client), PathBuf::from("Invoice_INV-2025-00001.pdf"));
+        assert_eq!(render_filename("../../{number}.pdf", &invoice, &client), PathBuf::from("INV-2025-00001.pdf"));
+    }
+
+    #[test]
+    fn test_unique_path_appends_counter() {
+        let dir = std::env::temp_dir().join(format!("invoiceflow_export_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("INV-007.pdf");
+        assert_eq!(unique_path(&target), target);
+        std::fs::write(&target, b"x").unwrap();
+        std::fs::write(dir.join("INV-007_1.pdf"), b"x").unwrap();
+        assert_eq!(unique_path(&target), dir.join("INV-007_2.pdf"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,289 @@
+//! Factur-X / ZUGFeRD 2 (EN 16931 profile) invoice data for PDF/A-3 embedding.
+//!
+//! The XML follows the UN/CEFACT Cross Industry Invoice schema; taxes are reported as a
+//! single [`VatBreakdown`], the same way the UBL export does.
+
+use flow_core::error::{FlowError, FlowResult};
+use flow_core::models::{Address, BusinessProfile, Client, Invoice};
+use flow_einvoice::xml::{amount, decimal, escape};
+use flow_einvoice::{buyer_name, country_code, VatBreakdown};
+use rust_decimal::Decimal;
+use std::fmt::Write;
+
+use crate::pdfa::{Archival, Attachment};
+use crate::template::InvoiceExtras;
+
+/// File name mandated by Factur-X 1.0 (ZUGFeRD 2.1+ uses the same name).
+pub const FILENAME: &str = "factur-x.xml";
+
+/// Conformance level written to the XML and the XMP metadata.
+pub const CONFORMANCE_LEVEL: &str = "EN 16931";
+
+const GUIDELINE_ID: &str = "urn:cen.eu:en16931:2017";
+
+/// Builds the Cross Industry Invoice XML for `invoice`.
+///
+/// Fails when the seller's or buyer's country has no ISO 3166 code, since EN 16931
+/// requires one for both parties.
+pub fn invoice_xml(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> FlowResult<String> {
+    let extras = InvoiceExtras::from_invoice(invoice);
+    let currency = invoice.currency.to_string();
+    let mut xml = String::new();
+
+    xml.push_str(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#, "\n",
+        r#"<rsm:CrossIndustryInvoice xmlns:rsm="urn:un:unece:uncefact:data:standard:CrossIndustryInvoice:100""#,
+        r#" xmlns:ram="urn:un:unece:uncefact:data:standard:ReusableAggregateBusinessInformationEntity:100""#,
+        r#" xmlns:qdt="urn:un:unece:uncefact:data:standard:QualifiedDataType:100""#,
+        r#" xmlns:udt="urn:un:unece:uncefact:data:standard:UnqualifiedDataType:100">"#, "\n",
+    ));
+
+    let _ = writeln!(
+        xml,
+        "<rsm:ExchangedDocumentContext><ram:GuidelineSpecifiedDocumentContextParameter><ram:ID>{}</ram:ID></ram:GuidelineSpecifiedDocumentContextParameter></rsm:ExchangedDocumentContext>",
+        GUIDELINE_ID
+    );
+    let _ = write!(
+        xml,
+        "<rsm:ExchangedDocument><ram:ID>{}</ram:ID><ram:TypeCode>380</ram:TypeCode><ram:IssueDateTime>{}</ram:IssueDateTime>",
+        escape(&invoice.number),
+        date_102(invoice.issue_date),
+    );
+    if let Some(terms) = invoice.terms_and_conditions.as_deref().filter(|t| !t.trim().is_empty()) {
+        let _ = write!(xml, "<ram:IncludedNote><ram:Content>{}</ram:Content></ram:IncludedNote>", escape(terms));
+    }
+    xml.push_str("</rsm:ExchangedDocument>\n<rsm:SupplyChainTradeTransaction>\n");
+
+    let vat = VatBreakdown::for_invoice(invoice);
+    for (index, item) in invoice.items.iter().enumerate() {
+        let _ = write!(
+            xml,
+            concat!(
+                "<ram:IncludedSupplyChainTradeLineItem>",
+                "<ram:AssociatedDocumentLineDocument><ram:LineID>{}</ram:LineID></ram:AssociatedDocumentLineDocument>",
+                "<ram:SpecifiedTradeProduct><ram:Name>{}</ram:Name></ram:SpecifiedTradeProduct>",
+                "<ram:SpecifiedLineTradeAgreement><ram:NetPriceProductTradePrice><ram:ChargeAmount>{}</ram:ChargeAmount></ram:NetPriceProductTradePrice></ram:SpecifiedLineTradeAgreement>",
+                "<ram:SpecifiedLineTradeDelivery><ram:BilledQuantity unitCode=\"C62\">{}</ram:BilledQuantity></ram:SpecifiedLineTradeDelivery>",
+                "<ram:SpecifiedLineTradeSettlement>{}",
+                "<ram:SpecifiedTradeSettlementLineMonetarySummation><ram:LineTotalAmount>{}</ram:LineTotalAmount></ram:SpecifiedTradeSettlementLineMonetarySummation>",
+                "</ram:SpecifiedLineTradeSettlement>",
+                "</ram:IncludedSupplyChainTradeLineItem>\n",
+            ),
+            index + 1,
+            escape(&item.description),
+            decimal(item.unit_price, 4),
+            decimal(item.quantity, 4),
+            line_tax(&vat),
+            amount(item.amount),
+        );
+    }
+
+    xml.push_str("<ram:ApplicableHeaderTradeAgreement>");
+    trade_party(&mut xml, "SellerTradeParty", &profile.name, &profile.address, profile.email.as_deref(), profile.tax_id.as_deref())?;
+    trade_party(&mut xml, "BuyerTradeParty", buyer_name(client), &client.address, client.email.as_deref(), None)?;
+    xml.push_str("</ram:ApplicableHeaderTradeAgreement>\n<ram:ApplicableHeaderTradeDelivery/>\n");
+
+    let _ = write!(
+        xml,
+        "<ram:ApplicableHeaderTradeSettlement><ram:InvoiceCurrencyCode>{}</ram:InvoiceCurrencyCode>",
+        escape(&currency)
+    );
+    if !extras.account_number.trim().is_empty() {
+        let _ = write!(
+            xml,
+            "<ram:SpecifiedTradeSettlementPaymentMeans><ram:TypeCode>30</ram:TypeCode><ram:PayeePartyCreditorFinancialAccount><ram:ProprietaryID>{}</ram:ProprietaryID></ram:PayeePartyCreditorFinancialAccount></ram:SpecifiedTradeSettlementPaymentMeans>",
+            escape(extras.account_number.trim())
+        );
+    }
+
+    let _ = write!(
+        xml,
+        "<ram:ApplicableTradeTax><ram:CalculatedAmount>{}</ram:CalculatedAmount><ram:TypeCode>VAT</ram:TypeCode>{}<ram:BasisAmount>{}</ram:BasisAmount><ram:CategoryCode>{}</ram:CategoryCode><ram:RateApplicablePercent>{}</ram:RateApplicablePercent></ram:ApplicableTradeTax>",
+        amount(vat.tax_amount),
+        vat.exemption_reason
+            .map(|reason| format!("<ram:ExemptionReason>{}</ram:ExemptionReason>", reason))
+            .unwrap_or_default(),
+        amount(vat.taxable_amount),
+        vat.category,
+        decimal(vat.rate, 2),
+    );
+    if invoice.discount_total > Decimal::ZERO {
+        let _ = write!(
+            xml,
+            "<ram:SpecifiedTradeAllowanceCharge><ram:ChargeIndicator><udt:Indicator>false</udt:Indicator></ram:ChargeIndicator><ram:ActualAmount>{}</ram:ActualAmount><ram:Reason>Discount</ram:Reason><ram:CategoryTradeTax><ram:TypeCode>VAT</ram:TypeCode><ram:CategoryCode>{}</ram:CategoryCode><ram:RateApplicablePercent>{}</ram:RateApplicablePercent></ram:CategoryTradeTax></ram:SpecifiedTradeAllowanceCharge>",
+            amount(invoice.discount_total),
+            vat.category,
+            decimal(vat.rate, 2),
+        );
+    }
+    let _ = write!(
+        xml,
+        "<ram:SpecifiedTradePaymentTerms><ram:DueDateDateTime>{}</ram:DueDateDateTime></ram:SpecifiedTradePaymentTerms>",
+        date_102(invoice.due_date)
+    );
+    let _ = write!(
+        xml,
+        concat!(
+            "<ram:SpecifiedTradeSettlementHeaderMonetarySummation>",
+            "<ram:LineTotalAmount>{}</ram:LineTotalAmount>",
+            "<ram:AllowanceTotalAmount>{}</ram:AllowanceTotalAmount>",
+            "<ram:TaxBasisTotalAmount>{}</ram:TaxBasisTotalAmount>",
+            "<ram:TaxTotalAmount currencyID=\"{}\">{}</ram:TaxTotalAmount>",
+            "<ram:GrandTotalAmount>{}</ram:GrandTotalAmount>",
+            "<ram:TotalPrepaidAmount>{}</ram:TotalPrepaidAmount>",
+            "<ram:DuePayableAmount>{}</ram:DuePayableAmount>",
+            "</ram:SpecifiedTradeSettlementHeaderMonetarySummation>",
+        ),
+        amount(invoice.subtotal),
+        amount(invoice.discount_total),
+        amount(vat.taxable_amount),
+        escape(&currency),
+        amount(invoice.tax_total),
+        amount(invoice.total),
+        amount(invoice.amount_paid),
+        amount(invoice.amount_due),
+    );
+    xml.push_str("</ram:ApplicableHeaderTradeSettlement>\n</rsm:SupplyChainTradeTransaction>\n</rsm:CrossIndustryInvoice>\n");
+    Ok(xml)
+}
+
+/// PDF/A-3 settings that embed the Factur-X XML and declare it in the XMP metadata.
+pub(crate) fn archival(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> FlowResult<Archival> {
+    Ok(Archival {
+        attachments: vec![Attachment {
+            name: FILENAME.to_string(),
+            mime_type: "text/xml",
+            description: "Factur-X/ZUGFeRD invoice".to_string(),
+            relationship: "Alternative",
+            data: invoice_xml(invoice, client, profile)?.into_bytes(),
+        }],
+        xmp_extension: xmp_extension(),
+    })
+}
+
+/// The `fx:` properties plus the PDF/A extension schema that declares them.
+fn xmp_extension() -> String {
+    let property = |name: &str, description: &str| {
+        format!(
+            "<rdf:li rdf:parseType=\"Resource\"><pdfaProperty:name>{}</pdfaProperty:name><pdfaProperty:valueType>Text</pdfaProperty:valueType><pdfaProperty:category>external</pdfaProperty:category><pdfaProperty:description>{}</pdfaProperty:description></rdf:li>\n",
+            name, description
+        )
+    };
+    format!(
+        concat!(
+            "<rdf:Description rdf:about=\"\" xmlns:fx=\"urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#\">\n",
+            "<fx:DocumentType>INVOICE</fx:DocumentType>\n",
+            "<fx:DocumentFileName>{filename}</fx:DocumentFileName>\n",
+            "<fx:Version>1.0</fx:Version>\n",
+            "<fx:ConformanceLevel>{level}</fx:ConformanceLevel>\n",
+            "</rdf:Description>\n",
+            "<rdf:Description rdf:about=\"\" xmlns:pdfaExtension=\"http://www.aiim.org/pdfa/ns/extension/\"",
+            " xmlns:pdfaSchema=\"http://www.aiim.org/pdfa/ns/schema#\" xmlns:pdfaProperty=\"http://www.aiim.org/pdfa/ns/property#\">\n",
+            "<pdfaExtension:schemas><rdf:Bag><rdf:li rdf:parseType=\"Resource\">\n",
+            "<pdfaSchema:schema>Factur-X PDFA Extension Schema</pdfaSchema:schema>\n",
+            "<pdfaSchema:namespaceURI>urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#</pdfaSchema:namespaceURI>\n",
+            "<pdfaSchema:prefix>fx</pdfaSchema:prefix>\n",
+            "<pdfaSchema:property><rdf:Seq>\n",
+            "{properties}",
+            "</rdf:Seq></pdfaSchema:property>\n",
+            "</rdf:li></rdf:Bag></pdfaExtension:schemas>\n",
+            "</rdf:Description>\n",
+        ),
+        filename = FILENAME,
+        level = CONFORMANCE_LEVEL,
+        properties = [
+            property("DocumentFileName", "The name of the embedded XML document"),
+            property("DocumentType", "The type of the hybrid document in capital letters, e.g. INVOICE or ORDER"),
+            property("Version", "The actual version of the standard applying to the embedded XML document"),
+            property("ConformanceLevel", "The conformance level of the embedded XML document"),
+        ]
+        .concat(),
+    )
+}
+
+fn line_tax(vat: &VatBreakdown) -> String {
+    format!(
+        "<ram:ApplicableTradeTax><ram:TypeCode>VAT</ram:TypeCode><ram:CategoryCode>{}</ram:CategoryCode><ram:RateApplicablePercent>{}</ram:RateApplicablePercent></ram:ApplicableTradeTax>",
+        vat.category,
+        decimal(vat.rate, 2)
+    )
+}
+
+fn trade_party(
+    xml: &mut String,
+    element: &str,
+    name: &str,
+    address: &Address,
+    email: Option<&str>,
+    tax_id: Option<&str>,
+) -> FlowResult<()> {
+    let country = country_code(&address.country).ok_or_else(|| {
+        FlowError::Validation(format!(
+            "Factur-X needs a country code for {}; \"{}\" is not a recognised country",
+            name.trim(),
+            address.country.trim()
+        ))
+    })?;
+    let _ = write!(xml, "<ram:{}><ram:Name>{}</ram:Name><ram:PostalTradeAddress>", element, escape(name));
+    let optional = |xml: &mut String, tag: &str, value: Option<&str>| {
+        if let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) {
+            let _ = write!(xml, "<ram:{tag}>{}</ram:{tag}>", escape(value));
+        }
+    };
+    optional(xml, "PostcodeCode", Some(&address.postal_code));
+    optional(xml, "LineOne", Some(&address.line1));
+    optional(xml, "LineTwo", address.line2.as_deref());
+    optional(xml, "CityName", Some(&address.city));
+    let _ = write!(xml, "<ram:CountryID>{}</ram:CountryID>", country);
+    optional(xml, "CountrySubDivisionName", address.state.as_deref());
+    xml.push_str("</ram:PostalTradeAddress>");
+    if let Some(email) = email.map(str::trim).filter(|e| !e.is_empty()) {
+        let _ = write!(
+            xml,
+            "<ram:URIUniversalCommunication><ram:URIID schemeID=\"EM\">{}</ram:URIID></ram:URIUniversalCommunication>",
+            escape(email)
+        );
+    }
+    if let Some(tax_id) = tax_id.map(str::trim).filter(|t| !t.is_empty()) {
+        let _ = write!(
+            xml,
+            "<ram:SpecifiedTaxRegistration><ram:ID schemeID=\"VA\">{}</ram:ID></ram:SpecifiedTaxRegistration>",
+            escape(tax_id)
+        );
+    }
+    let _ = write!(xml, "</ram:{}>", element);
+    Ok(())
+}
+
+fn date_102(date: chrono::NaiveDate) -> String {
+    format!("<udt:DateTimeString format=\"102\">{}</udt:DateTimeString>", date.format("%Y%m%d"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::tests::fixture;
+
+    #[test]
+    fn test_xml_totals_are_consistent() {
+        let (invoice, mut client, mut profile) = fixture();
+        profile.address.country = "India".to_string();
+        client.address.country = "United Kingdom".to_string();
+        let xml = invoice_xml(&invoice, &client, &profile).unwrap();
+        assert!(xml.contains("<ram:ID>urn:cen.eu:en16931:2017</ram:ID>"));
+        assert!(xml.contains(&format!("<ram:ID>{}</ram:ID>", invoice.number)));
+        assert!(xml.contains(&format!("<ram:GrandTotalAmount>{}</ram:GrandTotalAmount>", amount(invoice.total))));
+        assert_eq!(xml.matches("<ram:IncludedSupplyChainTradeLineItem>").count(), invoice.items.len());
+        assert!(xml.contains("<ram:CountryID>IN</ram:CountryID>"));
+        assert!(xml.contains("<ram:CountryID>GB</ram:CountryID>"));
+    }
+
+    #[test]
+    fn test_unmapped_country_is_refused() {
+        let (invoice, mut client, mut profile) = fixture();
+        profile.address.country = "India".to_string();
+        client.address.country = "Atlantis".to_string();
+        let err = invoice_xml(&invoice, &client, &profile).unwrap_err();
+        assert!(matches!(err, FlowError::Validation(ref message) if message.contains("\"Atlantis\"")));
+    }
+}
@@ -0,0 +1,690 @@
+//! Pure-Rust renderer that lays the invoice out directly as PDF, without a browser.
+//! Mirrors the structure of the HTML template so both backends produce the same document.
+
+use flow_core::error::FlowResult;
+use flow_core::models::{BusinessProfile, Client, Invoice};
+
+use crate::backend::PdfBackend;
+use crate::canvas::{Canvas, Color, DocumentInfo, Image, Weight, PAGE_HEIGHT, PAGE_WIDTH};
+use crate::facturx;
+use crate::payment;
+use crate::qr::{QrMatrix, QUIET_ZONE};
+use crate::template::{currency_symbol, custom_field_display, custom_fields_line, format_currency, InvoiceExtras};
+
+const MARGIN: f32 = 48.0;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const BOTTOM: f32 = PAGE_HEIGHT - MARGIN;
+
+const SLATE: Color = Color::hex(0x3e546c);
+const TEXT: Color = Color::hex(0x111827);
+const MUTED: Color = Color::hex(0x374151);
+const BORDER: Color = Color::hex(0xe5e7eb);
+const GREY_BG: Color = Color::hex(0xf3f4f6);
+const LOGO_BG: Color = Color::hex(0xf9fafb);
+const BLUE: Color = Color::hex(0x3b82f6);
+const GREEN: Color = Color::hex(0x10b981);
+const WHITE: Color = Color::hex(0xffffff);
+const AMBER_BG: Color = Color::hex(0xfffbeb);
+const TERMS_BG: Color = Color::hex(0xecfdf5);
+const TERMS_BORDER: Color = Color::hex(0xd1fae5);
+const SKY_BG: Color = Color::hex(0xe0f2fe);
+const SKY: Color = Color::hex(0x0284c7);
+const FAINT: Color = Color::hex(0x9ca3af);
+const QR_BG: Color = Color::hex(0x1e293b);
+const QR_DARK: Color = Color::hex(0x000000);
+const CAPTION: Color = Color::hex(0x6b7280);
+
+/// Points per millimetre; the Swiss QR-bill is specified in millimetres.
+const MM: f32 = 72.0 / 25.4;
+const QR_BILL_HEIGHT: f32 = 105.0 * MM;
+const QR_BILL_RECEIPT: f32 = 62.0 * MM;
+
+/// Renders invoices with an embedded copy of DejaVu Sans.
+/// Output is byte-for-byte reproducible for the same input.
+#[derive(Debug, Clone, Default)]
+pub struct NativeBackend;
+
+impl NativeBackend {
+    pub const NAME: &'static str = "native";
+
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl PdfBackend for NativeBackend {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn render(&self, invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> FlowResult<Vec<u8>> {
+        let mut layout = Layout::new();
+        layout.invoice(invoice, client, profile);
+
+        let info = DocumentInfo {
+            title: format!("Invoice {}", invoice.number),
+            author: profile.name.clone(),
+            created_at: invoice.created_at,
+            file_id: *invoice.id.as_bytes(),
+            archival: profile.pdf_archival.then(|| facturx::archival(invoice, client, profile)).transpose()?,
+        };
+        layout.canvas.finish(&info)
+    }
+}
+
+/// Top-down flow layout with automatic page breaks.
+struct Layout {
+    canvas: Canvas,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self { canvas: Canvas::new(), y: MARGIN }
+    }
+
+    /// Starts a new page when fewer than `height` points remain.
+    fn ensure_space(&mut self, height: f32) {
+        if self.y + height > BOTTOM {
+            self.canvas.add_page();
+            self.y = MARGIN;
+        }
+    }
+
+    fn invoice(&mut self, invoice: &Invoice, client: &Client, profile: &BusinessProfile) {
+        let extras = InvoiceExtras::from_invoice(invoice);
+        let currency = invoice.currency.to_string();
+
+        self.header(&extras);
+        self.info_grid(invoice, &extras);
+        self.bill_to(client);
+        self.project_details(&extras);
+        self.items(invoice, &currency);
+        self.payment_information(invoice, profile, &extras);
+        self.payment_terms(invoice, &extras);
+        self.footer(&extras);
+        if let Some(bill) = payment::invoice_swiss_qr_bill(invoice, client, profile, &extras) {
+            self.swiss_qr_bill(&bill);
+        }
+    }
+
+    fn header(&mut self, extras: &InvoiceExtras) {
+        let (box_w, box_h) = (144.0, 72.0);
+        self.canvas.fill_rect(MARGIN, self.y, box_w, box_h, LOGO_BG);
+        self.canvas.stroke_rect(MARGIN, self.y, box_w, box_h, BORDER, 0.75);
+
+        // A logo that fails to decode is left out rather than failing the whole invoice.
+        if let Some(image) = extras.logo.as_deref().and_then(|src| Image::from_source(src).ok()) {
+            let index = self.canvas.add_image(image);
+            self.fit_image(index, MARGIN + 6.0, self.y + 6.0, box_w - 12.0, box_h - 12.0);
+        }
+
+        self.canvas.text_right(PAGE_WIDTH - MARGIN, self.y + 6.0, 27.0, Weight::Bold, SLATE, "INVOICE");
+        self.y += box_h + 18.0;
+    }
+
+    fn fit_image(&mut self, index: usize, x: f32, y: f32, max_w: f32, max_h: f32) {
+        let (w, h) = self.canvas.image_size(index);
+        if w == 0 || h == 0 {
+            return;
+        }
+        let scale = (max_w / w as f32).min(max_h / h as f32);
+        let (draw_w, draw_h) = (w as f32 * scale, h as f32 * scale);
+        let dx = x + (max_w - draw_w) / 2.0;
+        let dy = y + (max_h - draw_h) / 2.0;
+        self.canvas.draw_image(index, dx, dy, draw_w, draw_h);
+    }
+
+    fn draw_qr(&mut self, qr: &QrMatrix, x: f32, y: f32, size: f32) {
+        self.canvas.fill_rect(x, y, size, size, WHITE);
+        let module = size / qr.size() as f32;
+        for (col, row, len) in qr.runs() {
+            self.canvas.fill_rect(x + col as f32 * module, y + row as f32 * module, len as f32 * module, module, QR_DARK);
+        }
+    }
+
+    fn info_grid(&mut self, invoice: &Invoice, extras: &InvoiceExtras) {
+        let height = 84.0;
+        let col_w = CONTENT_WIDTH / 2.0;
+        self.canvas.fill_rect(MARGIN, self.y, CONTENT_WIDTH, height, GREY_BG);
+        self.canvas.line(MARGIN + col_w, self.y, MARGIN + col_w, self.y + height, BORDER, 0.75);
+
+        let cells = [
+            ("Invoice Number:", invoice.number.clone(), "Invoice Date:", invoice.issue_date.format("%d %b %Y").to_string()),
+            ("Developer:", extras.developer.clone(), "Due Date:", invoice.due_date.format("%d %b %Y").to_string()),
+        ];
+        for (col, (label_a, value_a, label_b, value_b)) in cells.iter().enumerate() {
+            let x = MARGIN + col as f32 * col_w + 12.0;
+            self.canvas.text(x, self.y + 12.0, 10.5, Weight::Bold, TEXT, label_a);
+            self.canvas.text(x, self.y + 26.0, 12.0, Weight::Regular, MUTED, value_a);
+            self.canvas.text(x, self.y + 46.0, 10.5, Weight::Bold, TEXT, label_b);
+            self.canvas.text(x, self.y + 60.0, 12.0, Weight::Regular, MUTED, value_b);
+        }
+        self.y += height + 24.0;
+    }
+
+    fn section_title(&mut self, title: &str) {
+        self.canvas.text(MARGIN, self.y, 15.0, Weight::Bold, SLATE, title);
+        self.y += 22.0;
+    }
+
+    fn bill_to(&mut self, client: &Client) {
+        let mut lines: Vec<(String, Color)> = Vec::new();
+        if let Some(company) = &client.company {
+            lines.push((company.clone(), MUTED));
+        }
+        if let Some(email) = &client.email {
+            lines.push((format!("Website: {}", email), BLUE));
+        }
+        for field in &client.custom_fields {
+            lines.push((format!("{}: {}", field.name, custom_field_display(field)), MUTED));
+        }
+
+        let height = 34.0 + lines.len() as f32 * 16.0;
+        self.ensure_space(22.0 + height);
+        self.section_title("Bill To:");
+        self.canvas.stroke_rect(MARGIN, self.y, CONTENT_WIDTH, height, BORDER, 0.75);
+        self.canvas.text(MARGIN + 12.0, self.y + 12.0, 13.5, Weight::Bold, TEXT, &client.name);
+        let mut y = self.y + 32.0;
+        for (line, color) in &lines {
+            self.canvas.text(MARGIN + 12.0, y, 10.5, Weight::Regular, *color, line);
+            y += 16.0;
+        }
+        self.y += height + 24.0;
+    }
+
+    fn project_details(&mut self, extras: &InvoiceExtras) {
+        if extras.project_details.is_empty() {
+            return;
+        }
+
+        let value_x = MARGIN + 12.0 + 120.0;
+        let value_w = CONTENT_WIDTH - 144.0;
+        let rows: Vec<(&str, Vec<String>)> = extras
+            .project_details
+            .iter()
+            .map(|(label, value)| (label.as_str(), self.canvas.wrap(value, 10.5, Weight::Regular, value_w)))
+            .collect();
+        let height = 24.0 + rows.iter().map(|(_, lines)| lines.len() as f32 * 14.0 + 6.0).sum::<f32>();
+
+        self.ensure_space(22.0 + height);
+        self.section_title("Project Details");
+        self.canvas.stroke_rect(MARGIN, self.y, CONTENT_WIDTH, height, BORDER, 0.75);
+        let mut y = self.y + 12.0;
+        for (label, lines) in &rows {
+            self.canvas.text(MARGIN + 12.0, y, 10.5, Weight::Bold, TEXT, label);
+            for line in lines {
+                self.canvas.text(value_x, y, 10.5, Weight::Regular, TEXT, line);
+                y += 14.0;
+            }
+            y += 6.0;
+        }
+        self.y += height + 24.0;
+    }
+
+    fn items(&mut self, invoice: &Invoice, currency: &str) {
+        let amount_w = 110.0;
+        let desc_w = CONTENT_WIDTH - amount_w - 24.0;
+        let right = PAGE_WIDTH - MARGIN - 12.0;
+
+        self.ensure_space(22.0 + 26.0 + 40.0);
+        self.section_title("Services & Charges");
+        self.table_header(currency);
+
+        for item in &invoice.items {
+            let lines = self.canvas.wrap(&item.description, 10.5, Weight::Regular, desc_w);
+            let field_lines = match custom_fields_line(&item.custom_fields) {
+                fields if fields.is_empty() => Vec::new(),
+                fields => self.canvas.wrap(&fields, 9.0, Weight::Regular, desc_w),
+            };
+            let height = lines.len() as f32 * 15.0 + field_lines.len() as f32 * 13.0 + 18.0;
+            if self.y + height > BOTTOM {
+                self.canvas.add_page();
+                self.y = MARGIN;
+                self.table_header(currency);
+            }
+
+            self.canvas.line(MARGIN, self.y, MARGIN, self.y + height, BORDER, 0.75);
+            self.canvas.line(PAGE_WIDTH - MARGIN, self.y, PAGE_WIDTH - MARGIN, self.y + height, BORDER, 0.75);
+            self.canvas.line(MARGIN, self.y + height, PAGE_WIDTH - MARGIN, self.y + height, BORDER, 0.75);
+
+            let mut y = self.y + 9.0;
+            for line in &lines {
+                self.canvas.text(MARGIN + 12.0, y, 10.5, Weight::Regular, TEXT, line);
+                y += 15.0;
+            }
+            for line in &field_lines {
+                self.canvas.text(MARGIN + 12.0, y, 9.0, Weight::Regular, MUTED, line);
+                y += 13.0;
+            }
+            let amount = format_currency(&item.amount.to_string(), currency);
+            self.canvas.text_right(right, self.y + 9.0, 12.0, Weight::Bold, TEXT, &amount);
+            self.y += height;
+        }
+
+        self.ensure_space(32.0);
+        self.canvas.fill_rect(MARGIN, self.y, CONTENT_WIDTH, 32.0, GREEN);
+        self.canvas.text_right(right - amount_w - 12.0, self.y + 9.0, 13.5, Weight::Bold, WHITE, "TOTAL AMOUNT DUE:");
+        let total = format_currency(&invoice.amount_due.to_string(), currency);
+        self.canvas.text_right(right, self.y + 9.0, 13.5, Weight::Bold, WHITE, &total);
+        self.y += 32.0 + 36.0;
+    }
+
+    fn table_header(&mut self, currency: &str) {
+        self.canvas.fill_rect(MARGIN, self.y, CONTENT_WIDTH, 26.0, SLATE);
+        self.canvas.text(MARGIN + 12.0, self.y + 7.0, 11.0, Weight::Bold, WHITE, "Description");
+        let amount_label = format!("Amount ({})", currency_symbol(currency));
+        self.canvas.text_right(PAGE_WIDTH - MARGIN - 12.0, self.y + 7.0, 11.0, Weight::Bold, WHITE, &amount_label);
+        self.y += 26.0;
+    }
+
+    fn payment_information(&mut self, invoice: &Invoice, profile: &BusinessProfile, extras: &InvoiceExtras) {
+        let qr_w = 170.0;
+        let bank_w = CONTENT_WIDTH - qr_w - 18.0;
+        let height = 200.0;
+
+        self.ensure_space(22.0 + height);
+        self.section_title("Payment Information");
+
+        self.canvas.fill_rect(MARGIN, self.y, bank_w, height, AMBER_BG);
+        self.canvas.text(MARGIN + 12.0, self.y + 12.0, 12.0, Weight::Bold, SLATE, "Bank Account Details");
+        let [(number_label, number), (code_label, code)] = extras.account_rows();
+        let rows = [
+            ("Account Holder:", extras.account_holder.as_str(), TEXT),
+            (number_label, number.as_str(), TEXT),
+            (code_label, code.as_str(), BLUE),
+            ("Bank:", extras.bank_name.as_str(), TEXT),
+            ("Branch:", extras.branch.as_str(), TEXT),
+            ("UPI ID:", extras.upi_id.as_str(), BLUE),
+        ];
+        let mut y = self.y + 34.0;
+        for (label, value, color) in rows {
+            self.canvas.text(MARGIN + 12.0, y, 10.0, Weight::Bold, TEXT, label);
+            self.canvas.text(MARGIN + 112.0, y, 10.0, Weight::Regular, color, value);
+            y += 15.0;
+        }
+        y += 6.0;
+        self.canvas.text(MARGIN + 12.0, y, 10.0, Weight::Bold, TEXT, "Payment Methods:");
+        for method in extras.payment_methods() {
+            y += 14.0;
+            self.canvas.text(MARGIN + 12.0, y, 10.0, Weight::Regular, MUTED, method);
+        }
+
+        let qr_x = MARGIN + bank_w + 18.0;
+        let center = qr_x + qr_w / 2.0;
+        self.canvas.line(qr_x, self.y, qr_x, self.y + height, BORDER, 0.75);
+        let box_size = 136.0;
+        let box_x = center - box_size / 2.0;
+        let box_y = self.y + 22.0;
+        // A registered GST e-invoice must carry the portal's signed QR instead of the UPI code.
+        let irn = invoice.gst_registration.as_ref().and_then(|r| QrMatrix::encode(&r.signed_qr).map(|qr| (r, qr)));
+        if let Some((registration, qr)) = irn {
+            self.canvas.text_centered(center, self.y + 4.0, 10.5, Weight::Bold, SLATE, "e-Invoice (IRN)");
+            self.draw_qr(&qr, box_x, box_y, box_size);
+            let ack = format!("Ack No: {}", registration.ack_no);
+            self.canvas.text_centered(center, box_y + box_size + 6.0, 8.0, Weight::Bold, TEXT, &ack);
+            let (first, second) = registration.irn.split_at(registration.irn.len() / 2);
+            self.canvas.text_centered(center, box_y + box_size + 18.0, 6.5, Weight::Regular, CAPTION, first);
+            self.canvas.text_centered(center, box_y + box_size + 27.0, 6.5, Weight::Regular, CAPTION, second);
+        } else if let Some(qr) = payment::invoice_epc_payload(invoice, profile, extras).and_then(|p| QrMatrix::encode(&p)) {
+            self.canvas.text_centered(center, self.y + 4.0, 10.5, Weight::Bold, SLATE, "Scan to Pay (GiroCode)");
+            self.draw_qr(&qr, box_x, box_y, box_size);
+            let holder = if extras.account_holder.is_empty() { &profile.name } else { &extras.account_holder };
+            self.canvas.text_centered(center, box_y + box_size + 6.0, 9.0, Weight::Bold, TEXT, holder);
+            let iban = payment::group_iban(&extras.iban);
+            self.canvas.text_centered(center, box_y + box_size + 18.0, 7.5, Weight::Regular, CAPTION, &iban);
+        } else {
+            self.canvas.text_centered(center, self.y + 4.0, 10.5, Weight::Bold, SLATE, "Scan to Pay via UPI");
+            self.canvas.fill_rect(box_x, box_y, box_size, box_size, QR_BG);
+            // Prefer a QR for the exact amount due; the uploaded image is a fallback.
+            if let Some(qr) = payment::invoice_upi_uri(invoice, extras).and_then(|uri| QrMatrix::encode(&uri)) {
+                self.draw_qr(&qr, box_x, box_y, box_size);
+            } else if let Some(image) = extras.qr_code_url.as_deref().and_then(|src| Image::from_source(src).ok()) {
+                self.canvas.fill_rect(box_x, box_y, box_size, box_size, WHITE);
+                let index = self.canvas.add_image(image);
+                self.fit_image(index, box_x, box_y, box_size, box_size);
+            }
+            self.canvas.text_centered(center, box_y + box_size + 6.0, 9.0, Weight::Bold, TEXT, &extras.developer);
+            self.canvas.text_centered(center, box_y + box_size + 18.0, 7.5, Weight::Regular, CAPTION, &extras.upi_id);
+        }
+
+        self.y += height + 24.0;
+    }
+
+    fn payment_terms(&mut self, invoice: &Invoice, extras: &InvoiceExtras) {
+        let note_lines = self.canvas.wrap(&extras.payment_terms_note, 10.5, Weight::Regular, CONTENT_WIDTH - 70.0);
+        let height = 70.0 + note_lines.len() as f32 * 14.0;
+
+        self.ensure_space(height);
+        self.canvas.fill_rect(MARGIN, self.y, CONTENT_WIDTH, height, TERMS_BG);
+        self.canvas.stroke_rect(MARGIN, self.y, CONTENT_WIDTH, height, TERMS_BORDER, 0.75);
+        let x = MARGIN + 12.0;
+        self.canvas.text(x, self.y + 12.0, 12.0, Weight::Bold, TEXT, "Payment Terms:");
+        self.canvas.text(x, self.y + 30.0, 10.5, Weight::Regular, TEXT, "• Payment is due upon receipt of this invoice");
+        let reference = format!("• Please include Invoice Number ({}) in payment reference", invoice.number);
+        self.canvas.text(x, self.y + 44.0, 10.5, Weight::Regular, TEXT, &reference);
+        self.canvas.text(x, self.y + 62.0, 10.5, Weight::Bold, TEXT, "Note:");
+        let mut y = self.y + 62.0;
+        for line in &note_lines {
+            self.canvas.text(x + 40.0, y, 10.5, Weight::Regular, TEXT, line);
+            y += 14.0;
+        }
+        self.y += height + 24.0;
+    }
+
+    fn footer(&mut self, extras: &InvoiceExtras) {
+        let height = 56.0;
+        self.ensure_space(height + 30.0);
+        let center = PAGE_WIDTH / 2.0;
+        self.canvas.fill_rect(MARGIN, self.y, CONTENT_WIDTH, height, SKY_BG);
+        self.canvas.text_centered(center, self.y + 10.0, 15.0, Weight::Bold, SKY, "Thank You for Your Business!");
+        let contact = if extras.developer.is_empty() { "the developer" } else { extras.developer.as_str() };
+        let line = format!("For any queries regarding this invoice, please contact {}", contact);
+        self.canvas.text_centered(center, self.y + 34.0, 10.5, Weight::Regular, Color::hex(0x1f2937), &line);
+        self.y += height + 18.0;
+
+        self.canvas.text_centered(
+            center,
+            self.y,
+            9.0,
+            Weight::Regular,
+            FAINT,
+            "This is a computer-generated invoice and does not require a physical signature.",
+        );
+        self.y += 12.0;
+    }
+
+    /// Swiss QR-bill payment slip (receipt and payment part), 210 × 105 mm at the foot of the last page.
+    fn swiss_qr_bill(&mut self, bill: &payment::SwissQrBill) {
+        let Some(qr) = QrMatrix::encode(&bill.payload()) else {
+            return;
+        };
+        let top = PAGE_HEIGHT - QR_BILL_HEIGHT;
+        if self.y > top {
+            self.canvas.add_page();
+        }
+        self.y = PAGE_HEIGHT;
+        self.canvas.line(0.0, top, PAGE_WIDTH, top, TEXT, 0.5);
+        self.canvas.line(QR_BILL_RECEIPT, top, QR_BILL_RECEIPT, PAGE_HEIGHT, TEXT, 0.5);
+
+        let amount = bill.display_amount().unwrap_or_default();
+        let reference = bill.display_reference();
+
+        // Receipt
+        let x = 5.0 * MM;
+        self.canvas.text(x, top + 5.0 * MM, 11.0, Weight::Bold, TEXT, "Receipt");
+        let mut y = top + 12.0 * MM;
+        y = self.qr_bill_field(x, y, 6.0, "Account / Payable to", &self.qr_bill_account(bill));
+        if !reference.is_empty() {
+            y = self.qr_bill_field(x, y, 6.0, "Reference", std::slice::from_ref(&reference));
+        }
+        if let Some(debtor) = &bill.debtor {
+            self.qr_bill_field(x, y, 6.0, "Payable by", &debtor.display_lines());
+        }
+        self.qr_bill_amount(x, top + 68.0 * MM, 6.0, &bill.currency, &amount);
+        self.canvas.text_right(QR_BILL_RECEIPT - 5.0 * MM, top + 82.0 * MM, 6.0, Weight::Bold, TEXT, "Acceptance point");
+
+        // Payment part
+        let x = QR_BILL_RECEIPT + 5.0 * MM;
+        self.canvas.text(x, top + 5.0 * MM, 11.0, Weight::Bold, TEXT, "Payment part");
+        self.draw_swiss_qr(&qr, x, top + 17.0 * MM);
+        self.qr_bill_amount(x, top + 68.0 * MM, 8.0, &bill.currency, &amount);
+
+        let x = QR_BILL_RECEIPT + 56.0 * MM;
+        let mut y = top + 5.0 * MM;
+        y = self.qr_bill_field(x, y, 8.0, "Account / Payable to", &self.qr_bill_account(bill));
+        if !reference.is_empty() {
+            y = self.qr_bill_field(x, y, 8.0, "Reference", &[reference]);
+        }
+        if !bill.message.is_empty() {
+            y = self.qr_bill_field(x, y, 8.0, "Additional information", std::slice::from_ref(&bill.message));
+        }
+        if let Some(debtor) = &bill.debtor {
+            self.qr_bill_field(x, y, 8.0, "Payable by", &debtor.display_lines());
+        }
+    }
+
+    fn qr_bill_account(&self, bill: &payment::SwissQrBill) -> Vec<String> {
+        let mut lines = vec![bill.display_iban()];
+        lines.extend(bill.creditor.display_lines());
+        lines
+    }
+
+    /// Heading plus value lines; values are set 2 pt larger than the heading. Returns the next y.
+    fn qr_bill_field(&mut self, x: f32, y: f32, heading: f32, title: &str, lines: &[String]) -> f32 {
+        let size = heading + 2.0;
+        self.canvas.text(x, y, heading, Weight::Bold, TEXT, title);
+        let mut y = y + heading + 3.0;
+        for line in lines {
+            self.canvas.text(x, y, size, Weight::Regular, TEXT, line);
+            y += size + 1.5;
+        }
+        y + size
+    }
+
+    fn qr_bill_amount(&mut self, x: f32, y: f32, heading: f32, currency: &str, amount: &str) {
+        let size = heading + 2.0;
+        self.canvas.text(x, y, heading, Weight::Bold, TEXT, "Currency");
+        self.canvas.text(x + 15.0 * MM, y, heading, Weight::Bold, TEXT, "Amount");
+        self.canvas.text(x, y + heading + 3.0, size, Weight::Regular, TEXT, currency);
+        if !amount.is_empty() {
+            self.canvas.text(x + 15.0 * MM, y + heading + 3.0, size, Weight::Regular, TEXT, amount);
+        }
+    }
+
+    /// The Swiss QR Code: exactly 46 mm of modules with the 7 mm Swiss cross in the middle.
+    fn draw_swiss_qr(&mut self, qr: &QrMatrix, x: f32, y: f32) {
+        let module = 46.0 * MM / qr.width() as f32;
+        let quiet = QUIET_ZONE as f32 * module;
+        self.draw_qr(qr, x - quiet, y - quiet, module * qr.size() as f32);
+
+        let cross = 7.0 * MM;
+        let (cx, cy) = (x + 23.0 * MM - cross / 2.0, y + 23.0 * MM - cross / 2.0);
+        self.canvas.fill_rect(cx, cy, cross, cross, WHITE);
+        self.canvas.fill_rect(cx + 0.5 * MM, cy + 0.5 * MM, cross - 1.0 * MM, cross - 1.0 * MM, QR_DARK);
+        let (arm, bar) = (3.9 * MM, 1.17 * MM);
+        let (mid_x, mid_y) = (cx + cross / 2.0, cy + cross / 2.0);
+        self.canvas.fill_rect(mid_x - bar / 2.0, mid_y - arm / 2.0, bar, arm, WHITE);
+        self.canvas.fill_rect(mid_x - arm / 2.0, mid_y - bar / 2.0, arm, bar, WHITE);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use flow_core::models::{Address, BankAccount, CustomFieldValue, InvoiceItem, ProjectDetail};
+    use flow_core::types::{Currency, CustomFieldType, InvoiceStatus, PaymentTerms};
+    use rust_decimal::Decimal;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    pub(crate) fn fixture() -> (Invoice, Client, BusinessProfile) {
+        let created = Utc.with_ymd_and_hms(2025, 1, 15, 9, 30, 0).unwrap();
+        let invoice_id = Uuid::from_u128(0x1);
+        let client = Client {
+            id: Uuid::from_u128(0x2),
+            name: "Acme Corp".to_string(),
+            email: Some("acme.example.com".to_string()),
+            phone: None,
+            company: Some("Acme Holdings Ltd".to_string()),
+            address: Address::default(),
+            shipping_address: None,
+            tax_id: None,
+            tax_ids: vec![],
+            contacts: vec![],
+            notes: None,
+            custom_fields: vec![],
+            archived_at: None,
+            created_at: created,
+            updated_at: created,
+        };
+        let profile = BusinessProfile {
+            id: Uuid::from_u128(0x3),
+            name: "Jabir Studio".to_string(),
+            email: None,
+            phone: None,
+            address: Address::default(),
+            tax_id: None,
+            logo_path: None,
+            default_currency: Currency::INR,
+            default_payment_terms: PaymentTerms::Net30,
+            theme_preference: "system".to_string(),
+            pdf_export_dir: None,
+            pdf_filename_pattern: crate::export::DEFAULT_FILENAME_PATTERN.to_string(),
+            pdf_backend: NativeBackend::NAME.to_string(),
+            pdf_archival: false,
+            browser_path: None,
+            created_at: created,
+            updated_at: created,
+        };
+        let items = [("Website design and development\nFive responsive pages", "45000"), ("Hosting (12 months)", "6000")]
+            .iter()
+            .enumerate()
+            .map(|(i, (desc, amount))| InvoiceItem {
+                id: Uuid::from_u128(0x10 + i as u128),
+                invoice_id,
+                description: desc.to_string(),
+                quantity: Decimal::ONE,
+                unit_price: amount.parse().unwrap(),
+                amount: amount.parse().unwrap(),
+                tax_rate_name: None,
+                hsn_code: None,
+                sort_order: i as i32,
+                custom_fields: vec![],
+            })
+            .collect();
+        let bank_account = BankAccount {
+            id: Uuid::from_u128(0x4),
+            label: "State Bank of India".to_string(),
+            account_holder: "Mohammed Jabir".to_string(),
+            bank_name: "State Bank of India".to_string(),
+            branch: Some("Kochi".to_string()),
+            account_number: Some("000123456789".to_string()),
+            iban: None,
+            swift_bic: None,
+            ifsc_code: Some("SBIN0000001".to_string()),
+            routing_number: None,
+            upi_id: Some("jabir@upi".to_string()),
+            currency: Some(Currency::INR),
+            is_default: true,
+            created_at: created,
+            updated_at: created,
+        };
+        let invoice = Invoice {
+            id: invoice_id,
+            number: "INV-2025-00001".to_string(),
+            status: InvoiceStatus::Sent,
+            client_id: client.id,
+            business_profile_id: profile.id,
+            issue_date: NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            due_date: NaiveDate::from_ymd_opt(2025, 2, 14).unwrap(),
+            currency: Currency::INR,
+            items,
+            tax_rates: vec![],
+            discount: None,
+            subtotal: Decimal::from(51000),
+            tax_total: Decimal::ZERO,
+            discount_total: Decimal::ZERO,
+            total: Decimal::from(51000),
+            amount_paid: Decimal::ZERO,
+            amount_due: Decimal::from(51000),
+            payment_terms: PaymentTerms::Net30,
+            notes: None,
+            terms_and_conditions: None,
+            payment_note: Some("Thanks for the prompt payment.".to_string()),
+            developer: Some("Mohammed Jabir".to_string()),
+            logo_path: None,
+            qr_code_url: None,
+            project_details: vec![ProjectDetail { label: "Project".to_string(), value: "Company website".to_string() }],
+            custom_fields: vec![],
+            gst_registration: None,
+            bank_account: Some(bank_account),
+            archived_at: None,
+            created_at: created,
+            updated_at: created,
+        };
+        (invoice, client, profile)
+    }
+
+    #[test]
+    fn test_native_output_is_deterministic() {
+        let (invoice, client, profile) = fixture();
+        let first = NativeBackend::new().render(&invoice, &client, &profile).unwrap();
+        let second = NativeBackend::new().render(&invoice, &client, &profile).unwrap();
+        assert!(first.starts_with(b"%PDF-"));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_archival_output_embeds_factur_x() {
+        let (invoice, mut client, mut profile) = fixture();
+        profile.pdf_archival = true;
+        profile.address.country = "India".to_string();
+        client.address.country = "India".to_string();
+        let rendered = NativeBackend::new().render(&invoice, &client, &profile).unwrap();
+        let text = String::from_utf8_lossy(&rendered);
+        assert!(text.contains("<pdfaid:part>3</pdfaid:part>"));
+        assert!(text.contains("<fx:DocumentFileName>factur-x.xml</fx:DocumentFileName>"));
+        assert!(text.contains("/S /GTS_PDFA1"));
+        assert!(text.contains("/AFRelationship /Alternative"));
+        assert!(text.contains("<rsm:CrossIndustryInvoice"));
+    }
+
+    /// Compares against the checked-in golden file; run with `UPDATE_GOLDEN=1` to regenerate it.
+    #[test]
+    fn test_native_matches_golden_file() {
+        let (invoice, client, profile) = fixture();
+        let rendered = NativeBackend::new().render(&invoice, &client, &profile).unwrap();
+        let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/native_invoice.pdf");
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(golden.parent().unwrap()).unwrap();
+            std::fs::write(&golden, &rendered).unwrap();
+        }
+        let expected = std::fs::read(&golden).expect("golden file missing; run with UPDATE_GOLDEN=1");
+        assert!(rendered == expected, "native PDF output differs from {}", golden.display());
+    }
+
+    #[test]
+    fn test_custom_fields_are_rendered() {
+        let (mut invoice, mut client, profile) = fixture();
+        let field = |n: u128, name: &str, field_type: CustomFieldType, value: &str| CustomFieldValue {
+            field_id: Uuid::from_u128(n),
+            name: name.to_string(),
+            field_type,
+            value: value.to_string(),
+        };
+        invoice.custom_fields = vec![field(0x20, "PO Number", CustomFieldType::Text, "PO-4471")];
+        invoice.items[0].custom_fields = vec![
+            field(0x21, "Cost Code", CustomFieldType::Text, "MKT-7"),
+            field(0x22, "Billable", CustomFieldType::Boolean, "true"),
+        ];
+        client.custom_fields = vec![field(0x23, "Vendor ID", CustomFieldType::Date, "2025-03-01")];
+
+        let html = crate::template::render_invoice_html(&invoice, &client, &profile);
+        assert!(html.contains("<div>PO-4471</div>"));
+        assert!(html.contains("Cost Code: MKT-7 · Billable: Yes"));
+        assert!(html.contains("<p>Vendor ID: 01 Mar 2025</p>"));
+
+        let plain = NativeBackend::new().render(&fixture().0, &fixture().1, &profile).unwrap();
+        let rendered = NativeBackend::new().render(&invoice, &client, &profile).unwrap();
+        assert_ne!(plain, rendered);
+    }
+
+    #[test]
+    fn test_long_item_lists_break_across_pages() {
+        let (mut invoice, client, profile) = fixture();
+        let template = invoice.items[1].clone();
+        invoice.items = (0..80)
+            .map(|i| InvoiceItem { sort_order: i, ..template.clone() })
+            .collect();
+        let rendered = NativeBackend::new().render(&invoice, &client, &profile).unwrap();
+        let needle = b"/Type /Page";
+        let pages = rendered
+            .windows(needle.len() + 1)
+            .filter(|w| w.starts_with(needle) && w[needle.len()] != b's')
+            .count();
+        assert!(pages > 1);
+    }
+}
@@ -0,0 +1,398 @@
+//! Payment QR payloads printed in the invoice's payment block: UPI for INR invoices,
+//! EPC (GiroCode) for SEPA transfers and the Swiss QR-bill for CH/LI accounts.
+
+use flow_core::bank::{is_valid_iban, is_valid_upi_id, mod97, normalize_iban};
+use flow_core::models::{Address, BusinessProfile, Client, Invoice};
+use flow_einvoice::country_code;
+use rust_decimal::Decimal;
+
+use crate::qr::QrMatrix;
+use crate::template::InvoiceExtras;
+
+/// Largest amount the EPC and Swiss QR-bill formats can carry.
+const MAX_QR_AMOUNT: Decimal = Decimal::from_parts(1_410_065_407, 2, 0, false, 2); // 999_999_999.99
+
+/// `upi://pay` deep link (NPCI UPI linking specification) for a fixed INR amount.
+/// Returns `None` when `upi_id` is not a valid virtual payment address.
+pub fn upi_uri(upi_id: &str, payee: &str, amount: Decimal, note: &str) -> Option<String> {
+    let upi_id = upi_id.trim();
+    if !is_valid_upi_id(upi_id) {
+        return None;
+    }
+    let mut uri = format!("upi://pay?pa={}", encode(upi_id));
+    if !payee.trim().is_empty() {
+        uri.push_str("&pn=");
+        uri.push_str(&encode(payee.trim()));
+    }
+    if amount > Decimal::ZERO {
+        uri.push_str(&format!("&am={:.2}", amount.round_dp(2)));
+    }
+    uri.push_str("&cu=INR");
+    if !note.trim().is_empty() {
+        // Payer apps truncate longer notes.
+        let note: String = note.trim().chars().take(80).collect();
+        uri.push_str("&tn=");
+        uri.push_str(&encode(&note));
+    }
+    Some(uri)
+}
+
+/// UPI QR for `upi_uri` as an inline SVG, for previews outside the PDF.
+pub fn upi_qr_svg(upi_id: &str, payee: &str, amount: Decimal, note: &str) -> Option<String> {
+    let uri = upi_uri(upi_id, payee, amount, note)?;
+    Some(QrMatrix::encode(&uri)?.svg("qr-img", "UPI QR Code"))
+}
+
+/// The UPI payment link for the invoice's outstanding amount, when it can be paid by UPI.
+pub(crate) fn invoice_upi_uri(invoice: &Invoice, extras: &InvoiceExtras) -> Option<String> {
+    if invoice.currency.to_string() != "INR" || invoice.amount_due <= Decimal::ZERO {
+        return None;
+    }
+    let payee = if extras.account_holder.trim().is_empty() { &extras.developer } else { &extras.account_holder };
+    upi_uri(&extras.upi_id, payee, invoice.amount_due, &format!("Invoice {}", invoice.number))
+}
+
+/// EPC069-12 (version 002) payload for a SEPA credit transfer, a.k.a. GiroCode.
+/// A structured RF `reference` takes precedence over the free `text`.
+pub fn epc_payload(beneficiary: &str, iban: &str, bic: &str, amount: Decimal, reference: Option<&str>, text: &str) -> Option<String> {
+    let iban = normalize_iban(iban);
+    let beneficiary = truncate(beneficiary.trim(), 70);
+    if !is_valid_iban(&iban) || beneficiary.is_empty() {
+        return None;
+    }
+    let amount = if amount > Decimal::ZERO && amount <= MAX_QR_AMOUNT {
+        format!("EUR{:.2}", amount.round_dp(2))
+    } else {
+        String::new()
+    };
+    let (reference, text) = match reference {
+        Some(reference) => (reference.to_string(), String::new()),
+        None => (String::new(), truncate(text.trim(), 140)),
+    };
+    let lines = [
+        "BCD",
+        "002",
+        "1",
+        "SCT",
+        bic.trim(),
+        &beneficiary,
+        &iban,
+        &amount,
+        "",
+        &reference,
+        &text,
+    ];
+    Some(lines.join("\n").trim_end().to_string())
+}
+
+/// The GiroCode for a EUR invoice paid to a SEPA account outside CH/LI.
+pub(crate) fn invoice_epc_payload(invoice: &Invoice, profile: &BusinessProfile, extras: &InvoiceExtras) -> Option<String> {
+    let iban = normalize_iban(&extras.iban);
+    if invoice.currency.to_string() != "EUR" || is_swiss_iban(&iban) {
+        return None;
+    }
+    let beneficiary = if extras.account_holder.trim().is_empty() { &profile.name } else { &extras.account_holder };
+    let reference = creditor_reference(&invoice.number);
+    epc_payload(
+        beneficiary,
+        &iban,
+        &extras.bic,
+        invoice.amount_due,
+        reference.as_deref(),
+        &format!("Invoice {}", invoice.number),
+    )
+}
+
+/// Postal address in the structured (`S`) form the Swiss QR-bill requires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwissAddress {
+    pub name: String,
+    pub street: String,
+    pub building_number: String,
+    pub postal_code: String,
+    pub town: String,
+    /// ISO 3166-1 alpha-2.
+    pub country: String,
+}
+
+impl SwissAddress {
+    /// Splits a trailing house number off the first address line ("Bahnhofstrasse 7a").
+    pub fn from_address(name: &str, address: &Address) -> Option<Self> {
+        let line = address.line1.trim();
+        let (street, building_number) = match line.rsplit_once(' ') {
+            Some((street, number)) if number.starts_with(|c: char| c.is_ascii_digit()) => (street.trim(), number),
+            _ => (line, ""),
+        };
+        let country = country_code(&address.country)?;
+        let town = address.city.trim();
+        let postal_code = address.postal_code.trim();
+        if name.trim().is_empty() || town.is_empty() || postal_code.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: truncate(name.trim(), 70),
+            street: truncate(street, 70),
+            building_number: truncate(building_number, 16),
+            postal_code: truncate(postal_code, 16),
+            town: truncate(town, 35),
+            country: country.to_string(),
+        })
+    }
+
+    fn push_lines(&self, lines: &mut Vec<String>) {
+        lines.push("S".to_string());
+        lines.push(self.name.clone());
+        lines.push(self.street.clone());
+        lines.push(self.building_number.clone());
+        lines.push(self.postal_code.clone());
+        lines.push(self.town.clone());
+        lines.push(self.country.clone());
+    }
+
+    /// Lines as printed in the payment part.
+    pub fn display_lines(&self) -> Vec<String> {
+        let street = format!("{} {}", self.street, self.building_number).trim().to_string();
+        let town = format!("{}-{} {}", self.country, self.postal_code, self.town);
+        [self.name.clone(), street, town].into_iter().filter(|l| !l.is_empty()).collect()
+    }
+}
+
+/// Data for the payment part of a Swiss QR-bill (SIX Implementation Guidelines 2.2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwissQrBill {
+    pub iban: String,
+    pub creditor: SwissAddress,
+    pub amount: Option<Decimal>,
+    /// `CHF` or `EUR`.
+    pub currency: String,
+    pub debtor: Option<SwissAddress>,
+    /// `QRR` with a QR-IBAN, `SCOR` (RF reference) or `NON`.
+    pub reference_type: &'static str,
+    pub reference: String,
+    pub message: String,
+}
+
+impl SwissQrBill {
+    /// The Swiss QR Code payload.
+    pub fn payload(&self) -> String {
+        let mut lines = vec!["SPC".to_string(), "0200".to_string(), "1".to_string(), self.iban.clone()];
+        self.creditor.push_lines(&mut lines);
+        // Ultimate creditor: reserved for future use, always empty.
+        lines.extend(std::iter::repeat_n(String::new(), 7));
+        lines.push(self.amount.map(|a| format!("{:.2}", a.round_dp(2))).unwrap_or_default());
+        lines.push(self.currency.clone());
+        match &self.debtor {
+            Some(debtor) => debtor.push_lines(&mut lines),
+            None => lines.extend(std::iter::repeat_n(String::new(), 7)),
+        }
+        lines.push(self.reference_type.to_string());
+        lines.push(self.reference.clone());
+        lines.push(truncate(&self.message, 140));
+        lines.push("EPD".to_string());
+        lines.join("\n")
+    }
+
+    /// IBAN in blocks of four, as printed.
+    pub fn display_iban(&self) -> String {
+        group_iban(&self.iban)
+    }
+
+    /// Reference in the blocks the guidelines prescribe (five from the right for QRR).
+    pub fn display_reference(&self) -> String {
+        match self.reference_type {
+            "QRR" => group(&self.reference, 5, true),
+            _ => group(&self.reference, 4, false),
+        }
+    }
+
+    /// Amount with a space as thousands separator, e.g. `1 234.50`.
+    pub fn display_amount(&self) -> Option<String> {
+        let amount = format!("{:.2}", self.amount?.round_dp(2));
+        let (whole, cents) = amount.split_once('.').unwrap_or((&amount, "00"));
+        Some(format!("{}.{}", group(whole, 3, true), cents))
+    }
+}
+
+/// The QR-bill for an invoice paid to a Swiss or Liechtenstein account in CHF or EUR.
+pub(crate) fn invoice_swiss_qr_bill(invoice: &Invoice, client: &Client, profile: &BusinessProfile, extras: &InvoiceExtras) -> Option<SwissQrBill> {
+    let iban = normalize_iban(&extras.iban);
+    let currency = invoice.currency.to_string();
+    if !is_swiss_iban(&iban) || !is_valid_iban(&iban) || !matches!(currency.as_str(), "CHF" | "EUR") {
+        return None;
+    }
+    let creditor_name = if extras.account_holder.trim().is_empty() { &profile.name } else { &extras.account_holder };
+    let creditor = SwissAddress::from_address(creditor_name, &profile.address)?;
+    let debtor_name = client.company.as_deref().filter(|c| !c.trim().is_empty()).unwrap_or(&client.name);
+    let debtor = SwissAddress::from_address(debtor_name, &client.address);
+
+    // A QR-IBAN only accepts QR references; a regular IBAN takes an RF reference or none.
+    let (reference_type, reference) = if is_qr_iban(&iban) {
+        ("QRR", qr_reference(&invoice.number)?)
+    } else {
+        match creditor_reference(&invoice.number) {
+            Some(reference) => ("SCOR", reference),
+            None => ("NON", String::new()),
+        }
+    };
+    let amount = Some(invoice.amount_due).filter(|a| *a > Decimal::ZERO && *a <= MAX_QR_AMOUNT);
+
+    Some(SwissQrBill {
+        iban,
+        creditor,
+        amount,
+        currency,
+        debtor,
+        reference_type,
+        reference,
+        message: format!("Invoice {}", invoice.number),
+    })
+}
+
+/// IBAN in blocks of four, as printed on paper.
+pub fn group_iban(iban: &str) -> String {
+    group(&normalize_iban(iban), 4, false)
+}
+
+fn is_swiss_iban(iban: &str) -> bool {
+    iban.starts_with("CH") || iban.starts_with("LI")
+}
+
+/// QR-IBANs carry an institution ID between 30000 and 31999.
+fn is_qr_iban(iban: &str) -> bool {
+    is_swiss_iban(iban) && iban.get(4..9).and_then(|iid| iid.parse::<u32>().ok()).is_some_and(|iid| (30000..=31999).contains(&iid))
+}
+
+/// ISO 11649 structured creditor reference built from the alphanumerics of `reference`,
+/// e.g. `INV-2025-0042` becomes `RF50INV20250042`. `None` when there are none or more than 21.
+pub fn creditor_reference(reference: &str) -> Option<String> {
+    let body: String = reference.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_ascii_uppercase();
+    if body.is_empty() || body.len() > 21 {
+        return None;
+    }
+    let check = 98 - mod97(body.chars().chain("RF00".chars()));
+    Some(format!("RF{:02}{}", check, body))
+}
+
+/// Validates the check digits of an ISO 11649 `RF` reference.
+pub fn is_creditor_reference(reference: &str) -> bool {
+    let reference = normalize_iban(reference);
+    (5..=25).contains(&reference.len())
+        && reference.starts_with("RF")
+        && reference.chars().all(|c| c.is_ascii_alphanumeric())
+        && mod97(reference[4..].chars().chain(reference[..4].chars())) == 1
+}
+
+/// 27-digit Swiss QR reference from the digits of `reference`, with the mod-10
+/// recursive check digit. `None` when there are no digits or more than 26.
+pub fn qr_reference(reference: &str) -> Option<String> {
+    const TABLE: [u32; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+    let digits: String = reference.chars().filter(char::is_ascii_digit).collect();
+    if digits.is_empty() || digits.len() > 26 {
+        return None;
+    }
+    let body = format!("{:0>26}", digits);
+    let carry = body.bytes().fold(0, |carry, b| TABLE[((carry + u32::from(b - b'0')) % 10) as usize]);
+    Some(format!("{}{}", body, (10 - carry) % 10))
+}
+
+/// Splits into blocks of `size`, counted from the end when `from_right` is set.
+fn group(value: &str, size: usize, from_right: bool) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let offset = if from_right { chars.len() % size } else { 0 };
+    let mut grouped = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if i > 0 && (i + size - offset).is_multiple_of(size) {
+            grouped.push(' ');
+        }
+        grouped.push(*c);
+    }
+    grouped
+}
+
+fn truncate(value: &str, max: usize) -> String {
+    value.chars().take(max).collect()
+}
+
+/// Percent-encodes everything outside the RFC 3986 unreserved set.
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            b'@' => encoded.push('@'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upi_uri() {
+        let uri = upi_uri("studio.pay@okhdfc", "Asha & Co", Decimal::new(1250050, 2), "Invoice INV-2025-0007");
+        assert_eq!(
+            uri.as_deref(),
+            Some("upi://pay?pa=studio.pay@okhdfc&pn=Asha%20%26%20Co&am=12500.50&cu=INR&tn=Invoice%20INV-2025-0007")
+        );
+        assert!(upi_uri("not-a-vpa", "", Decimal::ONE, "").is_none());
+        assert!(upi_uri("a@1bank", "", Decimal::ONE, "").is_none());
+    }
+
+    #[test]
+    fn test_references() {
+        assert!(is_qr_iban("CH4431999123000889012"));
+        assert!(!is_qr_iban("CH9300762011623852957"));
+
+        assert_eq!(creditor_reference("539007547034").as_deref(), Some("RF18539007547034"));
+        assert!(is_creditor_reference("RF18 5390 0754 7034"));
+        assert!(!is_creditor_reference("RF19539007547034"));
+        assert!(creditor_reference("-").is_none());
+
+        assert_eq!(qr_reference("21000000000313947143000901").as_deref(), Some("210000000003139471430009017"));
+        assert_eq!(group("210000000003139471430009017", 5, true), "21 00000 00003 13947 14300 09017");
+    }
+
+    #[test]
+    fn test_epc_payload() {
+        let payload = epc_payload("Red Cross", "BE72 0000 0000 1616", "BPOTBEB1", Decimal::new(100, 0), None, "Donation").unwrap();
+        assert_eq!(payload, "BCD\n002\n1\nSCT\nBPOTBEB1\nRed Cross\nBE72000000001616\nEUR100.00\n\n\nDonation");
+        let payload = epc_payload("Studio", "DE89370400440532013000", "", Decimal::ZERO, Some("RF18539007547034"), "ignored").unwrap();
+        assert!(payload.ends_with("Studio\nDE89370400440532013000\n\n\nRF18539007547034"));
+        assert!(epc_payload("Studio", "DE00370400440532013000", "", Decimal::ONE, None, "").is_none());
+    }
+
+    #[test]
+    fn test_swiss_qr_bill_payload() {
+        let address = |name: &str, line1: &str, postal: &str, city: &str| {
+            let address = Address {
+                line1: line1.to_string(),
+                postal_code: postal.to_string(),
+                city: city.to_string(),
+                country: "Switzerland".to_string(),
+                ..Address::default()
+            };
+            SwissAddress::from_address(name, &address).unwrap()
+        };
+        let bill = SwissQrBill {
+            iban: "CH4431999123000889012".to_string(),
+            creditor: address("Robert Schneider AG", "Rue du Lac 1268", "2501", "Biel"),
+            amount: Some(Decimal::new(199995, 2)),
+            currency: "CHF".to_string(),
+            debtor: Some(address("Pia-Maria Rutschmann-Schnyder", "Grosse Marktgasse 28", "9400", "Rorschach")),
+            reference_type: "QRR",
+            reference: "210000000003139471430009017".to_string(),
+            message: "Order of 15.06.2020".to_string(),
+        };
+        let payload = bill.payload();
+        let lines: Vec<&str> = payload.split('\n').collect();
+        assert_eq!(lines.len(), 31);
+        assert_eq!(&lines[..11], ["SPC", "0200", "1", "CH4431999123000889012", "S", "Robert Schneider AG", "Rue du Lac", "1268", "2501", "Biel", "CH"]);
+        assert_eq!(&lines[18..20], ["1999.95", "CHF"]);
+        assert_eq!(&lines[27..], ["QRR", "210000000003139471430009017", "Order of 15.06.2020", "EPD"]);
+        assert_eq!(bill.display_amount().as_deref(), Some("1 999.95"));
+        assert_eq!(bill.display_iban(), "CH44 3199 9123 0008 8901 2");
+    }
+}
@@ -0,0 +1,150 @@
+//! PDF/A-3b support for the canvas: XMP metadata, the sRGB output intent and
+//! associated files (the machine-readable invoice embedded alongside the pages).
+
+use chrono::{DateTime, Utc};
+use flow_einvoice::xml::escape;
+use pdf_writer::types::OutputIntentSubtype;
+use pdf_writer::{Finish, Name, Pdf, Ref, Str, TextStr};
+
+use crate::canvas::{pdf_date, DocumentInfo};
+
+static SRGB_PROFILE: &[u8] = include_bytes!("../assets/icc/sRGB-v4.icc");
+
+/// A file embedded as a PDF/A-3 associated file.
+pub(crate) struct Attachment {
+    pub name: String,
+    pub mime_type: &'static str,
+    pub description: String,
+    /// Value of `/AFRelationship`, e.g. `Alternative` or `Data`.
+    pub relationship: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// Switches the canvas output to PDF/A-3b.
+pub(crate) struct Archival {
+    pub attachments: Vec<Attachment>,
+    /// Additional `rdf:Description` elements, including any `pdfaExtension` schema
+    /// declarations they need, appended to the XMP packet.
+    pub xmp_extension: String,
+}
+
+/// Object references reserved for the archival parts of the document.
+pub(crate) struct ArchivalRefs {
+    pub metadata: Ref,
+    pub icc_profile: Ref,
+    /// `(file specification, embedded file stream)` per attachment.
+    pub files: Vec<(Ref, Ref)>,
+}
+
+impl ArchivalRefs {
+    pub(crate) fn allocate(archival: &Archival, alloc: &mut Ref) -> Self {
+        Self {
+            metadata: alloc.bump(),
+            icc_profile: alloc.bump(),
+            files: archival.attachments.iter().map(|_| (alloc.bump(), alloc.bump())).collect(),
+        }
+    }
+}
+
+/// Adds the PDF/A entries to the document catalog.
+pub(crate) fn write_catalog_entries(
+    catalog: &mut pdf_writer::writers::Catalog,
+    archival: &Archival,
+    refs: &ArchivalRefs,
+) {
+    catalog.metadata(refs.metadata);
+    catalog
+        .insert(Name(b"OutputIntents"))
+        .array()
+        .push()
+        .start::<pdf_writer::writers::OutputIntent>()
+        .subtype(OutputIntentSubtype::PDFA)
+        .output_condition_identifier(TextStr("sRGB"))
+        .info(TextStr("sRGB IEC61966-2.1"))
+        .dest_output_profile(refs.icc_profile);
+
+    if archival.attachments.is_empty() {
+        return;
+    }
+    let mut names = catalog.names();
+    let mut files = names.embedded_files();
+    let mut entries = files.names();
+    for (attachment, (spec_id, _)) in archival.attachments.iter().zip(&refs.files) {
+        entries.insert(Str(attachment.name.as_bytes()), *spec_id);
+    }
+    entries.finish();
+    files.finish();
+    names.finish();
+    catalog
+        .insert(Name(b"AF"))
+        .array()
+        .items(refs.files.iter().map(|(spec_id, _)| *spec_id));
+}
+
+/// Writes the metadata stream, ICC profile and embedded files.
+pub(crate) fn write_objects(pdf: &mut Pdf, info: &DocumentInfo, archival: &Archival, refs: &ArchivalRefs) {
+    let xmp = xmp_packet(info, archival);
+    pdf.metadata(refs.metadata, xmp.as_bytes());
+    pdf.icc_profile(refs.icc_profile, SRGB_PROFILE)
+        .n(3)
+        .range([0.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+
+    let modified = pdf_date(&info.created_at);
+    for (attachment, &(spec_id, file_id)) in archival.attachments.iter().zip(&refs.files) {
+        let mut spec = pdf.file_spec(spec_id);
+        spec.path(Str(attachment.name.as_bytes()))
+            .unic_file(TextStr(&attachment.name))
+            .description(TextStr(&attachment.description));
+        spec.insert(Name(b"EF"))
+            .dict()
+            .pair(Name(b"F"), file_id)
+            .pair(Name(b"UF"), file_id);
+        spec.pair(Name(b"AFRelationship"), Name(attachment.relationship.as_bytes()));
+        spec.finish();
+
+        let mut file = pdf.embedded_file(file_id, &attachment.data);
+        file.subtype(Name(attachment.mime_type.as_bytes()));
+        file.params()
+            .size(attachment.data.len() as i32)
+            .modification_date(modified);
+    }
+}
+
+fn xmp_packet(info: &DocumentInfo, archival: &Archival) -> String {
+    let date = xmp_date(&info.created_at);
+    let title = escape(&info.title);
+    let author = escape(&info.author);
+    format!(
+        r#"<?xpacket begin="{bom}" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about="" xmlns:pdfaid="http://www.aiim.org/pdfa/ns/id/">
+<pdfaid:part>3</pdfaid:part>
+<pdfaid:conformance>B</pdfaid:conformance>
+</rdf:Description>
+<rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:format>application/pdf</dc:format>
+<dc:title><rdf:Alt><rdf:li xml:lang="x-default">{title}</rdf:li></rdf:Alt></dc:title>
+<dc:creator><rdf:Seq><rdf:li>{author}</rdf:li></rdf:Seq></dc:creator>
+</rdf:Description>
+<rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+<xmp:CreatorTool>InvoiceFlow</xmp:CreatorTool>
+<xmp:CreateDate>{date}</xmp:CreateDate>
+<xmp:ModifyDate>{date}</xmp:ModifyDate>
+<xmp:MetadataDate>{date}</xmp:MetadataDate>
+</rdf:Description>
+<rdf:Description rdf:about="" xmlns:pdf="http://ns.adobe.com/pdf/1.3/">
+<pdf:Producer>InvoiceFlow</pdf:Producer>
+</rdf:Description>
+{extension}</rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#,
+        bom = '\u{feff}',
+        extension = archival.xmp_extension,
+    )
+}
+
+/// Formats a timestamp the way XMP expects, matching the info dictionary dates.
+fn xmp_date(dt: &DateTime<Utc>) -> String {
+    dt.format("%Y-%m-%dT%H:%M:%S+00:00").to_string()
+}
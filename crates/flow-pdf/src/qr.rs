@@ -0,0 +1,77 @@
+//! QR codes drawn as vector shapes so they stay sharp in print and in both backends.
+
+use qrcode::{Color as Module, QrCode};
+
+/// Light modules required around the code by the QR specification.
+pub(crate) const QUIET_ZONE: usize = 4;
+
+pub(crate) struct QrMatrix {
+    width: usize,
+    dark: Vec<bool>,
+}
+
+impl QrMatrix {
+    /// Returns `None` when the payload is too long for any QR version.
+    pub(crate) fn encode(payload: &str) -> Option<Self> {
+        let code = QrCode::new(payload.as_bytes()).ok()?;
+        let width = code.width();
+        let dark = code.into_colors().into_iter().map(|m| m == Module::Dark).collect();
+        Some(Self { width, dark })
+    }
+
+    /// Width in modules without the quiet zone.
+    pub(crate) fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Width in modules including the quiet zone on both sides.
+    pub(crate) fn size(&self) -> usize {
+        self.width + 2 * QUIET_ZONE
+    }
+
+    /// Horizontal runs of dark modules as `(column, row, length)`, offset by the quiet zone.
+    pub(crate) fn runs(&self) -> Vec<(usize, usize, usize)> {
+        let mut runs = Vec::new();
+        for (row, modules) in self.dark.chunks(self.width).enumerate() {
+            let mut col = 0;
+            while col < self.width {
+                if !modules[col] {
+                    col += 1;
+                    continue;
+                }
+                let start = col;
+                while col < self.width && modules[col] {
+                    col += 1;
+                }
+                runs.push((start + QUIET_ZONE, row + QUIET_ZONE, col - start));
+            }
+        }
+        runs
+    }
+
+    /// Inline SVG element; it scales to whatever box the surrounding CSS gives it.
+    pub(crate) fn svg(&self, class: &str, label: &str) -> String {
+        let path: String = self.runs().iter().map(|(x, y, len)| format!("M{} {}h{}v1h-{}z", x, y, len, len)).collect();
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {size} {size}" class="{}" role="img" aria-label="{}" shape-rendering="crispEdges"><rect width="{size}" height="{size}" fill="#fff"/><path d="{}" fill="#000"/></svg>"##,
+            class,
+            label,
+            path,
+            size = self.size()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_cover_every_dark_module() {
+        let qr = QrMatrix::encode("upi://pay?pa=studio@upi&am=100.00").unwrap();
+        let dark = qr.dark.iter().filter(|d| **d).count();
+        assert_eq!(qr.runs().iter().map(|(_, _, len)| len).sum::<usize>(), dark);
+        assert!(qr.svg("qr-img", "QR").starts_with("<svg"));
+        assert!(QrMatrix::encode(&"x".repeat(8000)).is_none());
+    }
+}
@@ -1,58 +1,145 @@
-use flow_core::models::{BusinessProfile, Client, Invoice};
-use serde_json::Value;
+use flow_core::models::{BankAccount, BusinessProfile, Client, CustomFieldValue, Invoice};
+use flow_core::types::CustomFieldType;
 
-pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &BusinessProfile) -> String {
-    let mut developer = String::new();
-    let mut parsed_logo = None;
-    let mut qr_code_url = None;
-    let mut bank_name = String::new();
-    let mut branch = String::new();
-    let mut account_holder = String::new();
-    let mut account_number = String::new();
-    let mut ifsc_code = String::new();
-    let mut upi_id = String::new();
-    let mut payment_terms_note = String::new();
-    let mut project_details_html = String::new();
+use crate::payment;
+use crate::qr::QrMatrix;
 
-    if let Some(ref notes) = invoice.notes {
-        if let Ok(json) = serde_json::from_str::<Value>(notes) {
-            developer = json.get("developer").and_then(|v| v.as_str()).unwrap_or("").to_string();
-            parsed_logo = json.get("logoPath").and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
-            qr_code_url = json.get("qrCodeUrl").and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
-            payment_terms_note = json.get("paymentTermsNote").and_then(|v| v.as_str()).unwrap_or("").to_string();
+/// Invoice presentation fields flattened for the renderers, with the referenced bank
+/// account's details copied in.
+#[derive(Debug, Clone, Default)]
+pub struct InvoiceExtras {
+    pub developer: String,
+    pub logo: Option<String>,
+    pub qr_code_url: Option<String>,
+    pub bank_name: String,
+    pub branch: String,
+    pub account_holder: String,
+    pub account_number: String,
+    pub ifsc_code: String,
+    pub routing_number: String,
+    pub upi_id: String,
+    pub iban: String,
+    pub bic: String,
+    pub payment_terms_note: String,
+    pub project_details: Vec<(String, String)>,
+}
 
-            if let Some(bank) = json.get("bankDetails") {
-                bank_name = bank.get("bankName").and_then(|v| v.as_str()).unwrap_or("").to_string();
-                branch = bank.get("branch").and_then(|v| v.as_str()).unwrap_or("").to_string();
-                account_holder = bank.get("accountHolder").and_then(|v| v.as_str()).unwrap_or("").to_string();
-                account_number = bank.get("accountNumber").and_then(|v| v.as_str()).unwrap_or("").to_string();
-                ifsc_code = bank.get("ifscCode").and_then(|v| v.as_str()).unwrap_or("").to_string();
-                upi_id = bank.get("upiId").and_then(|v| v.as_str()).unwrap_or("").to_string();
-            }
+impl InvoiceExtras {
+    pub fn from_invoice(invoice: &Invoice) -> Self {
+        let present = |value: &Option<String>| value.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+        let mut extras = Self {
+            developer: present(&invoice.developer).unwrap_or_default(),
+            logo: present(&invoice.logo_path),
+            qr_code_url: present(&invoice.qr_code_url),
+            payment_terms_note: present(&invoice.payment_note).unwrap_or_default(),
+            // Invoice custom fields (PO number, cost centre…) print alongside the project details.
+            project_details: invoice
+                .project_details
+                .iter()
+                .filter(|detail| !detail.value.is_empty())
+                .map(|detail| (detail.label.clone(), detail.value.clone()))
+                .chain(invoice.custom_fields.iter().map(|field| (field.name.clone(), custom_field_display(field))))
+                .collect(),
+            ..Self::default()
+        };
+
+        if let Some(account) = &invoice.bank_account {
+            extras.apply_bank_account(account);
+        }
+
+        extras
+    }
+
+    fn apply_bank_account(&mut self, account: &BankAccount) {
+        let text = |value: &Option<String>| value.as_deref().unwrap_or("").trim().to_string();
+        self.account_holder = account.account_holder.clone();
+        self.bank_name = account.bank_name.clone();
+        self.branch = text(&account.branch);
+        self.account_number = text(&account.account_number);
+        self.ifsc_code = text(&account.ifsc_code);
+        self.routing_number = text(&account.routing_number);
+        self.upi_id = text(&account.upi_id);
+        self.iban = account.iban.as_deref().map(flow_core::bank::normalize_iban).unwrap_or_default();
+        self.bic = text(&account.swift_bic);
+    }
+
+    /// Account rows for the bank details block: IBAN/BIC for SEPA and Swiss accounts,
+    /// account number with IFSC or routing number otherwise.
+    pub(crate) fn account_rows(&self) -> [(&'static str, String); 2] {
+        if !self.iban.is_empty() {
+            [("IBAN:", payment::group_iban(&self.iban)), ("BIC / SWIFT:", self.bic.clone())]
+        } else if self.ifsc_code.is_empty() && !self.routing_number.is_empty() {
+            [("Account Number:", self.account_number.clone()), ("Routing Number:", self.routing_number.clone())]
+        } else {
+            [("Account Number:", self.account_number.clone()), ("IFSC Code:", self.ifsc_code.clone())]
+        }
+    }
 
-            if let Some(arr) = json.get("projectDetails").and_then(|v| v.as_array()) {
-                let mut html = String::new();
-                for item in arr {
-                    let label = item.get("label").and_then(|v| v.as_str()).unwrap_or("");
-                    let value = item.get("value").and_then(|v| v.as_str()).unwrap_or("");
-                    if !value.is_empty() {
-                        html.push_str(&format!(
-                            r#"
+    /// Payment methods listed under the bank details.
+    pub(crate) fn payment_methods(&self) -> [&'static str; 3] {
+        if self.iban.is_empty() {
+            ["• Bank Transfer (NEFT/RTGS/IMPS)", "• UPI Payment (Scan QR Code →)", "• Cash"]
+        } else {
+            ["• Bank Transfer (SEPA / SWIFT)", "• Banking App (Scan QR Code →)", "• Cash"]
+        }
+    }
+}
+
+/// Printable form of a stored custom field value.
+pub(crate) fn custom_field_display(field: &CustomFieldValue) -> String {
+    match field.field_type {
+        CustomFieldType::Boolean => if field.value == "true" { "Yes" } else { "No" }.to_string(),
+        CustomFieldType::Date => chrono::NaiveDate::parse_from_str(&field.value, "%Y-%m-%d")
+            .map(|d| d.format("%d %b %Y").to_string())
+            .unwrap_or_else(|_| field.value.clone()),
+        _ => field.value.clone(),
+    }
+}
+
+/// Item custom fields on one line, e.g. "PO Line: 3 · Cost Code: MKT".
+pub(crate) fn custom_fields_line(fields: &[CustomFieldValue]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("{}: {}", field.name, custom_field_display(field)))
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+pub fn render_invoice_html(invoice: &Invoice, client: &Client, profile: &BusinessProfile) -> String {
+    let extras = InvoiceExtras::from_invoice(invoice);
+    // Prefer a QR for the exact amount due (UPI or GiroCode); the uploaded image is a fallback.
+    let upi_qr = payment::invoice_upi_uri(invoice, &extras).and_then(|uri| QrMatrix::encode(&uri));
+    let epc_qr = payment::invoice_epc_payload(invoice, profile, &extras).and_then(|p| QrMatrix::encode(&p));
+    let qr_bill_html = payment::invoice_swiss_qr_bill(invoice, client, profile, &extras)
+        .map(|bill| swiss_qr_bill_html(&bill))
+        .unwrap_or_default();
+    let [(number_label, account_number), (code_label, account_code)] = extras.account_rows();
+    let payment_methods = extras.payment_methods().join("<br>");
+    let InvoiceExtras {
+        developer,
+        logo: parsed_logo,
+        qr_code_url,
+        bank_name,
+        branch,
+        account_holder,
+        upi_id,
+        iban,
+        payment_terms_note,
+        project_details,
+        ..
+    } = extras;
+
+    let project_details_html: String = project_details.iter().map(|(label, value)| {
+        format!(
+            r#"
                             <div class="grid grid-cols-160 items-center">
                                 <div class="font-bold">{}</div>
                                 <div>{}</div>
                             </div>
                             "#,
-                            label, value
-                        ));
-                    }
-                }
-                if !html.is_empty() {
-                    project_details_html = html;
-                }
-            }
-        }
-    }
+            label, value
+        )
+    }).collect();
 
     let logo_html = if let Some(logo) = parsed_logo {
         if logo.starts_with("data:image") {
@@ -66,19 +153,41 @@ pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &Busine
     };
 
     let items_html: String = invoice.items.iter().map(|item| {
+        let fields = custom_fields_line(&item.custom_fields);
         format!(
             r#"
             <tr class="border-row">
-                <td class="td-desc">{}</td>
+                <td class="td-desc">{}{}</td>
                 <td class="td-amount">{}</td>
             </tr>
             "#,
             item.description.replace("\n", "<br>"),
+            if fields.is_empty() { String::new() } else { format!(r#"<div class="item-fields">{}</div>"#, fields) },
             format_currency(&item.amount.to_string(), &invoice.currency.to_string())
         )
     }).collect();
 
-    let qr_html = if let Some(qr) = qr_code_url {
+    // A registered GST e-invoice must carry the portal's signed QR instead of the UPI code.
+    let irn_qr = invoice
+        .gst_registration
+        .as_ref()
+        .and_then(|r| QrMatrix::encode(&r.signed_qr).map(|qr| (r, qr)));
+    let (qr_title, qr_caption, qr_subcaption) = match &irn_qr {
+        Some((registration, _)) => ("e-Invoice (IRN)", format!("Ack No: {}", registration.ack_no), registration.irn.clone()),
+        None if upi_qr.is_none() && epc_qr.is_some() => {
+            let holder = if account_holder.is_empty() { profile.name.clone() } else { account_holder.clone() };
+            ("Scan to Pay (GiroCode)", holder, payment::group_iban(&iban))
+        }
+        None => ("Scan to Pay via UPI", developer.clone(), upi_id.clone()),
+    };
+
+    let qr_html = if let Some((_, qr)) = &irn_qr {
+        qr.svg("qr-img bg-white", "e-Invoice QR Code")
+    } else if let Some(qr) = &upi_qr {
+        qr.svg("qr-img bg-white", "UPI QR Code")
+    } else if let Some(qr) = &epc_qr {
+        qr.svg("qr-img bg-white", "GiroCode")
+    } else if let Some(qr) = qr_code_url {
         if qr.starts_with("data:image") {
             format!(r#"<img src="{}" alt="UPI QR Code" class="qr-img bg-white" />"#, qr)
         } else {
@@ -257,6 +366,11 @@ pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &Busine
                     color: #111827;
                     line-height: 1.6;
                 }}
+
+                .item-fields {{
+                    font-size: 12px;
+                    color: #6b7280;
+                }}
                 
                 .td-amount {{
                     padding: 12px 16px;
@@ -437,6 +551,7 @@ pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &Busine
                         <div class="client-detail">
                             {}
                             {}
+                            {}
                         </div>
                     </div>
                 </div>
@@ -478,11 +593,11 @@ pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &Busine
                             <span class="bank-value">{}</span>
                         </div>
                         <div class="bank-row">
-                            <span class="bank-label">Account Number:</span>
+                            <span class="bank-label">{}</span>
                             <span class="bank-value">{}</span>
                         </div>
                         <div class="bank-row">
-                            <span class="bank-label">IFSC Code:</span>
+                            <span class="bank-label">{}</span>
                             <span class="bank-value text-blue">{}</span>
                         </div>
                         <div class="bank-row">
@@ -500,21 +615,19 @@ pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &Busine
                         <div style="margin-top: 12px;">
                             <span style="font-weight: 700; display: block; margin-bottom: 4px;">Payment Methods:</span>
                             <p style="margin: 0; line-height: 1.4; color: #374151;">
-                                • Bank Transfer (NEFT/RTGS/IMPS)<br>
-                                • UPI Payment (Scan QR Code →)<br>
-                                • Cash
+                                {}
                             </p>
                         </div>
                     </div>
                     
                     <!-- QR Code -->
                     <div class="qr-section">
-                        <h3 class="qr-title">Scan to Pay via UPI</h3>
+                        <h3 class="qr-title">{}</h3>
                         <div class="qr-box">
                             {}
                         </div>
                         <div style="margin-top: 8px; text-align: center; font-size: 12px; font-weight: 700; color: #111827;">{}</div>
-                        <div style="text-align: center; font-size: 10px; color: #6b7280;">{}</div>
+                        <div style="text-align: center; font-size: 10px; color: #6b7280; word-break: break-all;">{}</div>
                     </div>
                 </div>
                 
@@ -539,6 +652,7 @@ pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &Busine
                     This is a computer-generated invoice and does not require a physical signature.
                 </div>
             </div>
+            {}
         </body>
         </html>
         "#,
@@ -551,6 +665,7 @@ pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &Busine
         client.name,
         if let Some(company) = &client.company { format!("<p>{}</p>", company) } else { String::new() },
         if let Some(email) = &client.email { format!(r#"<p>Website: <span class="client-email">{}</span></p>"#, email) } else { String::new() },
+        client.custom_fields.iter().map(|field| format!("<p>{}: {}</p>", field.name, custom_field_display(field))).collect::<String>(),
         if !project_details_html.is_empty() {
             format!(
                 r#"
@@ -569,21 +684,108 @@ pub fn render_invoice_html(invoice: &Invoice, client: &Client, _profile: &Busine
         items_html,
         format_currency(&invoice.amount_due.to_string(), &invoice.currency.to_string()),
         account_holder,
+        number_label,
         account_number,
-        ifsc_code,
+        code_label,
+        account_code,
         bank_name,
         branch,
         upi_id,
+        payment_methods,
+        qr_title,
         qr_html,
-        developer,
-        upi_id,
+        qr_caption,
+        qr_subcaption,
         invoice.number,
         payment_terms_note.replace("\n", "<br>"),
-        if developer.is_empty() { "the developer" } else { &developer }
+        if developer.is_empty() { "the developer" } else { &developer },
+        qr_bill_html
+    )
+}
+
+/// Swiss QR-bill payment slip (receipt and payment part) sized in millimetres for print.
+fn swiss_qr_bill_html(bill: &payment::SwissQrBill) -> String {
+    let Some(qr) = QrMatrix::encode(&bill.payload()) else {
+        return String::new();
+    };
+    let field = |title: &str, lines: &[String], size: u32| -> String {
+        format!(
+            r#"<div style="margin-bottom: 3mm;"><div style="font-size: {}pt; font-weight: 700;">{}</div><div style="font-size: {}pt; line-height: 1.15;">{}</div></div>"#,
+            size,
+            title,
+            size + 2,
+            lines.join("<br>")
+        )
+    };
+    let mut account = vec![bill.display_iban()];
+    account.extend(bill.creditor.display_lines());
+    let reference = bill.display_reference();
+    let amount = bill.display_amount().unwrap_or_default();
+    let amount_block = |size: u32| -> String {
+        format!(
+            r#"<div style="display: flex; gap: 5mm;"><div><div style="font-size: {}pt; font-weight: 700;">Currency</div><div style="font-size: {}pt;">{}</div></div><div><div style="font-size: {}pt; font-weight: 700;">Amount</div><div style="font-size: {}pt;">{}</div></div></div>"#,
+            size,
+            size + 2,
+            bill.currency,
+            size,
+            size + 2,
+            amount
+        )
+    };
+
+    let mut receipt = field("Account / Payable to", &account, 6);
+    let mut details = field("Account / Payable to", &account, 8);
+    if !reference.is_empty() {
+        receipt.push_str(&field("Reference", std::slice::from_ref(&reference), 6));
+        details.push_str(&field("Reference", &[reference], 8));
+    }
+    if !bill.message.is_empty() {
+        details.push_str(&field("Additional information", std::slice::from_ref(&bill.message), 8));
+    }
+    if let Some(debtor) = &bill.debtor {
+        receipt.push_str(&field("Payable by", &debtor.display_lines(), 6));
+        details.push_str(&field("Payable by", &debtor.display_lines(), 8));
+    }
+
+    // The SVG includes the quiet zone, so it is scaled up for the modules to span exactly 46 mm.
+    let qr_mm = 46.0 * qr.size() as f32 / qr.width() as f32;
+    let quiet_mm = (qr_mm - 46.0) / 2.0;
+    format!(
+        r##"
+            <div style="page-break-before: auto; break-inside: avoid; width: 210mm; height: 105mm; margin-top: 24px; border-top: 1px dashed #000; display: flex; font-family: Arial, Helvetica, sans-serif; color: #000; box-sizing: border-box;">
+                <div style="width: 62mm; padding: 5mm; border-right: 1px dashed #000; box-sizing: border-box; position: relative;">
+                    <div style="font-size: 11pt; font-weight: 700; margin-bottom: 3mm;">Receipt</div>
+                    {}
+                    <div style="position: absolute; top: 68mm; left: 5mm;">{}</div>
+                    <div style="position: absolute; top: 82mm; right: 5mm; font-size: 6pt; font-weight: 700;">Acceptance point</div>
+                </div>
+                <div style="width: 148mm; padding: 5mm; box-sizing: border-box; display: flex;">
+                    <div style="width: 51mm; position: relative;">
+                        <div style="font-size: 11pt; font-weight: 700;">Payment part</div>
+                        <div style="position: absolute; top: 12mm; left: -{quiet:.2}mm; width: {size:.2}mm; height: {size:.2}mm;">
+                            {}
+                            <svg viewBox="0 0 7 7" style="position: absolute; left: {cross:.2}mm; top: {cross:.2}mm; width: 7mm; height: 7mm;"><rect width="7" height="7" fill="#fff"/><rect x="0.5" y="0.5" width="6" height="6" fill="#000"/><rect x="2.915" y="1.55" width="1.17" height="3.9" fill="#fff"/><rect x="1.55" y="2.915" width="3.9" height="1.17" fill="#fff"/></svg>
+                        </div>
+                        <div style="position: absolute; top: 63mm;">{}</div>
+                    </div>
+                    <div style="flex: 1;">
+                        {}
+                    </div>
+                </div>
+            </div>
+        "##,
+        receipt,
+        amount_block(6),
+        qr.svg("qr-bill-code", "Swiss QR Code").replace("<svg ", r#"<svg style="width: 100%; height: 100%;" "#),
+        amount_block(8),
+        details,
+        quiet = quiet_mm,
+        size = qr_mm,
+        cross = (qr_mm - 7.0) / 2.0,
     )
 }
 
-fn currency_symbol(currency: &str) -> String {
+pub(crate) fn currency_symbol(currency: &str) -> String {
     match currency {
         "USD" => "$".to_string(),
         "EUR" => "€".to_string(),
@@ -594,7 +796,7 @@ fn currency_symbol(currency: &str) -> String {
     }
 }
 
-fn format_currency(amount: &str, currency: &str) -> String {
+pub(crate) fn format_currency(amount: &str, currency: &str) -> String {
     let amt: f64 = amount.parse().unwrap_or(0.0);
     
     // Add basic comma formatting for thousands (Indian format logic mostly)